@@ -0,0 +1,81 @@
+use std::io::BufRead;
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+
+// Ambisonics example
+//
+// Several sources are encoded in a single third order sound field, which is rotated
+// with the orientation of the listener and rendered for headphones by a single
+// binaural decoder.
+//
+// `cargo run --release --example ambisonics`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example ambisonics`
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let order = 3;
+
+    // the rotator follows the orientation of the listener
+    let rotator = context.create_ambisonic_rotator(order);
+    let decoder = context.create_ambisonic_binaural_decoder(order);
+    rotator.connect(&decoder);
+    decoder.connect(&context.destination());
+
+    // sources on a circle around the listener
+    let files = [
+        "samples/siren.mp3",
+        "samples/think-mono-48000.wav",
+        "samples/major-scale.ogg",
+        "samples/vocals-dry.wav",
+    ];
+
+    for (i, path) in files.iter().enumerate() {
+        let file = std::fs::File::open(path).unwrap();
+        let buffer = context.decode_audio_data_sync(file).unwrap();
+
+        let angle = i as f32 * std::f32::consts::FRAC_PI_2;
+        let encoder = context.create_ambisonic_encoder(order);
+        encoder.set_position(2. * angle.sin(), 0., -2. * angle.cos());
+        encoder.connect(&rotator);
+
+        let gain = context.create_gain();
+        gain.gain().set_value(0.5);
+        gain.connect(&encoder);
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.set_loop(true);
+        src.connect(&gain);
+        src.start();
+    }
+
+    // enjoy listening
+    println!("Four sources are placed around the listener");
+    println!("Press <Enter> to turn the head of the listener by 45 degrees");
+
+    let mut heading = 0.;
+    std::io::stdin().lock().lines().for_each(|_| {
+        heading += std::f32::consts::FRAC_PI_4;
+        let listener = context.listener();
+        listener.forward_x().set_value(heading.sin());
+        listener.forward_z().set_value(-heading.cos());
+        println!("Listener heading: {:.0} degrees", heading.to_degrees());
+    });
+}
@@ -4,7 +4,9 @@ use std::{thread, time};
 use web_audio_api::context::{
     AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
 };
-use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, ConvolverNode, ConvolverOptions};
+use web_audio_api::node::{
+    AudioNode, AudioScheduledSourceNode, ConvolverNode, ConvolverOptions, ConvolverPartitioning,
+};
 use web_audio_api::AudioRenderCapacityOptions;
 
 // ConvolverNode example
@@ -65,6 +67,13 @@ fn main() {
     thread::sleep(time::Duration::from_millis(4_000));
 
     println!("Parking garage");
+    convolver.set_buffer(impulse_buffer2.clone());
+    thread::sleep(time::Duration::from_millis(5_000));
+
+    println!("Parking garage - non-uniform partitioning");
+    convolver.set_partitioning(ConvolverPartitioning::NonUniform {
+        background_thread: true,
+    });
     convolver.set_buffer(impulse_buffer2);
     thread::sleep(time::Duration::from_millis(5_000));
 
@@ -0,0 +1,58 @@
+use std::fs::File;
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+
+// PitchDetectorNode and OnsetDetectorNode example
+//
+// Prints the detected onsets and fundamental frequency of a sample
+//
+// `cargo run --release --example detectors`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example detectors`
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let file = File::open("samples/sample.wav").unwrap();
+    let buffer = context.decode_audio_data_sync(file).unwrap();
+
+    let onset_detector = context.create_onset_detector();
+    onset_detector.set_ononset(|event| {
+        println!(
+            "> onset at {:.3}s (strength {:.1})",
+            event.time, event.strength
+        );
+    });
+
+    let pitch_detector = context.create_pitch_detector();
+    pitch_detector.set_onpitch(|event| {
+        if event.confidence > 0.9 {
+            println!("  {:7.1} Hz at {:.3}s", event.frequency, event.time);
+        }
+    });
+
+    onset_detector.connect(&pitch_detector);
+    pitch_detector.connect(&context.destination());
+
+    let mut src = context.create_buffer_source();
+    src.set_buffer(buffer);
+    src.connect(&onset_detector);
+    src.start();
+
+    std::thread::sleep(std::time::Duration::from_secs(8));
+}
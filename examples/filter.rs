@@ -0,0 +1,86 @@
+use std::fs::File;
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{
+    AudioNode, AudioScheduledSourceNode, FilterDesign, FilterNode, FilterOptions, FilterSlope,
+    FilterType,
+};
+
+// FilterNode example
+//
+// A 4th order Linkwitz-Riley crossover splits the signal in two bands, the low band
+// is sent to the left channel and the high band to the right channel.
+//
+// `cargo run --release --example filter`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example filter`
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let file = File::open("samples/think-mono-48000.wav").unwrap();
+    let buffer = context.decode_audio_data_sync(file).unwrap();
+
+    let merger = context.create_channel_merger(2);
+    merger.connect(&context.destination());
+
+    let options = FilterOptions {
+        frequency: 800.,
+        design: FilterDesign::LinkwitzRiley,
+        slope: FilterSlope::Db24,
+        ..FilterOptions::default()
+    };
+
+    let lowpass = FilterNode::new(&context, options.clone());
+    lowpass.connect_from_output_to_input(&merger, 0, 0);
+
+    let highpass = FilterNode::new(
+        &context,
+        FilterOptions {
+            type_: FilterType::Highpass,
+            ..options
+        },
+    );
+    highpass.connect_from_output_to_input(&merger, 0, 1);
+
+    let mut src = context.create_buffer_source();
+    src.connect(&lowpass);
+    src.connect(&highpass);
+    src.set_buffer(buffer);
+    src.set_loop(true);
+    src.start();
+
+    let frequency_hz = [100., 200., 400., 800., 1600., 3200., 6400.];
+    let mut low_mags = [0.; 7];
+    let mut high_mags = [0.; 7];
+    let mut phases = [0.; 7];
+
+    lowpass.get_frequency_response(&frequency_hz, &mut low_mags, &mut phases);
+    highpass.get_frequency_response(&frequency_hz, &mut high_mags, &mut phases);
+
+    println!("> crossover frequency: {} Hz", lowpass.frequency().value());
+    for ((f, low), high) in frequency_hz.iter().zip(low_mags).zip(high_mags) {
+        println!(
+            "{f:>6} Hz -- low: {:>7.2} dB -- high: {:>7.2} dB",
+            20. * low.log10(),
+            20. * high.log10()
+        );
+    }
+
+    // enjoy listening
+    std::thread::sleep(std::time::Duration::from_secs(10));
+}
@@ -0,0 +1,65 @@
+use std::fs::File;
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+    OfflineAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+
+// LoudnessMeterNode example
+//
+// Measures the loudness of a file offline, then prints the live loudness while
+// playing it back
+//
+// `cargo run --release --example loudness`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example loudness`
+fn main() {
+    env_logger::init();
+
+    // offline measurement of the whole file
+    let offline = OfflineAudioContext::new(2, 1, 44_100.);
+    let file = File::open("samples/sample.wav").unwrap();
+    let buffer = offline.decode_audio_data_sync(file).unwrap();
+
+    let loudness = buffer.loudness();
+    println!(
+        "File - I: {:.1} LUFS - LRA: {:.1} LU - TP: {:.1} dBTP",
+        loudness.integrated, loudness.loudness_range, loudness.true_peak
+    );
+
+    // live measurement
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let meter = context.create_loudness_meter();
+    meter.connect(&context.destination());
+
+    let mut src = context.create_buffer_source();
+    src.connect(&meter);
+    src.set_buffer(buffer);
+    src.set_loop(true);
+    src.start();
+
+    loop {
+        println!(
+            "M: {:>6.1} LUFS - S: {:>6.1} LUFS - I: {:>6.1} LUFS - LRA: {:>4.1} LU - TP: {:>5.1} dBTP",
+            meter.momentary(),
+            meter.short_term(),
+            meter.integrated(),
+            meter.loudness_range(),
+            meter.true_peak(),
+        );
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,65 @@
+use std::fs::File;
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+
+// MeterNode example
+//
+// Prints a simple VU meter of the left and right channels
+//
+// `cargo run --release --example meter`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example meter`
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let file = File::open("samples/sample.wav").unwrap();
+    let buffer = context.decode_audio_data_sync(file).unwrap();
+
+    let meter = context.create_meter();
+    meter.connect(&context.destination());
+
+    let mut src = context.create_buffer_source();
+    src.connect(&meter);
+    src.set_buffer(buffer);
+    src.set_loop(true);
+    src.start();
+
+    let bar = |level: f32| {
+        // -60dB to 0dB scale
+        let db = 20. * level.max(1e-6).log10();
+        let len = ((db + 60.) / 60. * 40.).clamp(0., 40.) as usize;
+        format!("{:<40}", "#".repeat(len))
+    };
+
+    loop {
+        println!(
+            "L |{}| {:>6.1} dB (hold {:>6.1} dB)",
+            bar(meter.rms(0)),
+            20. * meter.peak(0).log10(),
+            20. * meter.peak_hold(0).log10(),
+        );
+        println!(
+            "R |{}| {:>6.1} dB (hold {:>6.1} dB)",
+            bar(meter.rms(1)),
+            20. * meter.peak(1).log10(),
+            20. * meter.peak_hold(1).log10(),
+        );
+        std::thread::sleep(std::time::Duration::from_millis(100));
+    }
+}
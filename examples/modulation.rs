@@ -0,0 +1,62 @@
+use std::fs::File;
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+
+// ChorusNode, FlangerNode and PhaserNode example
+//
+// `cargo run --release --example modulation`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example modulation`
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let file = File::open("samples/vocals-dry.wav").unwrap();
+    let buffer = context.decode_audio_data_sync(file).unwrap();
+
+    let chorus = context.create_chorus();
+    let flanger = context.create_flanger();
+    let phaser = context.create_phaser();
+
+    let mut src = context.create_buffer_source();
+    src.set_buffer(buffer);
+    src.set_loop(true);
+    src.start();
+
+    println!("> chorus");
+    src.connect(&chorus);
+    chorus.connect(&context.destination());
+    std::thread::sleep(std::time::Duration::from_secs(6));
+
+    println!("> flanger");
+    src.disconnect();
+    src.connect(&flanger);
+    flanger.connect(&context.destination());
+    flanger.feedback().set_value(0.8);
+    std::thread::sleep(std::time::Duration::from_secs(6));
+
+    println!("> flanger - negative feedback");
+    flanger.feedback().set_value(-0.8);
+    std::thread::sleep(std::time::Duration::from_secs(6));
+
+    println!("> phaser");
+    src.disconnect();
+    src.connect(&phaser);
+    phaser.connect(&context.destination());
+    std::thread::sleep(std::time::Duration::from_secs(6));
+}
@@ -0,0 +1,60 @@
+use std::fs::File;
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+
+// PitchShifterNode example
+//
+// `cargo run --release --example pitch_shifter`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example pitch_shifter`
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let file = File::open("samples/vocals-dry.wav").unwrap();
+    let buffer = context.decode_audio_data_sync(file).unwrap();
+
+    let mut pitch_shifter = context.create_pitch_shifter();
+    pitch_shifter.connect(&context.destination());
+    println!("> latency: {:.3} sec", pitch_shifter.latency());
+
+    let mut src = context.create_buffer_source();
+    src.connect(&pitch_shifter);
+    src.set_buffer(buffer);
+    src.set_loop(true);
+    src.start();
+
+    println!("> original pitch");
+    std::thread::sleep(std::time::Duration::from_secs(4));
+
+    println!("> 4 semitones up");
+    pitch_shifter.pitch().set_value(400.);
+    std::thread::sleep(std::time::Duration::from_secs(4));
+
+    println!("> 4 semitones up - preserve formants");
+    pitch_shifter.set_preserve_formants(true);
+    std::thread::sleep(std::time::Duration::from_secs(4));
+
+    println!("> 5 semitones down - preserve formants");
+    pitch_shifter.pitch().set_value(-500.);
+    std::thread::sleep(std::time::Duration::from_secs(4));
+
+    println!("> 5 semitones down");
+    pitch_shifter.set_preserve_formants(false);
+    std::thread::sleep(std::time::Duration::from_secs(4));
+}
@@ -0,0 +1,63 @@
+use std::fs::File;
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+
+// ReverbNode example
+//
+// `cargo run --release --example reverb`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example reverb`
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let file = File::open("samples/vocals-dry.wav").unwrap();
+    let buffer = context.decode_audio_data_sync(file).unwrap();
+
+    let reverb = context.create_reverb();
+    reverb.connect(&context.destination());
+
+    let mut src = context.create_buffer_source();
+    src.connect(&reverb);
+    src.set_buffer(buffer);
+    src.set_loop(true);
+    src.start();
+
+    println!("> dry");
+    reverb.mix().set_value(0.);
+    std::thread::sleep(std::time::Duration::from_secs(4));
+
+    println!("> small room");
+    reverb.mix().set_value(0.3);
+    reverb.size().set_value(0.1);
+    reverb.decay_time().set_value(0.8);
+    reverb.damping().set_value(0.7);
+    std::thread::sleep(std::time::Duration::from_secs(4));
+
+    println!("> large hall");
+    reverb.mix().set_value(0.4);
+    reverb.size().set_value(0.9);
+    reverb.decay_time().set_value(4.);
+    reverb.pre_delay().set_value(0.03);
+    reverb.damping().set_value(0.3);
+    std::thread::sleep(std::time::Duration::from_secs(6));
+
+    println!("> stop input - flush out remaining reverberation");
+    src.stop();
+    std::thread::sleep(std::time::Duration::from_secs(5));
+}
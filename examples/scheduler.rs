@@ -0,0 +1,69 @@
+use std::sync::Arc;
+
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+
+// Play an arpeggio with callbacks scheduled ahead of the context time, each
+// callback scheduling the next note
+//
+// `cargo run --release --example scheduler`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example scheduler`
+
+const NOTES: [f32; 4] = [440., 554.37, 659.25, 880.];
+const NOTE_DURATION: f64 = 0.125;
+const NUM_NOTES: usize = 32;
+
+fn play_note(context: Arc<AudioContext>, when: f64, index: usize) {
+    let env = context.create_gain();
+    env.gain().set_value(0.);
+    env.connect(&context.destination());
+
+    let mut osc = context.create_oscillator();
+    osc.frequency().set_value(NOTES[index % NOTES.len()]);
+    osc.connect(&env);
+    osc.start_at(when);
+    osc.stop_at(when + NOTE_DURATION);
+
+    env.gain()
+        .set_value_at_time(0.3, when)
+        .exponential_ramp_to_value_at_time(0.0001, when + NOTE_DURATION);
+
+    if index + 1 < NUM_NOTES {
+        let next = when + NOTE_DURATION;
+        let context_clone = Arc::clone(&context);
+        context.schedule_callback(next, move |when| {
+            play_note(context_clone, when, index + 1);
+        });
+    }
+}
+
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = Arc::new(AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    }));
+
+    // run the callbacks 50ms ahead of the notes
+    context.set_schedule_lookahead(0.05);
+
+    let start = context.current_time() + 0.1;
+    let context_clone = Arc::clone(&context);
+    context.schedule_callback(start, move |when| play_note(context_clone, when, 0));
+
+    let duration = NUM_NOTES as f64 * NOTE_DURATION + 0.5;
+    std::thread::sleep(std::time::Duration::from_secs_f64(duration));
+}
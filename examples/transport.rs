@@ -0,0 +1,62 @@
+use web_audio_api::context::{
+    AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
+};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+use web_audio_api::MusicalTime;
+
+// Metronome scheduled in musical time with a Transport, accelerating from 90 to
+// 180 bpm over four bars
+//
+// `cargo run --release --example transport`
+//
+// If you are on Linux and use ALSA as audio backend backend, you might want to run
+// the example with the `WEB_AUDIO_LATENCY=playback ` env variable which will
+// increase the buffer size to 1024
+//
+// `WEB_AUDIO_LATENCY=playback cargo run --release --example transport`
+fn main() {
+    env_logger::init();
+
+    let latency_hint = match std::env::var("WEB_AUDIO_LATENCY").as_deref() {
+        Ok("playback") => AudioContextLatencyCategory::Playback,
+        _ => AudioContextLatencyCategory::default(),
+    };
+
+    let context = AudioContext::new(AudioContextOptions {
+        latency_hint,
+        ..AudioContextOptions::default()
+    });
+
+    let mut transport = context.create_transport();
+    transport.set_tempo(90.);
+    transport.linear_ramp_to_tempo_at_position(180., MusicalTime::new(4, 0, 0));
+    // leave some time to schedule the first clicks
+    transport.start_at(context.current_time() + 0.1);
+
+    for bar in 0..6 {
+        for beat in 0..4 {
+            let position = MusicalTime::new(bar, beat, 0);
+            let frequency = if beat == 0 { 1760. } else { 880. };
+
+            let env = context.create_gain();
+            env.gain().set_value(0.);
+            env.connect(&context.destination());
+
+            let mut osc = context.create_oscillator();
+            osc.frequency().set_value(frequency);
+            osc.connect(&env);
+
+            let time = transport
+                .start_source_at_position(&mut osc, position)
+                .unwrap();
+            osc.stop_at(time + 0.1);
+
+            env.gain()
+                .set_value_at_time(0.5, time)
+                .exponential_ramp_to_value_at_time(0.0001, time + 0.1);
+        }
+    }
+
+    let end = transport.position_to_seconds(MusicalTime::new(6, 0, 0));
+    std::thread::sleep(std::time::Duration::from_secs_f64(end + 0.5));
+}
@@ -3,7 +3,7 @@ use std::fs::File;
 use web_audio_api::context::{
     AudioContext, AudioContextLatencyCategory, AudioContextOptions, BaseAudioContext,
 };
-use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, OverSampleType};
+use web_audio_api::node::{AudioNode, AudioScheduledSourceNode, OverSampleQuality, OverSampleType};
 
 // WaveshaperNode example
 //
@@ -56,8 +56,17 @@ fn main() {
     shaper.set_oversample(OverSampleType::None);
     // shaper.set_oversample(OverSampleType::X2);
     // shaper.set_oversample(OverSampleType::X4);
+    // heavy distortion needs higher oversampling factors to avoid audible aliasing
+    // shaper.set_oversample(OverSampleType::X16);
+    // steeper anti-aliasing filters, at the cost of a larger latency
+    // shaper.set_oversample_quality(OverSampleQuality::High);
+    shaper.set_oversample_quality(OverSampleQuality::Medium);
     shaper.connect(&post_gain);
     shaper.set_curve(curve);
+    println!(
+        "> latency of the oversampling filters: {:.2}ms",
+        shaper.latency() * 1000.
+    );
 
     let pre_gain = context.create_gain();
     pre_gain.connect(&shaper);
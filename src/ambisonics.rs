@@ -0,0 +1,579 @@
+//! Ambisonics primitives
+//!
+//! Shared by the ambisonic nodes. Sound fields use the AmbiX convention: ACN channel ordering
+//! and SN3D normalization. Directions are expressed in the ambisonic coordinate system: x to the
+//! front, y to the left and z up.
+
+use std::f64::consts::PI;
+
+use crate::node::{AudioNodeOptions, ChannelCountMode, ChannelInterpretation};
+
+/// Highest supported ambisonic order, its number of channels fits in `MAX_CHANNELS`
+pub(crate) const MAX_AMBISONIC_ORDER: usize = 4;
+
+/// Number of channels of a sound field of the given order
+pub(crate) fn number_of_channels(order: usize) -> usize {
+    (order + 1) * (order + 1)
+}
+
+/// Order of the given ACN channel
+pub(crate) fn channel_order(acn: usize) -> usize {
+    (acn as f64).sqrt().floor() as usize
+}
+
+/// Assert that the given ambisonic order is supported
+///
+/// # Panics
+///
+/// This function panics if the order is zero or greater than `MAX_AMBISONIC_ORDER`
+#[track_caller]
+#[inline(always)]
+pub(crate) fn assert_valid_order(order: usize) {
+    assert!(
+        (1..=MAX_AMBISONIC_ORDER).contains(&order),
+        "NotSupportedError - ambisonic order must be in the range [1, {MAX_AMBISONIC_ORDER}]"
+    );
+}
+
+/// Assert that the channel count is valid for an ambisonic node
+///
+/// # Panics
+///
+/// This function panics if given count is not the number of channels of the sound field
+///
+#[track_caller]
+#[inline(always)]
+pub(crate) fn assert_valid_ambisonic_channel_count(count: usize, order: usize) {
+    assert_eq!(
+        count,
+        number_of_channels(order),
+        "NotSupportedError - channel count of an ambisonic node must be (order + 1)²"
+    );
+}
+
+/// Assert that the channel count mode is valid for an ambisonic node
+///
+/// # Panics
+///
+/// This function panics if given count mode is not [`ChannelCountMode::Explicit`]
+///
+#[track_caller]
+#[inline(always)]
+pub(crate) fn assert_valid_ambisonic_channel_count_mode(mode: ChannelCountMode) {
+    assert_eq!(
+        mode,
+        ChannelCountMode::Explicit,
+        "NotSupportedError - channel count mode of an ambisonic node must be explicit"
+    );
+}
+
+/// Channel configuration of the nodes processing an ambisonic sound field
+pub(crate) fn ambisonic_audio_node_options(order: usize) -> AudioNodeOptions {
+    AudioNodeOptions {
+        channel_count: number_of_channels(order),
+        channel_count_mode: ChannelCountMode::Explicit,
+        channel_interpretation: ChannelInterpretation::Discrete,
+    }
+}
+
+/// Convert a vector from the Web Audio coordinate system (x to the right, y up, front towards
+/// negative z) to the ambisonic coordinate system
+pub(crate) fn to_ambisonic_frame([x, y, z]: [f32; 3]) -> [f32; 3] {
+    [-z, -x, y]
+}
+
+/// Direction (in the ambisonic coordinate system) of the given azimuth and elevation in degrees,
+/// the azimuth is counter-clockwise from the front
+pub(crate) fn direction(azimuth: f32, elevation: f32) -> [f32; 3] {
+    let azimuth = azimuth.to_radians();
+    let elevation = elevation.to_radians();
+
+    [
+        azimuth.cos() * elevation.cos(),
+        azimuth.sin() * elevation.cos(),
+        elevation.sin(),
+    ]
+}
+
+/// Real spherical harmonics (ACN/SN3D) of the given direction, up to the given order
+///
+/// The direction does not need to be normalized, a null vector is treated as the front.
+pub(crate) fn spherical_harmonics(order: usize, direction: [f32; 3], output: &mut [f32]) {
+    let [x, y, z] = direction.map(f64::from);
+    let norm = (x * x + y * y + z * z).sqrt();
+
+    let (azimuth, sin_elevation) = if norm < 1e-12 {
+        (0., 0.)
+    } else {
+        (y.atan2(x), (z / norm).clamp(-1., 1.))
+    };
+
+    // associated Legendre functions, without the Condon-Shortley phase
+    let mut legendre = [[0.; MAX_AMBISONIC_ORDER + 1]; MAX_AMBISONIC_ORDER + 1];
+    let cos_elevation = (1. - sin_elevation * sin_elevation).sqrt();
+
+    for m in 0..=order {
+        // P_m^m = (2m - 1)!! cos^m
+        legendre[m][m] = (1..=m).fold(1., |acc, k| acc * (2 * k - 1) as f64 * cos_elevation);
+
+        if m < order {
+            legendre[m + 1][m] = sin_elevation * (2 * m + 1) as f64 * legendre[m][m];
+        }
+
+        for n in m + 2..=order {
+            legendre[n][m] = ((2 * n - 1) as f64 * sin_elevation * legendre[n - 1][m]
+                - (n + m - 1) as f64 * legendre[n - 2][m])
+                / (n - m) as f64;
+        }
+    }
+
+    for (n, legendre) in legendre.iter().enumerate().take(order + 1) {
+        for m in -(n as isize)..=n as isize {
+            let abs_m = m.unsigned_abs();
+
+            // SN3D normalization: sqrt((2 - delta_m) (n - |m|)! / (n + |m|)!)
+            let ratio = (n - abs_m + 1..=n + abs_m).fold(1., |acc, k| acc / k as f64);
+            let delta = if m == 0 { 1. } else { 2. };
+            let normalization = (delta * ratio).sqrt();
+
+            let trigonometric = if m >= 0 {
+                (m as f64 * azimuth).cos()
+            } else {
+                (abs_m as f64 * azimuth).sin()
+            };
+
+            let acn = n * n + (n as isize + m) as usize;
+            output[acn] = (normalization * legendre[abs_m] * trigonometric) as f32;
+        }
+    }
+}
+
+/// Per order weights of the max-rE decoding, which improve the localization of decoded sources
+///
+/// Computed for 3D (`horizontal == false`) or horizontal only layouts.
+pub(crate) fn max_re_weights(order: usize, horizontal: bool) -> Vec<f32> {
+    if horizontal {
+        (0..=order)
+            .map(|n| (n as f64 * PI / (2 * order + 2) as f64).cos() as f32)
+            .collect()
+    } else {
+        let x = (137.9f64.to_radians() / (order as f64 + 1.51)).cos();
+
+        // Legendre polynomials P_n(x)
+        let mut weights = vec![1., x];
+        for n in 2..=order {
+            let p = ((2 * n - 1) as f64 * x * weights[n - 1] - (n - 1) as f64 * weights[n - 2])
+                / n as f64;
+            weights.push(p);
+        }
+
+        weights.truncate(order + 1);
+        weights.into_iter().map(|w| w as f32).collect()
+    }
+}
+
+/// Decoding matrix (one row of `(order + 1)²` gains per speaker) of a sound field to the given
+/// speaker directions, using max-rE weighting
+///
+/// Layouts with all speakers in the horizontal plane only use the horizontal (sectoral)
+/// components of the sound field, with an order limited by the number of speakers.
+pub(crate) fn decoding_matrix(order: usize, speakers: &[[f32; 3]]) -> Vec<Vec<f32>> {
+    let count = speakers.len() as f32;
+    let channels = number_of_channels(order);
+    let horizontal = speakers.iter().all(|[_, _, z]| z.abs() < 1e-6);
+    let mut harmonics = [0.; (MAX_AMBISONIC_ORDER + 1) * (MAX_AMBISONIC_ORDER + 1)];
+
+    if horizontal {
+        let order = order.min((speakers.len().saturating_sub(1) / 2).max(1));
+        let weights = max_re_weights(order, true);
+
+        // gain of the sectoral components in the horizontal plane, to undo the SN3D normalization
+        spherical_harmonics(order, [1., 0., 0.], &mut harmonics);
+        let sectoral_gain: Vec<f32> = (0..=order).map(|n| harmonics[n * n + 2 * n]).collect();
+
+        speakers
+            .iter()
+            .map(|&speaker| {
+                spherical_harmonics(order, speaker, &mut harmonics);
+
+                (0..channels)
+                    .map(|acn| {
+                        let n = channel_order(acn);
+                        let m = acn as isize - (n * n + n) as isize;
+
+                        if acn == 0 {
+                            1. / count
+                        } else if n <= order && m.unsigned_abs() == n {
+                            2. * weights[n] * harmonics[acn] / sectoral_gain[n].powi(2) / count
+                        } else {
+                            0.
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    } else {
+        let weights = max_re_weights(order, false);
+
+        speakers
+            .iter()
+            .map(|&speaker| {
+                spherical_harmonics(order, speaker, &mut harmonics);
+
+                (0..channels)
+                    .map(|acn| {
+                        let n = channel_order(acn);
+                        (2 * n + 1) as f32 * weights[n] * harmonics[acn] / count
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+}
+
+/// Nearly uniform distribution of points on the sphere
+pub(crate) fn fibonacci_sphere(count: usize) -> Vec<[f32; 3]> {
+    let golden_angle = PI * (3. - 5f64.sqrt());
+
+    (0..count)
+        .map(|i| {
+            let z = 1. - (2 * i + 1) as f64 / count as f64;
+            let radius = (1. - z * z).sqrt();
+            let theta = golden_angle * i as f64;
+            [
+                (radius * theta.cos()) as f32,
+                (radius * theta.sin()) as f32,
+                z as f32,
+            ]
+        })
+        .collect()
+}
+
+/// Rotation matrix (3x3, row major) of the given yaw, pitch and roll in degrees
+///
+/// Yaw rotates around the z axis (positive to the left), pitch around the y axis (positive
+/// downwards) and roll around the x axis (positive to the right), applied in that order.
+pub(crate) fn yaw_pitch_roll_matrix(yaw: f32, pitch: f32, roll: f32) -> [[f32; 3]; 3] {
+    let (sy, cy) = yaw.to_radians().sin_cos();
+    let (sp, cp) = pitch.to_radians().sin_cos();
+    let (sr, cr) = roll.to_radians().sin_cos();
+
+    // Rz(yaw) * Ry(pitch) * Rx(roll)
+    [
+        [cy * cp, cy * sp * sr - sy * cr, cy * sp * cr + sy * sr],
+        [sy * cp, sy * sp * sr + cy * cr, sy * sp * cr - cy * sr],
+        [-sp, cp * sr, cp * cr],
+    ]
+}
+
+/// Product of two 3x3 matrices
+pub(crate) fn matrix_product(a: [[f32; 3]; 3], b: [[f32; 3]; 3]) -> [[f32; 3]; 3] {
+    let mut out = [[0.; 3]; 3];
+
+    for (i, row) in out.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| a[i][k] * b[k][j]).sum();
+        }
+    }
+
+    out
+}
+
+/// Invert a square matrix (row major) with Gauss-Jordan elimination
+fn invert(mut matrix: Vec<f64>, size: usize) -> Vec<f64> {
+    let mut inverse = vec![0.; size * size];
+    (0..size).for_each(|i| inverse[i * size + i] = 1.);
+
+    for column in 0..size {
+        // partial pivoting
+        let pivot = (column..size)
+            .max_by(|&a, &b| {
+                let va = matrix[a * size + column].abs();
+                let vb = matrix[b * size + column].abs();
+                va.total_cmp(&vb)
+            })
+            .unwrap();
+
+        for k in 0..size {
+            matrix.swap(column * size + k, pivot * size + k);
+            inverse.swap(column * size + k, pivot * size + k);
+        }
+
+        let scale = 1. / matrix[column * size + column];
+        for k in 0..size {
+            matrix[column * size + k] *= scale;
+            inverse[column * size + k] *= scale;
+        }
+
+        for row in 0..size {
+            if row == column {
+                continue;
+            }
+
+            let factor = matrix[row * size + column];
+            for k in 0..size {
+                matrix[row * size + k] -= factor * matrix[column * size + k];
+                inverse[row * size + k] -= factor * inverse[column * size + k];
+            }
+        }
+    }
+
+    inverse
+}
+
+/// Rotation of a sound field
+///
+/// The rotation of the spherical harmonics of order `n` is a linear combination of the
+/// spherical harmonics of the same order, so the rotation matrix is block diagonal. Each block
+/// is found by sampling the spherical harmonics on a set of directions `D` and their rotation
+/// `R D`: `M = Y(R D) pinv(Y(D))`, with the pseudo inverse precomputed on creation.
+#[derive(Debug)]
+pub(crate) struct SoundFieldRotation {
+    order: usize,
+    directions: Vec<[f32; 3]>,
+    /// Per order, `pinv(Y(D))` as a (directions x (2n + 1)) matrix
+    pseudo_inverses: Vec<Vec<f64>>,
+    /// Per order, current rotation block as a (2n + 1) x (2n + 1) matrix
+    blocks: Vec<Vec<f32>>,
+    /// Scratch buffer for the spherical harmonics of the rotated directions
+    harmonics: Vec<f32>,
+}
+
+impl SoundFieldRotation {
+    pub fn new(order: usize) -> Self {
+        let channels = number_of_channels(order);
+        let directions = fibonacci_sphere(4 * channels);
+
+        let mut sampled = vec![0.; channels * directions.len()];
+        let mut harmonics = vec![0.; channels];
+
+        directions.iter().enumerate().for_each(|(k, &d)| {
+            spherical_harmonics(order, d, &mut harmonics);
+            harmonics
+                .iter()
+                .enumerate()
+                .for_each(|(i, &h)| sampled[i * directions.len() + k] = h as f64);
+        });
+
+        let pseudo_inverses = (0..=order)
+            .map(|n| {
+                let size = 2 * n + 1;
+                let rows = &sampled[n * n * directions.len()..(n + 1) * (n + 1) * directions.len()];
+
+                // pinv(Y) = Y^T (Y Y^T)^-1
+                let mut gram = vec![0.; size * size];
+                for i in 0..size {
+                    for j in 0..size {
+                        gram[i * size + j] = (0..directions.len())
+                            .map(|k| {
+                                rows[i * directions.len() + k] * rows[j * directions.len() + k]
+                            })
+                            .sum();
+                    }
+                }
+
+                let gram_inverse = invert(gram, size);
+
+                let mut pseudo_inverse = vec![0.; directions.len() * size];
+                for k in 0..directions.len() {
+                    for j in 0..size {
+                        pseudo_inverse[k * size + j] = (0..size)
+                            .map(|i| rows[i * directions.len() + k] * gram_inverse[i * size + j])
+                            .sum();
+                    }
+                }
+
+                pseudo_inverse
+            })
+            .collect();
+
+        let blocks = (0..=order)
+            .map(|n| {
+                let size = 2 * n + 1;
+                let mut identity = vec![0.; size * size];
+                (0..size).for_each(|i| identity[i * size + i] = 1.);
+                identity
+            })
+            .collect();
+
+        Self {
+            order,
+            pseudo_inverses,
+            blocks,
+            harmonics: vec![0.; channels * directions.len()],
+            directions,
+        }
+    }
+
+    /// Update the rotation, given as a 3x3 row major matrix in the ambisonic coordinate system
+    pub fn set_rotation(&mut self, rotation: [[f32; 3]; 3]) {
+        let count = self.directions.len();
+        let channels = number_of_channels(self.order);
+
+        for (k, d) in self.directions.iter().enumerate() {
+            let rotated = rotation.map(|row| row[0] * d[0] + row[1] * d[1] + row[2] * d[2]);
+            spherical_harmonics(
+                self.order,
+                rotated,
+                &mut self.harmonics[k * channels..(k + 1) * channels],
+            );
+        }
+
+        for (n, (block, pseudo_inverse)) in self
+            .blocks
+            .iter_mut()
+            .zip(self.pseudo_inverses.iter())
+            .enumerate()
+        {
+            let size = 2 * n + 1;
+
+            for i in 0..size {
+                for j in 0..size {
+                    let value: f64 = (0..count)
+                        .map(|k| {
+                            self.harmonics[k * channels + n * n + i] as f64
+                                * pseudo_inverse[k * size + j]
+                        })
+                        .sum();
+                    block[i * size + j] = value as f32;
+                }
+            }
+        }
+    }
+
+    /// Rotate a sample frame of the sound field
+    pub fn apply(&self, input: &[f32], output: &mut [f32]) {
+        for (n, block) in self.blocks.iter().enumerate() {
+            let size = 2 * n + 1;
+            let offset = n * n;
+
+            for i in 0..size {
+                output[offset + i] = (0..size)
+                    .map(|j| block[i * size + j] * input[offset + j])
+                    .sum();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_first_order_harmonics() {
+        let mut output = [0.; 4];
+
+        // front
+        spherical_harmonics(1, [1., 0., 0.], &mut output);
+        assert_float_eq!(output, [1., 0., 0., 1.], abs_all <= 1e-6);
+
+        // left
+        spherical_harmonics(1, [0., 1., 0.], &mut output);
+        assert_float_eq!(output, [1., 1., 0., 0.], abs_all <= 1e-6);
+
+        // up, not normalized
+        spherical_harmonics(1, [0., 0., 2.], &mut output);
+        assert_float_eq!(output, [1., 0., 1., 0.], abs_all <= 1e-6);
+
+        // null vector is the front
+        spherical_harmonics(1, [0., 0., 0.], &mut output);
+        assert_float_eq!(output, [1., 0., 0., 1.], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_addition_theorem() {
+        // with SN3D, the sum over m of Y_n^m(a) Y_n^m(b) is the Legendre polynomial P_n(a.b)
+        let a = direction(30., 20.);
+        let b = direction(-75., -10.);
+        let cos_gamma: f32 = (0..3).map(|i| a[i] * b[i]).sum();
+
+        let mut ya = [0.; 25];
+        let mut yb = [0.; 25];
+        spherical_harmonics(4, a, &mut ya);
+        spherical_harmonics(4, b, &mut yb);
+
+        let x = cos_gamma;
+        let legendre = [
+            1.,
+            x,
+            0.5 * (3. * x * x - 1.),
+            0.5 * (5. * x * x * x - 3. * x),
+            0.125 * (35. * x.powi(4) - 30. * x * x + 3.),
+        ];
+
+        for (n, expected) in legendre.into_iter().enumerate() {
+            let sum: f32 = (n * n..(n + 1) * (n + 1)).map(|i| ya[i] * yb[i]).sum();
+            assert_float_eq!(sum, expected, abs <= 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_rotation() {
+        let order = 3;
+        let channels = number_of_channels(order);
+        let mut rotation = SoundFieldRotation::new(order);
+
+        let matrix = yaw_pitch_roll_matrix(40., -25., 10.);
+        rotation.set_rotation(matrix);
+
+        // rotating the encoded field is the same as encoding the rotated direction
+        let source = direction(70., 15.);
+        let rotated =
+            matrix.map(|row| row[0] * source[0] + row[1] * source[1] + row[2] * source[2]);
+
+        let mut encoded = vec![0.; channels];
+        spherical_harmonics(order, source, &mut encoded);
+        let mut expected = vec![0.; channels];
+        spherical_harmonics(order, rotated, &mut expected);
+
+        let mut output = vec![0.; channels];
+        rotation.apply(&encoded, &mut output);
+
+        assert_float_eq!(output[..], expected[..], abs_all <= 1e-4);
+    }
+
+    #[test]
+    fn test_yaw() {
+        // positive yaw moves the front to the left
+        let matrix = yaw_pitch_roll_matrix(90., 0., 0.);
+        let front = [1., 0., 0.];
+        let rotated = matrix.map(|row| row[0] * front[0] + row[1] * front[1] + row[2] * front[2]);
+        assert_float_eq!(rotated, [0., 1., 0.], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_decoding_matrix() {
+        let order = 3;
+        let source = direction(40., 0.);
+        let mut encoded = [0.; 16];
+        spherical_harmonics(order, source, &mut encoded);
+
+        let decode = |speakers: &[[f32; 3]]| -> Vec<f32> {
+            decoding_matrix(order, speakers)
+                .iter()
+                .map(|row| row.iter().zip(encoded).map(|(d, e)| d * e).sum())
+                .collect()
+        };
+
+        // regular horizontal layout, the loudest speaker is the closest to the source
+        let octagon: Vec<_> = (0..8).map(|i| direction(i as f32 * 45., 0.)).collect();
+        let gains = decode(&octagon);
+        assert_float_eq!(gains.iter().sum::<f32>(), 1., abs <= 1e-5);
+        let loudest = (0..8).max_by(|&a, &b| gains[a].total_cmp(&gains[b]));
+        assert_eq!(loudest, Some(1));
+
+        // nearly uniform 3D layout
+        let sphere = fibonacci_sphere(50);
+        let gains = decode(&sphere);
+        assert_float_eq!(gains.iter().sum::<f32>(), 1., abs <= 0.05);
+        let closest = (0..50).max_by(|&a, &b| {
+            let dot = |k: usize| (0..3).map(|i| sphere[k][i] * source[i]).sum::<f32>();
+            dot(a).total_cmp(&dot(b))
+        });
+        let loudest = (0..50).max_by(|&a, &b| gains[a].total_cmp(&gains[b]));
+        assert_eq!(loudest, closest);
+    }
+}
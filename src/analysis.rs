@@ -6,8 +6,10 @@ use std::f32::consts::PI;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 
-use realfft::{num_complex::Complex, RealFftPlanner};
+use crossbeam_channel::{Receiver, Sender};
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
 
+use crate::node::AnalyserWindow;
 use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
 
 /// Blackman window values iterator with alpha = 0.16
@@ -23,18 +25,69 @@ fn generate_blackman(size: usize) -> impl Iterator<Item = f32> {
     })
 }
 
+/// Zeroth order modified Bessel function of the first kind, used by the Kaiser window
+fn bessel_i0(x: f64) -> f64 {
+    let mut sum = 1.;
+    let mut term = 1.;
+    let half_x = x / 2.;
+
+    for k in 1..50 {
+        term *= (half_x / k as f64).powi(2);
+        sum += term;
+
+        if term < sum * 1e-12 {
+            break;
+        }
+    }
+
+    sum
+}
+
+/// Fill `dst` with the values of the given window
+fn generate_window(window: AnalyserWindow, size: usize, dst: &mut Vec<f32>) {
+    dst.clear();
+
+    let phase = |i: usize| 2. * PI * i as f32 / size as f32;
+
+    match window {
+        AnalyserWindow::Blackman => dst.extend(generate_blackman(size)),
+        AnalyserWindow::Hann => dst.extend((0..size).map(|i| 0.5 - 0.5 * phase(i).cos())),
+        AnalyserWindow::Hamming => dst.extend((0..size).map(|i| 0.54 - 0.46 * phase(i).cos())),
+        AnalyserWindow::FlatTop => dst.extend((0..size).map(|i| {
+            let p = phase(i);
+            0.215_578_95 - 0.416_631_58 * p.cos() + 0.277_263_16 * (2. * p).cos()
+                - 0.083_578_95 * (3. * p).cos()
+                + 0.006_947_368 * (4. * p).cos()
+        })),
+        AnalyserWindow::Kaiser(beta) => {
+            let beta = beta as f64;
+            let norm = bessel_i0(beta);
+
+            dst.extend((0..size).map(|i| {
+                let x = 2. * i as f64 / size as f64 - 1.;
+                (bessel_i0(beta * (1. - x * x).sqrt()) / norm) as f32
+            }));
+        }
+        AnalyserWindow::Rectangular => dst.resize(size, 1.),
+    }
+}
+
 pub(crate) const DEFAULT_SMOOTHING_TIME_CONSTANT: f64 = 0.8;
 pub(crate) const DEFAULT_MIN_DECIBELS: f64 = -100.;
 pub(crate) const DEFAULT_MAX_DECIBELS: f64 = -30.;
 pub(crate) const DEFAULT_FFT_SIZE: usize = 2048;
+pub(crate) const DEFAULT_ZERO_PADDING: usize = 1;
 
 const MIN_FFT_SIZE: usize = 32;
 const MAX_FFT_SIZE: usize = 32768;
+// non spec extensions
+const MAX_EXTENDED_FFT_SIZE: usize = 131072;
+const MAX_ZERO_PADDING: usize = 16;
 
 // [spec] This MUST be a power of two in the range 32 to 32768, otherwise an
 // IndexSizeError exception MUST be thrown.
 #[allow(clippy::manual_range_contains)]
-fn assert_valid_fft_size(fft_size: usize) {
+fn assert_valid_fft_size(fft_size: usize, max_fft_size: usize) {
     assert!(
         fft_size.is_power_of_two(),
         "IndexSizeError - Invalid fft size: {:?} is not a power of two",
@@ -42,11 +95,20 @@ fn assert_valid_fft_size(fft_size: usize) {
     );
 
     assert!(
-        fft_size >= MIN_FFT_SIZE && fft_size <= MAX_FFT_SIZE,
+        fft_size >= MIN_FFT_SIZE && fft_size <= max_fft_size,
         "IndexSizeError - Invalid fft size: {:?} is outside range [{:?}, {:?}]",
         fft_size,
         MIN_FFT_SIZE,
-        MAX_FFT_SIZE
+        max_fft_size
+    );
+}
+
+fn assert_valid_zero_padding(zero_padding: usize) {
+    assert!(
+        zero_padding.is_power_of_two() && zero_padding <= MAX_ZERO_PADDING,
+        "IndexSizeError - Invalid zero padding factor: {:?} is not a power of two in range [1, {:?}]",
+        zero_padding,
+        MAX_ZERO_PADDING
     );
 }
 
@@ -72,7 +134,8 @@ fn assert_valid_decibels(min_decibels: f64, max_decibels: f64) {
 }
 
 // as the queue is composed of AtomicF32 having only 1 render quantum of extra
-// room should be enough
+// room should be enough, the buffer only grows beyond `MAX_FFT_SIZE` if an
+// extended fft size is requested
 const RING_BUFFER_SIZE: usize = MAX_FFT_SIZE + RENDER_QUANTUM_SIZE;
 
 // single producer / multiple consumer ring buffer
@@ -84,8 +147,12 @@ pub(crate) struct AnalyserRingBuffer {
 
 impl AnalyserRingBuffer {
     pub fn new() -> Self {
-        let mut buffer = Vec::with_capacity(RING_BUFFER_SIZE);
-        buffer.resize_with(RING_BUFFER_SIZE, || AtomicF32::new(0.));
+        Self::with_size(RING_BUFFER_SIZE)
+    }
+
+    fn with_size(size: usize) -> Self {
+        let mut buffer = Vec::with_capacity(size);
+        buffer.resize_with(size, || AtomicF32::new(0.));
 
         Self {
             buffer: buffer.into(),
@@ -93,25 +160,37 @@ impl AnalyserRingBuffer {
         }
     }
 
+    fn size(&self) -> usize {
+        self.buffer.len()
+    }
+
     pub fn write(&self, src: &[f32]) {
+        let size = self.size();
         let mut write_index = self.write_index.load(Ordering::SeqCst);
         let len = src.len();
 
         src.iter().enumerate().for_each(|(index, value)| {
-            let position = (write_index + index) % RING_BUFFER_SIZE;
+            let position = (write_index + index) % size;
             self.buffer[position].store(*value, Ordering::Relaxed);
         });
 
         write_index += len;
 
-        if write_index >= RING_BUFFER_SIZE {
-            write_index -= RING_BUFFER_SIZE;
+        if write_index >= size {
+            write_index -= size;
         }
 
         self.write_index.store(write_index, Ordering::SeqCst);
     }
 
     pub fn read(&self, dst: &mut [f32], max_len: usize) {
+        self.read_before(dst, max_len, 0);
+    }
+
+    // read the frames ending `offset` frames before the most recent one, the offset
+    // should stay within the extra render quantum of room of the buffer
+    pub fn read_before(&self, dst: &mut [f32], max_len: usize, offset: usize) {
+        let size = self.size();
         let write_index = self.write_index.load(Ordering::SeqCst);
         // let fft_size = self.fft_size.load(Ordering::SeqCst);
         let len = dst.len().min(max_len);
@@ -120,8 +199,8 @@ impl AnalyserRingBuffer {
             .take(len)
             .enumerate()
             .for_each(|(index, value)| {
-                // offset calculation by RING_BUFFER_SIZE so we can't negative values
-                let position = (RING_BUFFER_SIZE + write_index - len + index) % RING_BUFFER_SIZE;
+                // offset calculation by the buffer size so we can't negative values
+                let position = (2 * size + write_index - offset - len + index) % size;
                 *value = self.buffer[position].load(Ordering::Relaxed);
             });
     }
@@ -129,7 +208,7 @@ impl AnalyserRingBuffer {
     // to simply share tests with the unsafe version
     #[cfg(test)]
     fn raw(&self) -> Vec<f32> {
-        let mut slice = vec![0.; RING_BUFFER_SIZE];
+        let mut slice = vec![0.; self.size()];
 
         self.buffer.iter().zip(slice.iter_mut()).for_each(|(a, b)| {
             *b = a.load(Ordering::SeqCst);
@@ -144,6 +223,8 @@ impl AnalyserRingBuffer {
 pub(crate) struct Analyser {
     ring_buffer: AnalyserRingBuffer,
     fft_size: usize,
+    zero_padding: usize,
+    window: AnalyserWindow,
     smoothing_time_constant: f64,
     min_decibels: f64,
     max_decibels: f64,
@@ -153,13 +234,15 @@ pub(crate) struct Analyser {
     fft_output: Vec<Complex<f32>>,
     last_fft_output: Vec<f32>,
     last_fft_time: f64,
-    blackman: Vec<f32>,
+    window_values: Vec<f32>,
 }
 
 impl std::fmt::Debug for Analyser {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Analyser")
             .field("fft_size", &self.fft_size())
+            .field("zero_padding", &self.zero_padding())
+            .field("window", &self.window())
             .field("smoothing_time_constant", &self.smoothing_time_constant())
             .field("min_decibels", &self.min_decibels())
             .field("max_decibels", &self.max_decibels())
@@ -180,13 +263,19 @@ impl Analyser {
         let mut last_fft_output = Vec::with_capacity(fft_output.len());
         last_fft_output.resize_with(fft_output.len(), || 0.);
 
-        // precalculate Blackman window values, reserve enough space for all input sizes
-        let mut blackman = Vec::with_capacity(fft_input.len());
-        generate_blackman(DEFAULT_FFT_SIZE).for_each(|v| blackman.push(v));
+        // precalculate window values, reserve enough space for all spec input sizes
+        let mut window_values = Vec::with_capacity(fft_input.len());
+        generate_window(
+            AnalyserWindow::default(),
+            DEFAULT_FFT_SIZE,
+            &mut window_values,
+        );
 
         Self {
             ring_buffer,
             fft_size: DEFAULT_FFT_SIZE,
+            zero_padding: DEFAULT_ZERO_PADDING,
+            window: AnalyserWindow::default(),
             smoothing_time_constant: DEFAULT_SMOOTHING_TIME_CONSTANT,
             min_decibels: DEFAULT_MIN_DECIBELS,
             max_decibels: DEFAULT_MAX_DECIBELS,
@@ -196,7 +285,7 @@ impl Analyser {
             fft_output,
             last_fft_output,
             last_fft_time: f64::NEG_INFINITY,
-            blackman,
+            window_values,
         }
     }
 
@@ -209,19 +298,84 @@ impl Analyser {
     }
 
     pub fn set_fft_size(&mut self, fft_size: usize) {
-        assert_valid_fft_size(fft_size);
+        assert_valid_fft_size(fft_size, MAX_FFT_SIZE);
+        self.update_fft_size(fft_size, self.zero_padding);
+    }
+
+    // Returns the new ring buffer if it had to grow, the render thread must then
+    // write into it
+    pub fn set_extended_fft_size(&mut self, fft_size: usize) -> Option<AnalyserRingBuffer> {
+        assert_valid_fft_size(fft_size, MAX_EXTENDED_FFT_SIZE);
+
+        let ring_buffer = (fft_size + RENDER_QUANTUM_SIZE > self.ring_buffer.size()).then(|| {
+            self.ring_buffer = AnalyserRingBuffer::with_size(fft_size + RENDER_QUANTUM_SIZE);
+            self.ring_buffer.clone()
+        });
+
+        self.update_fft_size(fft_size, self.zero_padding);
+
+        ring_buffer
+    }
+
+    pub fn zero_padding(&self) -> usize {
+        self.zero_padding
+    }
 
-        let current_fft_size = self.fft_size;
+    pub fn set_zero_padding(&mut self, zero_padding: usize) {
+        assert_valid_zero_padding(zero_padding);
+        self.update_fft_size(self.fft_size, zero_padding);
+    }
+
+    pub fn window(&self) -> AnalyserWindow {
+        self.window
+    }
+
+    pub fn set_window(&mut self, window: AnalyserWindow) {
+        if let AnalyserWindow::Kaiser(beta) = window {
+            assert!(
+                beta.is_finite() && beta >= 0.,
+                "RangeError - Invalid Kaiser window beta: {:?} should be positive",
+                beta
+            );
+        }
+
+        if self.window != window {
+            self.window = window;
+            generate_window(window, self.fft_size, &mut self.window_values);
+        }
+    }
 
-        if current_fft_size != fft_size {
-            // reset last fft buffer
-            self.last_fft_output.iter_mut().for_each(|v| *v = 0.);
-            // generate blackman window
-            self.blackman.clear();
-            generate_blackman(fft_size).for_each(|v| self.blackman.push(v));
+    fn update_fft_size(&mut self, fft_size: usize, zero_padding: usize) {
+        if self.fft_size == fft_size && self.zero_padding == zero_padding {
+            return;
+        }
+
+        // grow the FFT buffers if the transform does not fit anymore, this only
+        // happens with the non spec extensions
+        let transform_size = fft_size * zero_padding;
+
+        if transform_size > self.fft_input.len() {
+            let r2c = self
+                .fft_planner
+                .lock()
+                .unwrap()
+                .plan_fft_forward(transform_size);
+
+            self.fft_input = r2c.make_input_vec();
+            self.fft_scratch = r2c.make_scratch_vec();
+            self.fft_output = r2c.make_output_vec();
+            self.last_fft_output.resize(self.fft_output.len(), 0.);
+        }
+
+        // reset last fft buffer
+        self.last_fft_output.iter_mut().for_each(|v| *v = 0.);
 
-            self.fft_size = fft_size;
+        if self.fft_size != fft_size {
+            generate_window(self.window, fft_size, &mut self.window_values);
         }
+
+        self.fft_size = fft_size;
+        self.zero_padding = zero_padding;
     }
 
     pub fn smoothing_time_constant(&self) -> f64 {
@@ -250,7 +404,7 @@ impl Analyser {
     }
 
     pub fn frequency_bin_count(&self) -> usize {
-        self.fft_size() / 2
+        self.fft_size() * self.zero_padding() / 2
     }
 
     // [spec] Write the current time-domain data (waveform data) into array.
@@ -277,24 +431,35 @@ impl Analyser {
 
     fn compute_fft(&mut self) {
         let fft_size = self.fft_size();
+        let transform_size = fft_size * self.zero_padding();
         let smoothing_time_constant = self.smoothing_time_constant() as f32;
         // setup FFT planner and properly sized buffers
-        let r2c = self.fft_planner.lock().unwrap().plan_fft_forward(fft_size);
-        let input = &mut self.fft_input[..fft_size];
-        let output = &mut self.fft_output[..fft_size / 2 + 1];
+        let r2c = self
+            .fft_planner
+            .lock()
+            .unwrap()
+            .plan_fft_forward(transform_size);
+        let input = &mut self.fft_input[..transform_size];
+        let output = &mut self.fft_output[..transform_size / 2 + 1];
         let scratch = &mut self.fft_scratch[..r2c.get_scratch_len()];
         // we ignore the Nyquist bin in output, see comment below
-        let last_fft_output = &mut self.last_fft_output[..fft_size / 2];
+        let last_fft_output = &mut self.last_fft_output[..transform_size / 2];
 
         // Compute the current time-domain data.
         // The most recent fftSize frames are used in computing the frequency data.
+        let (input, padding) = input.split_at_mut(fft_size);
         self.ring_buffer.read(input, fft_size);
 
-        // Apply a Blackman window to the time domain input data.
+        // Apply a window (Blackman by default) to the time domain input data.
         input
             .iter_mut()
-            .zip(self.blackman.iter())
-            .for_each(|(i, b)| *i *= *b);
+            .zip(self.window_values.iter())
+            .for_each(|(i, w)| *i *= *w);
+
+        // Zero pad the windowed data up to the transform size (non spec extension),
+        // the realfft input buffer is used as scratch space so it must be cleared
+        padding.fill(0.);
+        let input = &mut self.fft_input[..transform_size];
 
         // Apply a Fourier transform to the windowed time domain input data to
         // get real and imaginary frequency data.
@@ -332,6 +497,8 @@ impl Analyser {
         // In our case, it seems we can thus just ignore the Nyquist information
         // and take the DC bin as it is
 
+        // Zero padding does not change the magnitude of the bins, normalize with
+        // the number of actual input samples.
         let normalize_factor = 1. / fft_size as f32;
 
         last_fft_output
@@ -344,6 +511,53 @@ impl Analyser {
             });
     }
 
+    // Create both sides of a spectrogram capture with the current fft size, window
+    // and zero padding. `max_frames` frame buffers are allocated up front so the
+    // render thread never allocates.
+    pub fn spectrogram(
+        &self,
+        hop_size: usize,
+        max_frames: usize,
+    ) -> (SpectrogramReceiver, SpectrogramCapture) {
+        assert!(
+            hop_size > 0 && hop_size <= self.fft_size,
+            "IndexSizeError - Invalid hop size: {:?} is outside range [1, {:?}]",
+            hop_size,
+            self.fft_size
+        );
+        assert!(
+            max_frames > 0,
+            "RangeError - Invalid max frames: {:?} should be greater than zero",
+            max_frames
+        );
+
+        let transform_size = self.fft_size * self.zero_padding;
+        let r2c = self
+            .fft_planner
+            .lock()
+            .unwrap()
+            .plan_fft_forward(transform_size);
+
+        let (frame_sender, frames) = crossbeam_channel::bounded(max_frames);
+        let (pool, pool_receiver) = crossbeam_channel::bounded(max_frames);
+        (0..max_frames).for_each(|_| pool.send(vec![0.; transform_size / 2]).unwrap());
+
+        let capture = SpectrogramCapture {
+            hop_size,
+            fft_size: self.fft_size,
+            elapsed: 0,
+            input: r2c.make_input_vec(),
+            scratch: r2c.make_scratch_vec(),
+            output: r2c.make_output_vec(),
+            r2c,
+            window_values: self.window_values.clone(),
+            frames: frame_sender,
+            pool: pool_receiver,
+        };
+
+        (SpectrogramReceiver { frames, pool }, capture)
+    }
+
     pub fn get_float_frequency_data(&mut self, dst: &mut [f32], current_time: f64) {
         let frequency_bin_count = self.frequency_bin_count();
 
@@ -401,6 +615,106 @@ impl Analyser {
     }
 }
 
+/// A STFT frame computed in the render thread, in decibels
+#[derive(Debug)]
+pub(crate) struct SpectrogramFrame {
+    /// time of the end of the analysis window
+    time: f64,
+    bins: Vec<f32>,
+}
+
+/// Control thread side of a spectrogram capture
+#[derive(Debug)]
+pub(crate) struct SpectrogramReceiver {
+    frames: Receiver<SpectrogramFrame>,
+    /// return the frame buffers to the render thread once read
+    pool: Sender<Vec<f32>>,
+}
+
+impl SpectrogramReceiver {
+    // Copy the oldest pending frame into `dst` and return its time
+    pub fn read(&self, dst: &mut [f32]) -> Option<f64> {
+        let SpectrogramFrame { time, bins } = self.frames.try_recv().ok()?;
+
+        let len = dst.len().min(bins.len());
+        dst[..len].copy_from_slice(&bins[..len]);
+        // the pool has room for all frame buffers
+        let _ = self.pool.try_send(bins);
+
+        Some(time)
+    }
+}
+
+/// Render thread side of a spectrogram capture, computes a STFT frame every
+/// `hop_size` frames from the analyser ring buffer
+pub(crate) struct SpectrogramCapture {
+    hop_size: usize,
+    fft_size: usize,
+    /// number of frames written since the last STFT frame
+    elapsed: usize,
+    r2c: Arc<dyn RealToComplex<f32>>,
+    window_values: Vec<f32>,
+    input: Vec<f32>,
+    scratch: Vec<Complex<f32>>,
+    output: Vec<Complex<f32>>,
+    frames: Sender<SpectrogramFrame>,
+    pool: Receiver<Vec<f32>>,
+}
+
+impl SpectrogramCapture {
+    // To be called after each render quantum has been written into the ring buffer,
+    // `current_time` being the time of the start of this render quantum
+    pub fn process(
+        &mut self,
+        ring_buffer: &AnalyserRingBuffer,
+        current_time: f64,
+        sample_rate: f32,
+    ) {
+        self.elapsed += RENDER_QUANTUM_SIZE;
+
+        while self.elapsed >= self.hop_size {
+            self.elapsed -= self.hop_size;
+
+            // the analysis window ends `elapsed` frames before the end of the quantum
+            let offset = self.elapsed;
+            let time = current_time + (RENDER_QUANTUM_SIZE - offset) as f64 / sample_rate as f64;
+
+            // all frame buffers are pending in the queue, the control thread does
+            // not keep up so drop the frame
+            let Ok(mut bins) = self.pool.try_recv() else {
+                continue;
+            };
+
+            self.compute(ring_buffer, offset, &mut bins);
+            // cannot fail, the queue has room for all frame buffers
+            let _ = self.frames.try_send(SpectrogramFrame { time, bins });
+        }
+    }
+
+    fn compute(&mut self, ring_buffer: &AnalyserRingBuffer, offset: usize, bins: &mut [f32]) {
+        let fft_size = self.fft_size;
+        let (input, padding) = self.input.split_at_mut(fft_size);
+
+        ring_buffer.read_before(input, fft_size, offset);
+        input
+            .iter_mut()
+            .zip(self.window_values.iter())
+            .for_each(|(i, w)| *i *= *w);
+        padding.fill(0.);
+
+        self.r2c
+            .process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch)
+            .unwrap();
+
+        // same normalization as the analyser frequency data, without smoothing
+        let normalize_factor = 1. / fft_size as f32;
+
+        bins.iter_mut()
+            .zip(self.output.iter())
+            .for_each(|(b, c)| *b = 20. * (c.norm() * normalize_factor).log10());
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::RwLock;
@@ -436,6 +750,138 @@ mod tests {
         assert_eq!(max_pos, 1024);
     }
 
+    #[test]
+    fn test_windows() {
+        let size = 2048;
+        let mut values = vec![];
+
+        for window in [
+            AnalyserWindow::Hann,
+            AnalyserWindow::Hamming,
+            AnalyserWindow::FlatTop,
+            AnalyserWindow::Kaiser(8.6),
+        ] {
+            generate_window(window, size, &mut values);
+            assert_eq!(values.len(), size);
+            // symmetric around the center, where the window reaches 1
+            assert_float_eq!(values[size / 2], 1., abs <= 1e-5);
+            assert_float_eq!(values[1], values[size - 1], abs <= 1e-5);
+            assert!(values[0] < 0.1);
+        }
+
+        generate_window(AnalyserWindow::Rectangular, size, &mut values);
+        assert_float_eq!(&values[..], &vec![1.; size][..], abs_all <= 0.);
+
+        // a Kaiser window with beta = 0 is rectangular
+        generate_window(AnalyserWindow::Kaiser(0.), size, &mut values);
+        assert_float_eq!(&values[..], &vec![1.; size][..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_flat_top_amplitude() {
+        let sample_rate = 44100.;
+        let fft_size = 4096;
+        let bin_width = sample_rate / fft_size as f32;
+        let mut peaks = vec![];
+
+        // sines of the same amplitude from the center of a bin to its edge
+        for offset in [0., 0.25, 0.5] {
+            let freq = bin_width * (100. + offset);
+
+            let mut analyser = Analyser::new();
+            analyser.set_fft_size(fft_size);
+            analyser.set_smoothing_time_constant(0.);
+            analyser.set_window(AnalyserWindow::FlatTop);
+
+            let signal: Vec<f32> = (0..fft_size)
+                .map(|i| (2. * PI * freq * i as f32 / sample_rate).sin())
+                .collect();
+            analyser.get_ring_buffer_clone().write(&signal);
+
+            let mut bins = vec![0.; analyser.frequency_bin_count()];
+            analyser.get_float_frequency_data(&mut bins, 0.);
+            peaks.push(bins.iter().fold(f32::NEG_INFINITY, |a, &b| a.max(b)));
+        }
+
+        // no scalloping loss with a flat-top window
+        assert_float_eq!(peaks[1], peaks[0], abs <= 0.05);
+        assert_float_eq!(peaks[2], peaks[0], abs <= 0.05);
+    }
+
+    #[test]
+    fn test_zero_padding() {
+        let sample_rate = 44100.;
+        let fft_size = 1024;
+        let zero_padding = 4;
+        let num_bin = 20;
+        let freq = sample_rate / fft_size as f32 * num_bin as f32;
+
+        let mut analyser = Analyser::new();
+        analyser.set_fft_size(fft_size);
+        analyser.set_zero_padding(zero_padding);
+        assert_eq!(analyser.frequency_bin_count(), fft_size * zero_padding / 2);
+
+        let signal: Vec<f32> = (0..fft_size)
+            .map(|i| (2. * PI * freq * i as f32 / sample_rate).sin())
+            .collect();
+        analyser.get_ring_buffer_clone().write(&signal);
+
+        let mut bins = vec![0.; analyser.frequency_bin_count()];
+        analyser.get_float_frequency_data(&mut bins, 0.);
+
+        let mut padded_bins = bins.clone();
+        let highest =
+            bins.iter().enumerate().fold(
+                (0, f32::NEG_INFINITY),
+                |a, (i, &b)| if b > a.1 { (i, b) } else { a },
+            );
+        assert_eq!(highest.0, num_bin * zero_padding);
+
+        // same magnitude as without zero padding
+        analyser.set_zero_padding(1);
+        analyser.get_float_frequency_data(&mut padded_bins, 1.);
+        assert_float_eq!(padded_bins[num_bin], highest.1, abs <= 1e-3);
+    }
+
+    #[test]
+    fn test_extended_fft_size() {
+        let mut analyser = Analyser::new();
+        assert_eq!(analyser.ring_buffer.size(), RING_BUFFER_SIZE);
+
+        // the ring buffer only grows when needed
+        assert!(analyser.set_extended_fft_size(MAX_FFT_SIZE).is_none());
+        assert_eq!(analyser.ring_buffer.size(), RING_BUFFER_SIZE);
+
+        let ring_buffer = analyser.set_extended_fft_size(MAX_EXTENDED_FFT_SIZE);
+        assert_eq!(
+            ring_buffer.map(|r| r.size()),
+            Some(MAX_EXTENDED_FFT_SIZE + RENDER_QUANTUM_SIZE)
+        );
+        assert_eq!(analyser.fft_size(), MAX_EXTENDED_FFT_SIZE);
+        assert_eq!(analyser.frequency_bin_count(), MAX_EXTENDED_FFT_SIZE / 2);
+
+        let data = [1.; RENDER_QUANTUM_SIZE];
+        analyser.get_ring_buffer_clone().write(&data);
+
+        let mut bins = vec![0.; analyser.frequency_bin_count()];
+        analyser.get_float_frequency_data(&mut bins, 0.);
+        assert!(bins[0].is_finite());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_extended_fft_size_constraints() {
+        let mut analyser = Analyser::new();
+        analyser.set_extended_fft_size(MAX_EXTENDED_FFT_SIZE * 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_zero_padding_constraints() {
+        let mut analyser = Analyser::new();
+        analyser.set_zero_padding(3);
+    }
+
     #[test]
     fn test_ring_buffer_write_simple() {
         let ring_buffer = AnalyserRingBuffer::new();
@@ -1,9 +1,12 @@
 //! General purpose audio signal data structures
 use std::sync::Arc;
 
+use arrayvec::ArrayVec;
+
+use crate::loudness::{Loudness, LoudnessMeter};
 use crate::{
     assert_valid_buffer_length, assert_valid_channel_number, assert_valid_number_of_channels,
-    assert_valid_sample_rate,
+    assert_valid_sample_rate, MAX_CHANNELS,
 };
 
 /// Options for constructing an [`AudioBuffer`]
@@ -237,6 +240,23 @@ impl AudioBuffer {
         self.channel_data_mut(channel_number).as_mut_slice()
     }
 
+    /// Measure the loudness of the buffer, as specified by ITU-R BS.1770 and EBU R128
+    ///
+    /// The channels are weighted according to the 5.1 layout when the buffer has 6
+    /// channels, with equal weights otherwise.
+    ///
+    /// Note that this method is not part of the Web Audio API specification, see
+    /// [`LoudnessMeterNode`](crate::node::LoudnessMeterNode) to measure a live signal.
+    #[must_use]
+    pub fn loudness(&self) -> Loudness {
+        let channels: ArrayVec<&[f32], MAX_CHANNELS> =
+            self.channels.iter().map(ChannelData::as_slice).collect();
+
+        let mut meter = LoudnessMeter::new(self.sample_rate);
+        let _ = meter.process(&channels);
+        meter.loudness()
+    }
+
     /// Create a multi-channel audiobuffer directly from `ChannelData`s.
     // @todo - remove in favor of `AudioBuffer::from`
     pub(crate) fn from_channels(channels: Vec<ChannelData>, sample_rate: f32) -> Self {
@@ -814,4 +834,22 @@ mod tests {
             assert_float_eq!(buffer.sample_rate, target_sr as f32, abs_all <= 0.);
         });
     }
+
+    #[test]
+    fn test_loudness() {
+        let sample_rate = 48_000.;
+        // 1kHz stereo sine at -23dBFS, measures -23 LUFS
+        let amplitude = 10_f32.powf(-23. / 20.);
+        let channel: Vec<f32> = (0..sample_rate as usize * 10)
+            .map(|i| amplitude * (2. * PI * 1000. * i as f32 / sample_rate).sin())
+            .collect();
+        let buffer = AudioBuffer::from(vec![channel.clone(), channel], sample_rate);
+
+        let loudness = buffer.loudness();
+        assert_float_eq!(loudness.integrated, -23., abs <= 0.1);
+        assert_float_eq!(loudness.max_momentary, -23., abs <= 0.1);
+        assert_float_eq!(loudness.max_short_term, -23., abs <= 0.1);
+        assert_float_eq!(loudness.loudness_range, 0., abs <= 0.1);
+        assert_float_eq!(loudness.true_peak, -23., abs <= 0.1);
+    }
 }
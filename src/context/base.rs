@@ -3,13 +3,14 @@
 use crate::buffer::{AudioBuffer, AudioBufferOptions};
 use crate::context::{
     AudioContextRegistration, AudioContextState, AudioParamId, ConcreteBaseAudioContext,
-    DESTINATION_NODE_ID,
+    ScheduledCallbackId, DESTINATION_NODE_ID,
 };
 use crate::decoding::decode_media_data;
 use crate::events::{Event, EventHandler, EventType};
 use crate::node::{AudioNode, AudioNodeOptions};
 use crate::param::AudioParamDescriptor;
 use crate::periodic_wave::{PeriodicWave, PeriodicWaveOptions};
+use crate::transport::{Transport, TransportOptions};
 use crate::{node, AudioListener, RENDER_QUANTUM_SIZE};
 
 use std::future::Future;
@@ -123,6 +124,61 @@ pub trait BaseAudioContext {
         AudioBuffer::new(options)
     }
 
+    /// Creates an `AmbisonicBinauralDecoderNode`, rendering an ambisonic sound field of the
+    /// given order for headphones
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_ambisonic_binaural_decoder(
+        &self,
+        order: usize,
+    ) -> node::AmbisonicBinauralDecoderNode {
+        let opts = node::AmbisonicBinauralDecoderOptions {
+            order,
+            ..node::AmbisonicBinauralDecoderOptions::default()
+        };
+        node::AmbisonicBinauralDecoderNode::new(self.base(), opts)
+    }
+
+    /// Creates an `AmbisonicDecoderNode`, decoding an ambisonic sound field of the given order
+    /// to a speaker layout
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_ambisonic_decoder(
+        &self,
+        order: usize,
+        layout: node::AmbisonicSpeakerLayout,
+    ) -> node::AmbisonicDecoderNode {
+        let opts = node::AmbisonicDecoderOptions { order, layout };
+        node::AmbisonicDecoderNode::new(self.base(), opts)
+    }
+
+    /// Creates an `AmbisonicEncoderNode`, encoding a mono source into an ambisonic sound field
+    /// of the given order
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_ambisonic_encoder(&self, order: usize) -> node::AmbisonicEncoderNode {
+        let opts = node::AmbisonicEncoderOptions {
+            order,
+            ..node::AmbisonicEncoderOptions::default()
+        };
+        node::AmbisonicEncoderNode::new(self.base(), opts)
+    }
+
+    /// Creates an `AmbisonicRotatorNode`, rotating an ambisonic sound field of the given order
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_ambisonic_rotator(&self, order: usize) -> node::AmbisonicRotatorNode {
+        let opts = node::AmbisonicRotatorOptions {
+            order,
+            ..node::AmbisonicRotatorOptions::default()
+        };
+        node::AmbisonicRotatorNode::new(self.base(), opts)
+    }
+
     /// Creates a `AnalyserNode`
     #[must_use]
     fn create_analyser(&self) -> node::AnalyserNode {
@@ -141,6 +197,14 @@ pub trait BaseAudioContext {
         node::AudioBufferSourceNode::new(self.base(), node::AudioBufferSourceOptions::default())
     }
 
+    /// Creates a `ChorusNode`, a modulated delay effect thickening the signal
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_chorus(&self) -> node::ChorusNode {
+        node::ChorusNode::new(self.base(), node::ChorusOptions::default())
+    }
+
     /// Creates an `ConstantSourceNode`, a source representing a constant value
     #[must_use]
     fn create_constant_source(&self) -> node::ConstantSourceNode {
@@ -189,6 +253,23 @@ pub trait BaseAudioContext {
         node::DynamicsCompressorNode::new(self.base(), node::DynamicsCompressorOptions::default())
     }
 
+    /// Creates a `FilterNode`, a cascade of second order sections implementing higher order
+    /// filters
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_filter(&self) -> node::FilterNode {
+        node::FilterNode::new(self.base(), node::FilterOptions::default())
+    }
+
+    /// Creates a `FlangerNode`, a short modulated delay effect with feedback
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_flanger(&self) -> node::FlangerNode {
+        node::FlangerNode::new(self.base(), node::FlangerOptions::default())
+    }
+
     /// Creates an `GainNode`, to control audio volume
     #[must_use]
     fn create_gain(&self) -> node::GainNode {
@@ -213,6 +294,40 @@ pub trait BaseAudioContext {
         node::IIRFilterNode::new(self.base(), options)
     }
 
+    /// Creates a `LoudnessMeterNode`, measuring the loudness of the signal according to
+    /// ITU-R BS.1770 and EBU R128
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_loudness_meter(&self) -> node::LoudnessMeterNode {
+        node::LoudnessMeterNode::new(self.base(), node::LoudnessMeterOptions::default())
+    }
+
+    /// Creates a `MeterNode`, measuring the peak and RMS levels of the signal
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_meter(&self) -> node::MeterNode {
+        node::MeterNode::new(self.base(), node::MeterOptions::default())
+    }
+
+    /// Creates a `MultibandCompressorNode`, compressing the audio signal in three frequency
+    /// bands
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_multiband_compressor(&self) -> node::MultibandCompressorNode {
+        node::MultibandCompressorNode::new(self.base(), node::MultibandCompressorOptions::default())
+    }
+
+    /// Creates an `OnsetDetectorNode`, dispatching events on the onsets of the signal
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_onset_detector(&self) -> node::OnsetDetectorNode {
+        node::OnsetDetectorNode::new(self.base(), node::OnsetDetectorOptions::default())
+    }
+
     /// Creates an `OscillatorNode`, a source representing a periodic waveform.
     #[must_use]
     fn create_oscillator(&self) -> node::OscillatorNode {
@@ -225,6 +340,48 @@ pub trait BaseAudioContext {
         node::PannerNode::new(self.base(), node::PannerOptions::default())
     }
 
+    /// Creates a `ParametricEqNode`, a multi-band equalizer
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_parametric_eq(&self) -> node::ParametricEqNode {
+        node::ParametricEqNode::new(self.base(), node::ParametricEqOptions::default())
+    }
+
+    /// Creates a `PhaserNode`, a modulated all-pass filter effect
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_phaser(&self) -> node::PhaserNode {
+        node::PhaserNode::new(self.base(), node::PhaserOptions::default())
+    }
+
+    /// Creates a `PitchDetectorNode`, dispatching events with the fundamental frequency of
+    /// the signal
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_pitch_detector(&self) -> node::PitchDetectorNode {
+        node::PitchDetectorNode::new(self.base(), node::PitchDetectorOptions::default())
+    }
+
+    /// Creates a `PitchShifterNode`, changing the pitch of the signal without changing its
+    /// duration
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_pitch_shifter(&self) -> node::PitchShifterNode {
+        node::PitchShifterNode::new(self.base(), node::PitchShifterOptions::default())
+    }
+
+    /// Creates a `ReverbNode`, a parametric reverberation effect
+    ///
+    /// Note that this node is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_reverb(&self) -> node::ReverbNode {
+        node::ReverbNode::new(self.base(), node::ReverbOptions::default())
+    }
+
     /// Creates a periodic wave
     ///
     /// Please note that this constructor deviates slightly from the spec by requiring a single
@@ -264,6 +421,14 @@ pub trait BaseAudioContext {
         node::StereoPannerNode::new(self.base(), node::StereoPannerOptions::default())
     }
 
+    /// Creates a `Transport`, to schedule sources and automation events in musical time
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    #[must_use]
+    fn create_transport(&self) -> Transport {
+        Transport::new(self.base(), TransportOptions::default())
+    }
+
     /// Creates a `WaveShaperNode`
     #[must_use]
     fn create_wave_shaper(&self) -> node::WaveShaperNode {
@@ -288,6 +453,22 @@ pub trait BaseAudioContext {
         self.base().listener()
     }
 
+    /// Returns the HRIR dataset used by new `PannerNode`s with the HRTF panning model
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    #[must_use]
+    fn hrtf_dataset(&self) -> node::HrtfDataset {
+        self.base().hrtf_dataset()
+    }
+
+    /// Set the HRIR dataset used by `PannerNode`s created from now on
+    ///
+    /// Existing panners keep their dataset, use [`node::PannerNode::set_hrtf_dataset`] to
+    /// update them. Note that this method is not part of the Web Audio API specification.
+    fn set_hrtf_dataset(&self, dataset: node::HrtfDataset) {
+        self.base().set_hrtf_dataset(dataset);
+    }
+
     /// The sample rate (in sample-frames per second) at which the `AudioContext` handles audio.
     #[must_use]
     fn sample_rate(&self) -> f32 {
@@ -335,6 +516,71 @@ pub trait BaseAudioContext {
         (param, proc_id)
     }
 
+    /// Schedule a callback to run ahead of the given context time
+    ///
+    /// The callback runs once the current time of the context reaches `when` minus the
+    /// [`schedule_lookahead`](Self::schedule_lookahead), and receives `when` as argument so that
+    /// sources and automation events can be scheduled precisely at that time, e.g. with
+    /// `start_at(when)` or `set_value_at_time(value, when)`. If `when` has already passed, the
+    /// callback runs as soon as possible.
+    ///
+    /// The callbacks run on the event thread of an `AudioContext`, and between two render
+    /// quanta while an `OfflineAudioContext` is rendering.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `when` is negative or not finite
+    fn schedule_callback<F: FnOnce(f64) + Send + 'static>(
+        &self,
+        when: f64,
+        callback: F,
+    ) -> ScheduledCallbackId {
+        assert!(
+            when.is_finite() && when >= 0.,
+            "RangeError - Invalid time: {when:?}, should be positive"
+        );
+
+        self.base().schedule_callback(when, Box::new(callback))
+    }
+
+    /// Cancel a callback scheduled with [`schedule_callback`](Self::schedule_callback)
+    ///
+    /// Returns `false` if the callback has already run or has already been cancelled.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    fn cancel_scheduled_callback(&self, id: ScheduledCallbackId) -> bool {
+        self.base().cancel_scheduled_callback(id)
+    }
+
+    /// Time in seconds the scheduled callbacks run ahead of their target time, defaults to 0.1
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    #[must_use]
+    fn schedule_lookahead(&self) -> f64 {
+        self.base().schedule_lookahead()
+    }
+
+    /// Update the time in seconds the scheduled callbacks run ahead of their target time
+    ///
+    /// The lookahead should be large enough to cover the latency of the control thread,
+    /// otherwise the callbacks may schedule events in the past.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lookahead is negative or not finite
+    fn set_schedule_lookahead(&self, lookahead: f64) {
+        assert!(
+            lookahead.is_finite() && lookahead >= 0.,
+            "RangeError - Invalid lookahead: {lookahead:?}, should be positive"
+        );
+
+        self.base().set_schedule_lookahead(lookahead);
+    }
+
     /// Register callback to run when the state of the AudioContext has changed
     ///
     /// Only a single event handler is active at any time. Calling this method multiple times will
@@ -370,8 +616,10 @@ pub trait BaseAudioContext {
 mod tests {
     use super::*;
     use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
 
     use float_eq::assert_float_eq;
+    use std::sync::{Arc, Mutex};
 
     fn require_send_sync_static<T: Send + Sync + 'static>(_: T) {}
 
@@ -436,6 +684,47 @@ mod tests {
         assert!(context.decode_audio_data_sync(file).is_err());
     }
 
+    #[test]
+    fn test_schedule_callback() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 48_000, sample_rate);
+        context.set_schedule_lookahead(0.1);
+
+        let fired = Arc::new(Mutex::new(Vec::new()));
+
+        let base = context.base().clone();
+        let fired_clone = Arc::clone(&fired);
+        context.schedule_callback(0.5, move |when| {
+            fired_clone
+                .lock()
+                .unwrap()
+                .push((when, base.current_time()));
+
+            let mut src = base.create_constant_source();
+            src.connect(&base.destination());
+            src.start_at(when);
+        });
+
+        let fired_clone = Arc::clone(&fired);
+        let id = context.schedule_callback(0.25, move |when| {
+            fired_clone.lock().unwrap().push((when, 0.));
+        });
+        assert!(context.cancel_scheduled_callback(id));
+        assert!(!context.cancel_scheduled_callback(id));
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+
+        // the callback ran during the render quantum reaching the lookahead
+        let fired = fired.lock().unwrap();
+        assert_eq!(fired.len(), 1);
+        assert_float_eq!(fired[0].0, 0.5, abs <= 0.);
+        assert!(fired[0].1 >= 0.4 && fired[0].1 < 0.4 + 128. / sample_rate as f64);
+
+        assert_float_eq!(output[23_999], 0., abs <= 0.);
+        assert_float_eq!(output[24_000], 1., abs <= 0.);
+    }
+
     #[test]
     fn test_create_buffer() {
         let number_of_channels = 3;
@@ -1,12 +1,13 @@
 //! The `ConcreteBaseAudioContext` type
 
+use crate::context::scheduler::{ScheduledCallbackFn, Scheduler};
 use crate::context::{
     AudioContextRegistration, AudioContextState, AudioNodeId, BaseAudioContext,
-    DESTINATION_NODE_ID, LISTENER_NODE_ID, LISTENER_PARAM_IDS,
+    ScheduledCallbackId, DESTINATION_NODE_ID, LISTENER_NODE_ID, LISTENER_PARAM_IDS,
 };
 use crate::events::{EventDispatch, EventHandler, EventLoop, EventType};
 use crate::message::ControlMessage;
-use crate::node::{AudioDestinationNode, AudioNode, AudioNodeOptions, ChannelConfig};
+use crate::node::{AudioDestinationNode, AudioNode, AudioNodeOptions, ChannelConfig, HrtfDataset};
 use crate::param::AudioParam;
 use crate::render::AudioProcessor;
 use crate::spatial::AudioListenerParams;
@@ -114,6 +115,10 @@ struct ConcreteBaseAudioContextInner {
     event_send: Sender<EventDispatch>,
     /// Current audio graph connections (from node, output port, to node, input port)
     connections: Mutex<HashSet<(AudioNodeId, usize, AudioNodeId, usize)>>,
+    /// HRIR dataset used by the panner nodes with the HRTF panning model
+    hrtf_dataset: Mutex<HrtfDataset>,
+    /// Callbacks scheduled on the control thread
+    scheduler: Mutex<Scheduler>,
 }
 
 impl BaseAudioContext for ConcreteBaseAudioContext {
@@ -154,6 +159,8 @@ impl ConcreteBaseAudioContext {
             event_loop,
             event_send,
             connections: Mutex::new(HashSet::new()),
+            hrtf_dataset: Mutex::new(HrtfDataset::default()),
+            scheduler: Mutex::new(Scheduler::default()),
         };
         let base = Self {
             inner: Arc::new(base_inner),
@@ -218,7 +225,7 @@ impl ConcreteBaseAudioContext {
 
         // For an online AudioContext, pre-create the HRTF-database for panner nodes
         if !offline {
-            crate::node::load_hrtf_processor(sample_rate as u32);
+            base.hrtf_dataset().load_processor(sample_rate as u32);
         }
 
         base
@@ -545,6 +552,92 @@ impl ConcreteBaseAudioContext {
     pub(crate) fn clear_event_handler(&self, event: EventType) {
         self.inner.event_loop.clear_handler(event);
     }
+
+    /// Schedule a callback to run ahead of the given context time
+    pub(super) fn schedule_callback(
+        &self,
+        when: f64,
+        callback: Box<ScheduledCallbackFn>,
+    ) -> ScheduledCallbackId {
+        let mut scheduler = self.inner.scheduler.lock().unwrap();
+
+        if scheduler.register_handler() {
+            // hold a weak reference to prevent a reference cycle through the event loop
+            let inner = Arc::downgrade(&self.inner);
+            let callback = move |_| {
+                if let Some(inner) = inner.upgrade() {
+                    ConcreteBaseAudioContext { inner }.run_scheduled_callbacks();
+                }
+            };
+
+            self.set_event_handler(
+                EventType::Scheduler,
+                EventHandler::Multiple(Box::new(callback)),
+            );
+        }
+
+        let id = scheduler.insert(when, callback);
+        self.send_scheduler_wakeup(&scheduler);
+
+        id
+    }
+
+    /// Cancel a scheduled callback, returns false if it already ran or was cancelled
+    pub(super) fn cancel_scheduled_callback(&self, id: ScheduledCallbackId) -> bool {
+        let mut scheduler = self.inner.scheduler.lock().unwrap();
+        let removed = scheduler.remove(id);
+        self.send_scheduler_wakeup(&scheduler);
+
+        removed
+    }
+
+    pub(super) fn schedule_lookahead(&self) -> f64 {
+        self.inner.scheduler.lock().unwrap().lookahead()
+    }
+
+    pub(super) fn set_schedule_lookahead(&self, lookahead: f64) {
+        let mut scheduler = self.inner.scheduler.lock().unwrap();
+        scheduler.set_lookahead(lookahead);
+        self.send_scheduler_wakeup(&scheduler);
+    }
+
+    /// Notify the (possibly new) render thread of the next scheduler wakeup time
+    pub(super) fn update_scheduler_wakeup(&self) {
+        let scheduler = self.inner.scheduler.lock().unwrap();
+        self.send_scheduler_wakeup(&scheduler);
+    }
+
+    // The scheduler lock is held while sending, so that the render thread always
+    // receives the wakeup times in order
+    fn send_scheduler_wakeup(&self, scheduler: &Scheduler) {
+        let when = scheduler.wakeup_time();
+        self.send_control_msg(ControlMessage::SchedulerWakeup { when });
+    }
+
+    fn run_scheduled_callbacks(&self) {
+        let current_time = self.current_time();
+        let due = self.inner.scheduler.lock().unwrap().take_due(current_time);
+
+        // release the lock while running the callbacks, as they may schedule new ones
+        due.into_iter()
+            .for_each(|scheduled| (scheduled.callback)(scheduled.when));
+
+        self.update_scheduler_wakeup();
+    }
+
+    /// HRIR dataset used by new panner nodes with the HRTF panning model
+    pub(crate) fn hrtf_dataset(&self) -> HrtfDataset {
+        self.inner.hrtf_dataset.lock().unwrap().clone()
+    }
+
+    pub(crate) fn set_hrtf_dataset(&self, dataset: HrtfDataset) {
+        // For an online AudioContext, pre-create the HRTF processor for panner nodes
+        if !self.offline() {
+            dataset.load_processor(self.sample_rate() as u32);
+        }
+
+        *self.inner.hrtf_dataset.lock().unwrap() = dataset;
+    }
 }
 
 #[cfg(test)]
@@ -19,6 +19,9 @@ pub use offline::*;
 mod online;
 pub use online::*;
 
+mod scheduler;
+pub use scheduler::ScheduledCallbackId;
+
 // magic node values
 /// Destination node id is always at index 0
 pub(crate) const DESTINATION_NODE_ID: AudioNodeId = AudioNodeId(0);
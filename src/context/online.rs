@@ -442,6 +442,9 @@ impl AudioContext {
             .into_iter()
             .for_each(|m| self.base().send_control_msg(m));
 
+        // the new render thread should notify the scheduled callbacks
+        self.base().update_scheduler_wakeup();
+
         // explicitly release the lock to prevent concurrent render threads
         drop(backend_manager_guard);
 
@@ -0,0 +1,113 @@
+//! Lookahead scheduler of control thread callbacks
+
+/// Default lookahead of the scheduler, in seconds
+pub(crate) const DEFAULT_SCHEDULER_LOOKAHEAD: f64 = 0.1;
+
+/// Callback scheduled with [`BaseAudioContext::schedule_callback`](crate::context::BaseAudioContext::schedule_callback)
+pub(crate) type ScheduledCallbackFn = dyn FnOnce(f64) + Send + 'static;
+
+/// Identifier of a callback scheduled with
+/// [`BaseAudioContext::schedule_callback`](crate::context::BaseAudioContext::schedule_callback)
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ScheduledCallbackId(u64);
+
+pub(crate) struct ScheduledCallback {
+    pub when: f64,
+    id: ScheduledCallbackId,
+    pub callback: Box<ScheduledCallbackFn>,
+}
+
+/// Queue of the callbacks scheduled on a context, with their target times
+pub(crate) struct Scheduler {
+    lookahead: f64,
+    next_id: u64,
+    /// whether the event handler running the callbacks is set on the context
+    handler_registered: bool,
+    /// sorted by target time, then by insertion order
+    callbacks: Vec<ScheduledCallback>,
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self {
+            lookahead: DEFAULT_SCHEDULER_LOOKAHEAD,
+            next_id: 0,
+            handler_registered: false,
+            callbacks: Vec::new(),
+        }
+    }
+}
+
+impl Scheduler {
+    pub fn lookahead(&self) -> f64 {
+        self.lookahead
+    }
+
+    pub fn set_lookahead(&mut self, lookahead: f64) {
+        self.lookahead = lookahead;
+    }
+
+    /// Returns true the first time it is called, to lazily set the event handler
+    pub fn register_handler(&mut self) -> bool {
+        !std::mem::replace(&mut self.handler_registered, true)
+    }
+
+    pub fn insert(&mut self, when: f64, callback: Box<ScheduledCallbackFn>) -> ScheduledCallbackId {
+        let id = ScheduledCallbackId(self.next_id);
+        self.next_id += 1;
+
+        let index = self.callbacks.partition_point(|c| c.when <= when);
+        self.callbacks
+            .insert(index, ScheduledCallback { when, id, callback });
+
+        id
+    }
+
+    pub fn remove(&mut self, id: ScheduledCallbackId) -> bool {
+        let len = self.callbacks.len();
+        self.callbacks.retain(|c| c.id != id);
+        self.callbacks.len() != len
+    }
+
+    /// Remove and return the callbacks that are due at the given context time
+    pub fn take_due(&mut self, current_time: f64) -> Vec<ScheduledCallback> {
+        let lookahead = self.lookahead;
+        let count = self
+            .callbacks
+            .partition_point(|c| c.when - lookahead <= current_time);
+        self.callbacks.drain(..count).collect()
+    }
+
+    /// Context time at which the next callback is due
+    pub fn wakeup_time(&self) -> f64 {
+        self.callbacks
+            .first()
+            .map_or(f64::INFINITY, |c| c.when - self.lookahead)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_take_due() {
+        let mut scheduler = Scheduler::default();
+        assert_eq!(scheduler.wakeup_time(), f64::INFINITY);
+
+        scheduler.insert(1., Box::new(|_| ()));
+        let id = scheduler.insert(0.5, Box::new(|_| ()));
+        scheduler.insert(0.5, Box::new(|_| ()));
+        let lookahead = DEFAULT_SCHEDULER_LOOKAHEAD;
+        assert_eq!(scheduler.wakeup_time(), 0.5 - lookahead);
+
+        assert!(scheduler.remove(id));
+        assert!(!scheduler.remove(id));
+
+        assert!(scheduler.take_due(0.3).is_empty());
+        let due = scheduler.take_due(0.5 - lookahead);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].when, 0.5);
+        assert_eq!(scheduler.wakeup_time(), 1. - lookahead);
+    }
+}
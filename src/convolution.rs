@@ -0,0 +1,986 @@
+//! Non-uniform partitioned convolution engine
+//!
+//! This is used in the [`ConvolverNode`](crate::node::ConvolverNode) when
+//! [`ConvolverPartitioning::NonUniform`](crate::node::ConvolverPartitioning) is selected.
+//!
+//! The impulse response is split in segments of increasing partition sizes: the head of the
+//! response is convolved with partitions of a single render quantum (so there is no added
+//! latency) while the tail is convolved with larger, cheaper, partitions. Each segment is a
+//! uniformly partitioned overlap-save convolver (UPOLS) with a frequency-domain delay line.
+//!
+//! A segment of partition size `N` starting at offset `s` in the impulse response only
+//! contributes to the output `s` samples after the start of its input block, i.e. `s - N`
+//! samples after the block has been gathered. The layout guarantees `s >= 2N` for all segments
+//! but the first one, so the large segments are scheduled on a background thread at least one
+//! full partition ahead of their deadline. The results are collected without blocking, a
+//! result missing at its deadline is computed in the render thread instead, from a copy of the
+//! delay line kept up to date with the spectra computed by the background thread.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use crossbeam_channel::{Receiver, Sender};
+use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
+
+use crate::RENDER_QUANTUM_SIZE;
+
+/// Number of partitions in a segment before doubling the partition size
+const PARTITIONS_PER_SEGMENT: usize = 4;
+
+/// Largest partition size of the layout
+const MAX_PARTITION_SIZE: usize = RENDER_QUANTUM_SIZE * 64;
+
+/// Smallest partition size computed on the background thread, smaller segments are cheap
+/// enough to be computed in the render thread and have short deadlines.
+const MIN_BACKGROUND_PARTITION_SIZE: usize = RENDER_QUANTUM_SIZE * 8;
+
+/// Number of blocks the background thread can lag behind its deadlines before its segment is
+/// entirely computed in the render thread
+const LATE_BLOCKS: usize = 2;
+
+/// Describes a segment of the impulse response convolved with uniform partitions
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct SegmentLayout {
+    /// Position of the first sample of the segment in the impulse response
+    offset: usize,
+    /// Size of the partitions of this segment
+    block_size: usize,
+    /// Number of partitions of this segment
+    number_of_partitions: usize,
+}
+
+/// Compute the non-uniform partitioning for an impulse response of the given length
+///
+/// Segments are made of `PARTITIONS_PER_SEGMENT` partitions, the partition size doubles
+/// from one segment to the next until `MAX_PARTITION_SIZE` is reached.
+fn non_uniform_layout(length: usize) -> Vec<SegmentLayout> {
+    let mut layout = vec![];
+    let mut offset = 0;
+    let mut block_size = RENDER_QUANTUM_SIZE;
+
+    while offset < length {
+        let remaining = (length - offset).div_ceil(block_size);
+        let number_of_partitions = if block_size == MAX_PARTITION_SIZE {
+            remaining
+        } else {
+            remaining.min(PARTITIONS_PER_SEGMENT)
+        };
+
+        layout.push(SegmentLayout {
+            offset,
+            block_size,
+            number_of_partitions,
+        });
+
+        offset += number_of_partitions * block_size;
+        block_size = (block_size * 2).min(MAX_PARTITION_SIZE);
+    }
+
+    layout
+}
+
+/// Spectra of the partitions of a segment of the impulse response
+///
+/// The kernel is immutable, it is shared between the render thread and the background thread.
+struct Kernel {
+    block_size: usize,
+    r2c: Arc<dyn RealToComplex<f32>>,
+    c2r: Arc<dyn ComplexToReal<f32>>,
+    /// Spectra of the impulse response partitions, normalized for the inverse transform
+    partitions: Vec<Vec<Complex<f32>>>,
+}
+
+impl Kernel {
+    fn new(planner: &mut RealFftPlanner<f32>, impulse: &[f32], block_size: usize) -> Self {
+        let fft_size = 2 * block_size;
+        let r2c = planner.plan_fft_forward(fft_size);
+        let c2r = planner.plan_fft_inverse(fft_size);
+
+        let mut fft_input = r2c.make_input_vec();
+        let mut r2c_scratch = r2c.make_scratch_vec();
+        let norm = 1. / fft_size as f32;
+
+        let partitions = impulse
+            .chunks(block_size)
+            .map(|chunk| {
+                fft_input.fill(0.);
+                fft_input[..chunk.len()].copy_from_slice(chunk);
+
+                let mut spectrum = r2c.make_output_vec();
+                // input and output have the right lengths, so this cannot fail
+                let _ = r2c.process_with_scratch(&mut fft_input, &mut spectrum, &mut r2c_scratch);
+                spectrum.iter_mut().for_each(|c| *c *= norm);
+
+                spectrum
+            })
+            .collect();
+
+        Self {
+            block_size,
+            r2c,
+            c2r,
+            partitions,
+        }
+    }
+}
+
+/// Uniformly partitioned overlap-save convolver for a single segment of the impulse response
+struct Segment {
+    kernel: Arc<Kernel>,
+    /// Frequency-domain delay line, spectra of the most recent input frames
+    fdl: Vec<Vec<Complex<f32>>>,
+    /// Position of the most recent spectrum in the delay line
+    fdl_index: usize,
+    /// Previous and current input blocks
+    window: Vec<f32>,
+    fft_input: Vec<f32>,
+    fft_output: Vec<f32>,
+    accumulator: Vec<Complex<f32>>,
+    r2c_scratch: Vec<Complex<f32>>,
+    c2r_scratch: Vec<Complex<f32>>,
+}
+
+impl Segment {
+    fn new(kernel: Arc<Kernel>) -> Self {
+        let length = kernel.partitions.len();
+        Self::with_delay_line(kernel, length)
+    }
+
+    /// Create a segment whose delay line holds `length` spectra, at least one per partition
+    fn with_delay_line(kernel: Arc<Kernel>, length: usize) -> Self {
+        Self {
+            fdl: vec![kernel.r2c.make_output_vec(); length],
+            fdl_index: 0,
+            window: vec![0.; 2 * kernel.block_size],
+            fft_input: kernel.r2c.make_input_vec(),
+            fft_output: kernel.c2r.make_output_vec(),
+            accumulator: kernel.r2c.make_output_vec(),
+            r2c_scratch: kernel.r2c.make_scratch_vec(),
+            c2r_scratch: kernel.c2r.make_scratch_vec(),
+            kernel,
+        }
+    }
+
+    /// Convolve a block of `block_size` samples, the output replaces the input in place
+    fn process(&mut self, block: &mut [f32]) {
+        let n = self.kernel.block_size;
+
+        // slide the input window by one block
+        self.window.copy_within(n.., 0);
+        self.window[n..].copy_from_slice(block);
+
+        self.push_window();
+        self.convolve(block);
+    }
+
+    /// Rebuild the delay line from the input history and convolve its last block, the output
+    /// replaces the last block in place
+    ///
+    /// The history must contain `partitions + 1` blocks. The next call to `process` continues
+    /// from the last block of the history.
+    fn process_from_history(&mut self, history: &mut [f32]) {
+        let n = self.kernel.block_size;
+        let end = history.len();
+
+        // push the frames from the oldest to the most recent one
+        for p in (0..self.fdl.len()).rev() {
+            let stop = end - p * n;
+            self.window.copy_from_slice(&history[stop - 2 * n..stop]);
+            self.push_window();
+        }
+
+        self.convolve(&mut history[end - n..]);
+    }
+
+    /// Spectrum of the most recent input frame
+    fn spectrum(&self) -> &[Complex<f32>] {
+        &self.fdl[self.fdl_index]
+    }
+
+    /// Position in the delay line of the frame ending with the block of the given index
+    ///
+    /// The delay line is indexed by block, older frames are found at increasing positions.
+    fn slot(&self, block: usize) -> usize {
+        let len = self.fdl.len();
+        (len - block % len) % len
+    }
+
+    /// Store the spectrum of the frame ending with the block of the given index
+    fn store_spectrum(&mut self, block: usize, spectrum: &[Complex<f32>]) {
+        let slot = self.slot(block);
+        self.fdl[slot].copy_from_slice(spectrum);
+    }
+
+    /// Convolve the block of the given index, `window` holds this block and the previous one
+    ///
+    /// The spectra of the frames ending with the previous blocks must be stored in the delay
+    /// line, which must be longer than the number of partitions.
+    fn process_block(&mut self, block: usize, window: &[f32], output: &mut [f32]) {
+        self.fdl_index = self.slot(block);
+        self.fft_input.copy_from_slice(window);
+        let _ = self.kernel.r2c.process_with_scratch(
+            &mut self.fft_input,
+            &mut self.fdl[self.fdl_index],
+            &mut self.r2c_scratch,
+        );
+
+        self.convolve(output);
+    }
+
+    /// Store the spectrum of the input window in the delay line
+    fn push_window(&mut self) {
+        let len = self.fdl.len();
+
+        self.fft_input.copy_from_slice(&self.window);
+        self.fdl_index = (self.fdl_index + len - 1) % len;
+        let _ = self.kernel.r2c.process_with_scratch(
+            &mut self.fft_input,
+            &mut self.fdl[self.fdl_index],
+            &mut self.r2c_scratch,
+        );
+    }
+
+    /// Apply the partitions to the delay line, only the last half of the frame is valid
+    fn convolve(&mut self, output: &mut [f32]) {
+        let n = self.kernel.block_size;
+        let len = self.fdl.len();
+
+        // partition `p` is applied to the frame received `p` blocks ago
+        self.accumulator.fill(Complex::default());
+
+        for (p, partition) in self.kernel.partitions.iter().enumerate() {
+            let spectrum = &self.fdl[(self.fdl_index + p) % len];
+
+            self.accumulator
+                .iter_mut()
+                .zip(partition.iter().zip(spectrum.iter()))
+                .for_each(|(a, (h, x))| *a += h * x);
+        }
+
+        // the DC and Nyquist bins of a real signal have no imaginary part
+        self.accumulator[0].im = 0.;
+        self.accumulator[n].im = 0.;
+
+        let _ = self.kernel.c2r.process_with_scratch(
+            &mut self.accumulator,
+            &mut self.fft_output,
+            &mut self.c2r_scratch,
+        );
+
+        // overlap-save: only the last half of the frame is valid
+        output.copy_from_slice(&self.fft_output[n..]);
+    }
+}
+
+/// Whether the segment is computed on the background thread, if any
+fn is_background(layout: &SegmentLayout) -> bool {
+    layout.block_size >= MIN_BACKGROUND_PARTITION_SIZE
+}
+
+/// Number of blocks of a background segment which can be in flight
+///
+/// A job is in flight from the moment its block is gathered until the render quantum its
+/// output is due, plus `LATE_BLOCKS` blocks to give a late background thread a chance to
+/// catch up.
+fn pool_size(layout: &SegmentLayout) -> usize {
+    let SegmentLayout {
+        offset, block_size, ..
+    } = *layout;
+
+    (offset + RENDER_QUANTUM_SIZE - block_size) / block_size + 1 + LATE_BLOCKS
+}
+
+/// Number of jobs of a background segment which can be queued: the pool, plus the job which
+/// resumes the segment on the background thread after its blocks were computed inline
+fn max_jobs_in_flight(layout: &SegmentLayout) -> usize {
+    pool_size(layout) + 1
+}
+
+/// Block of samples sent to, and received back from, the background thread
+struct Job {
+    /// Index of the convolver in the background thread, to send back the result
+    convolver: usize,
+    /// Index of the segment in the background thread
+    segment: usize,
+    /// Index of the segment in the convolver
+    slot: usize,
+    /// Absolute position (in frames) at which the convolved block must be added to the output
+    target: usize,
+    /// Input block, replaced by its convolution. The job resuming a segment holds the input
+    /// history instead, and its last block is replaced by the convolution.
+    block: Vec<f32>,
+    /// Spectrum of the frame ending with the block, to update the delay line of the render
+    /// thread
+    spectrum: Vec<Complex<f32>>,
+}
+
+impl Job {
+    fn new(kernel: &Kernel, length: usize) -> Self {
+        Self {
+            convolver: 0,
+            segment: 0,
+            slot: 0,
+            target: 0,
+            block: vec![0.; length],
+            spectrum: kernel.r2c.make_output_vec(),
+        }
+    }
+}
+
+/// Background thread computing the largest partitions of the convolvers of an impulse response
+///
+/// A single thread is shared by the convolvers of all the channels of the impulse response. It
+/// is started by [`Self::spawn`] once the convolvers are created, and ends when they are dropped.
+pub(crate) struct ConvolutionWorker {
+    segments: Vec<Segment>,
+    jobs: Sender<Job>,
+    job_receiver: Receiver<Job>,
+    results: Vec<Sender<Job>>,
+}
+
+impl ConvolutionWorker {
+    /// Prepare a background thread for the given number of convolvers of an impulse response
+    /// of the given length
+    pub fn new(impulse_length: usize, number_of_convolvers: usize) -> Self {
+        let jobs_per_convolver: usize = non_uniform_layout(impulse_length)
+            .iter()
+            .filter(|layout| is_background(layout))
+            .map(max_jobs_in_flight)
+            .sum();
+
+        // all the blocks of all the convolvers can be queued, sending never fails
+        let capacity = (jobs_per_convolver * number_of_convolvers).max(1);
+        let (jobs, job_receiver) = crossbeam_channel::bounded(capacity);
+
+        Self {
+            segments: vec![],
+            jobs,
+            job_receiver,
+            results: vec![],
+        }
+    }
+
+    /// Start the background thread, if any segment of the convolvers runs on it
+    pub fn spawn(self) {
+        let Self {
+            mut segments,
+            job_receiver,
+            results,
+            ..
+        } = self;
+
+        if segments.is_empty() {
+            return;
+        }
+
+        std::thread::Builder::new()
+            .name("web-audio-api convolver".into())
+            .spawn(move || {
+                // the loop ends when all the convolvers are dropped
+                for mut job in job_receiver.iter() {
+                    let segment = &mut segments[job.segment];
+
+                    if job.block.len() > segment.kernel.block_size {
+                        // the previous blocks were computed in the render thread
+                        segment.process_from_history(&mut job.block);
+                    } else {
+                        segment.process(&mut job.block);
+                    }
+
+                    job.spectrum.copy_from_slice(segment.spectrum());
+                    // the result channel can hold all the blocks of the convolver, so this
+                    // never blocks and only fails if the convolver is dropped
+                    let _ = results[job.convolver].send(job);
+                }
+            })
+            .expect("Unable to spawn the convolver background thread");
+    }
+}
+
+/// Render thread side of the background thread
+struct WorkerHandle {
+    /// Index of the convolver in the background thread
+    convolver: usize,
+    jobs: Sender<Job>,
+    results: Receiver<Job>,
+}
+
+/// Block of a background segment waiting for its output
+#[derive(Clone, Copy)]
+struct PendingBlock {
+    target: usize,
+    /// Whether the block was sent to the background thread
+    submitted: bool,
+    /// Whether the block was computed inline
+    computed: bool,
+}
+
+/// Render thread side of a segment computed on the background thread
+///
+/// The render thread never waits for the background thread: if a result is not received at
+/// its deadline, the block is computed inline and the late result is discarded when it
+/// arrives. The delay line of the inline copy of the segment is indexed by block and updated
+/// with the spectra received from the background thread, so that a missed deadline only costs
+/// the convolution of that block.
+struct BackgroundSegment {
+    /// Index of the segment in the background thread
+    index: usize,
+    /// Copy of the segment to compute the blocks inline
+    fallback: Segment,
+    /// Most recent input blocks, enough to compute any block in flight and to resume the
+    /// segment on the background thread
+    history: Vec<f32>,
+    /// Target of the last block of the history
+    history_target: usize,
+    /// Blocks waiting for their output, in input order
+    pending: VecDeque<PendingBlock>,
+    /// Recycled jobs, available to submit the next blocks
+    pool: Vec<Job>,
+    /// Job holding the input history, to resume the segment on the background thread after
+    /// it was detached. Dropped if the background thread is gone.
+    resume: Option<Job>,
+    /// Output of the blocks computed inline
+    output: Vec<f32>,
+    /// Set when the background thread cannot keep up, the blocks are then computed inline
+    /// until the jobs in flight are received
+    detached: bool,
+}
+
+impl BackgroundSegment {
+    fn new(index: usize, kernel: Arc<Kernel>, layout: &SegmentLayout) -> Self {
+        let block_size = layout.block_size;
+        let pool_size = pool_size(layout);
+        let number_of_partitions = kernel.partitions.len();
+        let history_len = (number_of_partitions.max(pool_size) + 1) * block_size;
+        let resume_len = (number_of_partitions + 1) * block_size;
+
+        Self {
+            index,
+            pool: (0..pool_size)
+                .map(|_| Job::new(&kernel, block_size))
+                .collect(),
+            resume: Some(Job::new(&kernel, resume_len)),
+            // a block may be received while up to `pool_size` older blocks are not yet
+            // computed inline, their frames must not be overwritten
+            fallback: Segment::with_delay_line(kernel, number_of_partitions + pool_size),
+            history: vec![0.; history_len],
+            history_target: 0,
+            pending: VecDeque::with_capacity(2 * pool_size + 1),
+            output: vec![0.; block_size],
+            detached: false,
+        }
+    }
+
+    fn push_history(&mut self, block: &[f32], target: usize) {
+        let n = block.len();
+        let len = self.history.len();
+
+        self.history.copy_within(n.., 0);
+        self.history[len - n..].copy_from_slice(block);
+        self.history_target = target;
+    }
+
+    /// Submit the last block of the history to the background thread, or queue it to be
+    /// computed inline at its deadline
+    fn submit(&mut self, worker: &WorkerHandle, slot: usize) {
+        let target = self.history_target;
+        let in_flight = self.pending.iter().any(|pending| pending.submitted);
+
+        let job = if !self.detached {
+            // an empty pool means the background thread is far behind its deadlines
+            self.pool.pop()
+        } else if !in_flight && !worker.jobs.is_full() {
+            // the background thread caught up, resume the segment from the input history
+            self.resume.take()
+        } else {
+            None
+        };
+
+        let Some(mut job) = job else {
+            self.queue_inline(target);
+            return;
+        };
+
+        let len = job.block.len();
+        job.block
+            .copy_from_slice(&self.history[self.history.len() - len..]);
+        job.convolver = worker.convolver;
+        job.segment = self.index;
+        job.slot = slot;
+        job.target = target;
+
+        match worker.jobs.try_send(job) {
+            Ok(()) => {
+                self.detached = false;
+                self.pending.push_back(PendingBlock {
+                    target,
+                    submitted: true,
+                    computed: false,
+                });
+            }
+            Err(e) => {
+                let disconnected = e.is_disconnected();
+                let job = e.into_inner();
+
+                if job.block.len() == self.output.len() {
+                    self.pool.push(job);
+                } else if !disconnected {
+                    self.resume = Some(job);
+                }
+
+                self.queue_inline(target);
+            }
+        }
+    }
+
+    /// Queue the block to be computed inline, the segment stays detached until the jobs in
+    /// flight are received
+    fn queue_inline(&mut self, target: usize) {
+        self.detached = true;
+        self.pending.push_back(PendingBlock {
+            target,
+            submitted: false,
+            computed: false,
+        });
+    }
+
+    /// Add the result of a job to the output, unless it was already computed inline
+    fn complete(&mut self, job: Job, ring: &mut [f32]) {
+        let n = self.output.len();
+        let position = self
+            .pending
+            .iter()
+            .position(|pending| pending.submitted && pending.target == job.target);
+
+        if let Some(pending) = position.and_then(|i| self.pending.remove(i)) {
+            if !pending.computed {
+                let len = job.block.len();
+                add_to_ring(ring, job.target, &job.block[len - n..]);
+                self.fallback.store_spectrum(job.target / n, &job.spectrum);
+            }
+        }
+
+        if job.block.len() == n {
+            self.pool.push(job);
+        } else {
+            self.resume = Some(job);
+        }
+    }
+
+    /// Compute inline the blocks which are due before `deadline`
+    fn compute_due(&mut self, deadline: usize, ring: &mut [f32]) {
+        for i in 0..self.pending.len() {
+            let PendingBlock {
+                target, computed, ..
+            } = self.pending[i];
+
+            if target >= deadline {
+                break;
+            }
+
+            if !computed {
+                self.compute_inline(target, ring);
+                self.pending[i].computed = true;
+            }
+        }
+
+        // the blocks not sent to the background thread are done
+        self.pending
+            .retain(|pending| pending.submitted || !pending.computed);
+    }
+
+    /// Compute inline the block of the history with the given target
+    ///
+    /// The spectra of the previous blocks are in the delay line: the blocks are computed in
+    /// input order, and a block not computed inline was received before its deadline.
+    fn compute_inline(&mut self, target: usize, ring: &mut [f32]) {
+        let n = self.output.len();
+        let end = self.history.len() - (self.history_target - target);
+
+        self.fallback.process_block(
+            target / n,
+            &self.history[end - 2 * n..end],
+            &mut self.output,
+        );
+        add_to_ring(ring, target, &self.output);
+    }
+}
+
+/// Segment whose convolution is either computed in the render thread, or in the background
+enum SegmentRunner {
+    Local(Box<Segment>),
+    Background(Box<BackgroundSegment>),
+}
+
+struct SegmentState {
+    layout: SegmentLayout,
+    /// Input samples gathered for the next block
+    block: Vec<f32>,
+    fill: usize,
+    runner: SegmentRunner,
+}
+
+/// Zero latency convolver using non-uniform partitions
+pub(crate) struct PartitionedConvolver {
+    segments: Vec<SegmentState>,
+    worker: Option<WorkerHandle>,
+    /// Output accumulation buffer indexed by absolute frame position, its length is a power of two
+    ring: Vec<f32>,
+    /// Absolute position (in frames) of the current render quantum
+    frame: usize,
+}
+
+impl std::fmt::Debug for PartitionedConvolver {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let layout: Vec<_> = self.segments.iter().map(|s| s.layout).collect();
+
+        f.debug_struct("PartitionedConvolver")
+            .field("layout", &layout)
+            .field("background_thread", &self.worker.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartitionedConvolver {
+    /// Create a new convolver for the given impulse response
+    ///
+    /// If a `worker` is provided, the largest partitions are computed on its background
+    /// thread, which must be started with [`ConvolutionWorker::spawn`] once all the
+    /// convolvers sharing it are created.
+    pub fn new(impulse: &[f32], mut worker: Option<&mut ConvolutionWorker>) -> Self {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let layout = non_uniform_layout(impulse.len());
+
+        let mut segments = Vec::with_capacity(layout.len());
+        let mut number_of_jobs = 0;
+
+        for layout in layout {
+            let SegmentLayout {
+                offset,
+                block_size,
+                number_of_partitions,
+            } = layout;
+
+            let end = (offset + block_size * number_of_partitions).min(impulse.len());
+            let kernel = Arc::new(Kernel::new(&mut planner, &impulse[offset..end], block_size));
+
+            let runner = match worker.as_deref_mut() {
+                Some(worker) if is_background(&layout) => {
+                    worker.segments.push(Segment::new(Arc::clone(&kernel)));
+                    let index = worker.segments.len() - 1;
+                    number_of_jobs += max_jobs_in_flight(&layout);
+
+                    SegmentRunner::Background(Box::new(BackgroundSegment::new(
+                        index, kernel, &layout,
+                    )))
+                }
+                _ => SegmentRunner::Local(Box::new(Segment::new(kernel))),
+            };
+
+            segments.push(SegmentState {
+                layout,
+                block: vec![0.; block_size],
+                fill: 0,
+                runner,
+            });
+        }
+
+        let worker = worker.filter(|_| number_of_jobs > 0).map(|worker| {
+            let (sender, results) = crossbeam_channel::bounded(number_of_jobs);
+            worker.results.push(sender);
+
+            WorkerHandle {
+                convolver: worker.results.len() - 1,
+                jobs: worker.jobs.clone(),
+                results,
+            }
+        });
+
+        // the furthest sample written ahead of the current render quantum is
+        // `offset + RENDER_QUANTUM_SIZE` for the last segment
+        let ring_len = segments
+            .last()
+            .map(|s| s.layout.offset + s.layout.block_size)
+            .unwrap_or(0)
+            + 2 * RENDER_QUANTUM_SIZE;
+
+        Self {
+            segments,
+            worker,
+            ring: vec![0.; ring_len.next_power_of_two()],
+            frame: 0,
+        }
+    }
+
+    /// Convolve a render quantum of input samples
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        let frame = self.frame;
+
+        for (slot, segment) in self.segments.iter_mut().enumerate() {
+            let block_size = segment.layout.block_size;
+
+            segment.block[segment.fill..segment.fill + RENDER_QUANTUM_SIZE].copy_from_slice(input);
+            segment.fill += RENDER_QUANTUM_SIZE;
+
+            if segment.fill < block_size {
+                continue;
+            }
+
+            segment.fill = 0;
+            // the block started at `frame + RENDER_QUANTUM_SIZE - block_size`, its convolution
+            // with the segment starts `offset` frames later
+            let target = frame + RENDER_QUANTUM_SIZE - block_size + segment.layout.offset;
+
+            match &mut segment.runner {
+                SegmentRunner::Local(convolver) => {
+                    convolver.process(&mut segment.block);
+                    add_to_ring(&mut self.ring, target, &segment.block);
+                }
+                SegmentRunner::Background(background) => {
+                    background.push_history(&segment.block, target);
+
+                    match self.worker.as_ref() {
+                        Some(worker) => background.submit(worker, slot),
+                        None => background.queue_inline(target),
+                    }
+                }
+            }
+        }
+
+        if let Some(worker) = self.worker.as_ref() {
+            while let Ok(job) = worker.results.try_recv() {
+                if let SegmentRunner::Background(background) = &mut self.segments[job.slot].runner {
+                    background.complete(job, &mut self.ring);
+                }
+            }
+        }
+
+        // never wait for the background thread
+        for segment in self.segments.iter_mut() {
+            if let SegmentRunner::Background(background) = &mut segment.runner {
+                background.compute_due(frame + RENDER_QUANTUM_SIZE, &mut self.ring);
+            }
+        }
+
+        let mask = self.ring.len() - 1;
+        let start = frame & mask;
+        let current = &mut self.ring[start..start + RENDER_QUANTUM_SIZE];
+        output.copy_from_slice(current);
+        current.fill(0.);
+
+        self.frame += RENDER_QUANTUM_SIZE;
+    }
+}
+
+fn add_to_ring(ring: &mut [f32], target: usize, block: &[f32]) {
+    let mask = ring.len() - 1;
+
+    block
+        .iter()
+        .enumerate()
+        .for_each(|(i, v)| ring[(target + i) & mask] += v);
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    fn direct_convolution(input: &[f32], impulse: &[f32]) -> Vec<f32> {
+        let mut output = vec![0.; input.len()];
+
+        for (n, o) in output.iter_mut().enumerate() {
+            for (k, h) in impulse.iter().enumerate().take(n + 1) {
+                *o += h * input[n - k];
+            }
+        }
+
+        output
+    }
+
+    fn noise(length: usize, mut seed: u32) -> Vec<f32> {
+        (0..length)
+            .map(|_| {
+                // xorshift
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                (seed as f32 / u32::MAX as f32) * 2. - 1.
+            })
+            .collect()
+    }
+
+    fn partitioned_convolution(input: &[f32], impulse: &[f32], background: bool) -> Vec<f32> {
+        let mut worker = background.then(|| ConvolutionWorker::new(impulse.len(), 1));
+        let convolver = PartitionedConvolver::new(impulse, worker.as_mut());
+        if let Some(worker) = worker {
+            worker.spawn();
+        }
+
+        run(convolver, input)
+    }
+
+    fn run(mut convolver: PartitionedConvolver, input: &[f32]) -> Vec<f32> {
+        let mut output = vec![0.; input.len()];
+
+        input
+            .chunks(RENDER_QUANTUM_SIZE)
+            .zip(output.chunks_mut(RENDER_QUANTUM_SIZE))
+            .for_each(|(i, o)| convolver.process(i, o));
+
+        output
+    }
+
+    #[test]
+    fn test_layout() {
+        let layout = non_uniform_layout(RENDER_QUANTUM_SIZE * 100_000);
+
+        // contiguous segments, starting with a single render quantum
+        assert_eq!(layout[0].offset, 0);
+        assert_eq!(layout[0].block_size, RENDER_QUANTUM_SIZE);
+
+        layout.windows(2).for_each(|w| {
+            assert_eq!(
+                w[1].offset,
+                w[0].offset + w[0].block_size * w[0].number_of_partitions
+            );
+            assert!(w[1].block_size <= MAX_PARTITION_SIZE);
+            // the block is due at least one full partition after it is gathered
+            assert!(w[1].offset >= 2 * w[1].block_size);
+        });
+
+        // short impulse response
+        let layout = non_uniform_layout(10);
+        assert_eq!(
+            layout,
+            vec![SegmentLayout {
+                offset: 0,
+                block_size: RENDER_QUANTUM_SIZE,
+                number_of_partitions: 1,
+            }]
+        );
+
+        assert!(non_uniform_layout(0).is_empty());
+    }
+
+    #[test]
+    fn test_against_direct_convolution() {
+        let input = noise(RENDER_QUANTUM_SIZE * 200, 1);
+
+        for length in [1, 100, RENDER_QUANTUM_SIZE * 4, 5000, 20_000] {
+            let impulse = noise(length, 2);
+            let expected = direct_convolution(&input, &impulse);
+
+            for background in [false, true] {
+                let result = partitioned_convolution(&input, &impulse, background);
+                assert_float_eq!(result[..], expected[..], abs_all <= 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_long_impulse_response() {
+        // spans several segments of the largest partition size
+        let length = MAX_PARTITION_SIZE * 5 + 17;
+        let mut impulse = vec![0.; length];
+        impulse[0] = 1.;
+        impulse[MAX_PARTITION_SIZE * 3 + 5] = 0.5;
+        impulse[length - 1] = -0.25;
+
+        let mut input =
+            vec![0.; (length + RENDER_QUANTUM_SIZE * 4).next_multiple_of(RENDER_QUANTUM_SIZE)];
+        input[3] = 1.;
+
+        for background in [false, true] {
+            let result = partitioned_convolution(&input, &impulse, background);
+
+            let mut expected = vec![0.; input.len()];
+            expected[3] = 1.;
+            expected[MAX_PARTITION_SIZE * 3 + 8] = 0.5;
+            expected[length + 2] = -0.25;
+
+            assert_float_eq!(result[..], expected[..], abs_all <= 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_shared_background_thread() {
+        let input = noise(RENDER_QUANTUM_SIZE * 200, 1);
+        let impulses = [noise(20_000, 2), noise(20_000, 3)];
+
+        // a single background thread for all the channels of the impulse response
+        let mut worker = ConvolutionWorker::new(20_000, 2);
+        let convolvers: Vec<_> = impulses
+            .iter()
+            .map(|impulse| PartitionedConvolver::new(impulse, Some(&mut worker)))
+            .collect();
+        worker.spawn();
+
+        for (convolver, impulse) in convolvers.into_iter().zip(impulses.iter()) {
+            let expected = direct_convolution(&input, impulse);
+            let result = run(convolver, &input);
+            assert_float_eq!(result[..], expected[..], abs_all <= 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_stalled_background_thread() {
+        let input = noise(RENDER_QUANTUM_SIZE * 200, 1);
+        let impulse = noise(20_000, 2);
+        let expected = direct_convolution(&input, &impulse);
+
+        // the background thread never processes the jobs, the render thread must neither
+        // block nor drop the contribution of the tail
+        let mut worker = ConvolutionWorker::new(impulse.len(), 1);
+        let convolver = PartitionedConvolver::new(&impulse, Some(&mut worker));
+        let result = run(convolver, &input);
+        assert_float_eq!(result[..], expected[..], abs_all <= 1e-3);
+        drop(worker);
+
+        // the background thread is gone
+        let mut worker = ConvolutionWorker::new(impulse.len(), 1);
+        let convolver = PartitionedConvolver::new(&impulse, Some(&mut worker));
+        drop(worker);
+        let result = run(convolver, &input);
+        assert_float_eq!(result[..], expected[..], abs_all <= 1e-3);
+    }
+
+    #[test]
+    fn test_resumed_background_thread() {
+        let input = noise(RENDER_QUANTUM_SIZE * 200, 1);
+        let impulse = noise(20_000, 2);
+        let expected = direct_convolution(&input, &impulse);
+
+        let mut worker = ConvolutionWorker::new(impulse.len(), 1);
+        let mut convolver = PartitionedConvolver::new(&impulse, Some(&mut worker));
+        let mut result = vec![0.; input.len()];
+
+        let detached = |convolver: &PartitionedConvolver| {
+            convolver.segments.iter().any(|segment| {
+                matches!(&segment.runner, SegmentRunner::Background(background) if background.detached)
+            })
+        };
+
+        let mut chunks = input
+            .chunks(RENDER_QUANTUM_SIZE)
+            .zip(result.chunks_mut(RENDER_QUANTUM_SIZE));
+
+        // the background thread starts late, the blocks are computed inline meanwhile
+        chunks
+            .by_ref()
+            .take(100)
+            .for_each(|(i, o)| convolver.process(i, o));
+        assert!(detached(&convolver));
+
+        worker.spawn();
+        chunks.for_each(|(i, o)| {
+            convolver.process(i, o);
+            std::thread::sleep(std::time::Duration::from_millis(1));
+        });
+
+        assert_float_eq!(result[..], expected[..], abs_all <= 1e-3);
+        // the segments resumed on the background thread
+        assert!(!detached(&convolver));
+    }
+}
@@ -31,6 +31,9 @@ pub(crate) enum EventType {
     Message(AudioNodeId),
     Complete,
     AudioProcessing(AudioNodeId),
+    Pitch(AudioNodeId),
+    Onset(AudioNodeId),
+    Scheduler,
 }
 
 /// The Error Event interface
@@ -71,6 +74,40 @@ impl Drop for AudioProcessingEvent {
     }
 }
 
+/// The PitchEvent interface, dispatched by the
+/// [`PitchDetectorNode`](crate::node::PitchDetectorNode)
+///
+/// Note that this event is not part of the Web Audio API specification.
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct PitchEvent {
+    /// The detected fundamental frequency, in Hz
+    pub frequency: f32,
+    /// The confidence of the detection, in the range [0, 1]
+    pub confidence: f32,
+    /// The time of the end of the analysed frame, in the same time coordinate system
+    /// as the AudioContext's currentTime
+    pub time: f64,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
+/// The OnsetEvent interface, dispatched by the
+/// [`OnsetDetectorNode`](crate::node::OnsetDetectorNode)
+///
+/// Note that this event is not part of the Web Audio API specification.
+#[non_exhaustive]
+#[derive(Debug, Clone)]
+pub struct OnsetEvent {
+    /// The time of the onset, at the sample precision, in the same time coordinate
+    /// system as the AudioContext's currentTime
+    pub time: f64,
+    /// The strength of the onset, i.e. the spectral flux of the detection frame
+    pub strength: f32,
+    /// Inherits from this base Event
+    pub event: Event,
+}
+
 /// The OfflineAudioCompletionEvent Event interface
 #[non_exhaustive]
 #[derive(Debug)]
@@ -92,6 +129,8 @@ pub(crate) enum EventPayload {
     AudioContextState(AudioContextState),
     Complete(AudioBuffer),
     AudioProcessing(AudioProcessingEvent),
+    Pitch(PitchEvent),
+    Onset(OnsetEvent),
 }
 
 #[derive(Debug)]
@@ -164,6 +203,27 @@ impl EventDispatch {
             payload: EventPayload::AudioProcessing(value),
         }
     }
+
+    pub fn pitch(id: AudioNodeId, value: PitchEvent) -> Self {
+        EventDispatch {
+            type_: EventType::Pitch(id),
+            payload: EventPayload::Pitch(value),
+        }
+    }
+
+    pub fn onset(id: AudioNodeId, value: OnsetEvent) -> Self {
+        EventDispatch {
+            type_: EventType::Onset(id),
+            payload: EventPayload::Onset(value),
+        }
+    }
+
+    pub fn scheduler() -> Self {
+        EventDispatch {
+            type_: EventType::Scheduler,
+            payload: EventPayload::None,
+        }
+    }
 }
 
 pub(crate) enum EventHandler {
@@ -49,16 +49,24 @@ pub use param::*;
 mod periodic_wave;
 pub use periodic_wave::*;
 
+mod loudness;
+pub use loudness::Loudness;
+
+mod transport;
+pub use transport::*;
+
 mod render;
 
 mod stats;
 
 mod spatial;
-pub use spatial::AudioListener;
+pub use spatial::{AudioListener, ShoeboxRoom};
 
 mod io;
 
+mod ambisonics;
 mod analysis;
+mod convolution;
 mod message;
 
 mod decoding;
@@ -0,0 +1,596 @@
+//! Loudness measurement as specified by ITU-R BS.1770 and EBU R128
+//!
+//! - ITU-R BS.1770: <https://www.itu.int/rec/R-REC-BS.1770>
+//! - EBU R128: <https://tech.ebu.ch/publications/r128>
+//! - EBU Tech 3342 (loudness range): <https://tech.ebu.ch/publications/tech3342>
+use std::f64::consts::PI;
+
+use arrayvec::ArrayVec;
+
+use crate::MAX_CHANNELS;
+
+/// Duration (in seconds) of the steps between two gating blocks
+const STEP_DURATION: f64 = 0.1;
+/// Number of steps of the momentary loudness window (400ms)
+const MOMENTARY_STEPS: usize = 4;
+/// Number of steps of the short-term loudness window (3s)
+const SHORT_TERM_STEPS: usize = 30;
+
+/// Absolute gating threshold (in LUFS)
+const ABSOLUTE_GATE: f64 = -70.;
+/// Relative gating threshold (in LU) of the integrated loudness
+const INTEGRATED_RELATIVE_GATE: f64 = -10.;
+/// Relative gating threshold (in LU) of the loudness range
+const LOUDNESS_RANGE_RELATIVE_GATE: f64 = -20.;
+
+/// Resolution (in LU) of the loudness histograms
+const HISTOGRAM_RESOLUTION: f64 = 0.01;
+/// Highest loudness (in LUFS) of the loudness histograms, louder blocks are clamped
+const HISTOGRAM_MAX: f64 = 20.;
+
+/// Oversampling factor of the true-peak measurement
+const TRUE_PEAK_FACTOR: usize = 4;
+/// Number of taps per phase of the true-peak interpolation filter
+const TRUE_PEAK_TAPS: usize = 12;
+
+/// Loudness measurement of an [`AudioBuffer`](crate::AudioBuffer), see
+/// [`AudioBuffer::loudness`](crate::AudioBuffer::loudness)
+///
+/// Loudness values are expressed in LUFS, and are negative infinity when the signal is
+/// too short or too quiet to be measured. Note that this is not part of the Web Audio API
+/// specification.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Loudness {
+    /// Integrated (gated) loudness of the whole signal, in LUFS
+    pub integrated: f64,
+    /// Loudness range of the signal, in LU
+    pub loudness_range: f64,
+    /// Highest momentary loudness (400ms window), in LUFS
+    pub max_momentary: f64,
+    /// Highest short-term loudness (3s window), in LUFS
+    pub max_short_term: f64,
+    /// Highest true peak level of the channels, in dBTP
+    pub true_peak: f64,
+}
+
+/// Loudness (in LUFS) of a mean square power
+#[inline(always)]
+pub(crate) fn power_to_loudness(power: f64) -> f64 {
+    -0.691 + 10. * power.log10()
+}
+
+/// Weight of a channel in the loudness sum: the surround channels of a 5.1 layout are
+/// boosted, and the LFE channel is discarded
+fn channel_weight(channel: usize, number_of_channels: usize) -> f64 {
+    if number_of_channels == 6 {
+        [1., 1., 1., 0., 1.41, 1.41][channel]
+    } else {
+        1.
+    }
+}
+
+/// Biquad filter in direct form 1
+#[derive(Clone, Copy, Debug, Default)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+}
+
+/// Two stage K-weighting filter: a high shelf modelling the acoustic effect of the head,
+/// followed by the RLB high-pass filter
+#[derive(Clone, Copy, Debug)]
+struct KWeighting {
+    stages: [Biquad; 2],
+}
+
+impl KWeighting {
+    /// Compute the filter coefficients for the given sample rate, from the analog
+    /// prototypes of the 48kHz coefficients of BS.1770
+    fn new(sample_rate: f64) -> Self {
+        // high shelf
+        let f0 = 1_681.974_450_955_533;
+        let gain = 3.999_843_853_973_347;
+        let q = 0.707_175_236_955_419_6;
+        let k = (PI * f0 / sample_rate).tan();
+        let vh = 10_f64.powf(gain / 20.);
+        let vb = vh.powf(0.499_666_774_154_541_6);
+        let a0 = 1. + k / q + k * k;
+        let shelf = Biquad {
+            b: [
+                (vh + vb * k / q + k * k) / a0,
+                2. * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            a: [2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+        };
+
+        // high-pass
+        let f0 = 38.135_470_876_024_44;
+        let q = 0.500_327_037_323_877_3;
+        let k = (PI * f0 / sample_rate).tan();
+        let a0 = 1. + k / q + k * k;
+        let highpass = Biquad {
+            b: [1., -2., 1.],
+            a: [2. * (k * k - 1.) / a0, (1. - k / q + k * k) / a0],
+        };
+
+        Self {
+            stages: [shelf, highpass],
+        }
+    }
+}
+
+/// Polyphase interpolation filter of the true-peak measurement
+#[derive(Debug)]
+struct TruePeakFilter {
+    /// Blackman-Harris windowed sinc, one set of taps per phase
+    phases: [[f64; TRUE_PEAK_TAPS]; TRUE_PEAK_FACTOR],
+}
+
+impl TruePeakFilter {
+    fn new() -> Self {
+        let length = TRUE_PEAK_FACTOR * TRUE_PEAK_TAPS;
+        let center = (length - 1) as f64 / 2.;
+        let mut phases = [[0.; TRUE_PEAK_TAPS]; TRUE_PEAK_FACTOR];
+
+        (0..length).for_each(|n| {
+            let x = (n as f64 - center) / TRUE_PEAK_FACTOR as f64;
+            let sinc = if x == 0. {
+                1.
+            } else {
+                (PI * x).sin() / (PI * x)
+            };
+            let t = 2. * PI * n as f64 / (length - 1) as f64;
+            let window = 0.358_75 - 0.488_29 * t.cos() + 0.141_28 * (2. * t).cos()
+                - 0.011_68 * (3. * t).cos();
+            phases[n % TRUE_PEAK_FACTOR][n / TRUE_PEAK_FACTOR] = sinc * window;
+        });
+
+        // unity gain for each phase
+        phases.iter_mut().for_each(|taps| {
+            let sum: f64 = taps.iter().sum();
+            taps.iter_mut().for_each(|t| *t /= sum);
+        });
+
+        Self { phases }
+    }
+}
+
+/// Filter states of a channel
+#[derive(Clone, Copy, Debug, Default)]
+struct ChannelState {
+    /// `[x1, x2, y1, y2]` of each K-weighting stage
+    k_weighting: [[f64; 4]; 2],
+    /// last input samples, most recent first
+    history: [f64; TRUE_PEAK_TAPS],
+}
+
+/// Histogram of the loudness of the gating blocks above the absolute threshold
+#[derive(Debug)]
+struct Histogram {
+    counts: Vec<u64>,
+    powers: Vec<f64>,
+}
+
+impl Histogram {
+    fn new() -> Self {
+        let len = ((HISTOGRAM_MAX - ABSOLUTE_GATE) / HISTOGRAM_RESOLUTION) as usize;
+        Self {
+            counts: vec![0; len],
+            powers: vec![0.; len],
+        }
+    }
+
+    fn clear(&mut self) {
+        self.counts.fill(0);
+        self.powers.fill(0.);
+    }
+
+    fn index(&self, loudness: f64) -> usize {
+        let index = ((loudness - ABSOLUTE_GATE) / HISTOGRAM_RESOLUTION).max(0.) as usize;
+        index.min(self.counts.len() - 1)
+    }
+
+    fn loudness(index: usize) -> f64 {
+        ABSOLUTE_GATE + (index as f64 + 0.5) * HISTOGRAM_RESOLUTION
+    }
+
+    /// Add the power of a block, if above the absolute gating threshold
+    fn add(&mut self, power: f64) {
+        let loudness = power_to_loudness(power);
+        if loudness >= ABSOLUTE_GATE {
+            let index = self.index(loudness);
+            self.counts[index] += 1;
+            self.powers[index] += power;
+        }
+    }
+
+    /// Index of the first bin above the relative gating threshold
+    fn relative_gate(&self, relative_gate: f64) -> Option<usize> {
+        let count: u64 = self.counts.iter().sum();
+        if count == 0 {
+            return None;
+        }
+
+        let power = self.powers.iter().sum::<f64>() / count as f64;
+        Some(self.index(power_to_loudness(power) + relative_gate))
+    }
+
+    /// Gated mean power of the blocks
+    fn integrated(&self) -> f64 {
+        let Some(start) = self.relative_gate(INTEGRATED_RELATIVE_GATE) else {
+            return f64::NEG_INFINITY;
+        };
+
+        let count: u64 = self.counts[start..].iter().sum();
+        let power: f64 = self.powers[start..].iter().sum();
+        power_to_loudness(power / count as f64)
+    }
+
+    /// Difference between the 95th and 10th percentiles of the gated blocks loudness
+    fn loudness_range(&self) -> f64 {
+        let Some(start) = self.relative_gate(LOUDNESS_RANGE_RELATIVE_GATE) else {
+            return 0.;
+        };
+
+        let counts = &self.counts[start..];
+        let count: u64 = counts.iter().sum();
+        let percentile = |p: f64| {
+            let target = ((count - 1) as f64 * p).round() as u64;
+            let mut seen = 0;
+            let index = counts
+                .iter()
+                .position(|c| {
+                    seen += c;
+                    seen > target
+                })
+                .unwrap_or(counts.len() - 1);
+            Self::loudness(start + index)
+        };
+
+        percentile(0.95) - percentile(0.1)
+    }
+}
+
+/// Loudness meter implementing the BS.1770 loudness, the EBU R128 momentary, short-term
+/// and integrated loudness, the EBU Tech 3342 loudness range and the true peak level
+#[derive(Debug)]
+pub(crate) struct LoudnessMeter {
+    k_weighting: KWeighting,
+    true_peak_filter: TruePeakFilter,
+    states: ArrayVec<ChannelState, MAX_CHANNELS>,
+    /// number of samples of a step
+    step_length: usize,
+    /// number of samples already processed in the current step
+    step_position: usize,
+    /// weighted sum of the squared samples of the current step
+    step_energy: f64,
+    /// energy of the last steps, most recent first
+    steps: [f64; SHORT_TERM_STEPS],
+    /// number of completed steps, saturates at `SHORT_TERM_STEPS`
+    number_of_steps: usize,
+    integrated_histogram: Histogram,
+    loudness_range_histogram: Histogram,
+    momentary: f64,
+    short_term: f64,
+    max_momentary: f64,
+    max_short_term: f64,
+    true_peak: f64,
+}
+
+impl LoudnessMeter {
+    pub(crate) fn new(sample_rate: f32) -> Self {
+        let sample_rate = f64::from(sample_rate);
+
+        Self {
+            k_weighting: KWeighting::new(sample_rate),
+            true_peak_filter: TruePeakFilter::new(),
+            states: ArrayVec::new(),
+            step_length: (STEP_DURATION * sample_rate).round() as usize,
+            step_position: 0,
+            step_energy: 0.,
+            steps: [0.; SHORT_TERM_STEPS],
+            number_of_steps: 0,
+            integrated_histogram: Histogram::new(),
+            loudness_range_histogram: Histogram::new(),
+            momentary: f64::NEG_INFINITY,
+            short_term: f64::NEG_INFINITY,
+            max_momentary: f64::NEG_INFINITY,
+            max_short_term: f64::NEG_INFINITY,
+            true_peak: 0.,
+        }
+    }
+
+    /// Restart the measurement
+    pub(crate) fn reset(&mut self) {
+        self.states.clear();
+        self.step_position = 0;
+        self.step_energy = 0.;
+        self.steps = [0.; SHORT_TERM_STEPS];
+        self.number_of_steps = 0;
+        self.integrated_histogram.clear();
+        self.loudness_range_histogram.clear();
+        self.momentary = f64::NEG_INFINITY;
+        self.short_term = f64::NEG_INFINITY;
+        self.max_momentary = f64::NEG_INFINITY;
+        self.max_short_term = f64::NEG_INFINITY;
+        self.true_peak = 0.;
+    }
+
+    /// Measure the given channels, which must all have the same length
+    ///
+    /// Returns whether new loudness values are available, i.e. if a 100ms step has been
+    /// completed.
+    pub(crate) fn process(&mut self, channels: &[&[f32]]) -> bool {
+        let number_of_channels = channels.len();
+        if number_of_channels != self.states.len() {
+            self.states.clear();
+            (0..number_of_channels).for_each(|_| self.states.push(ChannelState::default()));
+        }
+
+        let length = channels.first().map_or(0, |c| c.len());
+        let mut offset = 0;
+        let mut updated = false;
+
+        // split the input at the step boundaries
+        while offset < length {
+            let len = (self.step_length - self.step_position).min(length - offset);
+            let range = offset..offset + len;
+
+            for (channel_number, (channel, state)) in
+                channels.iter().zip(self.states.iter_mut()).enumerate()
+            {
+                let weight = channel_weight(channel_number, number_of_channels);
+                let energy = process_channel(
+                    &self.k_weighting,
+                    &self.true_peak_filter,
+                    state,
+                    &channel[range.clone()],
+                    &mut self.true_peak,
+                );
+                self.step_energy += weight * energy;
+            }
+
+            self.step_position += len;
+            offset += len;
+
+            if self.step_position == self.step_length {
+                self.complete_step();
+                updated = true;
+            }
+        }
+
+        updated
+    }
+
+    fn complete_step(&mut self) {
+        self.steps.rotate_right(1);
+        self.steps[0] = self.step_energy / self.step_length as f64;
+        self.number_of_steps = (self.number_of_steps + 1).min(SHORT_TERM_STEPS);
+        self.step_energy = 0.;
+        self.step_position = 0;
+
+        let mean_power = |steps: &[f64]| steps.iter().sum::<f64>() / steps.len() as f64;
+
+        let momentary_steps = self.number_of_steps.min(MOMENTARY_STEPS);
+        let momentary_power = mean_power(&self.steps[..momentary_steps]);
+        self.momentary = power_to_loudness(momentary_power);
+
+        let short_term_power = mean_power(&self.steps[..self.number_of_steps]);
+        self.short_term = power_to_loudness(short_term_power);
+
+        // only complete windows are used for the gating and the maximum values
+        if momentary_steps == MOMENTARY_STEPS {
+            self.integrated_histogram.add(momentary_power);
+            self.max_momentary = self.max_momentary.max(self.momentary);
+        }
+
+        if self.number_of_steps == SHORT_TERM_STEPS {
+            self.loudness_range_histogram.add(short_term_power);
+            self.max_short_term = self.max_short_term.max(self.short_term);
+        }
+    }
+
+    /// Loudness of the last 400ms, in LUFS
+    pub(crate) fn momentary(&self) -> f64 {
+        self.momentary
+    }
+
+    /// Loudness of the last 3s, in LUFS
+    pub(crate) fn short_term(&self) -> f64 {
+        self.short_term
+    }
+
+    /// Gated loudness since the start of the measurement, in LUFS
+    pub(crate) fn integrated(&self) -> f64 {
+        self.integrated_histogram.integrated()
+    }
+
+    /// Loudness range since the start of the measurement, in LU
+    pub(crate) fn loudness_range(&self) -> f64 {
+        self.loudness_range_histogram.loudness_range()
+    }
+
+    /// Highest true peak since the start of the measurement, in dBTP
+    pub(crate) fn true_peak(&self) -> f64 {
+        20. * self.true_peak.log10()
+    }
+
+    pub(crate) fn loudness(&self) -> Loudness {
+        Loudness {
+            integrated: self.integrated(),
+            loudness_range: self.loudness_range(),
+            max_momentary: self.max_momentary,
+            max_short_term: self.max_short_term,
+            true_peak: self.true_peak(),
+        }
+    }
+}
+
+/// Apply the K-weighting filter and the true-peak interpolation to a channel, returns the
+/// sum of the squared weighted samples
+fn process_channel(
+    k_weighting: &KWeighting,
+    true_peak_filter: &TruePeakFilter,
+    state: &mut ChannelState,
+    samples: &[f32],
+    true_peak: &mut f64,
+) -> f64 {
+    samples.iter().fold(0., |energy, &sample| {
+        let x = f64::from(sample);
+
+        // true peak
+        state.history.rotate_right(1);
+        state.history[0] = x;
+        true_peak_filter.phases.iter().for_each(|taps| {
+            let value: f64 = taps
+                .iter()
+                .zip(state.history.iter())
+                .map(|(t, h)| t * h)
+                .sum();
+            *true_peak = true_peak.max(value.abs());
+        });
+
+        // K-weighting
+        let y = k_weighting
+            .stages
+            .iter()
+            .zip(state.k_weighting.iter_mut())
+            .fold(x, |x, (c, [x1, x2, y1, y2])| {
+                let mut y = c.b[0] * x + c.b[1] * *x1 + c.b[2] * *x2 - c.a[0] * *y1 - c.a[1] * *y2;
+                // flush subnormals to zero
+                if !y.is_normal() {
+                    y = 0.;
+                }
+                *x2 = *x1;
+                *x1 = x;
+                *y2 = *y1;
+                *y1 = y;
+                y
+            });
+
+        energy + y * y
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use super::*;
+
+    fn sine(frequency: f64, amplitude: f64, sample_rate: f32, duration: f64) -> Vec<f32> {
+        let length = (duration * f64::from(sample_rate)) as usize;
+        (0..length)
+            .map(|i| {
+                let phase = 2. * PI * frequency * i as f64 / f64::from(sample_rate);
+                (amplitude * phase.sin()) as f32
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_k_weighting_48k_coefficients() {
+        // reference coefficients of BS.1770 at 48kHz
+        let k_weighting = KWeighting::new(48_000.);
+        let [shelf, highpass] = k_weighting.stages;
+
+        assert_float_eq!(
+            shelf.b,
+            [
+                1.535_124_859_586_97,
+                -2.691_696_189_406_38,
+                1.198_392_810_852_85
+            ],
+            abs_all <= 1e-8
+        );
+        assert_float_eq!(
+            shelf.a,
+            [-1.690_659_293_182_41, 0.732_480_774_215_85],
+            abs_all <= 1e-8
+        );
+        assert_float_eq!(
+            highpass.a,
+            [-1.990_047_454_833_98, 0.990_072_250_366_21],
+            abs_all <= 1e-8
+        );
+    }
+
+    #[test]
+    fn test_sine_loudness() {
+        // EBU Tech 3341 test case 1: a 1kHz sine at -23dBFS on both channels of a stereo
+        // signal measures -23 LUFS
+        for sample_rate in [44_100., 48_000.] {
+            let amplitude = 10_f64.powf(-23. / 20.);
+            let signal = sine(1000., amplitude, sample_rate, 20.);
+
+            let mut meter = LoudnessMeter::new(sample_rate);
+            let _ = meter.process(&[&signal, &signal]);
+
+            assert_float_eq!(meter.integrated(), -23., abs <= 0.1);
+            assert_float_eq!(meter.momentary(), -23., abs <= 0.1);
+            assert_float_eq!(meter.short_term(), -23., abs <= 0.1);
+            assert_float_eq!(meter.loudness_range(), 0., abs <= 0.1);
+        }
+    }
+
+    #[test]
+    fn test_gating() {
+        // EBU Tech 3341 test case 3: -36 LUFS / -23 LUFS / -36 LUFS, the quiet parts are
+        // removed by the relative gate
+        let sample_rate = 48_000.;
+        let amplitude = |loudness: f64| 10_f64.powf(loudness / 20.);
+
+        let mut signal = sine(1000., amplitude(-36.), sample_rate, 10.);
+        signal.extend(sine(1000., amplitude(-23.), sample_rate, 60.));
+        signal.extend(sine(1000., amplitude(-36.), sample_rate, 10.));
+
+        let mut meter = LoudnessMeter::new(sample_rate);
+        let _ = meter.process(&[&signal, &signal]);
+
+        assert_float_eq!(meter.integrated(), -23., abs <= 0.1);
+    }
+
+    #[test]
+    fn test_loudness_range() {
+        // EBU Tech 3342 test case 1: 20s at -20 LUFS then 20s at -30 LUFS, LRA of 10 LU
+        let sample_rate = 48_000.;
+        let amplitude = |loudness: f64| 10_f64.powf(loudness / 20.);
+
+        let mut signal = sine(1000., amplitude(-20.), sample_rate, 20.);
+        signal.extend(sine(1000., amplitude(-30.), sample_rate, 20.));
+
+        let mut meter = LoudnessMeter::new(sample_rate);
+        let _ = meter.process(&[&signal, &signal]);
+
+        assert_float_eq!(meter.loudness_range(), 10., abs <= 0.1);
+    }
+
+    #[test]
+    fn test_true_peak() {
+        // a sine at a quarter of the sample rate, sampled at 45 degrees, has sample peaks
+        // 3dB below its true peak
+        let sample_rate = 48_000.;
+        let signal: Vec<f32> = (0..48_000)
+            .map(|i| (PI / 2. * i as f64 + PI / 4.).sin() as f32 * 0.5)
+            .collect();
+
+        let sample_peak = signal.iter().fold(0_f32, |m, s| m.max(s.abs()));
+        assert_float_eq!(20. * f64::from(sample_peak).log10(), -9.03, abs <= 0.01);
+
+        let mut meter = LoudnessMeter::new(sample_rate);
+        let _ = meter.process(&[&signal]);
+        assert_float_eq!(meter.true_peak(), -6.02, abs <= 0.2);
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut meter = LoudnessMeter::new(48_000.);
+        let _ = meter.process(&[&[0.; 48_000]]);
+
+        assert_eq!(meter.integrated(), f64::NEG_INFINITY);
+        assert_eq!(meter.momentary(), f64::NEG_INFINITY);
+        assert_eq!(meter.true_peak(), f64::NEG_INFINITY);
+        assert_float_eq!(meter.loudness_range(), 0., abs <= 0.);
+    }
+}
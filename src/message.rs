@@ -84,6 +84,10 @@ pub(crate) enum ControlMessage {
         id: AudioNodeId,
         interpretation: ChannelInterpretation,
     },
+
+    /// Notify the control thread when the given context time is reached, to run the
+    /// scheduled callbacks
+    SchedulerWakeup { when: f64 },
 }
 
 /// Helper object to emit single notification
@@ -0,0 +1,363 @@
+use crate::ambisonics::{
+    ambisonic_audio_node_options, assert_valid_ambisonic_channel_count,
+    assert_valid_ambisonic_channel_count_mode, assert_valid_order, decoding_matrix,
+    fibonacci_sphere, number_of_channels, to_ambisonic_frame,
+};
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::convolution::PartitionedConvolver;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, ChannelConfig, ChannelCountMode, HrtfDataset};
+
+/// Options for constructing an [`AmbisonicBinauralDecoderNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicBinauralDecoderOptions {
+    /// Ambisonic order of the input sound field, in the range [1, 4]
+    pub order: usize,
+    /// HRIR dataset of the decoder, defaults to the dataset of the context
+    pub hrtf_dataset: Option<HrtfDataset>,
+}
+
+impl Default for AmbisonicBinauralDecoderOptions {
+    fn default() -> Self {
+        Self {
+            order: 1,
+            hrtf_dataset: None,
+        }
+    }
+}
+
+/// `AmbisonicBinauralDecoderNode` renders an ambisonic sound field for headphones
+///
+/// The input is a sound field of `(order + 1)²` channels, using the AmbiX convention (ACN
+/// channel ordering and SN3D normalization). The sound field is decoded to a set of virtual
+/// speakers evenly spread on the sphere, each of them rendered with the closest measurement of
+/// the [`HrtfDataset`]. Both steps are folded into one pair of filters per input channel, so
+/// the cost of the decoder only depends on the order, not on the number of sources encoded in
+/// the sound field.
+///
+/// The output is stereo.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_ambisonic_binaural_decoder`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let decoder = context.create_ambisonic_binaural_decoder(3);
+/// decoder.connect(&context.destination());
+///
+/// for x in [-1., 1.] {
+///     let encoder = context.create_ambisonic_encoder(3);
+///     encoder.position_x().set_value(x);
+///     encoder.connect(&decoder);
+///
+///     let mut osc = context.create_oscillator();
+///     osc.connect(&encoder);
+///     osc.start();
+/// }
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example ambisonics`
+///
+#[derive(Debug)]
+pub struct AmbisonicBinauralDecoderNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    /// Ambisonic order of the input sound field
+    order: usize,
+    /// HRIR dataset of the decoder
+    hrtf_dataset: HrtfDataset,
+}
+
+impl AudioNode for AmbisonicBinauralDecoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_ambisonic_channel_count(count, self.order);
+        self.channel_config.set_count(count, self.registration());
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_ambisonic_channel_count_mode(mode);
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+}
+
+impl AmbisonicBinauralDecoderNode {
+    /// returns an `AmbisonicBinauralDecoderNode` instance
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - ambisonic binaural decoder options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the order is not in the range [1, 4]
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicBinauralDecoderOptions) -> Self {
+        let AmbisonicBinauralDecoderOptions {
+            order,
+            hrtf_dataset,
+        } = options;
+
+        assert_valid_order(order);
+
+        let hrtf_dataset = hrtf_dataset.unwrap_or_else(|| context.hrtf_dataset());
+        let filters = binaural_filters(order, &hrtf_dataset, context.sample_rate());
+        let filter_length = filters.first().map_or(0, |[left, _]| left.len());
+
+        context.base().register(move |registration| {
+            let convolvers = filters
+                .iter()
+                .map(|ears| ears.each_ref().map(|f| PartitionedConvolver::new(f, None)))
+                .collect();
+
+            let renderer = AmbisonicBinauralDecoderRenderer {
+                convolvers,
+                filter_length,
+                tail_count: 0,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: ambisonic_audio_node_options(order).into(),
+                order,
+                hrtf_dataset,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Ambisonic order of the input sound field
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// HRIR dataset of the decoder
+    pub fn hrtf_dataset(&self) -> &HrtfDataset {
+        &self.hrtf_dataset
+    }
+}
+
+/// Left and right ear filters of each channel of the sound field
+///
+/// The sound field is decoded to virtual speakers, each one rendered with the HRIR of the
+/// closest measurement point: the filter of a channel is the sum of the HRIRs weighted by the
+/// decoding gains of this channel.
+fn binaural_filters(
+    order: usize,
+    hrtf_dataset: &HrtfDataset,
+    sample_rate: f32,
+) -> Vec<[Vec<f32>; 2]> {
+    let measurements: Vec<_> = hrtf_dataset
+        .measurements(sample_rate)
+        .into_iter()
+        .map(|(position, left, right)| (to_ambisonic_frame(position), left, right))
+        .collect();
+    let length = measurements.first().map_or(0, |(_, left, _)| left.len());
+
+    let channels = number_of_channels(order);
+    let speakers = fibonacci_sphere(4 * channels);
+    let matrix = decoding_matrix(order, &speakers);
+
+    let mut filters = vec![[vec![0.; length], vec![0.; length]]; channels];
+
+    speakers
+        .iter()
+        .zip(matrix.iter())
+        .for_each(|(speaker, gains)| {
+            let dot = |p: &[f32; 3]| p.iter().zip(speaker).map(|(a, b)| a * b).sum::<f32>();
+            let closest = measurements
+                .iter()
+                .max_by(|(a, ..), (b, ..)| dot(a).total_cmp(&dot(b)));
+
+            let Some((_, left, right)) = closest else {
+                return;
+            };
+
+            filters
+                .iter_mut()
+                .zip(gains)
+                .for_each(|([filter_left, filter_right], &gain)| {
+                    filter_left
+                        .iter_mut()
+                        .zip(left)
+                        .for_each(|(f, h)| *f += gain * h);
+                    filter_right
+                        .iter_mut()
+                        .zip(right)
+                        .for_each(|(f, h)| *f += gain * h);
+                });
+        });
+
+    filters
+}
+
+struct AmbisonicBinauralDecoderRenderer {
+    convolvers: Vec<[PartitionedConvolver; 2]>,
+    filter_length: usize,
+    tail_count: usize,
+}
+
+impl AudioProcessor for AmbisonicBinauralDecoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // handle tail time and active processing
+        // return early if input is silent and we reached the end of the convolution
+        if input.is_silent() {
+            if self.tail_count >= self.filter_length {
+                output.make_silent();
+                return false;
+            }
+
+            self.tail_count += RENDER_QUANTUM_SIZE;
+        } else {
+            self.tail_count = 0;
+        }
+
+        output.set_number_of_channels(2);
+
+        let mut left = [0.; RENDER_QUANTUM_SIZE];
+        let mut right = [0.; RENDER_QUANTUM_SIZE];
+        let mut filtered = [0.; RENDER_QUANTUM_SIZE];
+
+        self.convolvers.iter_mut().zip(input.channels()).for_each(
+            |([convolver_left, convolver_right], channel)| {
+                convolver_left.process(channel, &mut filtered);
+                left.iter_mut().zip(filtered).for_each(|(o, f)| *o += f);
+
+                convolver_right.process(channel, &mut filtered);
+                right.iter_mut().zip(filtered).for_each(|(o, f)| *o += f);
+            },
+        );
+
+        output.channel_data_mut(0).copy_from_slice(&left);
+        output.channel_data_mut(1).copy_from_slice(&right);
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AmbisonicEncoderOptions, AudioScheduledSourceNode};
+    use crate::AudioBuffer;
+
+    use super::*;
+
+    fn render(order: usize, position: [f32; 3], length: usize) -> AudioBuffer {
+        let sample_rate = 44100.;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        let options = AmbisonicEncoderOptions {
+            order,
+            position_x: position[0],
+            position_y: position[1],
+            position_z: position[2],
+        };
+        let encoder = crate::node::AmbisonicEncoderNode::new(&context, options);
+        let decoder = context.create_ambisonic_binaural_decoder(order);
+        encoder.connect(&decoder);
+        decoder.connect(&context.destination());
+
+        // impulse
+        let mut impulse = context.create_buffer(1, 1, sample_rate);
+        impulse.copy_to_channel(&[1.], 0);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(impulse);
+        src.connect(&encoder);
+        src.start();
+
+        context.start_rendering_sync()
+    }
+
+    fn energy(samples: &[f32]) -> f32 {
+        samples.iter().map(|s| s * s).sum()
+    }
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let decoder = context.create_ambisonic_binaural_decoder(2);
+
+        assert_eq!(decoder.order(), 2);
+        assert_eq!(decoder.channel_count(), 9);
+        assert_eq!(decoder.hrtf_dataset(), &context.hrtf_dataset());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_count_mode() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let decoder = context.create_ambisonic_binaural_decoder(1);
+        decoder.set_channel_count_mode(ChannelCountMode::Max);
+    }
+
+    #[test]
+    fn test_lateralization() {
+        for order in [1, 3] {
+            // source on the right
+            let result = render(order, [1., 0., 0.], 4 * RENDER_QUANTUM_SIZE);
+            let left = energy(result.get_channel_data(0));
+            let right = energy(result.get_channel_data(1));
+            assert!(right > 2. * left, "order {order}: {left} {right}");
+
+            // source on the left
+            let result = render(order, [-1., 0., 0.], 4 * RENDER_QUANTUM_SIZE);
+            let left = energy(result.get_channel_data(0));
+            let right = energy(result.get_channel_data(1));
+            assert!(left > 2. * right, "order {order}: {left} {right}");
+        }
+    }
+
+    #[test]
+    fn test_tail_time() {
+        let result = render(1, [0., 0., -1.], 16 * RENDER_QUANTUM_SIZE);
+        let left = result.get_channel_data(0);
+
+        // the impulse response of the decoder is longer than a render quantum
+        assert!(energy(&left[RENDER_QUANTUM_SIZE..]) > 0.);
+        // and has ended by the end of the rendering
+        assert_eq!(energy(&left[8 * RENDER_QUANTUM_SIZE..]), 0.);
+    }
+}
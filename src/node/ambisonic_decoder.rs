@@ -0,0 +1,375 @@
+use crate::ambisonics::{
+    ambisonic_audio_node_options, assert_valid_ambisonic_channel_count,
+    assert_valid_ambisonic_channel_count_mode, assert_valid_order, decoding_matrix, direction,
+    number_of_channels,
+};
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::MAX_CHANNELS;
+
+use super::{AudioNode, ChannelConfig, ChannelCountMode};
+
+/// Speaker layout of an [`AmbisonicDecoderNode`]
+///
+/// Speaker directions are given in degrees, the azimuth is counter-clockwise from the front
+/// (i.e. positive to the left) and the elevation is positive upwards.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum AmbisonicSpeakerLayout {
+    /// Two virtual cardioid microphones pointing to the left and right
+    #[default]
+    Stereo,
+    /// Front left, front right, surround left and surround right at ±45° and ±135°
+    Quad,
+    /// Left, right, center, LFE (silent), surround left and surround right at ±30°, 0° and ±110°
+    Surround51,
+    /// One output channel per speaker, given as `[azimuth, elevation]` pairs
+    Custom(Vec<[f32; 2]>),
+}
+
+impl AmbisonicSpeakerLayout {
+    /// Number of output channels of the layout
+    pub fn number_of_channels(&self) -> usize {
+        match self {
+            Self::Stereo => 2,
+            Self::Quad => 4,
+            Self::Surround51 => 6,
+            Self::Custom(speakers) => speakers.len(),
+        }
+    }
+
+    /// Decoding matrix of the sound field of the given order, one row per output channel
+    fn matrix(&self, order: usize) -> Vec<Vec<f32>> {
+        let channels = number_of_channels(order);
+
+        match self {
+            Self::Stereo => [1., -1.]
+                .iter()
+                .map(|side| {
+                    let mut row = vec![0.; channels];
+                    row[0] = 0.5;
+                    row[1] = 0.5 * side;
+                    row
+                })
+                .collect(),
+            Self::Quad => {
+                let speakers = [45., -45., 135., -135.].map(|azimuth| direction(azimuth, 0.));
+                decoding_matrix(order, &speakers)
+            }
+            Self::Surround51 => {
+                let speakers = [30., -30., 0., 110., -110.].map(|azimuth| direction(azimuth, 0.));
+                let mut matrix = decoding_matrix(order, &speakers);
+                matrix.insert(3, vec![0.; channels]);
+                matrix
+            }
+            Self::Custom(speakers) => {
+                let speakers: Vec<_> = speakers
+                    .iter()
+                    .map(|&[azimuth, elevation]| direction(azimuth, elevation))
+                    .collect();
+                decoding_matrix(order, &speakers)
+            }
+        }
+    }
+}
+
+/// Options for constructing an [`AmbisonicDecoderNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicDecoderOptions {
+    /// Ambisonic order of the input sound field, in the range [1, 4]
+    pub order: usize,
+    /// Speaker layout of the output
+    pub layout: AmbisonicSpeakerLayout,
+}
+
+impl Default for AmbisonicDecoderOptions {
+    fn default() -> Self {
+        Self {
+            order: 1,
+            layout: AmbisonicSpeakerLayout::default(),
+        }
+    }
+}
+
+/// `AmbisonicDecoderNode` decodes an ambisonic sound field to a speaker layout
+///
+/// The input is a sound field of `(order + 1)²` channels, using the AmbiX convention (ACN
+/// channel ordering and SN3D normalization). The output has one channel per speaker of the
+/// [`AmbisonicSpeakerLayout`], in the order of the layout.
+///
+/// Speakers are fed with a max-rE weighted projection of the sound field, which performs best
+/// with regularly spaced layouts. If all speakers lie in the horizontal plane, the elevation of
+/// the sources is discarded.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_ambisonic_decoder`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AmbisonicSpeakerLayout};
+///
+/// let context = AudioContext::default();
+///
+/// let decoder = context.create_ambisonic_decoder(2, AmbisonicSpeakerLayout::Quad);
+/// decoder.connect(&context.destination());
+///
+/// let encoder = context.create_ambisonic_encoder(2);
+/// encoder.connect(&decoder);
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example ambisonics`
+///
+#[derive(Debug)]
+pub struct AmbisonicDecoderNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    /// Ambisonic order of the input sound field
+    order: usize,
+    /// Speaker layout of the output
+    layout: AmbisonicSpeakerLayout,
+}
+
+impl AudioNode for AmbisonicDecoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_ambisonic_channel_count(count, self.order);
+        self.channel_config.set_count(count, self.registration());
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_ambisonic_channel_count_mode(mode);
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+}
+
+impl AmbisonicDecoderNode {
+    /// returns an `AmbisonicDecoderNode` instance
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - ambisonic decoder options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// - the order is not in the range [1, 4]
+    /// - a custom layout has no speaker or more than `MAX_CHANNELS` speakers
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicDecoderOptions) -> Self {
+        let AmbisonicDecoderOptions { order, layout } = options;
+
+        assert_valid_order(order);
+        assert!(
+            (1..=MAX_CHANNELS).contains(&layout.number_of_channels()),
+            "NotSupportedError - speaker layout must have between 1 and {MAX_CHANNELS} speakers"
+        );
+
+        context.base().register(move |registration| {
+            let renderer = AmbisonicDecoderRenderer {
+                matrix: layout.matrix(order),
+            };
+
+            let node = Self {
+                registration,
+                channel_config: ambisonic_audio_node_options(order).into(),
+                order,
+                layout,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Ambisonic order of the input sound field
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Speaker layout of the output
+    pub fn layout(&self) -> &AmbisonicSpeakerLayout {
+        &self.layout
+    }
+}
+
+struct AmbisonicDecoderRenderer {
+    matrix: Vec<Vec<f32>>,
+}
+
+impl AudioProcessor for AmbisonicDecoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        output.set_number_of_channels(self.matrix.len());
+
+        output
+            .channels_mut()
+            .iter_mut()
+            .zip(self.matrix.iter())
+            .for_each(|(speaker, gains)| {
+                speaker.fill(0.);
+
+                input
+                    .channels()
+                    .iter()
+                    .zip(gains)
+                    .filter(|(_, &gain)| gain != 0.)
+                    .for_each(|(channel, &gain)| {
+                        speaker
+                            .iter_mut()
+                            .zip(channel.iter())
+                            .for_each(|(o, i)| *o += gain * i);
+                    });
+            });
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AmbisonicEncoderOptions, AudioScheduledSourceNode};
+    use crate::{AudioBuffer, RENDER_QUANTUM_SIZE};
+
+    use super::*;
+
+    fn render(order: usize, layout: AmbisonicSpeakerLayout, position: [f32; 3]) -> AudioBuffer {
+        let number_of_channels = layout.number_of_channels();
+        let mut context = OfflineAudioContext::new(number_of_channels, RENDER_QUANTUM_SIZE, 48000.);
+
+        let options = AmbisonicEncoderOptions {
+            order,
+            position_x: position[0],
+            position_y: position[1],
+            position_z: position[2],
+        };
+        let encoder = crate::node::AmbisonicEncoderNode::new(&context, options);
+        let decoder = context.create_ambisonic_decoder(order, layout);
+        encoder.connect(&decoder);
+        decoder.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        context.start_rendering_sync()
+    }
+
+    fn gains(buffer: &AudioBuffer) -> Vec<f32> {
+        (0..buffer.number_of_channels())
+            .map(|c| buffer.get_channel_data(c)[0])
+            .collect()
+    }
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+        let decoder = context.create_ambisonic_decoder(2, AmbisonicSpeakerLayout::Quad);
+
+        assert_eq!(decoder.order(), 2);
+        assert_eq!(decoder.layout(), &AmbisonicSpeakerLayout::Quad);
+        assert_eq!(decoder.channel_count(), 9);
+        assert_eq!(decoder.channel_count_mode(), ChannelCountMode::Explicit);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_layout() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+        let _ = context.create_ambisonic_decoder(1, AmbisonicSpeakerLayout::Custom(vec![]));
+    }
+
+    #[test]
+    fn test_stereo() {
+        // left
+        let result = render(1, AmbisonicSpeakerLayout::Stereo, [-1., 0., 0.]);
+        assert_float_eq!(gains(&result)[..], [1., 0.][..], abs_all <= 1e-6);
+
+        // front
+        let result = render(1, AmbisonicSpeakerLayout::Stereo, [0., 0., -1.]);
+        assert_float_eq!(gains(&result)[..], [0.5, 0.5][..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_quad() {
+        // source at the surround right speaker
+        let result = render(2, AmbisonicSpeakerLayout::Quad, [1., 0., 1.]);
+        let gains = gains(&result);
+
+        assert_float_eq!(gains.iter().sum::<f32>(), 1., abs <= 1e-5);
+        assert!(gains[3] > gains[1]);
+        assert!(gains[3] > gains[2]);
+        assert!(gains[1] > gains[0]);
+    }
+
+    #[test]
+    fn test_surround51_lfe_is_silent() {
+        let result = render(1, AmbisonicSpeakerLayout::Surround51, [0., 0., -1.]);
+        assert_eq!(result.number_of_channels(), 6);
+
+        let gains = gains(&result);
+        assert_float_eq!(gains[3], 0., abs <= 0.);
+        assert!(gains[2] > gains[4]);
+    }
+
+    #[test]
+    fn test_custom_3d_layout() {
+        // octahedron
+        let layout = AmbisonicSpeakerLayout::Custom(vec![
+            [0., 0.],
+            [90., 0.],
+            [180., 0.],
+            [-90., 0.],
+            [0., 90.],
+            [0., -90.],
+        ]);
+
+        // source above the listener
+        let result = render(1, layout, [0., 1., 0.]);
+        let gains = gains(&result);
+
+        let loudest = (0..6).max_by(|&a, &b| gains[a].total_cmp(&gains[b]));
+        assert_eq!(loudest, Some(4));
+        assert!(gains[5] < gains[0]);
+    }
+}
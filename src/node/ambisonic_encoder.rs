@@ -0,0 +1,430 @@
+use crate::ambisonics::{
+    assert_valid_order, number_of_channels, spherical_harmonics, to_ambisonic_frame,
+    MAX_AMBISONIC_ORDER,
+};
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::AudioParam;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Options for constructing an [`AmbisonicEncoderNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicEncoderOptions {
+    /// Ambisonic order of the output sound field, in the range [1, 4]
+    pub order: usize,
+    /// Initial value for the position_x parameter
+    pub position_x: f32,
+    /// Initial value for the position_y parameter
+    pub position_y: f32,
+    /// Initial value for the position_z parameter
+    pub position_z: f32,
+}
+
+impl Default for AmbisonicEncoderOptions {
+    fn default() -> Self {
+        Self {
+            order: 1,
+            position_x: 0.,
+            position_y: 0.,
+            position_z: -1.,
+        }
+    }
+}
+
+/// Assert that the channel count is valid for the AmbisonicEncoderNode
+///
+/// # Panics
+///
+/// This function panics if given count is not 1
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_count(count: usize) {
+    assert_eq!(
+        count, 1,
+        "NotSupportedError - AmbisonicEncoderNode channel count must be one"
+    );
+}
+
+/// Assert that the channel count mode is valid for the AmbisonicEncoderNode
+///
+/// # Panics
+///
+/// This function panics if given count mode is [`ChannelCountMode::Max`]
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
+    assert_ne!(
+        mode,
+        ChannelCountMode::Max,
+        "NotSupportedError - AmbisonicEncoderNode channel count mode cannot be set to max"
+    );
+}
+
+/// `AmbisonicEncoderNode` encodes a mono source into an ambisonic sound field
+///
+/// The output is a sound field of `(order + 1)²` channels, using the AmbiX convention (ACN
+/// channel ordering and SN3D normalization). The direction of the source is given by its
+/// position relative to the position of the [`AudioListener`](crate::AudioListener), in the
+/// Web Audio coordinate system. The orientation of the listener is not taken into account, use
+/// an [`AmbisonicRotatorNode`](super::AmbisonicRotatorNode) on the mixed sound field for this.
+///
+/// Contrary to the [`PannerNode`](super::PannerNode), no distance attenuation is applied.
+///
+/// Many encoded sources can be mixed into a single sound field and share a single
+/// decoding stage, e.g. with an [`AmbisonicBinauralDecoderNode`](super::AmbisonicBinauralDecoderNode).
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_ambisonic_encoder`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let decoder = context.create_ambisonic_binaural_decoder(3);
+/// decoder.connect(&context.destination());
+///
+/// // source on the right of the listener
+/// let encoder = context.create_ambisonic_encoder(3);
+/// encoder.position_x().set_value(1.);
+/// encoder.position_z().set_value(0.);
+/// encoder.connect(&decoder);
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&encoder);
+/// osc.start();
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example ambisonics`
+///
+#[derive(Debug)]
+pub struct AmbisonicEncoderNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    /// Ambisonic order of the output sound field
+    order: usize,
+    position_x: AudioParam,
+    position_y: AudioParam,
+    position_z: AudioParam,
+}
+
+impl AudioNode for AmbisonicEncoderNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_channel_count(count);
+        self.channel_config.set_count(count, self.registration());
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_channel_count_mode(mode);
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+}
+
+impl AmbisonicEncoderNode {
+    /// returns an `AmbisonicEncoderNode` instance
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - ambisonic encoder options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the order is not in the range [1, 4]
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicEncoderOptions) -> Self {
+        let AmbisonicEncoderOptions {
+            order,
+            position_x,
+            position_y,
+            position_z,
+        } = options;
+
+        assert_valid_order(order);
+
+        let node = context.base().register(move |registration| {
+            use crate::spatial::PARAM_OPTS;
+
+            let (param_px, render_px) = context.create_audio_param(PARAM_OPTS, &registration);
+            let (param_py, render_py) = context.create_audio_param(PARAM_OPTS, &registration);
+            let (param_pz, render_pz) = context.create_audio_param(PARAM_OPTS, &registration);
+
+            param_px.set_value(position_x);
+            param_py.set_value(position_y);
+            param_pz.set_value(position_z);
+
+            let renderer = AmbisonicEncoderRenderer {
+                order,
+                position_x: render_px,
+                position_y: render_py,
+                position_z: render_pz,
+            };
+
+            let audio_node_options = AudioNodeOptions {
+                channel_count: 1,
+                channel_count_mode: ChannelCountMode::Explicit,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                order,
+                position_x: param_px,
+                position_y: param_py,
+                position_z: param_pz,
+            };
+
+            // instruct to BaseContext to add the AudioListener if it has not already
+            context.base().ensure_audio_listener_present();
+
+            (node, Box::new(renderer))
+        });
+
+        // after the node is registered, connect the AudioListener
+        context
+            .base()
+            .connect_listener_to_panner(node.registration().id());
+
+        node
+    }
+
+    /// Ambisonic order of the output sound field
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    pub fn position_x(&self) -> &AudioParam {
+        &self.position_x
+    }
+
+    pub fn position_y(&self) -> &AudioParam {
+        &self.position_y
+    }
+
+    pub fn position_z(&self) -> &AudioParam {
+        &self.position_z
+    }
+
+    pub fn set_position(&self, x: f32, y: f32, z: f32) {
+        self.position_x.set_value(x);
+        self.position_y.set_value(y);
+        self.position_z.set_value(z);
+    }
+}
+
+struct AmbisonicEncoderRenderer {
+    order: usize,
+    position_x: AudioParamId,
+    position_y: AudioParamId,
+    position_z: AudioParamId,
+}
+
+impl AudioProcessor for AmbisonicEncoderRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        let [listener_x, listener_y, listener_z, ..] = params.listener_params();
+        let position_x = params.get(&self.position_x);
+        let position_y = params.get(&self.position_y);
+        let position_z = params.get(&self.position_z);
+
+        let channels = number_of_channels(self.order);
+        output.set_number_of_channels(channels);
+
+        let source = input.channel_data(0);
+        let mut harmonics = [0.; (MAX_AMBISONIC_ORDER + 1) * (MAX_AMBISONIC_ORDER + 1)];
+
+        let is_constant = [
+            &position_x[..],
+            &position_y[..],
+            &position_z[..],
+            &listener_x[..],
+            &listener_y[..],
+            &listener_z[..],
+        ]
+        .iter()
+        .all(|values| values.len() == 1);
+
+        if is_constant {
+            let direction = [
+                position_x[0] - listener_x[0],
+                position_y[0] - listener_y[0],
+                position_z[0] - listener_z[0],
+            ];
+            spherical_harmonics(self.order, to_ambisonic_frame(direction), &mut harmonics);
+
+            output
+                .channels_mut()
+                .iter_mut()
+                .zip(harmonics)
+                .for_each(|(channel, gain)| {
+                    channel
+                        .iter_mut()
+                        .zip(source.iter())
+                        .for_each(|(o, s)| *o = gain * s);
+                });
+        } else {
+            let output_channels = output.channels_mut();
+
+            for i in 0..RENDER_QUANTUM_SIZE {
+                let value = |values: &[f32]| values[i.min(values.len() - 1)];
+                let direction = [
+                    value(&position_x) - value(&listener_x),
+                    value(&position_y) - value(&listener_y),
+                    value(&position_z) - value(&listener_z),
+                ];
+                spherical_harmonics(self.order, to_ambisonic_frame(direction), &mut harmonics);
+
+                output_channels
+                    .iter_mut()
+                    .zip(harmonics)
+                    .for_each(|(channel, gain)| channel[i] = gain * source[i]);
+            }
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+    use crate::AudioBuffer;
+
+    use super::*;
+
+    fn render(position: [f32; 3]) -> AudioBuffer {
+        let sample_rate = 48000.;
+        let mut context = OfflineAudioContext::new(4, RENDER_QUANTUM_SIZE, sample_rate);
+
+        let options = AmbisonicEncoderOptions {
+            position_x: position[0],
+            position_y: position[1],
+            position_z: position[2],
+            ..AmbisonicEncoderOptions::default()
+        };
+        let encoder = AmbisonicEncoderNode::new(&context, options);
+        encoder.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+        let encoder = context.create_ambisonic_encoder(3);
+
+        assert_eq!(encoder.order(), 3);
+        assert_eq!(encoder.channel_count(), 1);
+        assert_eq!(encoder.position_z().value(), -1.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_order() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+        let _ = context.create_ambisonic_encoder(5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_count() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+        let encoder = context.create_ambisonic_encoder(1);
+        encoder.set_channel_count(2);
+    }
+
+    #[test]
+    fn test_first_order_encoding() {
+        // W, Y (left), Z (up), X (front)
+        let cases = [
+            ([0., 0., -1.], [1., 0., 0., 1.]),
+            ([-2., 0., 0.], [1., 1., 0., 0.]),
+            ([0., 3., 0.], [1., 0., 1., 0.]),
+            ([1., 0., 0.], [1., -1., 0., 0.]),
+        ];
+
+        for (position, expected) in cases {
+            let result = render(position);
+
+            for (channel, value) in expected.iter().enumerate() {
+                assert_float_eq!(
+                    result.get_channel_data(channel)[..],
+                    [*value; RENDER_QUANTUM_SIZE][..],
+                    abs_all <= 1e-6
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_relative_to_listener() {
+        let sample_rate = 48000.;
+        let mut context = OfflineAudioContext::new(4, RENDER_QUANTUM_SIZE, sample_rate);
+
+        // source is above the listener
+        context.listener().position_y().set_value(-1.);
+        let encoder = context.create_ambisonic_encoder(1);
+        encoder.set_position(0., 0., 0.);
+        encoder.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        let result = context.start_rendering_sync();
+        assert_float_eq!(result.get_channel_data(2)[0], 1., abs <= 1e-6);
+        assert_float_eq!(result.get_channel_data(3)[0], 0., abs <= 1e-6);
+    }
+}
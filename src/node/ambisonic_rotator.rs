@@ -0,0 +1,485 @@
+use std::any::Any;
+
+use crate::ambisonics::{
+    ambisonic_audio_node_options, assert_valid_ambisonic_channel_count,
+    assert_valid_ambisonic_channel_count_mode, assert_valid_order, matrix_product,
+    number_of_channels, to_ambisonic_frame, yaw_pitch_roll_matrix, SoundFieldRotation,
+    MAX_AMBISONIC_ORDER,
+};
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, ChannelConfig, ChannelCountMode};
+
+/// Options for constructing an [`AmbisonicRotatorNode`]
+#[derive(Clone, Debug)]
+pub struct AmbisonicRotatorOptions {
+    /// Ambisonic order of the sound field, in the range [1, 4]
+    pub order: usize,
+    /// Initial value for the yaw parameter, in degrees
+    pub yaw: f32,
+    /// Initial value for the pitch parameter, in degrees
+    pub pitch: f32,
+    /// Initial value for the roll parameter, in degrees
+    pub roll: f32,
+    /// Compensate the orientation of the [`AudioListener`](crate::AudioListener)
+    pub follow_listener: bool,
+}
+
+impl Default for AmbisonicRotatorOptions {
+    fn default() -> Self {
+        Self {
+            order: 1,
+            yaw: 0.,
+            pitch: 0.,
+            roll: 0.,
+            follow_listener: true,
+        }
+    }
+}
+
+/// `AmbisonicRotatorNode` rotates an ambisonic sound field
+///
+/// By default, the rotation compensates the orientation of the
+/// [`AudioListener`](crate::AudioListener), so that the sound field encoded with
+/// [`AmbisonicEncoderNode`](super::AmbisonicEncoderNode)s follows the head movements of the
+/// listener. An additional rotation of the sound field is given by the `yaw` (around the
+/// vertical axis, positive to the left), `pitch` (around the left-right axis, positive
+/// downwards) and `roll` (around the front-back axis, positive to the right) parameters.
+///
+/// The input and output are sound fields of `(order + 1)²` channels, using the AmbiX convention
+/// (ACN channel ordering and SN3D normalization).
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_ambisonic_rotator`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::AudioNode;
+///
+/// let context = AudioContext::default();
+///
+/// let rotator = context.create_ambisonic_rotator(3);
+/// let decoder = context.create_ambisonic_binaural_decoder(3);
+/// rotator.connect(&decoder);
+/// decoder.connect(&context.destination());
+///
+/// // the listener turns its head to the left
+/// context.listener().forward_x().set_value(-1.);
+/// context.listener().forward_z().set_value(0.);
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example ambisonics`
+///
+#[derive(Debug)]
+pub struct AmbisonicRotatorNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    /// Ambisonic order of the sound field
+    order: usize,
+    yaw: AudioParam,
+    pitch: AudioParam,
+    roll: AudioParam,
+    follow_listener: bool,
+}
+
+impl AudioNode for AmbisonicRotatorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_ambisonic_channel_count(count, self.order);
+        self.channel_config.set_count(count, self.registration());
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_ambisonic_channel_count_mode(mode);
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+}
+
+impl AmbisonicRotatorNode {
+    /// returns an `AmbisonicRotatorNode` instance
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - ambisonic rotator options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the order is not in the range [1, 4]
+    pub fn new<C: BaseAudioContext>(context: &C, options: AmbisonicRotatorOptions) -> Self {
+        let AmbisonicRotatorOptions {
+            order,
+            yaw,
+            pitch,
+            roll,
+            follow_listener,
+        } = options;
+
+        assert_valid_order(order);
+
+        let node = context.base().register(move |registration| {
+            let angle_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: f32::MIN,
+                max_value: f32::MAX,
+                default_value: 0.,
+                automation_rate: AutomationRate::K,
+            };
+
+            let (mut yaw_param, yaw_proc) =
+                context.create_audio_param(angle_opts.clone(), &registration);
+            yaw_param.set_automation_rate_constrained(true);
+            yaw_param.set_value(yaw);
+
+            let (mut pitch_param, pitch_proc) =
+                context.create_audio_param(angle_opts.clone(), &registration);
+            pitch_param.set_automation_rate_constrained(true);
+            pitch_param.set_value(pitch);
+
+            let (mut roll_param, roll_proc) = context.create_audio_param(angle_opts, &registration);
+            roll_param.set_automation_rate_constrained(true);
+            roll_param.set_value(roll);
+
+            let renderer = AmbisonicRotatorRenderer {
+                order,
+                yaw: yaw_proc,
+                pitch: pitch_proc,
+                roll: roll_proc,
+                follow_listener,
+                rotation: SoundFieldRotation::new(order),
+                matrix: None,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: ambisonic_audio_node_options(order).into(),
+                order,
+                yaw: yaw_param,
+                pitch: pitch_param,
+                roll: roll_param,
+                follow_listener,
+            };
+
+            // instruct to BaseContext to add the AudioListener if it has not already
+            context.base().ensure_audio_listener_present();
+
+            (node, Box::new(renderer))
+        });
+
+        // after the node is registered, connect the AudioListener
+        context
+            .base()
+            .connect_listener_to_panner(node.registration().id());
+
+        node
+    }
+
+    /// Ambisonic order of the sound field
+    pub fn order(&self) -> usize {
+        self.order
+    }
+
+    /// Rotation around the vertical axis in degrees, positive to the left
+    pub fn yaw(&self) -> &AudioParam {
+        &self.yaw
+    }
+
+    /// Rotation around the left-right axis in degrees, positive downwards
+    pub fn pitch(&self) -> &AudioParam {
+        &self.pitch
+    }
+
+    /// Rotation around the front-back axis in degrees, positive to the right
+    pub fn roll(&self) -> &AudioParam {
+        &self.roll
+    }
+
+    /// Denotes if the orientation of the listener is compensated
+    pub fn follow_listener(&self) -> bool {
+        self.follow_listener
+    }
+
+    /// Update the `follow_listener` setting
+    pub fn set_follow_listener(&mut self, value: bool) {
+        self.follow_listener = value;
+        self.registration.post_message(FollowListener(value));
+    }
+}
+
+struct FollowListener(bool);
+
+struct AmbisonicRotatorRenderer {
+    order: usize,
+    yaw: AudioParamId,
+    pitch: AudioParamId,
+    roll: AudioParamId,
+    follow_listener: bool,
+    rotation: SoundFieldRotation,
+    /// Current rotation matrix, to avoid needless updates of the sound field rotation
+    matrix: Option<[[f32; 3]; 3]>,
+}
+
+/// Rotation matrix compensating the orientation of the listener, in the ambisonic frame
+fn listener_rotation(forward: [f32; 3], up: [f32; 3]) -> [[f32; 3]; 3] {
+    let dot = |a: [f32; 3], b: [f32; 3]| a[0] * b[0] + a[1] * b[1] + a[2] * b[2];
+    let normalize = |a: [f32; 3]| {
+        let norm = dot(a, a).sqrt();
+        (norm > 1e-6).then(|| a.map(|v| v / norm))
+    };
+
+    let Some(forward) = normalize(forward) else {
+        return [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+    };
+
+    let projection = dot(up, forward);
+    let Some(up) = normalize([
+        up[0] - projection * forward[0],
+        up[1] - projection * forward[1],
+        up[2] - projection * forward[2],
+    ]) else {
+        return [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]];
+    };
+
+    let left = [
+        up[1] * forward[2] - up[2] * forward[1],
+        up[2] * forward[0] - up[0] * forward[2],
+        up[0] * forward[1] - up[1] * forward[0],
+    ];
+
+    // the coordinates of a direction in the frame of the listener are its projections on the
+    // forward, left and up axes
+    [
+        to_ambisonic_frame(forward),
+        to_ambisonic_frame(left),
+        to_ambisonic_frame(up),
+    ]
+}
+
+impl AudioProcessor for AmbisonicRotatorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        if input.is_silent() {
+            output.make_silent();
+            return false;
+        }
+
+        let yaw = params.get(&self.yaw)[0];
+        let pitch = params.get(&self.pitch)[0];
+        let roll = params.get(&self.roll)[0];
+        let mut matrix = yaw_pitch_roll_matrix(yaw, pitch, roll);
+
+        if self.follow_listener {
+            let [_, _, _, fx, fy, fz, ux, uy, uz] = params.listener_params();
+            let listener = listener_rotation([fx[0], fy[0], fz[0]], [ux[0], uy[0], uz[0]]);
+            matrix = matrix_product(matrix, listener);
+        }
+
+        if self.matrix != Some(matrix) {
+            self.rotation.set_rotation(matrix);
+            self.matrix = Some(matrix);
+        }
+
+        let channels = number_of_channels(self.order);
+        output.set_number_of_channels(channels);
+
+        let input_channels = input.channels();
+        let output_channels = output.channels_mut();
+
+        let mut frame_in = [0.; (MAX_AMBISONIC_ORDER + 1) * (MAX_AMBISONIC_ORDER + 1)];
+        let mut frame_out = [0.; (MAX_AMBISONIC_ORDER + 1) * (MAX_AMBISONIC_ORDER + 1)];
+
+        for i in 0..RENDER_QUANTUM_SIZE {
+            input_channels
+                .iter()
+                .zip(frame_in.iter_mut())
+                .for_each(|(c, v)| *v = c[i]);
+
+            self.rotation
+                .apply(&frame_in[..channels], &mut frame_out[..channels]);
+
+            output_channels
+                .iter_mut()
+                .zip(frame_out.iter())
+                .for_each(|(c, v)| c[i] = *v);
+        }
+
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&mut FollowListener(value)) = msg.downcast_mut::<FollowListener>() {
+            self.follow_listener = value;
+            return;
+        }
+
+        log::warn!("AmbisonicRotatorRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::{AmbisonicEncoderOptions, AudioScheduledSourceNode};
+    use crate::AudioBuffer;
+
+    use super::*;
+
+    fn render_rotated(
+        position: [f32; 3],
+        setup: impl FnOnce(&OfflineAudioContext, &mut AmbisonicRotatorNode),
+    ) -> AudioBuffer {
+        let mut context = OfflineAudioContext::new(9, RENDER_QUANTUM_SIZE, 48000.);
+
+        let options = AmbisonicEncoderOptions {
+            order: 2,
+            position_x: position[0],
+            position_y: position[1],
+            position_z: position[2],
+        };
+        let encoder = crate::node::AmbisonicEncoderNode::new(&context, options);
+
+        let mut rotator = context.create_ambisonic_rotator(2);
+        setup(&context, &mut rotator);
+
+        encoder.connect(&rotator);
+        rotator.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+
+        context.start_rendering_sync()
+    }
+
+    fn assert_same_field(a: &AudioBuffer, b: &AudioBuffer) {
+        assert_eq!(a.number_of_channels(), b.number_of_channels());
+
+        for c in 0..a.number_of_channels() {
+            assert_float_eq!(
+                a.get_channel_data(c)[..],
+                b.get_channel_data(c)[..],
+                abs_all <= 1e-4
+            );
+        }
+    }
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+        let rotator = context.create_ambisonic_rotator(3);
+
+        assert_eq!(rotator.order(), 3);
+        assert_eq!(rotator.channel_count(), 16);
+        assert_eq!(rotator.channel_count_mode(), ChannelCountMode::Explicit);
+        assert!(rotator.follow_listener());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_count() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 48000.);
+        let rotator = context.create_ambisonic_rotator(1);
+        rotator.set_channel_count(9);
+    }
+
+    #[test]
+    fn test_identity() {
+        let rotated = render_rotated([0.3, 0.5, -1.], |_, _| {});
+
+        let mut context = OfflineAudioContext::new(9, RENDER_QUANTUM_SIZE, 48000.);
+        let options = AmbisonicEncoderOptions {
+            order: 2,
+            position_x: 0.3,
+            position_y: 0.5,
+            position_z: -1.,
+        };
+        let encoder = crate::node::AmbisonicEncoderNode::new(&context, options);
+        encoder.connect(&context.destination());
+        let mut src = context.create_constant_source();
+        src.connect(&encoder);
+        src.start();
+        let expected = context.start_rendering_sync();
+
+        assert_same_field(&rotated, &expected);
+    }
+
+    #[test]
+    fn test_yaw() {
+        // source in front, rotated to the left
+        let rotated = render_rotated([0., 0., -1.], |_, rotator| {
+            rotator.yaw().set_value(90.);
+        });
+        let expected = render_rotated([-1., 0., 0.], |_, _| {});
+
+        assert_same_field(&rotated, &expected);
+    }
+
+    #[test]
+    fn test_follow_listener() {
+        // listener turns to the right, the source in front is now on its left
+        let rotated = render_rotated([0., 0., -1.], |context, _| {
+            context.listener().forward_x().set_value(1.);
+            context.listener().forward_z().set_value(0.);
+        });
+        let expected = render_rotated([-1., 0., 0.], |_, _| {});
+        assert_same_field(&rotated, &expected);
+
+        // listener looks up, the source in front is now below
+        let rotated = render_rotated([0., 0., -1.], |context, _| {
+            context.listener().forward_y().set_value(1.);
+            context.listener().forward_z().set_value(0.);
+            context.listener().up_y().set_value(0.);
+            context.listener().up_z().set_value(1.);
+        });
+        let expected = render_rotated([0., -1., 0.], |_, _| {});
+        assert_same_field(&rotated, &expected);
+
+        // unless disabled
+        let rotated = render_rotated([0., 0., -1.], |context, rotator| {
+            context.listener().forward_x().set_value(1.);
+            context.listener().forward_z().set_value(0.);
+            rotator.set_follow_listener(false);
+        });
+        let expected = render_rotated([0., 0., -1.], |_, _| {});
+        assert_same_field(&rotated, &expected);
+    }
+}
@@ -1,6 +1,9 @@
+use std::any::Any;
+
 use crate::analysis::{
-    Analyser, AnalyserRingBuffer, DEFAULT_FFT_SIZE, DEFAULT_MAX_DECIBELS, DEFAULT_MIN_DECIBELS,
-    DEFAULT_SMOOTHING_TIME_CONSTANT,
+    Analyser, AnalyserRingBuffer, SpectrogramCapture, SpectrogramReceiver, DEFAULT_FFT_SIZE,
+    DEFAULT_MAX_DECIBELS, DEFAULT_MIN_DECIBELS, DEFAULT_SMOOTHING_TIME_CONSTANT,
+    DEFAULT_ZERO_PADDING,
 };
 use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::render::{
@@ -9,6 +12,27 @@ use crate::render::{
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
 
+/// Window function applied to the time domain data before the frequency analysis
+///
+/// The specification mandates a Blackman window, the other windows are non spec
+/// extensions.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum AnalyserWindow {
+    /// Blackman window with alpha = 0.16, as required by the specification
+    #[default]
+    Blackman,
+    /// Hann window
+    Hann,
+    /// Hamming window
+    Hamming,
+    /// Flat-top window, giving accurate amplitude readings at the cost of a wide main lobe
+    FlatTop,
+    /// Kaiser window with the given (positive) beta parameter
+    Kaiser(f32),
+    /// Rectangular window, i.e. no windowing
+    Rectangular,
+}
+
 /// Options for constructing an [`AnalyserNode`]
 // dictionary AnalyserOptions : AudioNodeOptions {
 //   unsigned long fftSize = 2048;
@@ -22,6 +46,10 @@ pub struct AnalyserOptions {
     pub max_decibels: f64,
     pub min_decibels: f64,
     pub smoothing_time_constant: f64,
+    /// Window function applied before the frequency analysis (non spec extension)
+    pub window: AnalyserWindow,
+    /// Zero padding factor of the frequency analysis (non spec extension)
+    pub zero_padding: usize,
     pub audio_node_options: AudioNodeOptions,
 }
 
@@ -32,11 +60,35 @@ impl Default for AnalyserOptions {
             max_decibels: DEFAULT_MAX_DECIBELS,
             min_decibels: DEFAULT_MIN_DECIBELS,
             smoothing_time_constant: DEFAULT_SMOOTHING_TIME_CONSTANT,
+            window: AnalyserWindow::default(),
+            zero_padding: DEFAULT_ZERO_PADDING,
             audio_node_options: AudioNodeOptions::default(),
         }
     }
 }
 
+/// Options for the spectrogram capture of an [`AnalyserNode`]
+///
+/// Note that this is not part of the Web Audio API specification.
+#[derive(Clone, Debug)]
+pub struct SpectrogramOptions {
+    /// Number of sample-frames between the start of two consecutive frames, must be
+    /// in the range [1, fft_size]. The default value is 512.
+    pub hop_size: usize,
+    /// Number of frames that can be pending before the render thread starts dropping
+    /// frames. The default value is 256.
+    pub max_frames: usize,
+}
+
+impl Default for SpectrogramOptions {
+    fn default() -> Self {
+        Self {
+            hop_size: 512,
+            max_frames: 256,
+        }
+    }
+}
+
 /// `AnalyserNode` represents a node able to provide real-time frequency and
 /// time-domain analysis information.
 ///
@@ -74,6 +126,14 @@ impl Default for AnalyserOptions {
 /// }
 /// ```
 ///
+/// # Spectrogram capture
+///
+/// As a non spec extension, the node can compute frequency frames at a fixed hop
+/// size in the render thread and queue them, so a spectrogram can be drawn without
+/// gaps even if the control thread polls irregularly. See
+/// [`start_spectrogram`](Self::start_spectrogram) and
+/// [`get_spectrogram_frame`](Self::get_spectrogram_frame).
+///
 /// # Examples
 ///
 /// - `cargo run --release --example analyser`
@@ -84,6 +144,7 @@ pub struct AnalyserNode {
     registration: AudioContextRegistration,
     channel_config: ChannelConfig,
     analyser: Analyser,
+    spectrogram: Option<SpectrogramReceiver>,
 }
 
 impl AudioNode for AnalyserNode {
@@ -116,15 +177,19 @@ impl AnalyserNode {
             analyser.set_fft_size(fft_size);
             analyser.set_smoothing_time_constant(smoothing_time_constant);
             analyser.set_decibels(min_decibels, max_decibels);
+            analyser.set_window(options.window);
+            analyser.set_zero_padding(options.zero_padding);
 
             let render = AnalyserRenderer {
                 ring_buffer: analyser.get_ring_buffer_clone(),
+                spectrogram: None,
             };
 
             let node = AnalyserNode {
                 registration,
                 channel_config: options.audio_node_options.into(),
                 analyser,
+                spectrogram: None,
             };
 
             (node, Box::new(render))
@@ -149,6 +214,60 @@ impl AnalyserNode {
         self.analyser.set_fft_size(fft_size);
     }
 
+    /// Set an FFT size up to 131072, beyond the range allowed by the specification
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if fft_size is not a power of two or not in the range [32, 131072]
+    pub fn set_extended_fft_size(&mut self, fft_size: usize) {
+        // the history is only kept for the spec fft sizes, it grows on demand
+        if let Some(ring_buffer) = self.analyser.set_extended_fft_size(fft_size) {
+            self.registration.post_message(ring_buffer);
+        }
+    }
+
+    /// Window function applied to the time domain data before the frequency analysis
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    pub fn window(&self) -> AnalyserWindow {
+        self.analyser.window()
+    }
+
+    /// Set the window function applied before the frequency analysis
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the beta parameter of a Kaiser window is negative
+    pub fn set_window(&mut self, window: AnalyserWindow) {
+        self.analyser.set_window(window);
+    }
+
+    /// Zero padding factor of the frequency analysis. The default value is 1.
+    ///
+    /// The time domain data is padded with zeros up to `fft_size * zero_padding`
+    /// samples before the transform, which interpolates the spectrum and multiplies
+    /// the [`frequency_bin_count`](Self::frequency_bin_count) by the same factor.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    pub fn zero_padding(&self) -> usize {
+        self.analyser.zero_padding()
+    }
+
+    /// Set the zero padding factor
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the value is not a power of two in the range [1, 16]
+    pub fn set_zero_padding(&mut self, zero_padding: usize) {
+        self.analyser.set_zero_padding(zero_padding);
+    }
+
     /// Time averaging parameter with the last analysis frame.
     /// A value from 0 -> 1 where 0 represents no time averaging with the last
     /// analysis frame. The default value is 0.8.
@@ -209,7 +328,8 @@ impl AnalyserNode {
         self.analyser.set_decibels(self.min_decibels(), value);
     }
 
-    /// Number of bins in the FFT results, is half the FFT size
+    /// Number of bins in the FFT results, is half the FFT size (times the zero
+    /// padding factor)
     ///
     /// # Panics
     ///
@@ -236,6 +356,50 @@ impl AnalyserNode {
         self.analyser.get_byte_time_domain_data(buffer);
     }
 
+    /// Start computing frequency frames in the render thread every `hop_size`
+    /// sample-frames
+    ///
+    /// The frames use the FFT size, window and zero padding of the analyser at the
+    /// time of the call, they are expressed in decibels (as in
+    /// [`get_float_frequency_data`](Self::get_float_frequency_data)) but without time
+    /// smoothing. Any previous capture is stopped and its pending frames discarded.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// This function panics if the hop size is not in the range [1, fft_size] or if
+    /// `max_frames` is zero
+    pub fn start_spectrogram(&mut self, options: SpectrogramOptions) {
+        let (receiver, capture) = self
+            .analyser
+            .spectrogram(options.hop_size, options.max_frames);
+
+        self.registration.post_message(Some(capture));
+        self.spectrogram = Some(receiver);
+    }
+
+    /// Stop computing frequency frames in the render thread
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    pub fn stop_spectrogram(&mut self) {
+        if self.spectrogram.take().is_some() {
+            self.registration.post_message(None::<SpectrogramCapture>);
+        }
+    }
+
+    /// Copy the oldest pending spectrogram frame into the provided buffer and return
+    /// its timestamp, i.e. the context time of the end of its analysis window
+    ///
+    /// Returns `None` if no frame is pending or if no capture is running. Frames are
+    /// dropped in the render thread when `max_frames` frames are already pending, so
+    /// this should be called until it returns `None` on each poll.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    pub fn get_spectrogram_frame(&mut self, buffer: &mut [f32]) -> Option<f64> {
+        self.spectrogram.as_ref()?.read(buffer)
+    }
+
     /// Copy the current frequency data into the provided buffer
     ///
     /// # Panics
@@ -260,6 +424,7 @@ impl AnalyserNode {
 
 struct AnalyserRenderer {
     ring_buffer: AnalyserRingBuffer,
+    spectrogram: Option<SpectrogramCapture>,
 }
 
 impl AudioProcessor for AnalyserRenderer {
@@ -268,7 +433,7 @@ impl AudioProcessor for AnalyserRenderer {
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
         _params: AudioParamValues<'_>,
-        _scope: &AudioWorkletGlobalScope,
+        scope: &AudioWorkletGlobalScope,
     ) -> bool {
         // single input/output node
         let input = &inputs[0];
@@ -285,9 +450,29 @@ impl AudioProcessor for AnalyserRenderer {
         let data = mono.channel_data(0).as_ref();
         self.ring_buffer.write(data);
 
+        if let Some(spectrogram) = self.spectrogram.as_mut() {
+            spectrogram.process(&self.ring_buffer, scope.current_time, scope.sample_rate);
+        }
+
         // no tail-time
         false
     }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(spectrogram) = msg.downcast_mut::<Option<SpectrogramCapture>>() {
+            // Avoid deallocation in the render thread by swapping the capture.
+            std::mem::swap(&mut self.spectrogram, spectrogram);
+            return;
+        }
+
+        if let Some(ring_buffer) = msg.downcast_mut::<AnalyserRingBuffer>() {
+            // Avoid deallocation in the render thread by swapping the ring buffer.
+            std::mem::swap(&mut self.ring_buffer, ring_buffer);
+            return;
+        }
+
+        log::warn!("AnalyserRenderer: Dropping incoming message {msg:?}");
+    }
 }
 
 #[cfg(test)]
@@ -341,4 +526,117 @@ mod tests {
         };
         let _ = AnalyserNode::new(&context, options);
     }
+
+    #[test]
+    fn test_construct_window_and_zero_padding() {
+        let context = OfflineAudioContext::new(1, 128, 44_100.);
+        let options = AnalyserOptions {
+            window: AnalyserWindow::FlatTop,
+            zero_padding: 2,
+            ..AnalyserOptions::default()
+        };
+        let analyser = AnalyserNode::new(&context, options);
+        assert_eq!(analyser.window(), AnalyserWindow::FlatTop);
+        assert_eq!(analyser.zero_padding(), 2);
+        assert_eq!(analyser.frequency_bin_count(), DEFAULT_FFT_SIZE);
+    }
+
+    #[test]
+    fn test_spectrogram() {
+        let sample_rate = 48_000.;
+        let fft_size = 1024;
+        let hop_size = 200;
+        let length = 48_000;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let mut analyser = context.create_analyser();
+        analyser.set_fft_size(fft_size);
+        analyser.connect(&context.destination());
+        analyser.start_spectrogram(SpectrogramOptions {
+            hop_size,
+            max_frames: length / hop_size + 1,
+        });
+
+        // sine centered on bin 20
+        let mut osc = context.create_oscillator();
+        osc.frequency()
+            .set_value(sample_rate / fft_size as f32 * 20.);
+        osc.connect(&analyser);
+        osc.start();
+
+        let _ = context.start_rendering_sync();
+
+        let mut bins = vec![0.; analyser.frequency_bin_count()];
+        let mut count = 0;
+
+        while let Some(time) = analyser.get_spectrogram_frame(&mut bins) {
+            count += 1;
+            // no frame is missing
+            let expected = (count * hop_size) as f64 / sample_rate as f64;
+            assert_float_eq!(time, expected, abs <= 1e-9);
+
+            if count * hop_size >= fft_size {
+                let highest = bins
+                    .iter()
+                    .enumerate()
+                    .fold(
+                        (0, f32::NEG_INFINITY),
+                        |a, (i, &b)| if b > a.1 { (i, b) } else { a },
+                    );
+                assert_eq!(highest.0, 20);
+            }
+        }
+
+        assert_eq!(count, length / hop_size);
+
+        analyser.stop_spectrogram();
+        assert!(analyser.get_spectrogram_frame(&mut bins).is_none());
+    }
+
+    #[test]
+    fn test_spectrogram_drops_frames() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 48_000, sample_rate);
+
+        let mut analyser = context.create_analyser();
+        analyser.connect(&context.destination());
+        analyser.start_spectrogram(SpectrogramOptions {
+            hop_size: 512,
+            max_frames: 4,
+        });
+
+        let _ = context.start_rendering_sync();
+
+        // only the oldest frames are kept
+        let mut bins = vec![0.; analyser.frequency_bin_count()];
+        let mut times = vec![];
+        while let Some(time) = analyser.get_spectrogram_frame(&mut bins) {
+            times.push(time);
+        }
+
+        let expected: Vec<f64> = (1..=4).map(|i| (i * 512) as f64 / 48_000.).collect();
+        assert_float_eq!(&times[..], &expected[..], abs_all <= 1e-9);
+    }
+
+    #[test]
+    fn test_extended_fft_size_history() {
+        let sample_rate = 48_000.;
+        let fft_size = 65_536;
+        let mut context = OfflineAudioContext::new(1, 2 * fft_size, sample_rate);
+
+        let mut analyser = context.create_analyser();
+        analyser.set_extended_fft_size(fft_size);
+        analyser.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&analyser);
+        src.start();
+
+        let _ = context.start_rendering_sync();
+
+        // the render thread writes into the grown ring buffer
+        let mut buffer = vec![0.; fft_size];
+        analyser.get_float_time_domain_data(&mut buffer);
+        assert_float_eq!(&buffer[..], &vec![1.; fft_size][..], abs_all <= 0.);
+    }
 }
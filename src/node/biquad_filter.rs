@@ -16,16 +16,50 @@ use super::{AudioNode, AudioNodeOptions, ChannelConfig};
 
 /// Biquad filter coefficients normalized against a0
 #[derive(Clone, Copy, Debug, Default)]
-struct Coefficients {
+pub(crate) struct Coefficients {
+    pub(crate) b0: f64,
+    pub(crate) b1: f64,
+    pub(crate) b2: f64,
+    pub(crate) a1: f64,
+    pub(crate) a2: f64,
+}
+
+impl Coefficients {
+    /// Evaluate the Z-transform of the filter at given normalized frequency from 0 to 1
+    /// (1 corresponds to the Nyquist frequency).
+    pub(crate) fn response(&self, frequency: f64) -> Complex<f64> {
+        let Self { b0, b1, b2, a1, a2 } = *self;
+
+        // @note - comment from Firefox source code, blink/Biquad.cpp
+        //
+        // The z-transform of the filter is
+        //
+        // H(z) = (b0 + b1*z^(-1) + b2*z^(-2))/(1 + a1*z^(-1) + a2*z^(-2))
+        //
+        // Evaluate as
+        //
+        // b0 + (b1 + b2*z1)*z1
+        // --------------------
+        // 1 + (a1 + a2*z1)*z1
+        //
+        // with z1 = 1/z and z = exp(j*pi*frequency). Hence z1 = exp(-j*pi*frequency)
+        let omega = -PI * frequency;
+        let z = Complex::new(omega.cos(), omega.sin());
+        let numerator = b0 + (b1 + b2 * z) * z;
+        let denominator = Complex::new(1., 0.) + (a1 + a2 * z) * z;
+        numerator / denominator
+    }
+}
+
+// all coefs calculation functions adapted from `/wpt/webaudio/resources/biquad-filters.js`
+pub(crate) fn normalize_coefs(
     b0: f64,
     b1: f64,
     b2: f64,
+    a0: f64,
     a1: f64,
     a2: f64,
-}
-
-// all coefs calculation functions adapted from `/wpt/webaudio/resources/biquad-filters.js`
-fn normalize_coefs(b0: f64, b1: f64, b2: f64, a0: f64, a1: f64, a2: f64) -> Coefficients {
+) -> Coefficients {
     let scale = 1. / a0;
 
     Coefficients {
@@ -340,7 +374,7 @@ fn get_highshelf_coefs(freq: f64, gain: f64) -> Coefficients {
     }
 }
 
-fn calculate_coefs(
+pub(crate) fn calculate_coefs(
     filter_type: BiquadFilterType,
     sample_rate: f64,
     f0: f64,
@@ -364,7 +398,7 @@ fn calculate_coefs(
 }
 
 // frequency is clamped between 0 and nyquist later in the algorithm
-fn get_computed_freq(freq: f32, detune: f32) -> f32 {
+pub(crate) fn get_computed_freq(freq: f32, detune: f32) -> f32 {
     if detune != 0. {
         freq * (detune / 1200.).exp2()
     } else {
@@ -690,7 +724,7 @@ impl BiquadFilterNode {
         // get coefs
         let computed_freq = get_computed_freq(frequency, detune);
 
-        let Coefficients { b0, b1, b2, a1, a2 } = calculate_coefs(
+        let coefs = calculate_coefs(
             type_,
             sample_rate as f64,
             computed_freq as f64,
@@ -698,23 +732,6 @@ impl BiquadFilterNode {
             q as f64,
         );
 
-        // @note - comment from Firefox source code, blink/Biquad.cpp
-        //
-        // Evaluate the Z-transform of the filter at given normalized
-        // frequency from 0 to 1.  (1 corresponds to the Nyquist
-        // frequency.)
-        //
-        // The z-transform of the filter is
-        //
-        // H(z) = (b0 + b1*z^(-1) + b2*z^(-2))/(1 + a1*z^(-1) + a2*z^(-2))
-        //
-        // Evaluate as
-        //
-        // b0 + (b1 + b2*z1)*z1
-        // --------------------
-        // 1 + (a1 + a2*z1)*z1
-        //
-        // with z1 = 1/z and z = exp(j*pi*frequency). Hence z1 = exp(-j*pi*frequency)
         for (i, &freq) in frequency_hz.iter().enumerate() {
             // <https://webaudio.github.io/web-audio-api/#dom-biquadfilternode-getfrequencyresponse>
             // > If a value in the frequencyHz parameter is not within [0, sampleRate/2],
@@ -726,12 +743,7 @@ impl BiquadFilterNode {
                 phase_response[i] = f32::NAN;
             } else {
                 let f = freq / n_quist;
-
-                let omega = -PI * f64::from(f);
-                let z = Complex::new(omega.cos(), omega.sin());
-                let numerator = b0 + (b1 + b2 * z) * z;
-                let denominator = Complex::new(1., 0.) + (a1 + a2 * z) * z;
-                let response = numerator / denominator;
+                let response = coefs.response(f64::from(f));
 
                 let (mag, phase) = response.to_polar();
                 mag_response[i] = mag as f32;
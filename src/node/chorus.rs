@@ -0,0 +1,328 @@
+//! The chorus control and renderer parts
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::param::AudioParam;
+
+use super::modulation::{
+    assert_valid_channel_count, assert_valid_channel_count_mode, ModulatedDelayRenderer,
+    ModulationParams, ModulationValues,
+};
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Shortest delay time (in seconds) of the chorus voices
+const MIN_DELAY: f32 = 0.01;
+
+/// Range of the delay time modulation (in seconds) for a `depth` of 1
+const SWEEP: f32 = 0.02;
+
+/// Number of modulated delay voices per channel
+const NUM_VOICES: usize = 3;
+
+const DEFAULTS: ModulationValues = ModulationValues {
+    rate: 1.5,
+    depth: 0.5,
+    feedback: 0.,
+    mix: 0.5,
+    spread: 1.,
+};
+
+/// Options for constructing a [`ChorusNode`]
+#[derive(Clone, Debug)]
+pub struct ChorusOptions {
+    /// Frequency (in Hz) of the delay time modulation
+    pub rate: f32,
+    /// Amount of delay time modulation, from 0 to 1
+    pub depth: f32,
+    /// Amount of wet signal fed back into the delay lines, from -0.95 to 0.95
+    pub feedback: f32,
+    /// Amount of wet signal in the output, from 0 (dry) to 1 (wet)
+    pub mix: f32,
+    /// Phase offset between the left and right modulations, from 0 (in phase) to 1
+    /// (opposite phase)
+    pub spread: f32,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for ChorusOptions {
+    fn default() -> Self {
+        Self {
+            rate: DEFAULTS.rate,
+            depth: DEFAULTS.depth,
+            feedback: DEFAULTS.feedback,
+            mix: DEFAULTS.mix,
+            spread: DEFAULTS.spread,
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::ClampedMax,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// `ChorusNode` thickens its input by mixing it with copies delayed by slowly modulated
+/// delay times
+///
+/// Each channel is processed by three voices whose delay times, between 10ms and 30ms, are
+/// modulated by the same sine LFO with evenly spread phases. The input is mixed down to (or
+/// up to) stereo and the output is always stereo.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_chorus`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let chorus = context.create_chorus();
+/// chorus.rate().set_value(0.8);
+/// chorus.depth().set_value(0.7);
+/// chorus.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&chorus);
+/// osc.start();
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example modulation`
+///
+#[derive(Debug)]
+pub struct ChorusNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    params: ModulationParams,
+}
+
+impl AudioNode for ChorusNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_channel_count_mode(mode, "ChorusNode");
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_channel_count(count, "ChorusNode");
+        self.channel_config.set_count(count, self.registration());
+    }
+}
+
+impl ChorusNode {
+    /// Creates a `ChorusNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - chorus options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.audio_node_options.channel_count` is greater than 2
+    /// * `options.audio_node_options.channel_count_mode` is `ChannelCountMode::Max`
+    ///
+    pub fn new<C: BaseAudioContext>(context: &C, options: ChorusOptions) -> Self {
+        context.base().register(move |registration| {
+            let ChorusOptions {
+                rate,
+                depth,
+                feedback,
+                mix,
+                spread,
+                audio_node_options,
+            } = options;
+
+            assert_valid_channel_count_mode(audio_node_options.channel_count_mode, "ChorusNode");
+            assert_valid_channel_count(audio_node_options.channel_count, "ChorusNode");
+
+            let values = ModulationValues {
+                rate,
+                depth,
+                feedback,
+                mix,
+                spread,
+            };
+            let (params, ids) = ModulationParams::new(context, &registration, &DEFAULTS, &values);
+
+            let renderer = ModulatedDelayRenderer::new(
+                context.sample_rate(),
+                ids,
+                MIN_DELAY,
+                SWEEP,
+                NUM_VOICES,
+            );
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                params,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// K-rate [`AudioParam`] representing the frequency (in Hz) of the delay time
+    /// modulation, in the range `[0, 20]`.
+    #[must_use]
+    pub fn rate(&self) -> &AudioParam {
+        &self.params.rate
+    }
+
+    /// K-rate [`AudioParam`] representing the amount of delay time modulation, from 0
+    /// (static delay) to 1 (delay times sweeping over 20ms).
+    #[must_use]
+    pub fn depth(&self) -> &AudioParam {
+        &self.params.depth
+    }
+
+    /// K-rate [`AudioParam`] representing the amount of wet signal fed back into the
+    /// delay lines, in the range `[-0.95, 0.95]`.
+    #[must_use]
+    pub fn feedback(&self) -> &AudioParam {
+        &self.params.feedback
+    }
+
+    /// A-rate [`AudioParam`] representing the balance between the dry and the
+    /// wet signals, from 0 (dry only) to 1 (wet only).
+    #[must_use]
+    pub fn mix(&self) -> &AudioParam {
+        &self.params.mix
+    }
+
+    /// K-rate [`AudioParam`] representing the phase offset between the left and right
+    /// channels modulations, from 0 (in phase) to 1 (opposite phase).
+    #[must_use]
+    pub fn spread(&self) -> &AudioParam {
+        &self.params.spread
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let chorus = context.create_chorus();
+
+        assert_float_eq!(chorus.rate().value(), 1.5, abs <= 0.);
+        assert_float_eq!(chorus.depth().value(), 0.5, abs <= 0.);
+        assert_float_eq!(chorus.feedback().value(), 0., abs <= 0.);
+        assert_float_eq!(chorus.mix().value(), 0.5, abs <= 0.);
+        assert_float_eq!(chorus.spread().value(), 1., abs <= 0.);
+        assert_eq!(chorus.channel_count(), 2);
+        assert_eq!(chorus.channel_count_mode(), ChannelCountMode::ClampedMax);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_count() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let chorus = context.create_chorus();
+        chorus.set_channel_count(3);
+    }
+
+    #[test]
+    fn test_dry_signal() {
+        let mut context = OfflineAudioContext::new(2, 128, 44_100.);
+
+        let chorus = ChorusNode::new(
+            &context,
+            ChorusOptions {
+                mix: 0.,
+                ..ChorusOptions::default()
+            },
+        );
+        chorus.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&chorus);
+        src.start();
+
+        let res = context.start_rendering_sync();
+        // mono input is upmixed to stereo
+        assert_float_eq!(res.get_channel_data(0)[..], [1.; 128], abs_all <= 0.);
+        assert_float_eq!(res.get_channel_data(1)[..], [1.; 128], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_static_delay() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(2, 4096, sample_rate);
+
+        let chorus = ChorusNode::new(
+            &context,
+            ChorusOptions {
+                depth: 0.,
+                mix: 1.,
+                ..ChorusOptions::default()
+            },
+        );
+        chorus.connect(&context.destination());
+
+        let mut dirac = context.create_buffer(1, 1, sample_rate);
+        dirac.copy_to_channel(&[1.], 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&chorus);
+        src.set_buffer(dirac);
+        src.start();
+
+        let res = context.start_rendering_sync();
+
+        // without modulation, all voices are delayed by the minimum delay
+        let delay = (MIN_DELAY * sample_rate) as usize;
+        let mut expected = vec![0.; 4096];
+        expected[delay] = 1.;
+        assert_float_eq!(res.get_channel_data(0)[..], expected[..], abs_all <= 1e-6);
+        assert_float_eq!(res.get_channel_data(1)[..], expected[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_stereo_spread() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(2, 44_100, sample_rate);
+
+        let chorus = context.create_chorus();
+        chorus.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.connect(&chorus);
+        osc.start();
+
+        let res = context.start_rendering_sync();
+        assert!(res.get_channel_data(0) != res.get_channel_data(1));
+    }
+}
@@ -4,10 +4,11 @@ use fft_convolver::FFTConvolver;
 
 use crate::buffer::AudioBuffer;
 use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::convolution::{ConvolutionWorker, PartitionedConvolver};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::RENDER_QUANTUM_SIZE;
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
 
@@ -52,6 +53,28 @@ fn normalize_buffer(buffer: &AudioBuffer) -> f32 {
     scale
 }
 
+/// Partitioning scheme of the impulse response used by the [`ConvolverNode`]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum ConvolverPartitioning {
+    /// Impulse response split in partitions of equal size
+    ///
+    /// The computational load is spread evenly over the render quanta, which works well for
+    /// short to medium impulse responses.
+    #[default]
+    Uniform,
+    /// Impulse response split in partitions of increasing size
+    ///
+    /// The head of the response is convolved with partitions of a single render quantum, so
+    /// there is no added latency, while the tail uses larger and cheaper partitions. This is
+    /// much more efficient for long impulse responses (e.g. large halls or parking garages).
+    NonUniform {
+        /// Compute the largest partitions on a background thread shared by all the channels of
+        /// the response. The render thread never waits for it, a result which is not ready at
+        /// its deadline is computed in the render thread instead.
+        background_thread: bool,
+    },
+}
+
 /// `ConvolverNode` options
 //dictionary ConvolverOptions : AudioNodeOptions {
 //  AudioBuffer? buffer;
@@ -63,6 +86,8 @@ pub struct ConvolverOptions {
     pub buffer: Option<AudioBuffer>,
     /// The opposite of the desired initial value for the normalize attribute
     pub disable_normalization: bool,
+    /// The partitioning scheme of the convolution engine (not part of the spec)
+    pub partitioning: ConvolverPartitioning,
     /// AudioNode options
     pub audio_node_options: AudioNodeOptions,
 }
@@ -72,6 +97,7 @@ impl Default for ConvolverOptions {
         Self {
             buffer: None,
             disable_normalization: false,
+            partitioning: ConvolverPartitioning::default(),
             audio_node_options: AudioNodeOptions {
                 channel_count: 2,
                 channel_count_mode: ChannelCountMode::ClampedMax,
@@ -120,8 +146,17 @@ fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
 /// - specification: <https://webaudio.github.io/web-audio-api/#ConvolverNode>
 /// - see also: [`BaseAudioContext::create_convolver`]
 ///
-/// The current implementation only handles mono-to-mono convolutions. The provided impulse
-/// response buffer and the input signal will be downmixed appropriately.
+/// The input is mono or stereo, the output channels depend on the impulse response:
+///
+/// - 1 channel: each input channel is convolved with the response
+/// - 2 channels: the left and right input channels are convolved with the first and second
+///   channels of the response (a mono input is upmixed to stereo)
+/// - 4 channels (true stereo): the first two channels of the response are the left-to-left and
+///   left-to-right paths, the last two the right-to-left and right-to-right paths
+/// - any other number of channels (not part of the spec): the input is downmixed to mono and
+///   each channel of the response produces an output channel, e.g. a 6 channels response
+///   renders a 5.1 surround reverberation. A stereo input is downmixed to `0.5 * (L + R)`, its
+///   channels are not matched with the channels of the response
 ///
 /// # Usage
 ///
@@ -162,6 +197,8 @@ pub struct ConvolverNode {
     channel_config: ChannelConfig,
     /// Perform equal power normalization on response buffer
     normalize: bool,
+    /// Partitioning scheme of the convolution engine
+    partitioning: ConvolverPartitioning,
     /// The response buffer, nullable
     buffer: Option<AudioBuffer>,
 }
@@ -213,6 +250,7 @@ impl ConvolverNode {
         let ConvolverOptions {
             buffer,
             disable_normalization,
+            partitioning,
             audio_node_options,
         } = options;
 
@@ -231,6 +269,7 @@ impl ConvolverNode {
                 registration,
                 channel_config: audio_node_options.into(),
                 normalize: !disable_normalization,
+                partitioning,
                 buffer: None,
             };
 
@@ -252,14 +291,22 @@ impl ConvolverNode {
 
     /// Set or update the impulse response buffer
     ///
+    /// A buffer with 1, 2 or 4 channels follows the channel configurations of the spec. With any
+    /// other number of channels (not part of the spec), each channel of the buffer renders an
+    /// output channel from the input downmixed to mono, i.e. the channels of a stereo input are
+    /// not matched one by one with the channels of the buffer.
+    ///
     /// # Panics
     ///
-    /// Panics when the sample rate of the provided AudioBuffer differs from the audio context
-    /// sample rate.
+    /// Panics when
+    /// - the sample rate of the provided AudioBuffer differs from the audio context sample rate
+    /// - the provided AudioBuffer has more than [`MAX_CHANNELS`] channels
     pub fn set_buffer(&mut self, buffer: AudioBuffer) {
-        // If the buffer number of channels is not 1, 2, 4, or if the sample-rate of the buffer is
-        // not the same as the sample-rate of its associated BaseAudioContext, a NotSupportedError
-        // MUST be thrown.
+        // If the sample-rate of the buffer is not the same as the sample-rate of its associated
+        // BaseAudioContext, a NotSupportedError MUST be thrown.
+        //
+        // The spec also requires the buffer to have 1, 2 or 4 channels, we deviate from it to
+        // support multichannel impulse responses, each channel rendering an output channel.
 
         let sample_rate = buffer.sample_rate();
         assert_eq!(
@@ -270,8 +317,8 @@ impl ConvolverNode {
 
         let number_of_channels = buffer.number_of_channels();
         assert!(
-            [1, 2, 4].contains(&number_of_channels),
-            "NotSupportedError - the convolution buffer must consist of 1, 2 or 4 channels"
+            number_of_channels <= MAX_CHANNELS,
+            "NotSupportedError - convolution buffer cannot have more than {MAX_CHANNELS} channels, got {number_of_channels}"
         );
 
         // normalize before padding because the length of the buffer affects the scale
@@ -281,11 +328,22 @@ impl ConvolverNode {
             1.
         };
 
-        let mut convolvers = Vec::<FFTConvolver<f32>>::new();
+        let mut convolvers = Vec::<Convolver>::new();
         // @note - value defined by "rule of thumb", to be explored further
         let partition_size = RENDER_QUANTUM_SIZE * 8;
 
-        // Handle multichannel IR
+        // a single background thread for all the channels of the response
+        let mut worker = match self.partitioning {
+            ConvolverPartitioning::NonUniform {
+                background_thread: true,
+            } => Some(ConvolutionWorker::new(
+                buffer.length(),
+                number_of_channels.max(2),
+            )),
+            _ => None,
+        };
+
+        // Handle multichannel IR, one convolver per IR channel
         // cf. https://webaudio.github.io/web-audio-api/#Convolution-channel-configurations
         // Note that in case of mono IR we create 2 convolvers to properly handle stereo input
         for index in 0..number_of_channels.max(2) {
@@ -298,14 +356,26 @@ impl ConvolverNode {
                 .zip(buffer.get_channel_data(channel))
                 .for_each(|(o, i)| *o = *i * scale);
 
-            let mut convolver = FFTConvolver::<f32>::default();
-            convolver
-                .init(partition_size, &scaled_channel)
-                .expect("Unable to initialize convolution engine");
+            let convolver = match self.partitioning {
+                ConvolverPartitioning::Uniform => {
+                    let mut convolver = FFTConvolver::<f32>::default();
+                    convolver
+                        .init(partition_size, &scaled_channel)
+                        .expect("Unable to initialize convolution engine");
+                    Convolver::Uniform(convolver)
+                }
+                ConvolverPartitioning::NonUniform { .. } => Convolver::NonUniform(
+                    PartitionedConvolver::new(&scaled_channel, worker.as_mut()),
+                ),
+            };
 
             convolvers.push(convolver);
         }
 
+        if let Some(worker) = worker {
+            worker.spawn();
+        }
+
         let msg = ConvolverInfosMessage {
             convolvers: Some(convolvers),
             impulse_length: buffer.length(),
@@ -325,16 +395,43 @@ impl ConvolverNode {
     pub fn set_normalize(&mut self, value: bool) {
         self.normalize = value;
     }
+
+    /// Partitioning scheme of the convolution engine
+    pub fn partitioning(&self) -> ConvolverPartitioning {
+        self.partitioning
+    }
+
+    /// Update the partitioning scheme. This will only have an effect when `set_buffer` is called.
+    pub fn set_partitioning(&mut self, value: ConvolverPartitioning) {
+        self.partitioning = value;
+    }
+}
+
+/// Convolution engine of a single channel of the impulse response
+enum Convolver {
+    Uniform(FFTConvolver<f32>),
+    NonUniform(PartitionedConvolver),
+}
+
+impl Convolver {
+    fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        match self {
+            Self::Uniform(convolver) => {
+                let _ = convolver.process(input, output);
+            }
+            Self::NonUniform(convolver) => convolver.process(input, output),
+        }
+    }
 }
 
 struct ConvolverInfosMessage {
-    convolvers: Option<Vec<FFTConvolver<f32>>>,
+    convolvers: Option<Vec<Convolver>>,
     impulse_length: usize,
     impulse_number_of_channels: usize,
 }
 
 struct ConvolverRenderer {
-    convolvers: Option<Vec<FFTConvolver<f32>>>,
+    convolvers: Option<Vec<Convolver>>,
     impulse_length: usize,
     impulse_number_of_channels: usize,
     tail_count: usize,
@@ -381,7 +478,7 @@ impl AudioProcessor for ConvolverRenderer {
 
                 let i = &input.channel_data(0)[..];
                 let o = &mut output.channel_data_mut(0)[..];
-                let _ = convolvers[0].process(i, o);
+                convolvers[0].process(i, o);
             }
             (1, 2) => {
                 output.set_number_of_channels(2);
@@ -389,32 +486,32 @@ impl AudioProcessor for ConvolverRenderer {
                 let i = &input.channel_data(0)[..];
 
                 let o_left = &mut output.channel_data_mut(0)[..];
-                let _ = convolvers[0].process(i, o_left);
+                convolvers[0].process(i, o_left);
 
                 let o_right = &mut output.channel_data_mut(1)[..];
-                let _ = convolvers[1].process(i, o_right);
+                convolvers[1].process(i, o_right);
             }
             (2, 1) => {
                 output.set_number_of_channels(2);
 
                 let i_left = &input.channel_data(0)[..];
                 let o_left = &mut output.channel_data_mut(0)[..];
-                let _ = convolvers[0].process(i_left, o_left);
+                convolvers[0].process(i_left, o_left);
 
                 let i_right = &input.channel_data(1)[..];
                 let o_right = &mut output.channel_data_mut(1)[..];
-                let _ = convolvers[1].process(i_right, o_right);
+                convolvers[1].process(i_right, o_right);
             }
             (2, 2) => {
                 output.set_number_of_channels(2);
 
                 let i_left = &input.channel_data(0)[..];
                 let o_left = &mut output.channel_data_mut(0)[..];
-                let _ = convolvers[0].process(i_left, o_left);
+                convolvers[0].process(i_left, o_left);
 
                 let i_right = &input.channel_data(1)[..];
                 let o_right = &mut output.channel_data_mut(1)[..];
-                let _ = convolvers[1].process(i_right, o_right);
+                convolvers[1].process(i_right, o_right);
             }
             (2, 4) => {
                 output.set_number_of_channels(4);
@@ -422,16 +519,16 @@ impl AudioProcessor for ConvolverRenderer {
                 let i_left = &input.channel_data(0)[..];
 
                 let o_0 = &mut output.channel_data_mut(0)[..];
-                let _ = convolvers[0].process(i_left, o_0);
+                convolvers[0].process(i_left, o_0);
                 let o_1 = &mut output.channel_data_mut(1)[..];
-                let _ = convolvers[1].process(i_left, o_1);
+                convolvers[1].process(i_left, o_1);
 
                 let i_right = &input.channel_data(1)[..];
 
                 let o_2 = &mut output.channel_data_mut(2)[..];
-                let _ = convolvers[2].process(i_right, o_2);
+                convolvers[2].process(i_right, o_2);
                 let o_3 = &mut output.channel_data_mut(3)[..];
-                let _ = convolvers[3].process(i_right, o_3);
+                convolvers[3].process(i_right, o_3);
 
                 // mix output back to stereo
                 let o_2 = output.channel_data(2).clone();
@@ -457,13 +554,13 @@ impl AudioProcessor for ConvolverRenderer {
                 let i = &input.channel_data(0)[..];
 
                 let o_0 = &mut output.channel_data_mut(0)[..];
-                let _ = convolvers[0].process(i, o_0);
+                convolvers[0].process(i, o_0);
                 let o_1 = &mut output.channel_data_mut(1)[..];
-                let _ = convolvers[1].process(i, o_1);
+                convolvers[1].process(i, o_1);
                 let o_2 = &mut output.channel_data_mut(2)[..];
-                let _ = convolvers[2].process(i, o_2);
+                convolvers[2].process(i, o_2);
                 let o_3 = &mut output.channel_data_mut(3)[..];
-                let _ = convolvers[3].process(i, o_3);
+                convolvers[3].process(i, o_3);
 
                 // mix output back to stereo
                 let o_2 = output.channel_data(2).clone();
@@ -483,7 +580,31 @@ impl AudioProcessor for ConvolverRenderer {
 
                 output.set_number_of_channels(2);
             }
-            _ => unreachable!(),
+            (number_of_input_channels, number_of_channels) => {
+                // multichannel IR (not part of the spec), the input is downmixed to mono and
+                // each IR channel renders an output channel. A stereo input is folded to
+                // 0.5 * (L + R) rather than matched with the IR channels, as there is no
+                // meaningful pairing between the input channels and an arbitrary IR layout.
+                let mut mono = [0.; RENDER_QUANTUM_SIZE];
+
+                if number_of_input_channels == 1 {
+                    mono.copy_from_slice(&input.channel_data(0)[..]);
+                } else {
+                    let i_left = input.channel_data(0);
+                    let i_right = input.channel_data(1);
+
+                    mono.iter_mut()
+                        .zip(i_left.iter().zip(i_right.iter()))
+                        .for_each(|(m, (l, r))| *m = 0.5 * (l + r));
+                }
+
+                output.set_number_of_channels(number_of_channels);
+
+                convolvers
+                    .iter_mut()
+                    .zip(output.channels_mut())
+                    .for_each(|(convolver, o)| convolver.process(&mono, &mut o[..]));
+            }
         }
 
         true
@@ -512,6 +633,7 @@ impl AudioProcessor for ConvolverRenderer {
 mod tests {
     use float_eq::assert_float_eq;
 
+    use crate::buffer::ChannelData;
     use crate::context::{BaseAudioContext, OfflineAudioContext};
     use crate::node::{AudioBufferSourceNode, AudioBufferSourceOptions, AudioScheduledSourceNode};
 
@@ -533,8 +655,17 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
-    fn test_buffer_must_have_1_2_4_channels() {
+    #[should_panic(expected = "NotSupportedError")]
+    fn test_buffer_too_many_channels() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+
+        let ir = AudioBuffer::from_channels(vec![ChannelData::new(1); MAX_CHANNELS + 1], 48000.);
+        let mut conv = ConvolverNode::new(&context, ConvolverOptions::default());
+        conv.set_buffer(ir);
+    }
+
+    #[test]
+    fn test_buffer_multichannel() {
         let context = OfflineAudioContext::new(1, 128, 48000.);
 
         let ir = vec![1.];
@@ -544,7 +675,8 @@ mod tests {
             ..ConvolverOptions::default()
         };
 
-        let _ = ConvolverNode::new(&context, options);
+        let conv = ConvolverNode::new(&context, options);
+        assert_eq!(conv.buffer().unwrap().number_of_channels(), 3);
     }
 
     #[test]
@@ -989,4 +1121,220 @@ mod tests {
             abs_all <= 1e-7
         );
     }
+
+    fn render_partitioning(partitioning: ConvolverPartitioning) -> AudioBuffer {
+        let sample_rate = 44100.;
+        let length = RENDER_QUANTUM_SIZE * 200;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        // deterministic pseudo-random signal and decaying impulse response
+        let signal: Vec<f32> = (0..length / 2).map(|i| (i as f32 * 0.37).sin()).collect();
+        let ir: Vec<f32> = (0..12_000)
+            .map(|i| (i as f32 * 1.3).cos() * (-(i as f32) / 3000.).exp())
+            .collect();
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(vec![signal], sample_rate));
+
+        let conv = ConvolverNode::new(
+            &context,
+            ConvolverOptions {
+                buffer: Some(AudioBuffer::from(vec![ir.clone(), ir], sample_rate)),
+                partitioning,
+                ..ConvolverOptions::default()
+            },
+        );
+        assert_eq!(conv.partitioning(), partitioning);
+
+        src.connect(&conv);
+        conv.connect(&context.destination());
+        src.start();
+
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_non_uniform_partitioning() {
+        let uniform = render_partitioning(ConvolverPartitioning::Uniform);
+
+        for background_thread in [false, true] {
+            let result =
+                render_partitioning(ConvolverPartitioning::NonUniform { background_thread });
+
+            for channel in 0..2 {
+                assert_float_eq!(
+                    result.get_channel_data(channel)[..],
+                    uniform.get_channel_data(channel)[..],
+                    abs_all <= 1e-4
+                );
+            }
+        }
+    }
+
+    const FIXTURE_LENGTH: usize = 4096;
+
+    // first samples of each channel of the small room response
+    fn load_fixture(context: &OfflineAudioContext) -> Vec<Vec<f32>> {
+        let file = std::fs::File::open("samples/small-room-response.wav").unwrap();
+        let buffer = context.decode_audio_data_sync(file).unwrap();
+
+        (0..buffer.number_of_channels())
+            .map(|c| buffer.get_channel_data(c)[..FIXTURE_LENGTH].to_vec())
+            .collect()
+    }
+
+    fn render_fixture(
+        mut context: OfflineAudioContext,
+        input: Vec<Vec<f32>>,
+        ir: Vec<Vec<f32>>,
+    ) -> AudioBuffer {
+        let sample_rate = context.sample_rate();
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(AudioBuffer::from(input, sample_rate));
+
+        let conv = ConvolverNode::new(
+            &context,
+            ConvolverOptions {
+                buffer: Some(AudioBuffer::from(ir, sample_rate)),
+                disable_normalization: true,
+                ..ConvolverOptions::default()
+            },
+        );
+
+        src.connect(&conv);
+        conv.connect(&context.destination());
+        src.start();
+
+        context.start_rendering_sync()
+    }
+
+    fn delayed(signal: &[f32], delay: usize) -> Vec<f32> {
+        let mut output = vec![0.; signal.len()];
+        output[delay..].copy_from_slice(&signal[..signal.len() - delay]);
+        output
+    }
+
+    #[test]
+    fn test_true_stereo_fixture() {
+        let context = OfflineAudioContext::new(2, FIXTURE_LENGTH, 44100.);
+        let fixture = load_fixture(&context);
+
+        // distinct paths: L->L, L->R, R->L, R->R
+        let ir = vec![
+            fixture[0].clone(),
+            fixture[1].iter().map(|v| v * 0.5).collect(),
+            fixture[0].iter().map(|v| v * -0.25).collect(),
+            fixture[1].clone(),
+        ];
+
+        // left impulse at 0, right impulse at 100
+        let mut left = vec![0.; FIXTURE_LENGTH];
+        left[0] = 1.;
+        let mut right = vec![0.; FIXTURE_LENGTH];
+        right[100] = 1.;
+
+        let result = render_fixture(context, vec![left, right], ir.clone());
+
+        let expected_left: Vec<f32> = ir[0]
+            .iter()
+            .zip(delayed(&ir[2], 100))
+            .map(|(a, b)| a + b)
+            .collect();
+        let expected_right: Vec<f32> = ir[1]
+            .iter()
+            .zip(delayed(&ir[3], 100))
+            .map(|(a, b)| a + b)
+            .collect();
+
+        assert_eq!(result.number_of_channels(), 2);
+        assert_float_eq!(
+            result.get_channel_data(0)[..],
+            expected_left[..],
+            abs_all <= 1e-5
+        );
+        assert_float_eq!(
+            result.get_channel_data(1)[..],
+            expected_right[..],
+            abs_all <= 1e-5
+        );
+    }
+
+    #[test]
+    fn test_multichannel_ir_stereo_input_downmixed() {
+        let sample_rate = 44100.;
+        let context = OfflineAudioContext::new(3, 128, sample_rate);
+
+        // three distinct delays, one per IR channel
+        let ir: Vec<Vec<f32>> = (0..3)
+            .map(|c| {
+                let mut channel = vec![0.; 8];
+                channel[c + 1] = 1.;
+                channel
+            })
+            .collect();
+
+        // left and right differ, so channel-by-channel matching would be observable
+        let mut left = vec![0.; 128];
+        left[0] = 1.;
+        let mut right = vec![0.; 128];
+        right[10] = 1.;
+
+        let result = render_fixture(context, vec![left.clone(), right.clone()], ir);
+
+        assert_eq!(result.number_of_channels(), 3);
+
+        let mono: Vec<f32> = left
+            .iter()
+            .zip(right.iter())
+            .map(|(l, r)| 0.5 * (l + r))
+            .collect();
+
+        for c in 0..3 {
+            assert_float_eq!(
+                result.get_channel_data(c)[..],
+                delayed(&mono, c + 1)[..],
+                abs_all <= 1e-6
+            );
+        }
+    }
+
+    #[test]
+    fn test_multichannel_fixture_stereo_input_downmixed() {
+        let context = OfflineAudioContext::new(6, FIXTURE_LENGTH, 44100.);
+        let fixture = load_fixture(&context);
+
+        // 5.1 response built from the stereo fixture
+        let ir: Vec<Vec<f32>> = (0..6)
+            .map(|c| {
+                let gain = 1. / (c + 1) as f32;
+                fixture[c % 2].iter().map(|v| v * gain).collect()
+            })
+            .collect();
+
+        // distinct left and right inputs, the stereo input is downmixed to mono rather than
+        // matched with the channels of the response
+        let mut left = vec![0.; FIXTURE_LENGTH];
+        left[0] = 1.;
+        let mut right = vec![0.; FIXTURE_LENGTH];
+        right[50] = 1.;
+
+        let result = render_fixture(context, vec![left, right], ir.clone());
+
+        assert_eq!(result.number_of_channels(), 6);
+
+        for (c, channel_ir) in ir.iter().enumerate() {
+            let expected: Vec<f32> = channel_ir
+                .iter()
+                .zip(delayed(channel_ir, 50))
+                .map(|(a, b)| 0.5 * (a + b))
+                .collect();
+
+            assert_float_eq!(
+                result.get_channel_data(c)[..],
+                expected[..],
+                abs_all <= 1e-5
+            );
+        }
+    }
 }
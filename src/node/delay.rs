@@ -3,13 +3,34 @@ use crate::param::{AudioParam, AudioParamDescriptor};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
-use crate::RENDER_QUANTUM_SIZE;
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
 
+use std::any::Any;
 use std::cell::{Cell, RefCell, RefMut};
 use std::rc::Rc;
 
+/// Interpolation between the recorded samples of a [`DelayNode`], for fractional delays
+///
+/// The specification only defines the linear interpolation, the other modes are not part of the
+/// Web Audio API specification.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum DelayInterpolation {
+    /// Nearest sample, the delay is rounded to an integer number of samples
+    None,
+    /// Linear interpolation between the two nearest samples, attenuates the high frequencies
+    #[default]
+    Linear,
+    /// Cubic Hermite (Catmull-Rom) interpolation over the four nearest samples
+    Cubic,
+    /// Third order Lagrange interpolation over the four nearest samples
+    Lagrange,
+    /// First order all-pass interpolation, flat magnitude response but frequency dependent
+    /// phase, best suited for slowly modulated delays
+    Allpass,
+}
+
 /// Options for constructing a [`DelayNode`]
 // dictionary DelayOptions : AudioNodeOptions {
 //   double maxDelayTime = 1;
@@ -19,6 +40,11 @@ use std::rc::Rc;
 pub struct DelayOptions {
     pub max_delay_time: f64,
     pub delay_time: f64,
+    /// Interpolation for fractional delays (not part of the spec)
+    pub interpolation: DelayInterpolation,
+    /// Lift the three minutes limit of `max_delay_time`, e.g. for looper-style delays (not part
+    /// of the spec)
+    pub unbounded_max_delay_time: bool,
     pub audio_node_options: AudioNodeOptions,
 }
 
@@ -27,6 +53,8 @@ impl Default for DelayOptions {
         Self {
             max_delay_time: 1.,
             delay_time: 0.,
+            interpolation: DelayInterpolation::default(),
+            unbounded_max_delay_time: false,
             audio_node_options: AudioNodeOptions::default(),
         }
     }
@@ -37,6 +65,8 @@ struct PlaybackInfo {
     prev_block_index: usize,
     prev_frame_index: usize,
     k: f32,
+    // the next frame is the last recorded one, i.e. the delay is smaller than one sample
+    next_is_last: bool,
 }
 
 /// Node that delays the incoming audio signal by a certain amount
@@ -102,6 +132,7 @@ pub struct DelayNode {
     writer_registration: AudioContextRegistration,
     delay_time: AudioParam,
     channel_config: ChannelConfig,
+    interpolation: DelayInterpolation,
 }
 
 impl AudioNode for DelayNode {
@@ -279,7 +310,9 @@ impl DelayNode {
     ///
     /// # Panics
     ///
-    /// Panics when the max delay value is smaller than zero or langer than three minutes.
+    /// Panics when the max delay value is smaller than zero or langer than three minutes. The
+    /// upper limit is lifted with `unbounded_max_delay_time`, the max delay value must then be
+    /// finite.
     pub fn new<C: BaseAudioContext>(context: &C, options: DelayOptions) -> Self {
         let sample_rate = context.sample_rate() as f64;
 
@@ -287,19 +320,27 @@ impl DelayNode {
         // If specified, this value MUST be greater than zero and less than three
         // minutes or a NotSupportedError exception MUST be thrown. If not specified,
         // then 1 will be used.
-        assert!(
-            options.max_delay_time > 0. && options.max_delay_time < 180.,
-            "NotSupportedError - maxDelayTime MUST be greater than zero and less than three minutes",
-        );
+        if options.unbounded_max_delay_time {
+            assert!(
+                options.max_delay_time > 0. && options.max_delay_time.is_finite(),
+                "NotSupportedError - maxDelayTime MUST be greater than zero and finite",
+            );
+        } else {
+            assert!(
+                options.max_delay_time > 0. && options.max_delay_time < 180.,
+                "NotSupportedError - maxDelayTime MUST be greater than zero and less than three minutes",
+            );
+        }
 
         // Allocate large enough ring buffer to store all delayed samples.
         // We add one extra slot in the ring buffer so that reader never reads the
         // same entry in history as the writer, even if `delay_time == max_delay_time`
-        // of if `max_delay_time < quantum duration`
+        // of if `max_delay_time < quantum duration`, and another one for the frame
+        // before the oldest delayed sample, read by the four points interpolations
         let max_delay_time = options.max_delay_time;
         let num_quanta =
             (max_delay_time * sample_rate / RENDER_QUANTUM_SIZE as f64).ceil() as usize;
-        let ring_buffer = Vec::with_capacity(num_quanta + 1);
+        let ring_buffer = Vec::with_capacity(num_quanta + 2);
 
         let shared_ring_buffer = Rc::new(RefCell::new(ring_buffer));
         let shared_ring_buffer_clone = Rc::clone(&shared_ring_buffer);
@@ -334,6 +375,8 @@ impl DelayNode {
                     in_cycle: false,
                     last_written_index_checked: None,
                     latest_frame_written: latest_frame_written_clone,
+                    interpolation: options.interpolation,
+                    allpass_state: [0.; MAX_CHANNELS],
                 };
 
                 let node = DelayNode {
@@ -341,6 +384,7 @@ impl DelayNode {
                     writer_registration,
                     channel_config: options.audio_node_options.into(),
                     delay_time: param,
+                    interpolation: options.interpolation,
                 };
 
                 (node, Box::new(reader_render))
@@ -371,6 +415,21 @@ impl DelayNode {
     pub fn delay_time(&self) -> &AudioParam {
         &self.delay_time
     }
+
+    /// Interpolation for fractional delays
+    ///
+    /// Note that this attribute is not part of the Web Audio API specification.
+    pub fn interpolation(&self) -> DelayInterpolation {
+        self.interpolation
+    }
+
+    /// Update the interpolation for fractional delays
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    pub fn set_interpolation(&mut self, interpolation: DelayInterpolation) {
+        self.interpolation = interpolation;
+        self.reader_registration.post_message(interpolation);
+    }
 }
 
 struct DelayWriter {
@@ -497,6 +556,9 @@ struct DelayReader {
     last_written_index: Rc<Cell<Option<usize>>>,
     // local copy of shared `last_written_index` so as to avoid render ordering issues
     last_written_index_checked: Option<usize>,
+    interpolation: DelayInterpolation,
+    // last output sample of each channel, for the all-pass interpolation
+    allpass_state: [f32; MAX_CHANNELS],
 }
 
 // SAFETY:
@@ -566,6 +628,7 @@ impl AudioProcessor for DelayReader {
                     prev_block_index,
                     prev_frame_index,
                     k,
+                    next_is_last,
                 } = playback_infos[i - 1];
 
                 let mut prev_block_index = prev_block_index;
@@ -580,6 +643,7 @@ impl AudioProcessor for DelayReader {
                     prev_block_index,
                     prev_frame_index,
                     k,
+                    next_is_last,
                 };
             }
         } else {
@@ -608,6 +672,18 @@ impl AudioProcessor for DelayReader {
 
         // render channels aligned
         for (channel_number, output_channel) in output.channels_mut().iter_mut().enumerate() {
+            if self.interpolation != DelayInterpolation::Linear {
+                is_actively_processing |= render_interpolated(
+                    self.interpolation,
+                    &ring_buffer,
+                    channel_number,
+                    &playback_infos,
+                    &mut self.allpass_state[channel_number],
+                    output_channel,
+                );
+                continue;
+            }
+
             // store channel data locally and update pointer only when needed
             let mut block_index = playback_infos[0].prev_block_index;
             let mut channel_data = ring_buffer[block_index].channel_data(channel_number);
@@ -620,6 +696,7 @@ impl AudioProcessor for DelayReader {
                         prev_block_index,
                         prev_frame_index,
                         k,
+                        ..
                     } = *infos;
 
                     // find next sample address
@@ -681,6 +758,133 @@ impl AudioProcessor for DelayReader {
 
         true
     }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&interpolation) = msg.downcast_ref::<DelayInterpolation>() {
+            self.interpolation = interpolation;
+            return;
+        }
+
+        log::warn!("DelayReader: Dropping incoming message {msg:?}");
+    }
+}
+
+/// Address (block and frame index) in the ring buffer of the frame after the given one
+#[inline(always)]
+fn next_address(block_index: usize, frame_index: usize, ring_size: usize) -> (usize, usize) {
+    if frame_index + 1 >= RENDER_QUANTUM_SIZE {
+        ((block_index + 1) % ring_size, 0)
+    } else {
+        (block_index, frame_index + 1)
+    }
+}
+
+/// Address (block and frame index) in the ring buffer of the frame before the given one
+#[inline(always)]
+fn prev_address(block_index: usize, frame_index: usize, ring_size: usize) -> (usize, usize) {
+    if frame_index == 0 {
+        (
+            (block_index + ring_size - 1) % ring_size,
+            RENDER_QUANTUM_SIZE - 1,
+        )
+    } else {
+        (block_index, frame_index - 1)
+    }
+}
+
+/// Render a channel of the delay line with the given (non linear) interpolation, returns
+/// whether the output is actively processing
+fn render_interpolated(
+    interpolation: DelayInterpolation,
+    ring_buffer: &[AudioRenderQuantum],
+    channel_number: usize,
+    playback_infos: &[PlaybackInfo; RENDER_QUANTUM_SIZE],
+    allpass_state: &mut f32,
+    output_channel: &mut [f32],
+) -> bool {
+    let ring_size = ring_buffer.len();
+    let sample = |(block_index, frame_index): (usize, usize)| {
+        ring_buffer[block_index].channel_data(channel_number)[frame_index]
+    };
+
+    let mut is_actively_processing = false;
+
+    output_channel
+        .iter_mut()
+        .zip(playback_infos.iter())
+        .for_each(|(o, infos)| {
+            let PlaybackInfo {
+                prev_block_index,
+                prev_frame_index,
+                k,
+                next_is_last,
+            } = *infos;
+
+            let prev = (prev_block_index, prev_frame_index);
+            let next = next_address(prev_block_index, prev_frame_index, ring_size);
+
+            let value = match interpolation {
+                DelayInterpolation::None => {
+                    if k < 0.5 {
+                        sample(prev)
+                    } else {
+                        sample(next)
+                    }
+                }
+                DelayInterpolation::Linear => (1. - k).mul_add(sample(prev), k * sample(next)),
+                DelayInterpolation::Cubic | DelayInterpolation::Lagrange => {
+                    let y0 = sample(prev_address(prev.0, prev.1, ring_size));
+                    let y1 = sample(prev);
+                    let y2 = sample(next);
+                    // do not read past the last recorded frame
+                    let y3 = if next_is_last {
+                        y2
+                    } else {
+                        sample(next_address(next.0, next.1, ring_size))
+                    };
+
+                    if interpolation == DelayInterpolation::Cubic {
+                        let c1 = 0.5 * (y2 - y0);
+                        let c2 = y0 - 2.5 * y1 + 2. * y2 - 0.5 * y3;
+                        let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+                        ((c3 * k + c2) * k + c1) * k + y1
+                    } else {
+                        let l0 = -k * (k - 1.) * (k - 2.) / 6.;
+                        let l1 = (k + 1.) * (k - 1.) * (k - 2.) / 2.;
+                        let l2 = -(k + 1.) * k * (k - 2.) / 2.;
+                        let l3 = (k + 1.) * k * (k - 1.) / 6.;
+                        l0 * y0 + l1 * y1 + l2 * y2 + l3 * y3
+                    }
+                }
+                DelayInterpolation::Allpass => {
+                    // the fractional delay relative to the next frame is `1 - k`, keep it in
+                    // the range [0.5, 1.5) so that the pole of the filter stays away from the
+                    // unit circle
+                    let value = if k <= 0.5 || next_is_last {
+                        let eta = k / (2. - k);
+                        eta * (sample(next) - *allpass_state) + sample(prev)
+                    } else {
+                        let eta = (k - 1.) / (3. - k);
+                        let after_next = next_address(next.0, next.1, ring_size);
+                        eta * (sample(after_next) - *allpass_state) + sample(next)
+                    };
+                    *allpass_state = value;
+                    value
+                }
+            };
+
+            if value.is_normal() {
+                is_actively_processing = true;
+            }
+
+            *o = value;
+        });
+
+    if !is_actively_processing {
+        *allpass_state = 0.;
+    }
+
+    is_actively_processing
 }
 
 impl DelayReader {
@@ -739,6 +943,7 @@ impl DelayReader {
             prev_block_index: prev_block_index as usize,
             prev_frame_index: prev_frame_index as usize,
             k,
+            next_is_last: num_samples < 1.,
         }
     }
 }
@@ -1203,4 +1408,204 @@ mod tests {
 
         assert_float_eq!(channel[..], expected[..], abs_all <= 1e-5);
     }
+
+    /// Render a 10kHz sine at 48kHz delayed by 2.5 samples, returns its amplitude
+    fn render_interpolated_sine(interpolation: DelayInterpolation) -> f32 {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 4 * 128, sample_rate);
+
+        let options = DelayOptions {
+            delay_time: 2.5 / sample_rate as f64,
+            interpolation,
+            ..DelayOptions::default()
+        };
+        let delay = DelayNode::new(&context, options);
+        assert_eq!(delay.interpolation(), interpolation);
+        delay.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(10_000.);
+        osc.connect(&delay);
+        osc.start();
+
+        let result = context.start_rendering_sync();
+        // 5 periods of the sine
+        let channel = &result.get_channel_data(0)[4 * 128 - 120..];
+        let mean_square = channel.iter().map(|v| v * v).sum::<f32>() / channel.len() as f32;
+        (2. * mean_square).sqrt()
+    }
+
+    /// Render noise delayed by 255.5 samples with the given max delay time
+    fn render_interpolated_noise(
+        interpolation: DelayInterpolation,
+        max_delay_time: f64,
+    ) -> Vec<f32> {
+        let sample_rate = 48_000.;
+        let length = 8 * 128;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let options = DelayOptions {
+            max_delay_time,
+            delay_time: 255.5 / sample_rate as f64,
+            interpolation,
+            ..DelayOptions::default()
+        };
+        let delay = DelayNode::new(&context, options);
+        delay.connect(&context.destination());
+
+        let mut seed = 1_u32;
+        let noise: Vec<f32> = (0..length)
+            .map(|_| {
+                // xorshift
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                (seed as f32 / u32::MAX as f32) * 2. - 1.
+            })
+            .collect();
+        let mut buffer = context.create_buffer(1, length, sample_rate);
+        buffer.copy_to_channel(&noise, 0);
+
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&delay);
+        src.start();
+
+        let result = context.start_rendering_sync();
+        result.get_channel_data(0).to_vec()
+    }
+
+    #[test]
+    fn test_interpolation_at_max_delay_time() {
+        // the frame before the oldest delayed sample must not be overwritten
+        for interpolation in [DelayInterpolation::Cubic, DelayInterpolation::Lagrange] {
+            let at_max = render_interpolated_noise(interpolation, 255.5 / 48_000.);
+            let expected = render_interpolated_noise(interpolation, 1.);
+            assert_float_eq!(at_max[..], expected[..], abs_all <= 0.);
+        }
+    }
+
+    #[test]
+    fn test_interpolation_high_frequency_attenuation() {
+        assert_eq!(
+            DelayOptions::default().interpolation,
+            DelayInterpolation::Linear
+        );
+
+        // linear interpolation attenuates by cos(pi * 10 / 48)
+        let linear = render_interpolated_sine(DelayInterpolation::Linear);
+        assert_float_eq!(linear, 0.793, abs <= 0.01);
+
+        // 4-point interpolations are flatter
+        let cubic = render_interpolated_sine(DelayInterpolation::Cubic);
+        assert_float_eq!(cubic, 0.940, abs <= 0.01);
+        let lagrange = render_interpolated_sine(DelayInterpolation::Lagrange);
+        assert_float_eq!(lagrange, 0.940, abs <= 0.01);
+
+        // all-pass and no interpolation do not attenuate
+        let allpass = render_interpolated_sine(DelayInterpolation::Allpass);
+        assert_float_eq!(allpass, 1., abs <= 0.01);
+        let none = render_interpolated_sine(DelayInterpolation::None);
+        assert_float_eq!(none, 1., abs <= 0.01);
+    }
+
+    #[test]
+    fn test_interpolation_sample_accurate() {
+        // all interpolations are exact for an integer delay
+        for interpolation in [
+            DelayInterpolation::None,
+            DelayInterpolation::Cubic,
+            DelayInterpolation::Lagrange,
+            DelayInterpolation::Allpass,
+        ] {
+            for delay_in_samples in [3., 131.] {
+                let sample_rate = 48_000.;
+                let mut context = OfflineAudioContext::new(1, 256, sample_rate);
+
+                let mut delay = context.create_delay(1.);
+                delay.set_interpolation(interpolation);
+                delay.delay_time.set_value(delay_in_samples / sample_rate);
+                delay.connect(&context.destination());
+
+                let mut dirac = context.create_buffer(1, 1, sample_rate);
+                dirac.copy_to_channel(&[1.], 0);
+
+                let mut src = context.create_buffer_source();
+                src.connect(&delay);
+                src.set_buffer(dirac);
+                src.start_at(0.);
+
+                let result = context.start_rendering_sync();
+                let channel = result.get_channel_data(0);
+
+                let mut expected = vec![0.; 256];
+                expected[delay_in_samples as usize] = 1.;
+
+                assert_float_eq!(channel[..], expected[..], abs_all <= 1e-5);
+            }
+        }
+    }
+
+    #[test]
+    fn test_interpolation_none_rounds_delay() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 128, sample_rate);
+
+        let options = DelayOptions {
+            delay_time: 2.3 / sample_rate as f64,
+            interpolation: DelayInterpolation::None,
+            ..DelayOptions::default()
+        };
+        let delay = DelayNode::new(&context, options);
+        delay.connect(&context.destination());
+
+        let mut dirac = context.create_buffer(1, 1, sample_rate);
+        dirac.copy_to_channel(&[1.], 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&delay);
+        src.set_buffer(dirac);
+        src.start_at(0.);
+
+        let result = context.start_rendering_sync();
+        let mut expected = vec![0.; 128];
+        expected[2] = 1.;
+
+        assert_float_eq!(result.get_channel_data(0)[..], expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_max_delay_time_limit() {
+        let context = OfflineAudioContext::new(1, 128, 8_000.);
+        let options = DelayOptions {
+            max_delay_time: 200.,
+            ..DelayOptions::default()
+        };
+        let _ = DelayNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_unbounded_max_delay_time() {
+        let sample_rate = 8_000.;
+        let mut context = OfflineAudioContext::new(1, 128, sample_rate);
+
+        let options = DelayOptions {
+            max_delay_time: 200.,
+            delay_time: 190.,
+            unbounded_max_delay_time: true,
+            ..DelayOptions::default()
+        };
+        let delay = DelayNode::new(&context, options);
+        assert_float_eq!(delay.delay_time().max_value(), 200., abs <= 0.);
+        assert_float_eq!(delay.delay_time().value(), 190., abs <= 0.);
+        delay.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&delay);
+        src.start();
+
+        let result = context.start_rendering_sync();
+        assert_float_eq!(result.get_channel_data(0)[..], [0.; 128][..], abs_all <= 0.);
+    }
 }
@@ -11,14 +11,14 @@ use crate::{AtomicF32, RENDER_QUANTUM_SIZE};
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
 
 // Converting a value 𝑣 in decibels to linear gain unit means returning 10𝑣/20.
-fn db_to_lin(val: f32) -> f32 {
+pub(crate) fn db_to_lin(val: f32) -> f32 {
     (10.0_f32).powf(val / 20.)
 }
 
 // Converting a value 𝑣 in linear gain unit to decibel means executing the following steps:
 // If 𝑣 is equal to zero, return -1000.
 // Else, return 20log10𝑣.
-fn lin_to_db(val: f32) -> f32 {
+pub(crate) fn lin_to_db(val: f32) -> f32 {
     if val == 0. {
         -1000.
     } else {
@@ -0,0 +1,1039 @@
+//! The cascaded filter control and renderer parts
+use std::any::Any;
+use std::f64::consts::PI;
+
+use arrayvec::ArrayVec;
+use num_complex::Complex;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+
+use super::biquad_filter::{normalize_coefs, Coefficients};
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Maximum number of sections of a cascade, i.e. a 48dB/oct band-pass filter
+const MAX_SECTIONS: usize = 8;
+
+/// Minimum value of the `q` parameter, avoids infinite bandwidths
+const MIN_Q: f64 = 1e-3;
+
+/// Filter types of a [`FilterNode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterType {
+    /// Allows frequencies below the cutoff frequency to pass through and
+    /// attenuates frequencies above the cutoff.
+    #[default]
+    Lowpass,
+    /// Frequencies above the cutoff frequency are passed through, but
+    /// frequencies below the cutoff are attenuated.
+    Highpass,
+    /// Allows a range of frequencies around the center frequency to pass through,
+    /// built from a high-pass at the lower edge and a low-pass at the upper edge of the band.
+    Bandpass,
+}
+
+/// Filter designs of a [`FilterNode`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterDesign {
+    /// Maximally flat pass band, -3dB at the cutoff frequency
+    #[default]
+    Butterworth,
+    /// Squared Butterworth filter, -6dB at the cutoff frequency. The low-pass and high-pass
+    /// filters sum to a flat magnitude response, as needed by speaker crossovers.
+    LinkwitzRiley,
+    /// Steeper transition band at the expense of ripples in the pass band, whose amplitude
+    /// is given by the `ripple` of the filter. The pass band ends at the cutoff frequency.
+    Chebyshev,
+    /// Maximally flat group delay, i.e. preserves the shape of the waveforms in the pass band,
+    /// -3dB at the cutoff frequency
+    Bessel,
+}
+
+/// Slopes of a [`FilterNode`], in dB per octave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FilterSlope {
+    /// Second order filter
+    Db12,
+    /// Fourth order filter
+    #[default]
+    Db24,
+    /// Sixth order filter
+    Db36,
+    /// Eighth order filter
+    Db48,
+}
+
+impl FilterSlope {
+    fn order(self) -> usize {
+        match self {
+            FilterSlope::Db12 => 2,
+            FilterSlope::Db24 => 4,
+            FilterSlope::Db36 => 6,
+            FilterSlope::Db48 => 8,
+        }
+    }
+}
+
+/// Options for constructing a [`FilterNode`]
+#[derive(Clone, Debug)]
+pub struct FilterOptions {
+    /// Cutoff frequency, or center frequency of a band-pass filter
+    pub frequency: f32,
+    /// Multiplier of the quality factor of the sections, or quality factor of the band of a
+    /// band-pass filter
+    pub q: f32,
+    /// Filter type
+    pub type_: FilterType,
+    /// Filter design
+    pub design: FilterDesign,
+    /// Filter slope
+    pub slope: FilterSlope,
+    /// Amplitude of the pass band ripples of the Chebyshev design (dB)
+    pub ripple: f32,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for FilterOptions {
+    fn default() -> Self {
+        Self {
+            frequency: 350.,
+            q: 1.,
+            type_: FilterType::default(),
+            design: FilterDesign::default(),
+            slope: FilterSlope::default(),
+            ripple: 1.,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// Assert that the given ripple is valid for the Chebyshev design
+///
+/// # Panics
+///
+/// This function panics if given ripple is not strictly positive
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_ripple(ripple: f32) {
+    assert!(
+        ripple > 0. && ripple.is_finite(),
+        "RangeError - Invalid ripple: {ripple:?} is not strictly positive"
+    );
+}
+
+/// Second (or first) order section of the normalized analog prototype
+#[derive(Clone, Copy, Debug)]
+struct Section {
+    /// high-pass section, low-pass otherwise
+    highpass: bool,
+    /// the section is tuned to the upper edge of the band of a band-pass filter
+    upper_edge: bool,
+    /// frequency of the poles, relative to the cutoff frequency
+    omega: f64,
+    /// quality factor of the poles, `None` for a single real pole
+    q: Option<f64>,
+}
+
+impl Section {
+    /// Digital coefficients of the section, `k` being the prewarped cutoff frequency
+    fn coefficients(&self, k: f64, q_multiplier: f64) -> Coefficients {
+        // the high-pass sections are obtained with the s -> 1/s transform
+        let w = if self.highpass {
+            k / self.omega
+        } else {
+            k * self.omega
+        };
+
+        match self.q {
+            None => {
+                let (b0, b1) = if self.highpass { (1., -1.) } else { (w, w) };
+                normalize_coefs(b0, b1, 0., 1. + w, w - 1., 0.)
+            }
+            Some(q) => {
+                let q = q * q_multiplier;
+                let (b0, b1, b2) = if self.highpass {
+                    (1., -2., 1.)
+                } else {
+                    (w * w, 2. * w * w, w * w)
+                };
+                let a0 = 1. + w / q + w * w;
+                let a1 = 2. * w * w - 2.;
+                let a2 = 1. - w / q + w * w;
+                normalize_coefs(b0, b1, b2, a0, a1, a2)
+            }
+        }
+    }
+}
+
+/// Cascade of sections implementing a filter type, design and slope
+#[derive(Clone, Debug)]
+pub(crate) struct Cascade {
+    type_: FilterType,
+    sections: ArrayVec<Section, MAX_SECTIONS>,
+    /// overall gain, normalizes the pass band of the even order Chebyshev filters
+    gain: f64,
+}
+
+impl Cascade {
+    pub(crate) fn new(
+        type_: FilterType,
+        design: FilterDesign,
+        slope: FilterSlope,
+        ripple: f32,
+    ) -> Self {
+        let order = slope.order();
+
+        let (poles, gain) = match design {
+            FilterDesign::Butterworth => (butterworth_poles(order), 1.),
+            FilterDesign::LinkwitzRiley => {
+                let mut poles = butterworth_poles(order / 2);
+                poles.extend(poles.clone());
+                (poles, 1.)
+            }
+            FilterDesign::Chebyshev => {
+                let epsilon = (10_f64.powf(f64::from(ripple) / 10.) - 1.).sqrt();
+                let gain = if order % 2 == 0 {
+                    1. / (1. + epsilon * epsilon).sqrt()
+                } else {
+                    1.
+                };
+                (chebyshev_poles(order, epsilon), gain)
+            }
+            FilterDesign::Bessel => (bessel_poles(order), 1.),
+        };
+
+        let prototype = |highpass: bool, upper_edge: bool| {
+            poles.iter().map(move |&(omega, q)| Section {
+                highpass,
+                upper_edge,
+                omega,
+                q,
+            })
+        };
+
+        let (sections, gain) = match type_ {
+            FilterType::Lowpass => (prototype(false, false).collect(), gain),
+            FilterType::Highpass => (prototype(true, false).collect(), gain),
+            FilterType::Bandpass => (
+                prototype(true, false)
+                    .chain(prototype(false, true))
+                    .collect(),
+                gain * gain,
+            ),
+        };
+
+        Self {
+            type_,
+            sections,
+            gain,
+        }
+    }
+
+    /// Digital coefficients of the sections for the given parameters
+    pub(crate) fn coefficients(
+        &self,
+        sample_rate: f64,
+        frequency: f64,
+        q: f64,
+    ) -> ArrayVec<Coefficients, MAX_SECTIONS> {
+        let nyquist = sample_rate / 2.;
+        // keep the prewarped frequency finite
+        let prewarp = |f: f64| (PI * f.clamp(0., 0.999 * nyquist) / sample_rate).tan();
+        let q = q.max(MIN_Q);
+
+        match self.type_ {
+            FilterType::Lowpass | FilterType::Highpass => {
+                let k = prewarp(frequency);
+                self.sections.iter().map(|s| s.coefficients(k, q)).collect()
+            }
+            FilterType::Bandpass => {
+                // edges of the band, geometrically centered around the center frequency
+                let ratio = (1. / q + (1. / (q * q) + 4.).sqrt()) / 2.;
+                let lower = prewarp(frequency / ratio);
+                let upper = prewarp(frequency * ratio);
+                self.sections
+                    .iter()
+                    .map(|s| s.coefficients(if s.upper_edge { upper } else { lower }, 1.))
+                    .collect()
+            }
+        }
+    }
+}
+
+/// Poles of the normalized Butterworth prototype, as `(omega, q)` pairs
+fn butterworth_poles(order: usize) -> Vec<(f64, Option<f64>)> {
+    let mut poles: Vec<_> = (1..=order / 2)
+        .map(|k| {
+            let angle = PI * (2 * k - 1) as f64 / (2 * order) as f64;
+            (1., Some(1. / (2. * angle.sin())))
+        })
+        .collect();
+
+    if order % 2 == 1 {
+        poles.push((1., None));
+    }
+
+    poles
+}
+
+/// Poles of the normalized Chebyshev type I prototype, as `(omega, q)` pairs
+fn chebyshev_poles(order: usize, epsilon: f64) -> Vec<(f64, Option<f64>)> {
+    let mu = (1. / epsilon).asinh() / order as f64;
+
+    let mut poles: Vec<_> = (1..=order / 2)
+        .map(|k| {
+            let angle = PI * (2 * k - 1) as f64 / (2 * order) as f64;
+            let re = mu.sinh() * angle.sin();
+            let im = mu.cosh() * angle.cos();
+            let omega = re.hypot(im);
+            (omega, Some(omega / (2. * re)))
+        })
+        .collect();
+
+    if order % 2 == 1 {
+        poles.push((mu.sinh(), None));
+    }
+
+    poles
+}
+
+/// Poles of the Bessel prototype normalized to -3dB at the cutoff, as `(omega, q)` pairs
+fn bessel_poles(order: usize) -> Vec<(f64, Option<f64>)> {
+    // coefficients of the reverse Bessel polynomial, from the constant term
+    let factorial = |n: usize| (1..=n).map(|i| i as f64).product::<f64>();
+    let coefs: Vec<f64> = (0..=order)
+        .map(|k| {
+            factorial(2 * order - k)
+                / (2_f64.powi((order - k) as i32) * factorial(k) * factorial(order - k))
+        })
+        .collect();
+
+    // roots of the monic polynomial, with the Durand-Kerner method
+    let eval = |s: Complex<f64>| {
+        coefs
+            .iter()
+            .rev()
+            .fold(Complex::new(0., 0.), |acc, c| acc * s + c)
+    };
+    let radius = coefs[0].powf(1. / order as f64);
+    let mut roots: Vec<Complex<f64>> = (0..order)
+        .map(|i| Complex::new(0.4, 0.9).powi(i as i32) * radius)
+        .collect();
+
+    for _ in 0..500 {
+        for i in 0..order {
+            let denominator = (0..order)
+                .filter(|&j| j != i)
+                .fold(Complex::new(1., 0.), |acc, j| acc * (roots[i] - roots[j]));
+            let correction = eval(roots[i]) / denominator;
+            roots[i] -= correction;
+        }
+    }
+
+    // normalize to -3dB at the cutoff, the magnitude response is monotonic
+    let magnitude = |omega: f64| coefs[0] / eval(Complex::new(0., omega)).norm();
+    let (mut low, mut high) = (0., 10.);
+    for _ in 0..100 {
+        let mid = (low + high) / 2.;
+        if magnitude(mid) > 0.5_f64.sqrt() {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    let cutoff = (low + high) / 2.;
+
+    let mut poles: Vec<_> = roots
+        .iter()
+        .filter(|p| p.im > 1e-9)
+        .map(|p| {
+            let omega = p.norm() / cutoff;
+            (omega, Some(p.norm() / (2. * p.re.abs())))
+        })
+        .collect();
+
+    if order % 2 == 1 {
+        let real = roots.iter().find(|p| p.im.abs() <= 1e-9).unwrap();
+        poles.push((real.norm() / cutoff, None));
+    }
+
+    poles
+}
+
+/// `FilterNode` implements higher order low-pass, high-pass and band-pass filters as
+/// cascades of second order sections.
+///
+/// Butterworth, Linkwitz-Riley, Chebyshev and Bessel designs are available, with slopes
+/// from 12 to 48 dB per octave. Pairs of Linkwitz-Riley low-pass and high-pass filters
+/// sharing the same cutoff frequency form speaker crossovers.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_filter`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{
+///     AudioNode, AudioScheduledSourceNode, FilterDesign, FilterNode, FilterOptions,
+///     FilterSlope, FilterType,
+/// };
+///
+/// let context = AudioContext::default();
+///
+/// // 4th order Linkwitz-Riley crossover at 2kHz
+/// let options = FilterOptions {
+///     frequency: 2000.,
+///     design: FilterDesign::LinkwitzRiley,
+///     slope: FilterSlope::Db24,
+///     ..FilterOptions::default()
+/// };
+///
+/// let woofer = FilterNode::new(&context, options.clone());
+/// woofer.connect(&context.destination());
+///
+/// let tweeter = FilterNode::new(&context, FilterOptions {
+///     type_: FilterType::Highpass,
+///     ..options
+/// });
+/// tweeter.connect(&context.destination());
+///
+/// let mut src = context.create_oscillator();
+/// src.connect(&woofer);
+/// src.connect(&tweeter);
+/// src.start();
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example filter`
+///
+#[derive(Debug)]
+pub struct FilterNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    /// cutoff frequency, or center frequency of a band-pass filter
+    frequency: AudioParam,
+    /// multiplier of the quality factor of the sections, or quality factor of
+    /// the band of a band-pass filter
+    q: AudioParam,
+    /// Current filter type
+    type_: FilterType,
+    /// Current filter design
+    design: FilterDesign,
+    /// Current filter slope
+    slope: FilterSlope,
+    /// Amplitude of the pass band ripples of the Chebyshev design (dB)
+    ripple: f32,
+}
+
+impl AudioNode for FilterNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl FilterNode {
+    /// returns a `FilterNode` instance
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - filter options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the `ripple` is not strictly positive
+    pub fn new<C: BaseAudioContext>(context: &C, options: FilterOptions) -> Self {
+        let FilterOptions {
+            frequency,
+            q,
+            type_,
+            design,
+            slope,
+            ripple,
+            audio_node_options: channel_config,
+        } = options;
+
+        assert_valid_ripple(ripple);
+
+        context.base().register(move |registration| {
+            let sample_rate = context.sample_rate();
+
+            let freq_options = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.,
+                max_value: sample_rate / 2.,
+                default_value: 350.,
+                automation_rate: AutomationRate::A,
+            };
+            let (f_param, f_proc) = context.create_audio_param(freq_options, &registration);
+            f_param.set_value(frequency);
+
+            let q_options = AudioParamDescriptor {
+                name: String::new(),
+                min_value: MIN_Q as f32,
+                max_value: f32::MAX,
+                default_value: 1.,
+                automation_rate: AutomationRate::A,
+            };
+            let (q_param, q_proc) = context.create_audio_param(q_options, &registration);
+            q_param.set_value(q);
+
+            let renderer = FilterRenderer {
+                frequency: f_proc,
+                q: q_proc,
+                cascade: Cascade::new(type_, design, slope, ripple),
+                xy: ArrayVec::new(),
+                coefs_list: vec![ArrayVec::new(); RENDER_QUANTUM_SIZE],
+            };
+
+            let node = Self {
+                registration,
+                channel_config: channel_config.into(),
+                frequency: f_param,
+                q: q_param,
+                type_,
+                design,
+                slope,
+                ripple,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Returns the frequency audio parameter
+    #[must_use]
+    pub fn frequency(&self) -> &AudioParam {
+        &self.frequency
+    }
+
+    /// Returns the Q audio parameter
+    ///
+    /// For low-pass and high-pass filters, the quality factor of every section is multiplied
+    /// by this value, the nominal design being obtained with a value of 1. For band-pass
+    /// filters, this is the ratio between the center frequency and the bandwidth of the band.
+    #[must_use]
+    pub fn q(&self) -> &AudioParam {
+        &self.q
+    }
+
+    /// Returns the filter type
+    #[must_use]
+    pub fn type_(&self) -> FilterType {
+        self.type_
+    }
+
+    /// filter type setter
+    ///
+    /// # Arguments
+    ///
+    /// * `type_` - the filter type (lowpass, highpass, bandpass)
+    pub fn set_type(&mut self, type_: FilterType) {
+        self.type_ = type_;
+        self.update_cascade();
+    }
+
+    /// Returns the filter design
+    #[must_use]
+    pub fn design(&self) -> FilterDesign {
+        self.design
+    }
+
+    /// filter design setter
+    ///
+    /// # Arguments
+    ///
+    /// * `design` - the filter design (Butterworth, Linkwitz-Riley,...)
+    pub fn set_design(&mut self, design: FilterDesign) {
+        self.design = design;
+        self.update_cascade();
+    }
+
+    /// Returns the filter slope
+    #[must_use]
+    pub fn slope(&self) -> FilterSlope {
+        self.slope
+    }
+
+    /// filter slope setter
+    ///
+    /// # Arguments
+    ///
+    /// * `slope` - the filter slope, from 12 to 48 dB per octave
+    pub fn set_slope(&mut self, slope: FilterSlope) {
+        self.slope = slope;
+        self.update_cascade();
+    }
+
+    /// Returns the amplitude of the pass band ripples of the Chebyshev design (dB)
+    #[must_use]
+    pub fn ripple(&self) -> f32 {
+        self.ripple
+    }
+
+    /// Set the amplitude of the pass band ripples of the Chebyshev design (dB)
+    ///
+    /// # Panics
+    ///
+    /// Will panic if the `ripple` is not strictly positive
+    pub fn set_ripple(&mut self, ripple: f32) {
+        assert_valid_ripple(ripple);
+        self.ripple = ripple;
+        self.update_cascade();
+    }
+
+    fn cascade(&self) -> Cascade {
+        Cascade::new(self.type_, self.design, self.slope, self.ripple)
+    }
+
+    fn update_cascade(&self) {
+        self.registration.post_message(self.cascade());
+    }
+
+    /// Returns the frequency response for the specified frequencies
+    ///
+    /// # Arguments
+    ///
+    /// * `frequency_hz` - frequencies for which frequency response of the filter should be calculated
+    /// * `mag_response` - magnitude of the frequency response of the filter
+    /// * `phase_response` - phase of the frequency response of the filter
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if arguments' lengths don't match
+    ///
+    pub fn get_frequency_response(
+        &self,
+        frequency_hz: &[f32],
+        mag_response: &mut [f32],
+        phase_response: &mut [f32],
+    ) {
+        assert!(
+            frequency_hz.len() == mag_response.len() && mag_response.len() == phase_response.len(),
+            "InvalidAccessError - Parameter lengths must match",
+        );
+
+        let sample_rate = self.context().sample_rate();
+        let n_quist = sample_rate / 2.;
+
+        let cascade = self.cascade();
+        let coefs = cascade.coefficients(
+            f64::from(sample_rate),
+            f64::from(self.frequency().value()),
+            f64::from(self.q().value()),
+        );
+
+        for (i, &freq) in frequency_hz.iter().enumerate() {
+            // same behavior as `BiquadFilterNode::get_frequency_response`
+            if freq < 0. || freq > n_quist {
+                mag_response[i] = f32::NAN;
+                phase_response[i] = f32::NAN;
+            } else {
+                let f = f64::from(freq / n_quist);
+                let response = coefs
+                    .iter()
+                    .fold(Complex::new(cascade.gain, 0.), |acc, c| acc * c.response(f));
+
+                let (mag, phase) = response.to_polar();
+                mag_response[i] = mag as f32;
+                phase_response[i] = phase as f32;
+            }
+        }
+    }
+}
+
+/// `FilterRenderer` represents the rendering part of `FilterNode`
+struct FilterRenderer {
+    /// cutoff frequency, or center frequency of a band-pass filter
+    frequency: AudioParamId,
+    /// multiplier of the quality factor of the sections, or quality factor of
+    /// the band of a band-pass filter
+    q: AudioParamId,
+    /// sections of the filter
+    cascade: Cascade,
+    // keep the state of each section for each channel
+    xy: ArrayVec<[[f64; 4]; MAX_SECTIONS], MAX_CHANNELS>,
+    // coefficients of the sections for each frame of the render quantum
+    coefs_list: Vec<ArrayVec<Coefficients, MAX_SECTIONS>>,
+}
+
+impl AudioProcessor for FilterRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // handle tail time
+        if input.is_silent()
+            && !self
+                .xy
+                .iter()
+                .flatten()
+                .any(|v| v.iter().copied().any(f64::is_normal))
+        {
+            // input is silent and filter history is clean
+            output.make_silent();
+            return false;
+        }
+
+        // eventually resize state according to input number of channels
+        // if in tail time, we should continue with previous number of channels
+        if !input.is_silent() {
+            let num_channels = input.number_of_channels();
+
+            if num_channels != self.xy.len() {
+                self.xy.truncate(num_channels);
+                for _ in self.xy.len()..num_channels {
+                    self.xy.push([[0.; 4]; MAX_SECTIONS]);
+                }
+            }
+        }
+
+        output.set_number_of_channels(self.xy.len());
+
+        let frequency = params.get(&self.frequency);
+        let q = params.get(&self.q);
+        let sample_rate = f64::from(scope.sample_rate);
+        let gain = self.cascade.gain;
+
+        // compute first coefs and fill the coefs list with these values
+        let coefs =
+            self.cascade
+                .coefficients(sample_rate, f64::from(frequency[0]), f64::from(q[0]));
+        self.coefs_list.fill(coefs);
+
+        // if one of the params has a length of RENDER_QUANTUM_SIZE, we need
+        // to compute the coefs for each frame
+        if frequency.len() != 1 || q.len() != 1 {
+            self.coefs_list
+                .iter_mut()
+                .zip(frequency.iter().cycle())
+                .zip(q.iter().cycle())
+                .skip(1)
+                .for_each(|((coefs, &f), &q)| {
+                    *coefs = self
+                        .cascade
+                        .coefficients(sample_rate, f64::from(f), f64::from(q));
+                });
+        }
+
+        for (channel_number, output_channel) in output.channels_mut().iter_mut().enumerate() {
+            let input_channel = if input.is_silent() {
+                input.channel_data(0)
+            } else {
+                input.channel_data(channel_number)
+            };
+            let state = &mut self.xy[channel_number];
+
+            output_channel
+                .iter_mut()
+                .zip(input_channel.iter())
+                .zip(self.coefs_list.iter())
+                .for_each(|((o, &i), coefs)| {
+                    let mut x = f64::from(i);
+
+                    for (xy, c) in state.iter_mut().zip(coefs.iter()) {
+                        let [x1, x2, y1, y2] = *xy;
+                        let mut y = c.b0 * x + c.b1 * x1 + c.b2 * x2 - c.a1 * y1 - c.a2 * y2;
+
+                        // flush NaN, Infinity, subnormal (and zero) to zero
+                        if !y.is_normal() {
+                            y = 0.;
+                        }
+
+                        *xy = [x, x1, y, y1];
+                        x = y;
+                    }
+
+                    *o = (x * gain) as f32;
+                });
+        }
+
+        true
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(cascade) = msg.downcast_mut::<Cascade>() {
+            std::mem::swap(&mut self.cascade, cascade);
+            return;
+        }
+
+        log::warn!("FilterRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    fn response(options: FilterOptions, freqs: &[f32]) -> (Vec<f32>, Vec<f32>) {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let filter = FilterNode::new(&context, options);
+
+        let mut mags = vec![0.; freqs.len()];
+        let mut phases = vec![0.; freqs.len()];
+        filter.get_frequency_response(freqs, &mut mags, &mut phases);
+
+        (mags, phases)
+    }
+
+    fn db(mag: f32) -> f32 {
+        20. * mag.log10()
+    }
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let filter = context.create_filter();
+
+        assert_float_eq!(filter.frequency().value(), 350., abs <= 0.);
+        assert_float_eq!(filter.q().value(), 1., abs <= 0.);
+        assert_eq!(filter.type_(), FilterType::Lowpass);
+        assert_eq!(filter.design(), FilterDesign::Butterworth);
+        assert_eq!(filter.slope(), FilterSlope::Db24);
+        assert_float_eq!(filter.ripple(), 1., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_ripple() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut filter = context.create_filter();
+        filter.set_ripple(0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_frequency_response_arguments() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let filter = context.create_filter();
+
+        let mut mags = [0.; 2];
+        let mut phases = [0.];
+        filter.get_frequency_response(&[0.], &mut mags, &mut phases);
+    }
+
+    #[test]
+    fn test_cutoff_attenuation() {
+        for (design, expected) in [
+            (FilterDesign::Butterworth, -3.01),
+            (FilterDesign::LinkwitzRiley, -6.02),
+            (FilterDesign::Bessel, -3.01),
+        ] {
+            for slope in [
+                FilterSlope::Db12,
+                FilterSlope::Db24,
+                FilterSlope::Db36,
+                FilterSlope::Db48,
+            ] {
+                for type_ in [FilterType::Lowpass, FilterType::Highpass] {
+                    let options = FilterOptions {
+                        frequency: 1000.,
+                        type_,
+                        design,
+                        slope,
+                        ..FilterOptions::default()
+                    };
+                    let (mags, _) = response(options, &[1000.]);
+                    assert_float_eq!(db(mags[0]), expected, abs <= 0.01);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_slopes() {
+        let mut previous = 0.;
+
+        for (slope, order) in [
+            (FilterSlope::Db12, 2.),
+            (FilterSlope::Db24, 4.),
+            (FilterSlope::Db36, 6.),
+            (FilterSlope::Db48, 8.),
+        ] {
+            let options = FilterOptions {
+                frequency: 100.,
+                slope,
+                ..FilterOptions::default()
+            };
+            // one octave apart, far above the cutoff
+            let (mags, _) = response(options, &[800., 1600.]);
+            let slope = db(mags[1]) - db(mags[0]);
+
+            assert_float_eq!(slope, -6.02 * order, abs <= 0.5);
+            assert!(slope < previous);
+            previous = slope;
+        }
+    }
+
+    #[test]
+    fn test_chebyshev_ripple() {
+        for slope in [FilterSlope::Db24, FilterSlope::Db36] {
+            let options = FilterOptions {
+                frequency: 1000.,
+                design: FilterDesign::Chebyshev,
+                slope,
+                ripple: 2.,
+                ..FilterOptions::default()
+            };
+
+            let freqs: Vec<f32> = (1..=100).map(|i| i as f32 * 10.).collect();
+            let (mags, _) = response(options, &freqs);
+
+            let max = mags.iter().copied().fold(f32::MIN, f32::max);
+            let min = mags.iter().copied().fold(f32::MAX, f32::min);
+            assert_float_eq!(db(max), 0., abs <= 0.01);
+            assert_float_eq!(db(min), -2., abs <= 0.01);
+            // the pass band ends at the cutoff frequency
+            assert_float_eq!(db(mags[99]), -2., abs <= 0.01);
+        }
+    }
+
+    #[test]
+    fn test_bandpass() {
+        let options = FilterOptions {
+            frequency: 1000.,
+            q: 0.5,
+            type_: FilterType::Bandpass,
+            design: FilterDesign::Butterworth,
+            ..FilterOptions::default()
+        };
+
+        // edges of the band, for a bandwidth of 2kHz
+        let ratio = (2. + 8_f32.sqrt()) / 2.;
+        let (mags, _) = response(options, &[1000. / ratio, 1000., 1000. * ratio]);
+
+        assert_float_eq!(mags[1], 1., abs <= 0.01);
+        assert_float_eq!(1000. * ratio - 1000. / ratio, 2000., abs <= 0.01);
+        assert_float_eq!(db(mags[0]), -3.01, abs <= 0.1);
+        assert_float_eq!(db(mags[2]), -3.01, abs <= 0.1);
+    }
+
+    #[test]
+    fn test_linkwitz_riley_crossover() {
+        let freqs: Vec<f32> = (1..100).map(|i| i as f32 * 200.).collect();
+
+        for slope in [FilterSlope::Db24, FilterSlope::Db48] {
+            let options = FilterOptions {
+                frequency: 2000.,
+                design: FilterDesign::LinkwitzRiley,
+                slope,
+                ..FilterOptions::default()
+            };
+            let (low_mags, low_phases) = response(options.clone(), &freqs);
+            let highpass = FilterOptions {
+                type_: FilterType::Highpass,
+                ..options
+            };
+            let (high_mags, high_phases) = response(highpass, &freqs);
+
+            // the sum of both outputs has a flat magnitude response
+            for i in 0..freqs.len() {
+                let low = Complex::from_polar(low_mags[i], low_phases[i]);
+                let high = Complex::from_polar(high_mags[i], high_phases[i]);
+                assert_float_eq!((low + high).norm(), 1., abs <= 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_matches_frequency_response() {
+        let sample_rate = 44_100.;
+        let length = 32 * RENDER_QUANTUM_SIZE;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let options = FilterOptions {
+            frequency: 1000.,
+            slope: FilterSlope::Db48,
+            ..FilterOptions::default()
+        };
+        let filter = FilterNode::new(&context, options);
+        filter.connect(&context.destination());
+
+        let mut expected = [0.];
+        filter.get_frequency_response(&[1500.], &mut expected, &mut [0.]);
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(1500.);
+        osc.connect(&filter);
+        osc.start();
+
+        let result = context.start_rendering_sync();
+        let channel = result.get_channel_data(0);
+
+        // peak amplitude in the steady state
+        let peak = channel[length / 2..]
+            .iter()
+            .fold(0_f32, |max, s| max.max(s.abs()));
+        assert_float_eq!(peak, expected[0], abs <= 0.01);
+    }
+
+    #[test]
+    fn test_sample_accurate_frequency() {
+        let sample_rate = 44_100.;
+        let change = 200;
+
+        let render = |automate: bool| {
+            let mut context = OfflineAudioContext::new(1, 3 * RENDER_QUANTUM_SIZE, sample_rate);
+
+            let filter = context.create_filter();
+            filter.connect(&context.destination());
+            filter.frequency().set_value(5000.);
+            if automate {
+                let when = change as f64 / sample_rate as f64;
+                filter.frequency().set_value_at_time(500., when);
+            }
+
+            let mut osc = context.create_oscillator();
+            osc.frequency().set_value(2000.);
+            osc.connect(&filter);
+            osc.start();
+
+            context.start_rendering_sync().get_channel_data(0).to_vec()
+        };
+
+        let reference = render(false);
+        let automated = render(true);
+
+        // the change occurs in the middle of the second render quantum
+        assert_float_eq!(reference[..change], automated[..change], abs_all <= 0.);
+        assert!(reference[change..]
+            .iter()
+            .zip(&automated[change..])
+            .all(|(r, a)| r != a));
+    }
+}
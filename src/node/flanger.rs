@@ -0,0 +1,316 @@
+//! The flanger control and renderer parts
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::param::AudioParam;
+
+use super::modulation::{
+    assert_valid_channel_count, assert_valid_channel_count_mode, ModulatedDelayRenderer,
+    ModulationParams, ModulationValues,
+};
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Shortest delay time (in seconds) of the flanger
+const MIN_DELAY: f32 = 0.000_5;
+
+/// Range of the delay time modulation (in seconds) for a `depth` of 1
+const SWEEP: f32 = 0.005;
+
+const DEFAULTS: ModulationValues = ModulationValues {
+    rate: 0.25,
+    depth: 1.,
+    feedback: 0.5,
+    mix: 0.5,
+    spread: 0.,
+};
+
+/// Options for constructing a [`FlangerNode`]
+#[derive(Clone, Debug)]
+pub struct FlangerOptions {
+    /// Frequency (in Hz) of the delay time modulation
+    pub rate: f32,
+    /// Amount of delay time modulation, from 0 to 1
+    pub depth: f32,
+    /// Amount of wet signal fed back into the delay line, from -0.95 to 0.95
+    pub feedback: f32,
+    /// Amount of wet signal in the output, from 0 (dry) to 1 (wet)
+    pub mix: f32,
+    /// Phase offset between the left and right modulations, from 0 (in phase) to 1
+    /// (opposite phase)
+    pub spread: f32,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for FlangerOptions {
+    fn default() -> Self {
+        Self {
+            rate: DEFAULTS.rate,
+            depth: DEFAULTS.depth,
+            feedback: DEFAULTS.feedback,
+            mix: DEFAULTS.mix,
+            spread: DEFAULTS.spread,
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::ClampedMax,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// `FlangerNode` mixes its input with a copy delayed by a short, slowly modulated, delay
+/// time, producing sweeping comb filter notches
+///
+/// The delay time is modulated by a sine LFO between 0.5ms and 5.5ms, and the feedback is
+/// applied sample by sample, so it is not limited by the minimum delay of one render
+/// quantum of cycles in the audio graph. Negative feedback values produce the hollow
+/// sound of a flanger with inverted feedback. The input is mixed down to (or up to) stereo
+/// and the output is always stereo.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_flanger`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let flanger = context.create_flanger();
+/// flanger.feedback().set_value(0.8);
+/// flanger.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&flanger);
+/// osc.start();
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example modulation`
+///
+#[derive(Debug)]
+pub struct FlangerNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    params: ModulationParams,
+}
+
+impl AudioNode for FlangerNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_channel_count_mode(mode, "FlangerNode");
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_channel_count(count, "FlangerNode");
+        self.channel_config.set_count(count, self.registration());
+    }
+}
+
+impl FlangerNode {
+    /// Creates a `FlangerNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - flanger options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.audio_node_options.channel_count` is greater than 2
+    /// * `options.audio_node_options.channel_count_mode` is `ChannelCountMode::Max`
+    ///
+    pub fn new<C: BaseAudioContext>(context: &C, options: FlangerOptions) -> Self {
+        context.base().register(move |registration| {
+            let FlangerOptions {
+                rate,
+                depth,
+                feedback,
+                mix,
+                spread,
+                audio_node_options,
+            } = options;
+
+            assert_valid_channel_count_mode(audio_node_options.channel_count_mode, "FlangerNode");
+            assert_valid_channel_count(audio_node_options.channel_count, "FlangerNode");
+
+            let values = ModulationValues {
+                rate,
+                depth,
+                feedback,
+                mix,
+                spread,
+            };
+            let (params, ids) = ModulationParams::new(context, &registration, &DEFAULTS, &values);
+
+            let renderer =
+                ModulatedDelayRenderer::new(context.sample_rate(), ids, MIN_DELAY, SWEEP, 1);
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                params,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// K-rate [`AudioParam`] representing the frequency (in Hz) of the delay time
+    /// modulation, in the range `[0, 20]`.
+    #[must_use]
+    pub fn rate(&self) -> &AudioParam {
+        &self.params.rate
+    }
+
+    /// K-rate [`AudioParam`] representing the amount of delay time modulation, from 0
+    /// (static delay) to 1 (delay time sweeping over 5ms).
+    #[must_use]
+    pub fn depth(&self) -> &AudioParam {
+        &self.params.depth
+    }
+
+    /// K-rate [`AudioParam`] representing the amount of wet signal fed back into the
+    /// delay line, in the range `[-0.95, 0.95]`.
+    #[must_use]
+    pub fn feedback(&self) -> &AudioParam {
+        &self.params.feedback
+    }
+
+    /// A-rate [`AudioParam`] representing the balance between the dry and the
+    /// wet signals, from 0 (dry only) to 1 (wet only).
+    #[must_use]
+    pub fn mix(&self) -> &AudioParam {
+        &self.params.mix
+    }
+
+    /// K-rate [`AudioParam`] representing the phase offset between the left and right
+    /// channels modulations, from 0 (in phase) to 1 (opposite phase).
+    #[must_use]
+    pub fn spread(&self) -> &AudioParam {
+        &self.params.spread
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let flanger = context.create_flanger();
+
+        assert_float_eq!(flanger.rate().value(), 0.25, abs <= 0.);
+        assert_float_eq!(flanger.depth().value(), 1., abs <= 0.);
+        assert_float_eq!(flanger.feedback().value(), 0.5, abs <= 0.);
+        assert_float_eq!(flanger.mix().value(), 0.5, abs <= 0.);
+        assert_float_eq!(flanger.spread().value(), 0., abs <= 0.);
+        assert_eq!(flanger.channel_count(), 2);
+        assert_eq!(flanger.channel_count_mode(), ChannelCountMode::ClampedMax);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_count_mode() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let flanger = context.create_flanger();
+        flanger.set_channel_count_mode(ChannelCountMode::Max);
+    }
+
+    #[test]
+    fn test_feedback_below_render_quantum() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 256, sample_rate);
+
+        let feedback = 0.5;
+        let flanger = FlangerNode::new(
+            &context,
+            FlangerOptions {
+                depth: 0.,
+                feedback,
+                mix: 1.,
+                ..FlangerOptions::default()
+            },
+        );
+        flanger.connect(&context.destination());
+
+        let mut dirac = context.create_buffer(1, 1, sample_rate);
+        dirac.copy_to_channel(&[1.], 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&flanger);
+        src.set_buffer(dirac);
+        src.start();
+
+        let res = context.start_rendering_sync();
+        let channel = res.get_channel_data(0);
+
+        // echoes every 24 samples, well below the 128 samples of a render quantum
+        let delay = (MIN_DELAY * sample_rate) as usize;
+        let mut expected = vec![0.; 256];
+        let mut gain = 1.;
+        for index in (delay..256).step_by(delay) {
+            expected[index] = gain;
+            gain *= feedback;
+        }
+        assert_float_eq!(channel[..], expected[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_tail_keeps_node_alive() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(2, 1024, sample_rate);
+
+        // drop the control thread handles so the render lifecycle rules kick in
+        {
+            let flanger = FlangerNode::new(
+                &context,
+                FlangerOptions {
+                    depth: 0.,
+                    feedback: 0.9,
+                    mix: 1.,
+                    ..FlangerOptions::default()
+                },
+            );
+            flanger.connect(&context.destination());
+
+            let mut src = context.create_constant_source();
+            src.connect(&flanger);
+            src.start();
+            src.stop_at(128. / sample_rate as f64);
+        }
+
+        let res = context.start_rendering_sync();
+        assert!(res.get_channel_data(0)[512..].iter().any(|v| *v != 0.));
+    }
+}
@@ -0,0 +1,309 @@
+//! The loudness meter control and renderer parts
+use std::any::Any;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use arrayvec::ArrayVec;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::loudness::LoudnessMeter;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{AtomicF64, MAX_CHANNELS};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Options for constructing a [`LoudnessMeterNode`]
+#[derive(Clone, Debug, Default)]
+pub struct LoudnessMeterOptions {
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+/// Loudness values shared with the render thread
+#[derive(Debug)]
+struct LoudnessValues {
+    momentary: AtomicF64,
+    short_term: AtomicF64,
+    integrated: AtomicF64,
+    loudness_range: AtomicF64,
+    true_peak: AtomicF64,
+}
+
+impl LoudnessValues {
+    fn new() -> Self {
+        Self {
+            momentary: AtomicF64::new(f64::NEG_INFINITY),
+            short_term: AtomicF64::new(f64::NEG_INFINITY),
+            integrated: AtomicF64::new(f64::NEG_INFINITY),
+            loudness_range: AtomicF64::new(0.),
+            true_peak: AtomicF64::new(f64::NEG_INFINITY),
+        }
+    }
+
+    fn store(&self, meter: &LoudnessMeter) {
+        self.momentary.store(meter.momentary(), Ordering::Relaxed);
+        self.short_term.store(meter.short_term(), Ordering::Relaxed);
+        self.integrated.store(meter.integrated(), Ordering::Relaxed);
+        self.loudness_range
+            .store(meter.loudness_range(), Ordering::Relaxed);
+        self.true_peak.store(meter.true_peak(), Ordering::Relaxed);
+    }
+}
+
+/// Message to restart the measurement
+#[derive(Debug)]
+struct ResetLoudness;
+
+/// `LoudnessMeterNode` measures the loudness of its input as specified by ITU-R BS.1770
+/// and EBU R128
+///
+/// The node passes its input unchanged to its output. It measures the momentary (400ms),
+/// short-term (3s) and integrated loudness, the loudness range and the true peak level
+/// of its input. The values are updated every 100ms in the render thread and can be read
+/// cheaply from the control thread.
+///
+/// The channels are weighted according to the 5.1 layout when the input has 6 channels,
+/// with equal weights otherwise. Loudness values are negative infinity until enough
+/// signal has been measured.
+///
+/// To measure the loudness of an [`AudioBuffer`](crate::AudioBuffer), e.g. the output of
+/// an [`OfflineAudioContext`](crate::context::OfflineAudioContext), see
+/// [`AudioBuffer::loudness`](crate::AudioBuffer::loudness).
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_loudness_meter`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let file = File::open("samples/sample.wav").unwrap();
+/// let buffer = context.decode_audio_data_sync(file).unwrap();
+///
+/// let meter = context.create_loudness_meter();
+/// meter.connect(&context.destination());
+///
+/// let mut src = context.create_buffer_source();
+/// src.set_buffer(buffer);
+/// src.connect(&meter);
+/// src.start();
+///
+/// loop {
+///     println!(
+///         "M: {:.1} LUFS - S: {:.1} LUFS - I: {:.1} LUFS",
+///         meter.momentary(),
+///         meter.short_term(),
+///         meter.integrated(),
+///     );
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+/// }
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example loudness`
+///
+#[derive(Debug)]
+pub struct LoudnessMeterNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    values: Arc<LoudnessValues>,
+}
+
+impl AudioNode for LoudnessMeterNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl LoudnessMeterNode {
+    /// Creates a `LoudnessMeterNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - loudness meter options
+    pub fn new<C: BaseAudioContext>(context: &C, options: LoudnessMeterOptions) -> Self {
+        context.base().register(move |registration| {
+            let values = Arc::new(LoudnessValues::new());
+
+            let renderer = LoudnessMeterRenderer {
+                meter: LoudnessMeter::new(context.sample_rate()),
+                values: Arc::clone(&values),
+                number_of_channels: 1,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                values,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Loudness of the last 400ms, in LUFS
+    #[must_use]
+    pub fn momentary(&self) -> f64 {
+        self.values.momentary.load(Ordering::Relaxed)
+    }
+
+    /// Loudness of the last 3s, in LUFS
+    #[must_use]
+    pub fn short_term(&self) -> f64 {
+        self.values.short_term.load(Ordering::Relaxed)
+    }
+
+    /// Gated loudness since the start (or the last reset) of the measurement, in LUFS
+    #[must_use]
+    pub fn integrated(&self) -> f64 {
+        self.values.integrated.load(Ordering::Relaxed)
+    }
+
+    /// Loudness range since the start (or the last reset) of the measurement, in LU
+    #[must_use]
+    pub fn loudness_range(&self) -> f64 {
+        self.values.loudness_range.load(Ordering::Relaxed)
+    }
+
+    /// Highest true peak level since the start (or the last reset) of the measurement,
+    /// in dBTP
+    #[must_use]
+    pub fn true_peak(&self) -> f64 {
+        self.values.true_peak.load(Ordering::Relaxed)
+    }
+
+    /// Restart the measurement of the integrated loudness, loudness range and true peak
+    pub fn reset(&self) {
+        self.registration.post_message(ResetLoudness);
+    }
+}
+
+/// `LoudnessMeterRenderer` represents the rendering part of `LoudnessMeterNode`
+struct LoudnessMeterRenderer {
+    meter: LoudnessMeter,
+    values: Arc<LoudnessValues>,
+    /// number of channels measured, kept while the input is silent
+    number_of_channels: usize,
+}
+
+impl AudioProcessor for LoudnessMeterRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // pass through input
+        *output = input.clone();
+
+        // silence is measured as well, with the previous number of channels
+        if !input.is_silent() {
+            self.number_of_channels = input.number_of_channels();
+        }
+
+        let channels: ArrayVec<&[f32], MAX_CHANNELS> = (0..self.number_of_channels)
+            .map(|channel| {
+                if input.is_silent() {
+                    input.channel_data(0).as_ref()
+                } else {
+                    input.channel_data(channel).as_ref()
+                }
+            })
+            .collect();
+
+        if self.meter.process(&channels) {
+            self.values.store(&self.meter);
+        }
+
+        // no tail-time
+        false
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if msg.downcast_ref::<ResetLoudness>().is_some() {
+            self.meter.reset();
+            self.values.store(&self.meter);
+            return;
+        }
+
+        log::warn!("LoudnessMeterRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(2, 1, 48_000.);
+        let meter = context.create_loudness_meter();
+
+        assert_eq!(meter.momentary(), f64::NEG_INFINITY);
+        assert_eq!(meter.short_term(), f64::NEG_INFINITY);
+        assert_eq!(meter.integrated(), f64::NEG_INFINITY);
+        assert_float_eq!(meter.loudness_range(), 0., abs <= 0.);
+        assert_eq!(meter.true_peak(), f64::NEG_INFINITY);
+    }
+
+    #[test]
+    fn test_live_matches_offline() {
+        let sample_rate = 48_000.;
+        let length = 48_000 * 5;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        let meter = context.create_loudness_meter();
+        meter.connect(&context.destination());
+
+        let gain = context.create_gain();
+        gain.gain().set_value(0.1);
+        gain.connect(&meter);
+
+        let mut osc = context.create_oscillator();
+        osc.connect(&gain);
+        osc.start();
+
+        let buffer = context.start_rendering_sync();
+        let offline = buffer.loudness();
+
+        // the oscillator is mono, upmixed to stereo by the destination
+        assert_float_eq!(meter.integrated(), offline.integrated - 3.01, abs <= 0.1);
+        assert_float_eq!(meter.momentary(), meter.integrated(), abs <= 0.1);
+        assert_float_eq!(meter.short_term(), meter.integrated(), abs <= 0.1);
+        assert_float_eq!(meter.true_peak(), -20., abs <= 0.1);
+    }
+}
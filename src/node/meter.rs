@@ -0,0 +1,634 @@
+//! The meter control and renderer parts
+use std::any::Any;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{AtomicF32, MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig};
+
+/// Level (-120dB) below which the envelopes are considered silent
+const SILENCE_THRESHOLD: f32 = 1e-6;
+
+/// Signal output by a [`MeterNode`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum MeterOutput {
+    /// The input signal, unchanged
+    #[default]
+    Input,
+    /// The peak envelope of each channel
+    Peak,
+    /// The RMS envelope of each channel
+    Rms,
+}
+
+/// Options for constructing a [`MeterNode`]
+#[derive(Clone, Debug)]
+pub struct MeterOptions {
+    /// Time (in seconds) for the peak envelope to rise by 1 - 1/e of a level increase
+    pub attack: f32,
+    /// Time (in seconds) for the peak envelope to fall by 1 - 1/e of a level decrease
+    pub release: f32,
+    /// Integration time (in seconds) of the RMS envelope
+    pub rms_window: f64,
+    /// Time (in seconds) during which the highest peak is held
+    pub peak_hold_time: f64,
+    /// Signal output by the node
+    pub output: MeterOutput,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for MeterOptions {
+    fn default() -> Self {
+        Self {
+            attack: 0.,
+            release: 0.5,
+            rms_window: 0.3,
+            peak_hold_time: 2.,
+            output: MeterOutput::default(),
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// Assert that the given time is valid for a `MeterNode`
+///
+/// # Panics
+///
+/// This function panics if given time is negative or not finite
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_time(value: f64, name: &str) {
+    assert!(
+        value.is_finite() && value >= 0.,
+        "RangeError - Invalid {name}: {value:?}, should be positive"
+    );
+}
+
+/// Levels of each channel, shared with the render thread
+#[derive(Debug)]
+struct MeterLevels {
+    peak: [AtomicF32; MAX_CHANNELS],
+    rms: [AtomicF32; MAX_CHANNELS],
+    peak_hold: [AtomicF32; MAX_CHANNELS],
+}
+
+impl MeterLevels {
+    fn new() -> Self {
+        Self {
+            peak: std::array::from_fn(|_| AtomicF32::new(0.)),
+            rms: std::array::from_fn(|_| AtomicF32::new(0.)),
+            peak_hold: std::array::from_fn(|_| AtomicF32::new(0.)),
+        }
+    }
+}
+
+/// `MeterNode` measures the peak, RMS and peak-hold levels of each channel of its input
+///
+/// The levels are computed in the render thread and can be read cheaply from the control
+/// thread, e.g. to draw VU meters, without copying the signal as with an
+/// [`AnalyserNode`](super::AnalyserNode). The levels are linear amplitudes.
+///
+/// The peak envelope follows the input with the `attack` and `release` ballistics, the
+/// RMS envelope integrates the power of the input over `rms_window` seconds, and the
+/// peak-hold level keeps the highest peak for `peak_hold_time` seconds.
+///
+/// By default the node passes its input unchanged to its output. It can instead output
+/// the peak or RMS envelopes as audio, for example to drive an [`AudioParam`].
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_meter`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let meter = context.create_meter();
+/// meter.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&meter);
+/// osc.start();
+///
+/// loop {
+///     println!(
+///         "peak: {:.3} - rms: {:.3} - hold: {:.3}",
+///         meter.peak(0),
+///         meter.rms(0),
+///         meter.peak_hold(0),
+///     );
+///     std::thread::sleep(std::time::Duration::from_millis(100));
+/// }
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example meter`
+///
+#[derive(Debug)]
+pub struct MeterNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    attack: AudioParam,
+    release: AudioParam,
+    rms_window: f64,
+    peak_hold_time: f64,
+    output: MeterOutput,
+    levels: Arc<MeterLevels>,
+}
+
+impl AudioNode for MeterNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl MeterNode {
+    /// Creates a `MeterNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - meter options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `options.rms_window` or `options.peak_hold_time` is negative
+    pub fn new<C: BaseAudioContext>(context: &C, options: MeterOptions) -> Self {
+        let MeterOptions {
+            attack,
+            release,
+            rms_window,
+            peak_hold_time,
+            output,
+            audio_node_options,
+        } = options;
+
+        assert_valid_time(rms_window, "rms_window");
+        assert_valid_time(peak_hold_time, "peak_hold_time");
+
+        context.base().register(move |registration| {
+            let attack_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.,
+                max_value: 1.,
+                default_value: 0.,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut attack_param, attack_proc) =
+                context.create_audio_param(attack_opts, &registration);
+            attack_param.set_automation_rate_constrained(true);
+            attack_param.set_value(attack);
+
+            let release_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.,
+                max_value: 10.,
+                default_value: 0.5,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut release_param, release_proc) =
+                context.create_audio_param(release_opts, &registration);
+            release_param.set_automation_rate_constrained(true);
+            release_param.set_value(release);
+
+            let levels = Arc::new(MeterLevels::new());
+            let sample_rate = f64::from(context.sample_rate());
+
+            let renderer = MeterRenderer {
+                attack: attack_proc,
+                release: release_proc,
+                output,
+                rms_coef: one_pole_coef(rms_window * sample_rate),
+                peak_hold_frames: (peak_hold_time * sample_rate) as usize,
+                levels: Arc::clone(&levels),
+                states: [ChannelState::default(); MAX_CHANNELS],
+                number_of_channels: 0,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                attack: attack_param,
+                release: release_param,
+                rms_window,
+                peak_hold_time,
+                output,
+                levels,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// K-rate [`AudioParam`] representing the time (in seconds) for the peak envelope to
+    /// rise by `1 - 1/e` of a level increase, in the range `[0, 1]`.
+    #[must_use]
+    pub fn attack(&self) -> &AudioParam {
+        &self.attack
+    }
+
+    /// K-rate [`AudioParam`] representing the time (in seconds) for the peak envelope to
+    /// fall by `1 - 1/e` of a level decrease, in the range `[0, 10]`.
+    #[must_use]
+    pub fn release(&self) -> &AudioParam {
+        &self.release
+    }
+
+    /// Integration time (in seconds) of the RMS envelope
+    #[must_use]
+    pub fn rms_window(&self) -> f64 {
+        self.rms_window
+    }
+
+    /// Time (in seconds) during which the highest peak is held
+    #[must_use]
+    pub fn peak_hold_time(&self) -> f64 {
+        self.peak_hold_time
+    }
+
+    /// Returns the signal output by this node
+    #[must_use]
+    pub fn output(&self) -> MeterOutput {
+        self.output
+    }
+
+    /// Set the signal output by this node
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - the desired `MeterOutput` variant
+    pub fn set_output(&mut self, output: MeterOutput) {
+        self.output = output;
+        self.registration.post_message(output);
+    }
+
+    /// Current peak level of the given channel (linear amplitude)
+    ///
+    /// Returns 0 for the channels not present in the input.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `channel` is greater or equal to [`MAX_CHANNELS`]
+    #[must_use]
+    pub fn peak(&self, channel: usize) -> f32 {
+        assert_valid_channel_number(channel);
+        self.levels.peak[channel].load(Ordering::Relaxed)
+    }
+
+    /// Current RMS level of the given channel (linear amplitude)
+    ///
+    /// Returns 0 for the channels not present in the input.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `channel` is greater or equal to [`MAX_CHANNELS`]
+    #[must_use]
+    pub fn rms(&self, channel: usize) -> f32 {
+        assert_valid_channel_number(channel);
+        self.levels.rms[channel].load(Ordering::Relaxed)
+    }
+
+    /// Highest peak level of the given channel over the last `peak_hold_time` seconds
+    /// (linear amplitude)
+    ///
+    /// Returns 0 for the channels not present in the input.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `channel` is greater or equal to [`MAX_CHANNELS`]
+    #[must_use]
+    pub fn peak_hold(&self, channel: usize) -> f32 {
+        assert_valid_channel_number(channel);
+        self.levels.peak_hold[channel].load(Ordering::Relaxed)
+    }
+}
+
+/// Assert that the channel number is valid
+///
+/// # Panics
+///
+/// This function panics if given channel number is greater or equal to [`MAX_CHANNELS`]
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_number(channel: usize) {
+    assert!(
+        channel < MAX_CHANNELS,
+        "IndexSizeError - Invalid channel number {channel:?}, should be less than {MAX_CHANNELS}"
+    );
+}
+
+/// Coefficient of a one pole smoothing filter with a time constant of `frames`
+fn one_pole_coef(frames: f64) -> f32 {
+    if frames > 0. {
+        (-1. / frames).exp() as f32
+    } else {
+        0.
+    }
+}
+
+/// Envelopes of a channel
+#[derive(Clone, Copy, Default)]
+struct ChannelState {
+    peak: f32,
+    mean_square: f32,
+    peak_hold: f32,
+    /// number of frames left before the held peak is released
+    hold_remaining: usize,
+}
+
+impl ChannelState {
+    fn is_silent(&self) -> bool {
+        self.peak < SILENCE_THRESHOLD
+            && self.mean_square < SILENCE_THRESHOLD * SILENCE_THRESHOLD
+            && self.peak_hold < SILENCE_THRESHOLD
+    }
+}
+
+/// `MeterRenderer` represents the rendering part of `MeterNode`
+struct MeterRenderer {
+    attack: AudioParamId,
+    release: AudioParamId,
+    output: MeterOutput,
+    rms_coef: f32,
+    peak_hold_frames: usize,
+    levels: Arc<MeterLevels>,
+    states: [ChannelState; MAX_CHANNELS],
+    /// number of channels measured, kept while the envelopes decay
+    number_of_channels: usize,
+}
+
+impl MeterRenderer {
+    /// Publish the levels of the measured channels and clear the other ones
+    fn publish(&self) {
+        self.states.iter().enumerate().for_each(|(channel, state)| {
+            let (peak, rms, peak_hold) = if channel < self.number_of_channels {
+                (state.peak, state.mean_square.sqrt(), state.peak_hold)
+            } else {
+                (0., 0., 0.)
+            };
+
+            self.levels.peak[channel].store(peak, Ordering::Relaxed);
+            self.levels.rms[channel].store(rms, Ordering::Relaxed);
+            self.levels.peak_hold[channel].store(peak_hold, Ordering::Relaxed);
+        });
+    }
+}
+
+impl AudioProcessor for MeterRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+        let sample_rate = f64::from(scope.sample_rate);
+
+        // let the envelopes decay to silence before going idle
+        if input.is_silent() && self.states.iter().all(ChannelState::is_silent) {
+            self.states = [ChannelState::default(); MAX_CHANNELS];
+            self.number_of_channels = 0;
+            self.publish();
+            output.make_silent();
+            return false;
+        }
+
+        // if in tail time, we should continue with previous number of channels
+        if !input.is_silent() {
+            self.number_of_channels = input.number_of_channels();
+        }
+        let number_of_channels = self.number_of_channels;
+
+        let attack = params.get(&self.attack)[0];
+        let release = params.get(&self.release)[0];
+        let attack_coef = one_pole_coef(f64::from(attack) * sample_rate);
+        let release_coef = one_pole_coef(f64::from(release) * sample_rate);
+        let rms_coef = self.rms_coef;
+        let peak_hold_frames = self.peak_hold_frames;
+
+        if self.output == MeterOutput::Input {
+            *output = input.clone();
+        } else {
+            output.set_number_of_channels(number_of_channels);
+        }
+
+        for (channel, state) in self.states[..number_of_channels].iter_mut().enumerate() {
+            let input_channel = if input.is_silent() {
+                input.channel_data(0)
+            } else {
+                input.channel_data(channel)
+            };
+
+            let mut envelope = [0.; RENDER_QUANTUM_SIZE];
+
+            input_channel
+                .iter()
+                .zip(envelope.iter_mut())
+                .for_each(|(sample, e)| {
+                    let level = sample.abs();
+
+                    let coef = if level > state.peak {
+                        attack_coef
+                    } else {
+                        release_coef
+                    };
+                    state.peak = coef.mul_add(state.peak - level, level);
+                    state.mean_square =
+                        rms_coef.mul_add(state.mean_square - level * level, level * level);
+
+                    if state.peak >= state.peak_hold {
+                        state.peak_hold = state.peak;
+                        state.hold_remaining = peak_hold_frames;
+                    } else if state.hold_remaining > 0 {
+                        state.hold_remaining -= 1;
+                    } else {
+                        state.peak_hold = state.peak;
+                    }
+
+                    *e = match self.output {
+                        MeterOutput::Input | MeterOutput::Peak => state.peak,
+                        MeterOutput::Rms => state.mean_square.sqrt(),
+                    };
+                });
+
+            if self.output != MeterOutput::Input {
+                output.channel_data_mut(channel).copy_from_slice(&envelope);
+            }
+        }
+
+        self.publish();
+
+        true
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&output) = msg.downcast_ref::<MeterOutput>() {
+            self.output = output;
+            return;
+        }
+
+        log::warn!("MeterRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let meter = context.create_meter();
+
+        assert_float_eq!(meter.attack().value(), 0., abs <= 0.);
+        assert_float_eq!(meter.release().value(), 0.5, abs <= 0.);
+        assert_float_eq!(meter.rms_window(), 0.3, abs <= 0.);
+        assert_float_eq!(meter.peak_hold_time(), 2., abs <= 0.);
+        assert_eq!(meter.output(), MeterOutput::Input);
+        assert_float_eq!(meter.peak(0), 0., abs <= 0.);
+        assert_float_eq!(meter.rms(0), 0., abs <= 0.);
+        assert_float_eq!(meter.peak_hold(0), 0., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_number() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let meter = context.create_meter();
+        let _ = meter.peak(MAX_CHANNELS);
+    }
+
+    #[test]
+    fn test_levels() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(2, 44_100, sample_rate);
+
+        let meter = context.create_meter();
+        meter.connect(&context.destination());
+
+        // sine on the left channel, constant on the right channel
+        let merger = context.create_channel_merger(2);
+        merger.connect(&meter);
+
+        let mut osc = context.create_oscillator();
+        osc.connect_from_output_to_input(&merger, 0, 0);
+        osc.start();
+
+        let mut constant = context.create_constant_source();
+        constant.offset().set_value(0.5);
+        constant.connect_from_output_to_input(&merger, 0, 1);
+        constant.start();
+
+        let res = context.start_rendering_sync();
+
+        // input is passed through
+        assert_float_eq!(
+            res.get_channel_data(1)[..],
+            [0.5; 44_100][..],
+            abs_all <= 0.
+        );
+
+        assert!(meter.peak(0) <= 1.);
+        assert!(meter.peak(0) > 0.9);
+        // the mean square integrated over one second, with a time constant of 0.3 second
+        let integrated = 1. - (-1_f32 / 0.3).exp();
+        assert_float_eq!(meter.rms(0), (0.5 * integrated).sqrt(), abs <= 0.01);
+        assert_float_eq!(meter.peak_hold(0), 1., abs <= 1e-3);
+
+        assert_float_eq!(meter.peak(1), 0.5, abs <= 1e-6);
+        assert_float_eq!(meter.rms(1), 0.5 * integrated.sqrt(), abs <= 1e-3);
+        assert_float_eq!(meter.peak_hold(1), 0.5, abs <= 1e-6);
+
+        assert_float_eq!(meter.peak(2), 0., abs <= 0.);
+    }
+
+    #[test]
+    fn test_release_and_peak_hold() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(1, 44_100, sample_rate);
+
+        let meter = MeterNode::new(
+            &context,
+            MeterOptions {
+                release: 0.1,
+                ..MeterOptions::default()
+            },
+        );
+        meter.connect(&context.destination());
+
+        let mut constant = context.create_constant_source();
+        constant.connect(&meter);
+        constant.start();
+        constant.stop_at(0.5);
+
+        let _ = context.start_rendering_sync();
+
+        // released for 5 time constants
+        assert_float_eq!(meter.peak(0), (-5_f32).exp(), abs <= 1e-3);
+        // held for 2 seconds
+        assert_float_eq!(meter.peak_hold(0), 1., abs <= 0.);
+    }
+
+    #[test]
+    fn test_envelope_output() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(1, 44_100, sample_rate);
+
+        let meter = MeterNode::new(
+            &context,
+            MeterOptions {
+                output: MeterOutput::Rms,
+                ..MeterOptions::default()
+            },
+        );
+        meter.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.connect(&meter);
+        osc.start();
+
+        let res = context.start_rendering_sync();
+        let channel = res.get_channel_data(0);
+
+        // the output is the rising RMS envelope
+        assert!(channel[..128].iter().all(|v| *v >= 0. && *v < 0.1));
+        let integrated = 1. - (-1_f32 / 0.3).exp();
+        assert_float_eq!(channel[44_099], (0.5 * integrated).sqrt(), abs <= 0.01);
+    }
+}
@@ -12,6 +12,14 @@ mod scheduled_source;
 pub use scheduled_source::*;
 
 // nodes
+mod ambisonic_binaural_decoder;
+pub use ambisonic_binaural_decoder::*;
+mod ambisonic_decoder;
+pub use ambisonic_decoder::*;
+mod ambisonic_encoder;
+pub use ambisonic_encoder::*;
+mod ambisonic_rotator;
+pub use ambisonic_rotator::*;
 mod analyser;
 pub use analyser::*;
 mod audio_buffer_source;
@@ -22,6 +30,8 @@ mod channel_merger;
 pub use channel_merger::*;
 mod channel_splitter;
 pub use channel_splitter::*;
+mod chorus;
+pub use chorus::*;
 mod constant_source;
 pub use constant_source::*;
 mod convolver;
@@ -32,10 +42,16 @@ mod destination;
 pub use destination::*;
 mod dynamics_compressor;
 pub use dynamics_compressor::*;
+mod filter;
+pub use filter::*;
+mod flanger;
+pub use flanger::*;
 mod gain;
 pub use gain::*;
 mod iir_filter;
 pub use iir_filter::*;
+mod loudness_meter;
+pub use loudness_meter::*;
 mod media_element_source;
 pub use media_element_source::*;
 mod media_stream_destination;
@@ -44,10 +60,27 @@ mod media_stream_source;
 pub use media_stream_source::*;
 mod media_stream_track_source;
 pub use media_stream_track_source::*;
+mod meter;
+pub use meter::*;
+mod modulation;
+mod multiband_compressor;
+pub use multiband_compressor::*;
+mod onset_detector;
+pub use onset_detector::*;
 mod oscillator;
 pub use oscillator::*;
 mod panner;
 pub use panner::*;
+mod parametric_eq;
+pub use parametric_eq::*;
+mod phaser;
+pub use phaser::*;
+mod pitch_detector;
+pub use pitch_detector::*;
+mod pitch_shifter;
+pub use pitch_shifter::*;
+mod reverb;
+pub use reverb::*;
 mod script_processor;
 pub use script_processor::*;
 mod stereo_panner;
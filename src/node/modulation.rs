@@ -0,0 +1,357 @@
+//! Building blocks shared by the modulation effects: `ChorusNode`, `FlangerNode` and
+//! `PhaserNode`
+//!
+//! The effects are rendered as single processors rather than graphs of delay, oscillator and
+//! gain nodes: the feedback paths are computed sample by sample and are thus not subject to
+//! the minimum delay of one render quantum of cycles in the audio graph.
+use std::f32::consts::PI;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::ChannelCountMode;
+
+/// Maximum value of the `rate` parameter (in Hz)
+pub(crate) const MAX_RATE: f32 = 20.;
+
+/// Maximum absolute value of the `feedback` parameter, keeps the feedback loops stable
+pub(crate) const MAX_FEEDBACK: f32 = 0.95;
+
+/// Assert that the channel count is valid for a modulation effect node
+///
+/// # Panics
+///
+/// This function panics if given count is greater than 2
+///
+#[track_caller]
+#[inline(always)]
+pub(crate) fn assert_valid_channel_count(count: usize, node: &str) {
+    assert!(
+        count <= 2,
+        "NotSupportedError - {node} channel count cannot be greater than two"
+    );
+}
+
+/// Assert that the channel count mode is valid for a modulation effect node
+///
+/// # Panics
+///
+/// This function panics if given count mode is [`ChannelCountMode::Max`]
+///
+#[track_caller]
+#[inline(always)]
+pub(crate) fn assert_valid_channel_count_mode(mode: ChannelCountMode, node: &str) {
+    assert_ne!(
+        mode,
+        ChannelCountMode::Max,
+        "NotSupportedError - {node} channel count mode cannot be set to max"
+    );
+}
+
+/// Initial values of the parameters common to the modulation effects
+pub(crate) struct ModulationValues {
+    pub(crate) rate: f32,
+    pub(crate) depth: f32,
+    pub(crate) feedback: f32,
+    pub(crate) mix: f32,
+    pub(crate) spread: f32,
+}
+
+/// Parameters common to the modulation effects, control thread side
+#[derive(Debug)]
+pub(crate) struct ModulationParams {
+    pub(crate) rate: AudioParam,
+    pub(crate) depth: AudioParam,
+    pub(crate) feedback: AudioParam,
+    pub(crate) mix: AudioParam,
+    pub(crate) spread: AudioParam,
+}
+
+/// Parameters common to the modulation effects, render thread side
+pub(crate) struct ModulationParamIds {
+    pub(crate) rate: AudioParamId,
+    pub(crate) depth: AudioParamId,
+    pub(crate) feedback: AudioParamId,
+    pub(crate) mix: AudioParamId,
+    pub(crate) spread: AudioParamId,
+}
+
+impl ModulationParams {
+    /// Create the `rate`, `depth`, `feedback`, `mix` and `spread` parameters of a
+    /// modulation effect, `defaults` are the default values of the parameters
+    pub(crate) fn new<C: BaseAudioContext>(
+        context: &C,
+        registration: &AudioContextRegistration,
+        defaults: &ModulationValues,
+        values: &ModulationValues,
+    ) -> (Self, ModulationParamIds) {
+        let param = |default_value, min_value, max_value, automation_rate, value| {
+            let options = AudioParamDescriptor {
+                name: String::new(),
+                min_value,
+                max_value,
+                default_value,
+                automation_rate,
+            };
+            let (mut param, proc) = context.create_audio_param(options, registration);
+            if automation_rate == AutomationRate::K {
+                param.set_automation_rate_constrained(true);
+            }
+            param.set_value(value);
+            (param, proc)
+        };
+
+        let (rate, rate_proc) = param(defaults.rate, 0., MAX_RATE, AutomationRate::K, values.rate);
+        let (depth, depth_proc) = param(defaults.depth, 0., 1., AutomationRate::K, values.depth);
+        let (feedback, feedback_proc) = param(
+            defaults.feedback,
+            -MAX_FEEDBACK,
+            MAX_FEEDBACK,
+            AutomationRate::K,
+            values.feedback,
+        );
+        let (mix, mix_proc) = param(defaults.mix, 0., 1., AutomationRate::A, values.mix);
+        let (spread, spread_proc) =
+            param(defaults.spread, 0., 1., AutomationRate::K, values.spread);
+
+        let params = Self {
+            rate,
+            depth,
+            feedback,
+            mix,
+            spread,
+        };
+
+        let ids = ModulationParamIds {
+            rate: rate_proc,
+            depth: depth_proc,
+            feedback: feedback_proc,
+            mix: mix_proc,
+            spread: spread_proc,
+        };
+
+        (params, ids)
+    }
+}
+
+/// Sine low frequency oscillator, the phase is expressed in cycles
+#[derive(Default)]
+pub(crate) struct Lfo {
+    phase: f32,
+}
+
+impl Lfo {
+    /// Value of the oscillator in the range `[0, 1]` at the current phase plus `offset` cycles
+    #[inline(always)]
+    pub(crate) fn value(&self, offset: f32) -> f32 {
+        0.5 - 0.5 * (2. * PI * (self.phase + offset)).cos()
+    }
+
+    #[inline(always)]
+    pub(crate) fn advance(&mut self, increment: f32) {
+        self.phase += increment;
+        self.phase -= self.phase.floor();
+    }
+}
+
+/// Circular buffer of samples read at fractional delays
+pub(crate) struct FractionalDelayLine {
+    buffer: Vec<f32>,
+    write_index: usize,
+}
+
+impl FractionalDelayLine {
+    /// Create a delay line able to delay samples by up to `max_delay` samples
+    pub(crate) fn new(max_delay: usize) -> Self {
+        Self {
+            // cubic interpolation reads up to two samples past the delay
+            buffer: vec![0.; max_delay + 3],
+            write_index: 0,
+        }
+    }
+
+    /// Read the signal `delay` samples ago with cubic Hermite interpolation, `delay` is
+    /// clamped so the four samples read have been written
+    #[inline(always)]
+    pub(crate) fn read(&self, delay: f32) -> f32 {
+        let len = self.buffer.len();
+        let delay = delay.clamp(2., (len - 3) as f32);
+        let index = delay.floor();
+        let k = delay - index;
+        let index = index as usize;
+
+        // y0 is the most recent sample
+        let sample = |delay: usize| self.buffer[(self.write_index + len - delay) % len];
+        let y0 = sample(index - 1);
+        let y1 = sample(index);
+        let y2 = sample(index + 1);
+        let y3 = sample(index + 2);
+
+        let c1 = 0.5 * (y2 - y0);
+        let c2 = y0 - 2.5 * y1 + 2. * y2 - 0.5 * y3;
+        let c3 = 0.5 * (y3 - y0) + 1.5 * (y1 - y2);
+        ((c3 * k + c2) * k + c1) * k + y1
+    }
+
+    #[inline(always)]
+    pub(crate) fn write(&mut self, value: f32) {
+        self.buffer[self.write_index] = value;
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+    }
+
+    /// Maximum delay in samples
+    pub(crate) fn max_delay(&self) -> usize {
+        self.buffer.len() - 3
+    }
+}
+
+/// Rendering part of the `ChorusNode` and `FlangerNode`
+///
+/// Each channel is delayed by `voices` delay times modulated by a sine LFO, in the range
+/// `[min_delay, min_delay + depth * sweep]`. The voices are evenly spread over the LFO
+/// cycle, and the right channel LFO is shifted by `spread` half cycles.
+pub(crate) struct ModulatedDelayRenderer {
+    params: ModulationParamIds,
+    /// Shortest delay time (in seconds)
+    min_delay: f32,
+    /// Range of the delay time modulation (in seconds) for a depth of 1
+    sweep: f32,
+    voices: usize,
+    lfo: Lfo,
+    delay_lines: [FractionalDelayLine; 2],
+    /// Number of samples left before the feedback tail is considered silent
+    tail_remaining: usize,
+}
+
+impl ModulatedDelayRenderer {
+    pub(crate) fn new(
+        sample_rate: f32,
+        params: ModulationParamIds,
+        min_delay: f32,
+        sweep: f32,
+        voices: usize,
+    ) -> Self {
+        let max_delay = ((min_delay + sweep) * sample_rate).ceil() as usize;
+
+        Self {
+            params,
+            min_delay,
+            sweep,
+            voices,
+            lfo: Lfo::default(),
+            delay_lines: [
+                FractionalDelayLine::new(max_delay),
+                FractionalDelayLine::new(max_delay),
+            ],
+            tail_remaining: 0,
+        }
+    }
+}
+
+/// Number of samples it takes for a feedback loop of `length` samples to decay below -120dB
+pub(crate) fn feedback_tail(length: usize, feedback: f32) -> usize {
+    let feedback = feedback.abs();
+    let passes = if feedback > 0. {
+        (-6. / feedback.log10()).ceil() as usize
+    } else {
+        0
+    };
+
+    (passes + 1) * length
+}
+
+impl AudioProcessor for ModulatedDelayRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+        let sample_rate = scope.sample_rate;
+
+        let feedback = params.get(&self.params.feedback)[0];
+
+        // keep the node alive until the feedback tail decayed
+        if !input.is_silent() {
+            let max_delay = self.delay_lines[0].max_delay();
+            self.tail_remaining = feedback_tail(max_delay, feedback);
+        } else if self.tail_remaining == 0 {
+            output.make_silent();
+            return false;
+        } else {
+            self.tail_remaining = self.tail_remaining.saturating_sub(RENDER_QUANTUM_SIZE);
+        }
+
+        // k-rate parameters
+        let increment = params.get(&self.params.rate)[0] / sample_rate;
+        let depth = params.get(&self.params.depth)[0];
+        let spread = params.get(&self.params.spread)[0];
+        let min_delay = self.min_delay * sample_rate;
+        let sweep = depth * self.sweep * sample_rate;
+        let offsets = [0., 0.5 * spread];
+        let voice_gain = 1. / self.voices as f32;
+
+        // a-rate parameter
+        let mix = params.get(&self.params.mix);
+
+        let (input_left, input_right) = match input.number_of_channels() {
+            1 => (input.channel_data(0), input.channel_data(0)),
+            2 => (input.channel_data(0), input.channel_data(1)),
+            _ => panic!("Modulation effect nodes should not have more than 2 channels to process"),
+        };
+
+        output.set_number_of_channels(2);
+        let [output_left, output_right] = output.stereo_mut();
+
+        let Self {
+            voices,
+            lfo,
+            delay_lines,
+            ..
+        } = self;
+
+        for (i, (o_left, o_right)) in output_left
+            .iter_mut()
+            .zip(output_right.iter_mut())
+            .enumerate()
+        {
+            let dry = [input_left[i], input_right[i]];
+            let mix = if mix.len() == 1 { mix[0] } else { mix[i] };
+            let mut out = [0.; 2];
+
+            out.iter_mut()
+                .zip(dry.iter())
+                .zip(delay_lines.iter_mut())
+                .zip(offsets.iter())
+                .for_each(|(((o, &x), line), &offset)| {
+                    let wet = (0..*voices)
+                        .map(|voice| {
+                            let phase = offset + voice as f32 * voice_gain;
+                            line.read(min_delay + sweep * lfo.value(phase))
+                        })
+                        .sum::<f32>()
+                        * voice_gain;
+
+                    // the delay line has been read, so the wet signal can be fed back
+                    // without extra delay
+                    line.write(feedback.mul_add(wet, x));
+                    *o = (1. - mix).mul_add(x, mix * wet);
+                });
+
+            *o_left = out[0];
+            *o_right = out[1];
+
+            lfo.advance(increment);
+        }
+
+        true
+    }
+}
@@ -0,0 +1,700 @@
+//! The multiband compressor control and renderer parts
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use arrayvec::ArrayVec;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{AtomicF32, MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+
+use super::biquad_filter::Coefficients;
+use super::dynamics_compressor::{db_to_lin, lin_to_db};
+use super::filter::Cascade;
+use super::{
+    AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation,
+    FilterDesign, FilterSlope, FilterType,
+};
+
+/// Minimum number of bands of a `MultibandCompressorNode`
+const MIN_BANDS: usize = 3;
+/// Maximum number of bands of a `MultibandCompressorNode`
+const MAX_BANDS: usize = 5;
+const MAX_CROSSOVERS: usize = MAX_BANDS - 1;
+
+/// Options of a band of a [`MultibandCompressorNode`]
+#[derive(Clone, Debug)]
+pub struct MultibandCompressorBandOptions {
+    /// Level above which the compression starts (dB)
+    pub threshold: f32,
+    /// Amount of dB change in input for a 1 dB change in the output
+    pub ratio: f32,
+    /// Time to reduce the gain by 10dB (seconds)
+    pub attack: f32,
+    /// Time to increase the gain by 10dB (seconds)
+    pub release: f32,
+}
+
+impl Default for MultibandCompressorBandOptions {
+    fn default() -> Self {
+        Self {
+            threshold: -24., // dB
+            ratio: 4.,       // unit less
+            attack: 0.003,   // seconds
+            release: 0.25,   // seconds
+        }
+    }
+}
+
+/// Options for constructing a [`MultibandCompressorNode`]
+#[derive(Clone, Debug)]
+pub struct MultibandCompressorOptions {
+    /// Frequencies of the crossovers between the bands, in increasing order
+    pub crossover_frequencies: Vec<f32>,
+    /// Options of the bands, from the lowest to the highest band. There must be one more band
+    /// than crossover frequencies.
+    pub bands: Vec<MultibandCompressorBandOptions>,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for MultibandCompressorOptions {
+    fn default() -> Self {
+        Self {
+            crossover_frequencies: vec![200., 2000.],
+            bands: vec![MultibandCompressorBandOptions::default(); 3],
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::ClampedMax,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// Assert that the crossover frequencies and the bands are valid
+///
+/// # Panics
+///
+/// This function panics if:
+/// - the number of bands is not in the range [3, 5]
+/// - the number of bands does not match the number of crossover frequencies
+/// - the crossover frequencies are not strictly increasing in ]0, nyquist[
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_bands(crossover_frequencies: &[f32], number_of_bands: usize, sample_rate: f32) {
+    assert!(
+        (MIN_BANDS..=MAX_BANDS).contains(&number_of_bands),
+        "NotSupportedError - MultibandCompressorNode must have between {MIN_BANDS} and {MAX_BANDS} bands, got {number_of_bands}"
+    );
+
+    assert_eq!(
+        crossover_frequencies.len() + 1,
+        number_of_bands,
+        "IndexSizeError - MultibandCompressorNode must have one more band than crossover frequencies"
+    );
+
+    let mut previous = 0.;
+    crossover_frequencies.iter().for_each(|&frequency| {
+        assert!(
+            frequency > previous && frequency < sample_rate / 2.,
+            "RangeError - Invalid crossover frequency: {frequency:?}, crossover frequencies must be increasing and below the Nyquist frequency"
+        );
+        previous = frequency;
+    });
+}
+
+/// A band of a [`MultibandCompressorNode`]
+#[derive(Debug)]
+pub struct MultibandCompressorBand {
+    threshold: AudioParam,
+    ratio: AudioParam,
+    attack: AudioParam,
+    release: AudioParam,
+    reduction: Arc<AtomicF32>,
+}
+
+impl MultibandCompressorBand {
+    /// Returns the threshold audio parameter, level above which the compression starts (dB)
+    #[must_use]
+    pub fn threshold(&self) -> &AudioParam {
+        &self.threshold
+    }
+
+    /// Returns the ratio audio parameter
+    #[must_use]
+    pub fn ratio(&self) -> &AudioParam {
+        &self.ratio
+    }
+
+    /// Returns the attack audio parameter (seconds)
+    #[must_use]
+    pub fn attack(&self) -> &AudioParam {
+        &self.attack
+    }
+
+    /// Returns the release audio parameter (seconds)
+    #[must_use]
+    pub fn release(&self) -> &AudioParam {
+        &self.release
+    }
+
+    /// Returns the current gain reduction applied to the band (dB)
+    #[must_use]
+    pub fn reduction(&self) -> f32 {
+        self.reduction.load(Ordering::Relaxed)
+    }
+}
+
+/// `MultibandCompressorNode` splits the signal into frequency bands and compresses
+/// each band independently.
+///
+/// The bands are split with 4th order Linkwitz-Riley crossovers, and the phase shift of
+/// the crossovers is compensated in each band with all-pass filters, so that the bands
+/// sum back to a flat magnitude response when no compression is applied. Each band has
+/// its own threshold, ratio, attack and release parameters, and reports its gain reduction
+/// as [`DynamicsCompressorNode::reduction`](super::DynamicsCompressorNode::reduction) does.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_multiband_compressor`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let file = File::open("samples/sample.wav").unwrap();
+/// let buffer = context.decode_audio_data_sync(file).unwrap();
+///
+/// // three bands, split at 200Hz and 2kHz
+/// let compressor = context.create_multiband_compressor();
+/// compressor.connect(&context.destination());
+///
+/// // tame the low end
+/// let low = &compressor.bands()[0];
+/// low.threshold().set_value(-30.);
+/// low.ratio().set_value(8.);
+///
+/// let mut src = context.create_buffer_source();
+/// src.connect(&compressor);
+/// src.set_buffer(buffer);
+/// src.start();
+///
+/// println!("low band reduction: {} dB", low.reduction());
+/// ```
+///
+#[derive(Debug)]
+pub struct MultibandCompressorNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    /// Frequencies of the crossovers between the bands
+    crossover_frequencies: Vec<f32>,
+    /// Bands of the compressor
+    bands: Vec<MultibandCompressorBand>,
+}
+
+impl AudioNode for MultibandCompressorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl MultibandCompressorNode {
+    /// returns a `MultibandCompressorNode` instance
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - multiband compressor options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// - the number of bands is not in the range [3, 5]
+    /// - the number of bands is not the number of crossover frequencies plus one
+    /// - the crossover frequencies are not strictly increasing and below the Nyquist frequency
+    pub fn new<C: BaseAudioContext>(context: &C, options: MultibandCompressorOptions) -> Self {
+        let MultibandCompressorOptions {
+            crossover_frequencies,
+            bands,
+            audio_node_options: channel_config,
+        } = options;
+
+        let sample_rate = context.sample_rate();
+        assert_valid_bands(&crossover_frequencies, bands.len(), sample_rate);
+
+        context.base().register(move |registration| {
+            // the parameters are k-rate, as the ones of the `DynamicsCompressorNode`
+            let param = |default_value, min_value, max_value, value| {
+                let options = AudioParamDescriptor {
+                    name: String::new(),
+                    min_value,
+                    max_value,
+                    default_value,
+                    automation_rate: AutomationRate::K,
+                };
+                let (mut param, proc) = context.create_audio_param(options, &registration);
+                param.set_automation_rate_constrained(true);
+                param.set_value(value);
+                (param, proc)
+            };
+
+            let (bands, band_renderers): (Vec<_>, Vec<_>) = bands
+                .iter()
+                .map(|band| {
+                    let (threshold, threshold_proc) = param(-24., -100., 0., band.threshold);
+                    let (ratio, ratio_proc) = param(4., 1., 20., band.ratio);
+                    let (attack, attack_proc) = param(0.003, 0., 1., band.attack);
+                    let (release, release_proc) = param(0.25, 0., 1., band.release);
+                    let reduction = Arc::new(AtomicF32::new(0.));
+
+                    let band = MultibandCompressorBand {
+                        threshold,
+                        ratio,
+                        attack,
+                        release,
+                        reduction: Arc::clone(&reduction),
+                    };
+
+                    let renderer = BandRenderer {
+                        threshold: threshold_proc,
+                        ratio: ratio_proc,
+                        attack: attack_proc,
+                        release: release_proc,
+                        reduction,
+                        prev_detector_value: 0.,
+                    };
+
+                    (band, renderer)
+                })
+                .unzip();
+
+            let crossovers = crossover_frequencies
+                .iter()
+                .map(|&frequency| Crossover::new(sample_rate, frequency))
+                .collect();
+
+            let renderer = MultibandCompressorRenderer {
+                crossovers,
+                bands: band_renderers.into_iter().collect(),
+                states: ArrayVec::new(),
+                buffers: vec![[0.; RENDER_QUANTUM_SIZE]; MAX_BANDS * MAX_CHANNELS],
+            };
+
+            let node = Self {
+                registration,
+                channel_config: channel_config.into(),
+                crossover_frequencies,
+                bands,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Returns the frequencies of the crossovers between the bands
+    #[must_use]
+    pub fn crossover_frequencies(&self) -> &[f32] {
+        &self.crossover_frequencies
+    }
+
+    /// Returns the bands of the compressor, from the lowest to the highest
+    #[must_use]
+    pub fn bands(&self) -> &[MultibandCompressorBand] {
+        &self.bands
+    }
+}
+
+/// 4th order Linkwitz-Riley crossover, each filter is a Butterworth section applied twice
+struct Crossover {
+    lowpass: Coefficients,
+    highpass: Coefficients,
+    /// sum of the low-pass and high-pass filters, compensates the phase shift of the
+    /// crossover in the bands that are not split by it
+    allpass: Coefficients,
+}
+
+impl Crossover {
+    fn new(sample_rate: f32, frequency: f32) -> Self {
+        let coefficients = |type_| {
+            let cascade = Cascade::new(type_, FilterDesign::Butterworth, FilterSlope::Db12, 1.);
+            cascade.coefficients(f64::from(sample_rate), f64::from(frequency), 1.)[0]
+        };
+
+        let lowpass = coefficients(FilterType::Lowpass);
+        let highpass = coefficients(FilterType::Highpass);
+        // same poles, mirrored zeros
+        let allpass = Coefficients {
+            b0: lowpass.a2,
+            b1: lowpass.a1,
+            b2: 1.,
+            a1: lowpass.a1,
+            a2: lowpass.a2,
+        };
+
+        Self {
+            lowpass,
+            highpass,
+            allpass,
+        }
+    }
+}
+
+/// Filter states of a channel
+#[derive(Clone, Copy)]
+struct ChannelState {
+    /// states of the two low-pass and two high-pass sections of each crossover
+    crossovers: [[[f64; 4]; 4]; MAX_CROSSOVERS],
+    /// states of the phase compensation all-pass filters of each band
+    allpasses: [[[f64; 4]; MAX_CROSSOVERS]; MAX_BANDS],
+}
+
+impl ChannelState {
+    const SILENT: Self = Self {
+        crossovers: [[[0.; 4]; 4]; MAX_CROSSOVERS],
+        allpasses: [[[0.; 4]; MAX_CROSSOVERS]; MAX_BANDS],
+    };
+
+    fn is_silent(&self) -> bool {
+        !self
+            .crossovers
+            .iter()
+            .flatten()
+            .chain(self.allpasses.iter().flatten())
+            .any(|v| v.iter().copied().any(f64::is_normal))
+    }
+}
+
+/// Apply a biquad filter in place
+fn process_biquad(c: &Coefficients, xy: &mut [f64; 4], samples: &mut [f32]) {
+    let [mut x1, mut x2, mut y1, mut y2] = *xy;
+
+    samples.iter_mut().for_each(|s| {
+        let x = f64::from(*s);
+        let mut y = c.b0 * x + c.b1 * x1 + c.b2 * x2 - c.a1 * y1 - c.a2 * y2;
+
+        // flush NaN, Infinity, subnormal (and zero) to zero
+        if !y.is_normal() {
+            y = 0.;
+        }
+
+        x2 = x1;
+        x1 = x;
+        y2 = y1;
+        y1 = y;
+        *s = y as f32;
+    });
+
+    *xy = [x1, x2, y1, y2];
+}
+
+/// Rendering part of a band of the compressor
+struct BandRenderer {
+    threshold: AudioParamId,
+    ratio: AudioParamId,
+    attack: AudioParamId,
+    release: AudioParamId,
+    reduction: Arc<AtomicF32>,
+    prev_detector_value: f32,
+}
+
+/// `MultibandCompressorRenderer` represents the rendering part of `MultibandCompressorNode`
+struct MultibandCompressorRenderer {
+    crossovers: ArrayVec<Crossover, MAX_CROSSOVERS>,
+    bands: ArrayVec<BandRenderer, MAX_BANDS>,
+    // keep filter states for each channel
+    states: ArrayVec<ChannelState, MAX_CHANNELS>,
+    /// signal of each band for each channel, indexed by `band * MAX_CHANNELS + channel`
+    buffers: Vec<[f32; RENDER_QUANTUM_SIZE]>,
+}
+
+impl AudioProcessor for MultibandCompressorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+        let sample_rate = scope.sample_rate;
+
+        // handle tail time
+        if input.is_silent() && self.states.iter().all(ChannelState::is_silent) {
+            self.bands.iter_mut().for_each(|band| {
+                band.prev_detector_value = 0.;
+                band.reduction.store(0., Ordering::Relaxed);
+            });
+            output.make_silent();
+            return false;
+        }
+
+        // eventually resize state according to input number of channels
+        // if in tail time, we should continue with previous number of channels
+        if !input.is_silent() {
+            let num_channels = input.number_of_channels();
+            self.states.truncate(num_channels);
+            for _ in self.states.len()..num_channels {
+                self.states.push(ChannelState::SILENT);
+            }
+        }
+        let num_channels = self.states.len();
+
+        let Self {
+            crossovers,
+            bands,
+            states,
+            buffers,
+        } = self;
+
+        // split each channel into bands
+        for (channel_number, state) in states.iter_mut().enumerate() {
+            let input_channel = if input.is_silent() {
+                input.channel_data(0)
+            } else {
+                input.channel_data(channel_number)
+            };
+
+            let mut rest = [0.; RENDER_QUANTUM_SIZE];
+            rest.copy_from_slice(input_channel);
+
+            for (index, crossover) in crossovers.iter().enumerate() {
+                let band = &mut buffers[index * MAX_CHANNELS + channel_number];
+                band.copy_from_slice(&rest);
+
+                let [lp1, lp2, hp1, hp2] = &mut state.crossovers[index];
+                process_biquad(&crossover.lowpass, lp1, band);
+                process_biquad(&crossover.lowpass, lp2, band);
+                process_biquad(&crossover.highpass, hp1, &mut rest);
+                process_biquad(&crossover.highpass, hp2, &mut rest);
+
+                // compensate the phase shift of the higher crossovers
+                crossovers
+                    .iter()
+                    .zip(state.allpasses[index].iter_mut())
+                    .skip(index + 1)
+                    .for_each(|(crossover, xy)| process_biquad(&crossover.allpass, xy, band));
+            }
+
+            buffers[crossovers.len() * MAX_CHANNELS + channel_number].copy_from_slice(&rest);
+        }
+
+        output.set_number_of_channels(num_channels);
+        output.channels_mut().iter_mut().for_each(|c| c.fill(0.));
+
+        // compress each band and sum them back
+        // see `DynamicsCompressorRenderer`, without knee, makeup gain and lookahead
+        for (index, band) in bands.iter_mut().enumerate() {
+            let threshold = params.get(&band.threshold)[0];
+            let ratio = params.get(&band.ratio)[0];
+            let attack = params.get(&band.attack)[0];
+            let release = params.get(&band.release)[0];
+            let attack_tau = (-1. / (attack * sample_rate)).exp();
+            let release_tau = (-1. / (release * sample_rate)).exp();
+
+            let band_buffers = &buffers[index * MAX_CHANNELS..index * MAX_CHANNELS + num_channels];
+
+            let mut prev_detector_value = band.prev_detector_value;
+            let mut reduction_gains = [0.; RENDER_QUANTUM_SIZE]; // lin
+
+            reduction_gains.iter_mut().enumerate().for_each(|(i, g)| {
+                // pick highest value for this index across all channels
+                let max = band_buffers
+                    .iter()
+                    .fold(0_f32, |max, channel| max.max(channel[i].abs()));
+                let sample_db = lin_to_db(max);
+
+                // hard knee gain computer
+                let sample_attenuated = if sample_db <= threshold {
+                    sample_db
+                } else {
+                    threshold + (sample_db - threshold) / ratio
+                };
+                let sample_attenuation = sample_db - sample_attenuated;
+
+                // branching peak detector
+                let detector_value = if sample_attenuation > prev_detector_value {
+                    attack_tau * prev_detector_value + (1. - attack_tau) * sample_attenuation
+                } else {
+                    release_tau * prev_detector_value + (1. - release_tau) * sample_attenuation
+                };
+
+                *g = db_to_lin(-detector_value);
+                prev_detector_value = detector_value;
+            });
+
+            band.prev_detector_value = prev_detector_value;
+            band.reduction
+                .store(-prev_detector_value, Ordering::Relaxed);
+
+            output
+                .channels_mut()
+                .iter_mut()
+                .zip(band_buffers)
+                .for_each(|(o, b)| {
+                    o.iter_mut()
+                        .zip(b.iter())
+                        .zip(reduction_gains.iter())
+                        .for_each(|((o, b), g)| *o += b * g);
+                });
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44_100.);
+        let compressor = context.create_multiband_compressor();
+
+        assert_eq!(compressor.crossover_frequencies(), &[200., 2000.]);
+        assert_eq!(compressor.bands().len(), 3);
+
+        compressor.bands().iter().for_each(|band| {
+            assert_float_eq!(band.threshold().value(), -24., abs <= 0.);
+            assert_float_eq!(band.ratio().value(), 4., abs <= 0.);
+            assert_float_eq!(band.attack().value(), 0.003, abs <= 0.);
+            assert_float_eq!(band.release().value(), 0.25, abs <= 0.);
+            assert_float_eq!(band.reduction(), 0., abs <= 0.);
+        });
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_too_few_bands() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44_100.);
+        let options = MultibandCompressorOptions {
+            crossover_frequencies: vec![1000.],
+            bands: vec![MultibandCompressorBandOptions::default(); 2],
+            ..MultibandCompressorOptions::default()
+        };
+        let _ = MultibandCompressorNode::new(&context, options);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bands_mismatch() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44_100.);
+        let options = MultibandCompressorOptions {
+            crossover_frequencies: vec![100., 1000., 10000.],
+            ..MultibandCompressorOptions::default()
+        };
+        let _ = MultibandCompressorNode::new(&context, options);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_crossovers_not_increasing() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44_100.);
+        let options = MultibandCompressorOptions {
+            crossover_frequencies: vec![2000., 200.],
+            ..MultibandCompressorOptions::default()
+        };
+        let _ = MultibandCompressorNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_flat_recombination() {
+        let sample_rate = 44_100.;
+        let length = 64 * RENDER_QUANTUM_SIZE;
+
+        for crossover_frequencies in [vec![200., 2000.], vec![100., 500., 2000., 8000.]] {
+            let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+            let options = MultibandCompressorOptions {
+                bands: vec![
+                    MultibandCompressorBandOptions {
+                        threshold: 0.,
+                        ..MultibandCompressorBandOptions::default()
+                    };
+                    crossover_frequencies.len() + 1
+                ],
+                crossover_frequencies,
+                ..MultibandCompressorOptions::default()
+            };
+            let compressor = MultibandCompressorNode::new(&context, options);
+            compressor.connect(&context.destination());
+
+            // impulse of amplitude below the threshold
+            let mut impulse = context.create_buffer(1, 1, sample_rate);
+            impulse.copy_to_channel(&[0.5], 0);
+            let mut src = context.create_buffer_source();
+            src.set_buffer(impulse);
+            src.connect(&compressor);
+            src.start();
+
+            let result = context.start_rendering_sync();
+            let channel = result.get_channel_data(0);
+
+            // the recombined bands form an all-pass filter, which preserves the energy
+            let energy: f32 = channel.iter().map(|s| s * s).sum();
+            assert_float_eq!(energy, 0.25, abs <= 1e-4);
+        }
+    }
+
+    #[test]
+    fn test_band_reduction() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(1, 32 * RENDER_QUANTUM_SIZE, sample_rate);
+
+        let compressor = context.create_multiband_compressor();
+        compressor.connect(&context.destination());
+
+        // loud signal in the high band only
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(5000.);
+        osc.connect(&compressor);
+        osc.start();
+
+        let result = context.start_rendering_sync();
+        let channel = result.get_channel_data(0);
+
+        let bands = compressor.bands();
+        // only the leakage of the crossovers reaches the lower bands
+        assert_float_eq!(bands[0].reduction(), 0., abs <= 0.5);
+        assert_float_eq!(bands[1].reduction(), 0., abs <= 0.5);
+        // the signal is 24dB above the threshold, compressed with a 4:1 ratio
+        assert_float_eq!(bands[2].reduction(), -18., abs <= 1.);
+
+        let peak = channel[16 * RENDER_QUANTUM_SIZE..]
+            .iter()
+            .fold(0_f32, |max, s| max.max(s.abs()));
+        assert!(peak < 0.2);
+    }
+}
@@ -0,0 +1,460 @@
+//! The onset detector control and renderer parts
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use realfft::{num_complex::Complex, RealFftPlanner, RealToComplex};
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::events::{EventHandler, EventPayload, EventType, OnsetEvent};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
+
+/// Size of the analysed frames, a new frame is analysed every render quantum
+const FRAME_SIZE: usize = 1024;
+/// Number of past flux values used for the adaptive threshold
+const FLUX_HISTORY: usize = 16;
+/// Size of the blocks used to locate the onset inside the detection frame
+const BLOCK_SIZE: usize = 32;
+/// Compression of the magnitudes before computing the spectral flux
+const LOG_COMPRESSION: f32 = 100.;
+
+/// Options for constructing an [`OnsetDetectorNode`]
+#[derive(Clone, Debug)]
+pub struct OnsetDetectorOptions {
+    /// Spectral flux, above the recent average flux, triggering an onset
+    pub threshold: f32,
+    /// Minimum time (in seconds) between two onsets
+    pub min_interval: f64,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for OnsetDetectorOptions {
+    fn default() -> Self {
+        Self {
+            threshold: 1.,
+            min_interval: 0.05,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `OnsetDetectorNode` detects the onsets (note attacks, hits, beats) in its input
+///
+/// The node passes its input unchanged to its output. The render thread computes the
+/// spectral flux of the (down mixed) input every render quantum and dispatches an
+/// [`OnsetEvent`] each time it rises above the recent average flux by more than the
+/// `threshold`. The onset is then located in the analysed frame, so the time of the
+/// event is accurate to the sample, even though the event itself is dispatched a
+/// few milliseconds after the onset.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_onset_detector`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let file = File::open("samples/sample.wav").unwrap();
+/// let buffer = context.decode_audio_data_sync(file).unwrap();
+///
+/// let detector = context.create_onset_detector();
+/// detector.connect(&context.destination());
+/// detector.set_ononset(|event| println!("onset at {:.3}s", event.time));
+///
+/// let mut src = context.create_buffer_source();
+/// src.set_buffer(buffer);
+/// src.connect(&detector);
+/// src.start();
+///
+/// std::thread::sleep(std::time::Duration::from_secs(4));
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example detectors`
+///
+#[derive(Debug)]
+pub struct OnsetDetectorNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    threshold: f32,
+    min_interval: f64,
+}
+
+impl AudioNode for OnsetDetectorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl OnsetDetectorNode {
+    /// Creates an `OnsetDetectorNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - onset detector options
+    ///
+    /// # Panics
+    ///
+    /// This function panics if `threshold` or `min_interval` is negative or not finite
+    pub fn new<C: BaseAudioContext>(context: &C, options: OnsetDetectorOptions) -> Self {
+        let OnsetDetectorOptions {
+            threshold,
+            min_interval,
+            audio_node_options,
+        } = options;
+
+        assert!(
+            threshold.is_finite() && threshold >= 0.,
+            "RangeError - Invalid threshold: {threshold:?}, should be positive"
+        );
+        assert!(
+            min_interval.is_finite() && min_interval >= 0.,
+            "RangeError - Invalid min interval: {min_interval:?}, should be positive"
+        );
+
+        context.base().register(move |registration| {
+            let r2c = RealFftPlanner::<f32>::new().plan_fft_forward(FRAME_SIZE);
+
+            // Hann window, normalized so a full scale sine peaks at 1
+            let window_sum = FRAME_SIZE as f32 / 2.;
+            let window = (0..FRAME_SIZE)
+                .map(|i| (0.5 - 0.5 * (2. * PI * i as f32 / FRAME_SIZE as f32).cos()) / window_sum)
+                .collect();
+
+            let renderer = OnsetDetectorRenderer {
+                threshold,
+                min_interval,
+                input: r2c.make_input_vec(),
+                scratch: r2c.make_scratch_vec(),
+                output: r2c.make_output_vec(),
+                r2c,
+                window,
+                history: vec![0.; FRAME_SIZE],
+                magnitudes: vec![0.; FRAME_SIZE / 2 + 1],
+                fluxes: [0.; FLUX_HISTORY],
+                flux_index: 0,
+                above: false,
+                last_onset: f64::NEG_INFINITY,
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                threshold,
+                min_interval,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Spectral flux, above the recent average flux, triggering an onset
+    #[must_use]
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Minimum time (in seconds) between two onsets
+    #[must_use]
+    pub fn min_interval(&self) -> f64 {
+        self.min_interval
+    }
+
+    /// Register callback to run when an onset is detected
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler.
+    pub fn set_ononset<F: FnMut(OnsetEvent) + Send + 'static>(&self, mut callback: F) {
+        let callback = move |v| match v {
+            EventPayload::Onset(v) => callback(v),
+            _ => unreachable!(),
+        };
+
+        self.context().set_event_handler(
+            EventType::Onset(self.registration().id()),
+            EventHandler::Multiple(Box::new(callback)),
+        );
+    }
+
+    /// Unset the callback to run when an onset is detected
+    pub fn clear_ononset(&self) {
+        self.context()
+            .clear_event_handler(EventType::Onset(self.registration().id()));
+    }
+}
+
+/// Locate the onset in the given frame, returns the index of its first sample
+///
+/// The onset is in the block with the highest energy increase from the previous
+/// block, at the first sample exceeding half the peak level of the block.
+fn locate_onset(frame: &[f32]) -> usize {
+    let energy = |block: &[f32]| block.iter().map(|v| v * v).sum::<f32>();
+
+    let (block, _) = frame
+        .chunks_exact(BLOCK_SIZE)
+        .zip(frame.chunks_exact(BLOCK_SIZE).skip(1))
+        .enumerate()
+        .map(|(index, (previous, current))| {
+            // favor the rise from silence over the rise of an already loud signal
+            let rise = (energy(current) + 1e-9) / (energy(previous) + 1e-9);
+            (index + 1, rise)
+        })
+        .fold(
+            (0, 0.),
+            |acc, (index, rise)| {
+                if rise > acc.1 {
+                    (index, rise)
+                } else {
+                    acc
+                }
+            },
+        );
+
+    // the onset may start at the end of the previous block
+    let start = block.saturating_sub(1) * BLOCK_SIZE;
+    let end = (block + 1) * BLOCK_SIZE;
+    let previous_peak = frame[start..block * BLOCK_SIZE]
+        .iter()
+        .fold(0., |acc: f32, v| acc.max(v.abs()));
+    let peak = frame[block * BLOCK_SIZE..end]
+        .iter()
+        .fold(0., |acc: f32, v| acc.max(v.abs()));
+    let level = (previous_peak + peak) / 2.;
+
+    frame[start..end]
+        .iter()
+        .position(|v| v.abs() > level)
+        .map_or(block * BLOCK_SIZE, |index| start + index)
+}
+
+/// `OnsetDetectorRenderer` represents the rendering part of `OnsetDetectorNode`
+struct OnsetDetectorRenderer {
+    threshold: f32,
+    min_interval: f64,
+    r2c: Arc<dyn RealToComplex<f32>>,
+    window: Vec<f32>,
+    input: Vec<f32>,
+    scratch: Vec<Complex<f32>>,
+    output: Vec<Complex<f32>>,
+    /// the most recent `FRAME_SIZE` input frames
+    history: Vec<f32>,
+    /// compressed magnitudes of the previous frame
+    magnitudes: Vec<f32>,
+    /// ring buffer of the past spectral flux values
+    fluxes: [f32; FLUX_HISTORY],
+    flux_index: usize,
+    /// whether the flux is currently above the threshold
+    above: bool,
+    last_onset: f64,
+}
+
+impl OnsetDetectorRenderer {
+    /// Spectral flux of the current frame, i.e. the sum of the compressed magnitude
+    /// increases since the previous frame
+    fn spectral_flux(&mut self) -> f32 {
+        self.input
+            .iter_mut()
+            .zip(self.history.iter().zip(self.window.iter()))
+            .for_each(|(i, (h, w))| *i = h * w);
+
+        self.r2c
+            .process_with_scratch(&mut self.input, &mut self.output, &mut self.scratch)
+            .unwrap();
+
+        self.magnitudes
+            .iter_mut()
+            .zip(self.output.iter())
+            .fold(0., |flux, (previous, c)| {
+                let magnitude = (1. + LOG_COMPRESSION * c.norm()).ln();
+                let increase = (magnitude - *previous).max(0.);
+                *previous = magnitude;
+                flux + increase
+            })
+    }
+}
+
+impl AudioProcessor for OnsetDetectorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // pass through input
+        *output = input.clone();
+
+        // down mix to mono
+        let mut mono = input.clone();
+        mono.mix(1, ChannelInterpretation::Speakers);
+
+        self.history.copy_within(RENDER_QUANTUM_SIZE.., 0);
+        self.history[FRAME_SIZE - RENDER_QUANTUM_SIZE..].copy_from_slice(mono.channel_data(0));
+
+        let flux = self.spectral_flux();
+        let average = self.fluxes.iter().sum::<f32>() / FLUX_HISTORY as f32;
+        self.fluxes[self.flux_index] = flux;
+        self.flux_index = (self.flux_index + 1) % FLUX_HISTORY;
+
+        // trigger on the rising edge of the flux above the adaptive threshold
+        let above = flux > average + self.threshold;
+        let rising = above && !self.above;
+        self.above = above;
+
+        if rising {
+            // time of the first sample of the analysed frame
+            let frame_start = scope.current_time
+                - (FRAME_SIZE - RENDER_QUANTUM_SIZE) as f64 / scope.sample_rate as f64;
+            let index = locate_onset(&self.history);
+            let time = (frame_start + index as f64 / scope.sample_rate as f64).max(0.);
+
+            if time - self.last_onset >= self.min_interval {
+                self.last_onset = time;
+                scope.send_onset_event(time, flux);
+            }
+        }
+
+        // no tail-time
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+    use crate::AudioBuffer;
+
+    use super::*;
+
+    #[test]
+    fn test_locate_onset() {
+        let mut frame = vec![0.; FRAME_SIZE];
+        frame[700..]
+            .iter_mut()
+            .enumerate()
+            .for_each(|(i, v)| *v = (2. * PI * 440. * i as f32 / 48_000.).cos());
+        assert_eq!(locate_onset(&frame), 700);
+
+        // onset over an existing signal
+        let mut frame = vec![0.1; FRAME_SIZE];
+        frame[345..].iter_mut().for_each(|v| *v = 0.8);
+        assert_eq!(locate_onset(&frame), 345);
+    }
+
+    #[test]
+    fn test_events() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 96_000, sample_rate);
+
+        let detector = context.create_onset_detector();
+        detector.connect(&context.destination());
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let events_clone = Arc::clone(&events);
+        detector.set_ononset(move |event| events_clone.lock().unwrap().push(event));
+
+        // short decaying tones, at arbitrary (not render quantum aligned) positions
+        let onsets = [3_000, 20_111, 45_678, 70_001];
+        let mut signal = vec![0.; 96_000];
+        for onset in onsets {
+            signal[onset..onset + 4_800]
+                .iter_mut()
+                .enumerate()
+                .for_each(|(i, v)| {
+                    let t = i as f32 / sample_rate;
+                    *v = (2. * PI * 880. * t).cos() * (-t * 60.).exp();
+                });
+        }
+
+        let buffer = AudioBuffer::from(vec![signal], sample_rate);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&detector);
+        src.start();
+
+        let _ = context.start_rendering_sync();
+
+        let events = events.lock().unwrap();
+        let times: Vec<f64> = events.iter().map(|e| e.time).collect();
+        let expected: Vec<f64> = onsets
+            .iter()
+            .map(|&o| o as f64 / sample_rate as f64)
+            .collect();
+
+        assert_float_eq!(
+            &times[..],
+            &expected[..],
+            abs_all <= 1. / sample_rate as f64
+        );
+    }
+
+    #[test]
+    fn test_min_interval() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 48_000, sample_rate);
+
+        let options = OnsetDetectorOptions {
+            min_interval: 0.45,
+            ..OnsetDetectorOptions::default()
+        };
+        let detector = OnsetDetectorNode::new(&context, options);
+        detector.connect(&context.destination());
+
+        let events = Arc::new(Mutex::new(vec![]));
+        let events_clone = Arc::clone(&events);
+        detector.set_ononset(move |event| events_clone.lock().unwrap().push(event));
+
+        // clicks every 100ms
+        let mut signal = vec![0.; 48_000];
+        (0..10).for_each(|i| signal[i * 4_800 + 1_000] = 1.);
+
+        let buffer = AudioBuffer::from(vec![signal], sample_rate);
+        let mut src = context.create_buffer_source();
+        src.set_buffer(buffer);
+        src.connect(&detector);
+        src.start();
+
+        let _ = context.start_rendering_sync();
+
+        let events = events.lock().unwrap();
+        assert_eq!(events.len(), 2);
+        assert_float_eq!(events[0].time, 1_000. / 48_000., abs <= 1e-9);
+        assert_float_eq!(events[1].time, 25_000. / 48_000., abs <= 1e-9);
+    }
+}
@@ -1,16 +1,21 @@
 use std::any::Any;
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
 use std::f32::consts::PI;
-use std::sync::{Mutex, OnceLock};
+use std::path::Path;
+use std::sync::{Arc, Mutex, OnceLock};
 
 use float_eq::float_eq;
 use hrtf::{HrirSphere, HrtfContext, HrtfProcessor, Vec3};
 
+use crate::buffer::AudioBuffer;
 use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
-use crate::param::{AudioParam, AudioParamDescriptor};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
 use crate::render::{
     AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
 };
+use crate::spatial::ShoeboxRoom;
 use crate::RENDER_QUANTUM_SIZE;
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
@@ -31,40 +36,587 @@ pub(crate) fn assert_valid_cone_outer_gain(value: f64) {
     );
 }
 
-/// Load the HRTF processor for the given sample_rate
+/// Assert that the air absorption strength is valid
 ///
-/// The included data contains the impulse responses at 44100 Hertz, so it needs to be resampled
-/// for other values (which can easily take 100s of milliseconds). Therefore cache the result (per
-/// sample rate) in a global variable and clone it every time a new panner is created.
-pub(crate) fn load_hrtf_processor(sample_rate: u32) -> (HrtfProcessor, usize) {
-    static INSTANCE: OnceLock<Mutex<HashMap<u32, (HrtfProcessor, usize)>>> = OnceLock::new();
-    let cache = INSTANCE.get_or_init(|| Mutex::new(HashMap::new()));
-
-    // There's an upstream bug for low sample rates, so work around it by forcing sample_rate to be
-    // 27k minimum. The HRTF response will be a bit distorted but I assume you won't be using it
-    // anyway when running these low sample rates. <https://github.com/mrDIMAS/hrtf/issues/9>
-    let sample_rate = sample_rate.max(27_000);
-
-    // To avoid poisening the cache mutex, don't use the `entry()` API on HashMap
-    {
-        if let Some(value) = cache.lock().unwrap().get(&sample_rate) {
-            return value.clone();
+/// # Panics
+///
+/// This function will panic if the given value is not finite or lower than zero
+#[track_caller]
+#[inline(always)]
+fn assert_valid_air_absorption(value: f64) {
+    assert!(
+        value.is_finite() && value >= 0.,
+        "RangeError - airAbsorption must be finite and cannot be negative"
+    );
+}
+
+/// The HRIR sphere bundled with the library, IRCAM Listen subject 1003
+static DEFAULT_HRTF_DATASET: OnceLock<HrtfDataset> = OnceLock::new();
+
+/// Head-related impulse response measurement, used to create a custom [`HrtfDataset`]
+///
+/// The position follows the spherical coordinates convention of the AES SOFA format.
+#[derive(Clone, Debug, Default)]
+pub struct HrirMeasurement {
+    /// Azimuth in degrees, counter-clockwise from the front (i.e. 90 is on the left)
+    pub azimuth: f32,
+    /// Elevation in degrees, positive upwards
+    pub elevation: f32,
+    /// Impulse response of the left ear
+    pub left: Vec<f32>,
+    /// Impulse response of the right ear
+    pub right: Vec<f32>,
+}
+
+/// Set of head-related impulse responses (HRIR) used by the HRTF panning model
+///
+/// By default, the [`PannerNode`] uses the HRIR sphere bundled with the library. Custom (e.g.
+/// personalized) datasets can be loaded at runtime and selected per context with
+/// [`BaseAudioContext::set_hrtf_dataset`] or per node with [`PannerNode::set_hrtf_dataset`].
+///
+/// Datasets are loaded from the binary format of the [`hrtf`](https://crates.io/crates/hrtf)
+/// crate (cf. `resources/IRC_1003_C.bin`), from AES SOFA `SimpleFreeFieldHRIR` files with
+/// `HrtfDataset::from_sofa` (requires the `sofa` cargo feature, which links the netCDF C library
+/// to read the netCDF-4/HDF5 container), or from a set of measurements with
+/// [`HrtfDataset::from_measurements`].
+///
+/// The dataset is shallow cloned (using an `Arc` internally), the impulse responses resampled to
+/// the sample rate of the audio contexts are cached.
+#[derive(Clone)]
+pub struct HrtfDataset {
+    inner: Arc<HrtfDatasetInner>,
+}
+
+struct HrtfDatasetInner {
+    /// HRIR sphere in the binary format of the `hrtf` crate
+    resource: Cow<'static, [u8]>,
+    sample_rate: u32,
+    length: usize,
+    number_of_points: usize,
+    /// HRTF processors per sample rate
+    processors: Mutex<HashMap<u32, (HrtfProcessor, usize)>>,
+}
+
+impl std::fmt::Debug for HrtfDataset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HrtfDataset")
+            .field("sample_rate", &self.sample_rate())
+            .field("length", &self.length())
+            .field("number_of_points", &self.number_of_points())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PartialEq for HrtfDataset {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+}
+
+impl Default for HrtfDataset {
+    /// The HRIR sphere bundled with the library
+    fn default() -> Self {
+        DEFAULT_HRTF_DATASET
+            .get_or_init(|| {
+                let resource = include_bytes!("../../resources/IRC_1003_C.bin");
+                Self::from_resource(Cow::Borrowed(&resource[..])).unwrap()
+            })
+            .clone()
+    }
+}
+
+impl HrtfDataset {
+    /// Load a dataset from bytes, in the binary format of the `hrtf` crate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid HRIR sphere
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::from_resource(Cow::Owned(bytes))
+    }
+
+    /// Load a dataset from a file, in the binary format of the `hrtf` crate
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read or is not a valid HRIR sphere
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        Self::from_bytes(std::fs::read(path)?)
+    }
+
+    /// Create a dataset from a set of measurements, e.g. extracted from an AES SOFA file
+    ///
+    /// The measurement positions are projected on the unit sphere and triangulated, the
+    /// distance of the measurements is therefore ignored. Measurements sharing the same
+    /// direction (e.g. multiple azimuths at an elevation of 90 degrees) are only used once.
+    /// Impulse responses shorter than the longest one are zero padded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if
+    /// - the sample rate is zero
+    /// - the impulse responses are empty or do not have the same length for both ears
+    /// - the measurements do not span a volume (at least 4 points, not all on the same plane)
+    pub fn from_measurements(
+        sample_rate: u32,
+        measurements: &[HrirMeasurement],
+    ) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        if sample_rate == 0 {
+            return Err("sample rate of the HRIR measurements must be greater than zero".into());
+        }
+
+        if measurements
+            .iter()
+            .any(|m| m.left.is_empty() || m.left.len() != m.right.len())
+        {
+            return Err(
+                "HRIR measurements must have the same non-zero length for both ears".into(),
+            );
+        }
+
+        // Convert to the coordinate system of the HRIR sphere: x to the right, y up and the
+        // front towards negative z
+        let mut points: Vec<[f64; 3]> = vec![];
+        let mut sources: Vec<&HrirMeasurement> = vec![];
+
+        for measurement in measurements {
+            let azimuth = (measurement.azimuth as f64).to_radians();
+            let elevation = (measurement.elevation as f64).to_radians();
+            let point = [
+                -azimuth.sin() * elevation.cos(),
+                elevation.sin(),
+                -azimuth.cos() * elevation.cos(),
+            ];
+
+            let is_duplicate = points
+                .iter()
+                .any(|p| p.iter().zip(point).all(|(a, b)| (a - b).abs() < 1e-6));
+
+            if !is_duplicate {
+                points.push(point);
+                sources.push(measurement);
+            }
+        }
+
+        let triangles = convex_hull(&points)
+            .ok_or("HRIR measurements must span a volume, at least 4 non coplanar directions")?;
+
+        let length = sources.iter().map(|m| m.left.len()).max().unwrap_or(0);
+
+        // Serialize in the binary format of the `hrtf` crate
+        let mut bytes = Vec::with_capacity(20 + triangles.len() * 12 + points.len() * 8 * length);
+        bytes.extend_from_slice(b"HRIR");
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(length as u32).to_le_bytes());
+        bytes.extend_from_slice(&(points.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(triangles.len() as u32 * 3).to_le_bytes());
+
+        triangles
+            .iter()
+            .flatten()
+            .for_each(|&i| bytes.extend_from_slice(&(i as u32).to_le_bytes()));
+
+        for (point, measurement) in points.iter().zip(sources) {
+            point
+                .iter()
+                .for_each(|&v| bytes.extend_from_slice(&(v as f32).to_le_bytes()));
+
+            for ir in [&measurement.left, &measurement.right] {
+                ir.iter()
+                    .chain(std::iter::repeat(&0.))
+                    .take(length)
+                    .for_each(|v| bytes.extend_from_slice(&v.to_le_bytes()));
+            }
+        }
+
+        Self::from_bytes(bytes)
+    }
+
+    /// Load a dataset from an AES SOFA file with the `SimpleFreeFieldHRIR` convention
+    ///
+    /// The first receiver is the left ear, the second the right ear. Source positions are read in
+    /// spherical or cartesian coordinates, the listener is assumed to look to the front, i.e.
+    /// along the positive x axis of the SOFA coordinate system. See
+    /// [`HrtfDataset::from_measurements`] for how the measurements are converted.
+    ///
+    /// Note that this method is not part of the Web Audio API specification and requires the
+    /// `sofa` cargo feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, does not follow the `SimpleFreeFieldHRIR`
+    /// convention, or if the measurements are not a valid dataset.
+    #[cfg(feature = "sofa")]
+    pub fn from_sofa<P: AsRef<Path>>(path: P) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let file = netcdf::open(path)?;
+        Self::from_sofa_file(&file)
+    }
+
+    /// Load a dataset from the bytes of an AES SOFA file with the `SimpleFreeFieldHRIR`
+    /// convention
+    ///
+    /// See [`HrtfDataset::from_sofa`].
+    ///
+    /// Note that this method is not part of the Web Audio API specification and requires the
+    /// `sofa` cargo feature.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not a valid netCDF-4 file, do not follow the
+    /// `SimpleFreeFieldHRIR` convention, or if the measurements are not a valid dataset.
+    #[cfg(feature = "sofa")]
+    pub fn from_sofa_bytes(bytes: &[u8]) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let file = netcdf::open_mem(None, bytes)?;
+        Self::from_sofa_file(&file)
+    }
+
+    #[cfg(feature = "sofa")]
+    fn from_sofa_file(file: &netcdf::File) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let convention = match file
+            .attribute("SOFAConventions")
+            .map(|a| a.value())
+            .transpose()?
+        {
+            Some(netcdf::AttributeValue::Str(convention)) => convention,
+            _ => return Err("invalid SOFA file, missing SOFAConventions attribute".into()),
+        };
+        if convention != "SimpleFreeFieldHRIR" {
+            return Err(format!(
+                "unsupported SOFA convention {convention}, expected SimpleFreeFieldHRIR"
+            )
+            .into());
         }
+
+        let variable = |name: &str| {
+            file.variable(name)
+                .ok_or_else(|| format!("invalid SOFA file, missing variable {name}"))
+        };
+
+        let ir = variable("Data.IR")?;
+        let shape: Vec<_> = ir.dimensions().iter().map(|d| d.len()).collect();
+        let &[number_of_measurements, number_of_receivers, length] = &shape[..] else {
+            return Err("invalid SOFA file, Data.IR must have 3 dimensions".into());
+        };
+        let ir: Vec<f64> = ir.get_values(..)?;
+
+        let sample_rates: Vec<f64> = variable("Data.SamplingRate")?.get_values(..)?;
+
+        let positions = variable("SourcePosition")?;
+        let cartesian = match positions.attribute("Type").map(|a| a.value()).transpose()? {
+            Some(netcdf::AttributeValue::Str(kind)) => kind.eq_ignore_ascii_case("cartesian"),
+            _ => false,
+        };
+        let positions: Vec<f64> = positions.get_values(..)?;
+
+        let (sample_rate, measurements) = sofa_measurements(
+            &ir,
+            [number_of_measurements, number_of_receivers, length],
+            &sample_rates,
+            &positions,
+            cartesian,
+        )?;
+
+        Self::from_measurements(sample_rate, &measurements)
     }
 
-    // The following snippet might panic
-    let resource = include_bytes!("../../resources/IRC_1003_C.bin");
-    let hrir_sphere = HrirSphere::new(&resource[..], sample_rate).unwrap();
-    let len = hrir_sphere.len();
+    fn from_resource(resource: Cow<'static, [u8]>) -> Result<Self, Box<dyn Error + Send + Sync>> {
+        let read_u32 = |index: usize| -> Option<u32> {
+            let bytes = resource.get(4 * index..4 * index + 4)?;
+            Some(u32::from_le_bytes(bytes.try_into().unwrap()))
+        };
 
-    let interpolation_steps = 1; // TODO?
-    let samples_per_step = RENDER_QUANTUM_SIZE / interpolation_steps;
-    let processor = HrtfProcessor::new(hrir_sphere, interpolation_steps, samples_per_step);
+        if resource.get(..4) != Some(&b"HRIR"[..]) {
+            return Err("invalid HRIR sphere, missing HRIR header".into());
+        }
 
-    let value = (processor, len);
-    cache.lock().unwrap().insert(sample_rate, value.clone());
+        let header = (1..5).map(read_u32).collect::<Option<Vec<_>>>();
+        let Some(&[sample_rate, length, number_of_points, number_of_indices]) = header.as_deref()
+        else {
+            return Err("invalid HRIR sphere, truncated header".into());
+        };
 
-    value
+        let (length, number_of_points) = (length as usize, number_of_points as usize);
+        let number_of_indices = number_of_indices as usize;
+
+        let expected_size = 4 * (5 + number_of_indices + number_of_points * (3 + 2 * length));
+        if sample_rate == 0 || length == 0 || resource.len() != expected_size {
+            return Err("invalid HRIR sphere, inconsistent header".into());
+        }
+
+        let valid_indices = (5..5 + number_of_indices)
+            .all(|i| read_u32(i).is_some_and(|v| (v as usize) < number_of_points));
+        if number_of_indices == 0 || number_of_indices % 3 != 0 || !valid_indices {
+            return Err("invalid HRIR sphere, invalid triangulation".into());
+        }
+
+        // let the `hrtf` crate perform its own validation
+        HrirSphere::new(&resource[..], sample_rate).map_err(|e| format!("{e:?}"))?;
+
+        let inner = HrtfDatasetInner {
+            resource,
+            sample_rate,
+            length,
+            number_of_points,
+            processors: Mutex::new(HashMap::new()),
+        };
+
+        Ok(Self {
+            inner: Arc::new(inner),
+        })
+    }
+
+    /// Sample rate of the impulse responses
+    pub fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate
+    }
+
+    /// Length of the impulse responses, in sample-frames
+    pub fn length(&self) -> usize {
+        self.inner.length
+    }
+
+    /// Number of measurement points on the sphere
+    pub fn number_of_points(&self) -> usize {
+        self.inner.number_of_points
+    }
+
+    /// Measurement points of the dataset, with their impulse responses resampled at the given
+    /// sample rate
+    ///
+    /// Positions are unit vectors in the Web Audio coordinate system (x to the right, y up and
+    /// the front towards negative z).
+    pub(crate) fn measurements(&self, sample_rate: f32) -> Vec<([f32; 3], Vec<f32>, Vec<f32>)> {
+        let resource = &self.inner.resource;
+        let read_f32 = |index: usize| {
+            f32::from_le_bytes(resource[4 * index..4 * index + 4].try_into().unwrap())
+        };
+
+        let number_of_indices = u32::from_le_bytes(resource[16..20].try_into().unwrap()) as usize;
+        let length = self.length();
+        let stride = 3 + 2 * length;
+        let start = 5 + number_of_indices;
+
+        (0..self.number_of_points())
+            .map(|point| {
+                let offset = start + point * stride;
+                let position = [read_f32(offset), read_f32(offset + 1), read_f32(offset + 2)];
+                let norm = position.iter().map(|v| v * v).sum::<f32>().sqrt().max(1e-6);
+
+                let left = (0..length).map(|i| read_f32(offset + 3 + i)).collect();
+                let right = (0..length)
+                    .map(|i| read_f32(offset + 3 + length + i))
+                    .collect();
+
+                let mut buffer = AudioBuffer::from(vec![left, right], self.sample_rate() as f32);
+                buffer.resample(sample_rate);
+                let [left, right]: [Vec<f32>; 2] = buffer
+                    .channels()
+                    .iter()
+                    .map(|c| c.as_slice().to_vec())
+                    .collect::<Vec<_>>()
+                    .try_into()
+                    .unwrap();
+
+                (position.map(|v| v / norm), left, right)
+            })
+            .collect()
+    }
+
+    /// Load the HRTF processor for the given sample_rate
+    ///
+    /// The impulse responses need to be resampled if the sample rate differs from the one of the
+    /// dataset (which can easily take 100s of milliseconds). Therefore cache the result (per
+    /// sample rate) and clone it every time a new panner is created.
+    pub(crate) fn load_processor(&self, sample_rate: u32) -> (HrtfProcessor, usize) {
+        let cache = &self.inner.processors;
+
+        // There's an upstream bug for low sample rates, so work around it by forcing sample_rate
+        // to be 27k minimum. The HRTF response will be a bit distorted but I assume you won't be
+        // using it anyway when running these low sample rates.
+        // <https://github.com/mrDIMAS/hrtf/issues/9>
+        let sample_rate = sample_rate.max(27_000);
+
+        // To avoid poisening the cache mutex, don't use the `entry()` API on HashMap
+        {
+            if let Some(value) = cache.lock().unwrap().get(&sample_rate) {
+                return value.clone();
+            }
+        }
+
+        // The resource has been validated on creation, this should not panic
+        let hrir_sphere = HrirSphere::new(&self.inner.resource[..], sample_rate).unwrap();
+        let len = hrir_sphere.len();
+
+        // The position is k-rate for the HRTF panning model, so the processor interpolates once
+        // per render quantum. More steps would only smooth the motion within a render quantum,
+        // at the cost of one convolution per step.
+        let interpolation_steps = 1;
+        let samples_per_step = RENDER_QUANTUM_SIZE / interpolation_steps;
+        let processor = HrtfProcessor::new(hrir_sphere, interpolation_steps, samples_per_step);
+
+        let value = (processor, len);
+        cache.lock().unwrap().insert(sample_rate, value.clone());
+
+        value
+    }
+}
+
+/// Convert the arrays of a SOFA `SimpleFreeFieldHRIR` file to HRIR measurements
+///
+/// `ir` has the `[M, R, N]` shape of `Data.IR`, `positions` the `[M, 3]` (or `[1, 3]`) shape of
+/// `SourcePosition`, either spherical (azimuth and elevation in degrees, distance) or cartesian
+/// (x to the front, y to the left, z up).
+#[cfg(feature = "sofa")]
+fn sofa_measurements(
+    ir: &[f64],
+    [number_of_measurements, number_of_receivers, length]: [usize; 3],
+    sample_rates: &[f64],
+    positions: &[f64],
+    cartesian: bool,
+) -> Result<(u32, Vec<HrirMeasurement>), Box<dyn Error + Send + Sync>> {
+    if number_of_receivers != 2 {
+        return Err("invalid SOFA file, Data.IR must have 2 receivers (left and right ear)".into());
+    }
+    if length == 0 || ir.len() != number_of_measurements * number_of_receivers * length {
+        return Err("invalid SOFA file, inconsistent Data.IR size".into());
+    }
+
+    let sample_rate = match sample_rates {
+        [first, rest @ ..] if rest.iter().all(|s| s == first) => *first,
+        _ => return Err("invalid SOFA file, Data.SamplingRate must be a single value".into()),
+    };
+    if !sample_rate.is_finite() || sample_rate < 1. || sample_rate > u32::MAX as f64 {
+        return Err("invalid SOFA file, invalid Data.SamplingRate".into());
+    }
+
+    if positions.len() != 3 * number_of_measurements && positions.len() != 3 {
+        return Err(
+            "invalid SOFA file, SourcePosition must have one position per measurement".into(),
+        );
+    }
+
+    let measurements = ir
+        .chunks_exact(2 * length)
+        .enumerate()
+        .map(|(index, ir)| {
+            let offset = if positions.len() == 3 { 0 } else { 3 * index };
+            let [a, b, c] = [0, 1, 2].map(|i| positions[offset + i]);
+            let (azimuth, elevation) = if cartesian {
+                (b.atan2(a).to_degrees(), c.atan2(a.hypot(b)).to_degrees())
+            } else {
+                (a, b)
+            };
+
+            let (left, right) = ir.split_at(length);
+            HrirMeasurement {
+                azimuth: azimuth as f32,
+                elevation: elevation as f32,
+                left: left.iter().map(|&v| v as f32).collect(),
+                right: right.iter().map(|&v| v as f32).collect(),
+            }
+        })
+        .collect();
+
+    Ok((sample_rate.round() as u32, measurements))
+}
+
+/// Compute the convex hull of the given points
+///
+/// Returns the triangles of the hull, oriented counter-clockwise seen from the outside, or `None`
+/// if the points do not span a volume. Points inside the hull are ignored.
+fn convex_hull(points: &[[f64; 3]]) -> Option<Vec<[usize; 3]>> {
+    fn sub(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+        [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+    }
+    fn cross(a: [f64; 3], b: [f64; 3]) -> [f64; 3] {
+        [
+            a[1] * b[2] - a[2] * b[1],
+            a[2] * b[0] - a[0] * b[2],
+            a[0] * b[1] - a[1] * b[0],
+        ]
+    }
+    fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+        a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+    }
+    fn norm(a: [f64; 3]) -> f64 {
+        dot(a, a).sqrt()
+    }
+
+    const EPSILON: f64 = 1e-9;
+
+    // signed distance (scaled) of `p` above the plane of the triangle
+    let height = |[a, b, c]: [usize; 3], p: [f64; 3]| {
+        let normal = cross(sub(points[b], points[a]), sub(points[c], points[a]));
+        dot(normal, sub(p, points[a]))
+    };
+
+    // initial tetrahedron, as large as possible for numerical stability
+    let p0 = 0;
+    let p1 = (0..points.len())
+        .max_by(|&i, &j| {
+            let di = norm(sub(points[i], points[p0]));
+            let dj = norm(sub(points[j], points[p0]));
+            di.total_cmp(&dj)
+        })
+        .filter(|&i| norm(sub(points[i], points[p0])) > EPSILON)?;
+
+    let line_distance = |i: usize| {
+        norm(cross(
+            sub(points[p1], points[p0]),
+            sub(points[i], points[p0]),
+        ))
+    };
+    let p2 = (0..points.len())
+        .max_by(|&i, &j| line_distance(i).total_cmp(&line_distance(j)))
+        .filter(|&i| line_distance(i) > EPSILON)?;
+
+    let p3 = (0..points.len())
+        .max_by(|&i, &j| {
+            let hi = height([p0, p1, p2], points[i]).abs();
+            let hj = height([p0, p1, p2], points[j]).abs();
+            hi.total_cmp(&hj)
+        })
+        .filter(|&i| height([p0, p1, p2], points[i]).abs() > EPSILON)?;
+
+    // orient the base so that the apex is behind it
+    let (p1, p2) = if height([p0, p1, p2], points[p3]) > 0. {
+        (p2, p1)
+    } else {
+        (p1, p2)
+    };
+
+    let mut faces = vec![[p0, p1, p2], [p0, p3, p1], [p1, p3, p2], [p2, p3, p0]];
+
+    for (index, &point) in points.iter().enumerate() {
+        if [p0, p1, p2, p3].contains(&index) {
+            continue;
+        }
+
+        let (visible, hidden): (Vec<_>, Vec<_>) = faces
+            .into_iter()
+            .partition(|&face| height(face, point) > EPSILON);
+
+        faces = hidden;
+
+        if visible.is_empty() {
+            // inside the hull
+            continue;
+        }
+
+        // the horizon is made of the edges of the visible faces which are not shared with
+        // another visible face, connect them to the new point
+        let edges: HashSet<_> = visible
+            .iter()
+            .flat_map(|&[a, b, c]| [(a, b), (b, c), (c, a)])
+            .collect();
+
+        edges
+            .iter()
+            .filter(|&&(a, b)| !edges.contains(&(b, a)))
+            .for_each(|&(a, b)| faces.push([a, b, index]));
+    }
+
+    Some(faces)
 }
 
 /// Spatialization algorithm used to position the audio in 3D space
@@ -138,6 +690,16 @@ pub struct PannerOptions {
     pub cone_inner_angle: f64,
     pub cone_outer_angle: f64,
     pub cone_outer_gain: f64,
+    /// The HRIR dataset used by the HRTF panning model, defaults to the dataset of the context
+    /// (not part of the spec)
+    pub hrtf_dataset: Option<HrtfDataset>,
+    /// Strength of the air absorption, `1.` models air at 20°C and 50% humidity and `0.`
+    /// disables it (not part of the spec)
+    pub air_absorption: f64,
+    /// Initial value for the occlusion parameter (not part of the spec)
+    pub occlusion: f32,
+    /// Room simulating the early reflections of the source (not part of the spec)
+    pub room: Option<ShoeboxRoom>,
     pub audio_node_options: AudioNodeOptions,
 }
 
@@ -158,6 +720,10 @@ impl Default for PannerOptions {
             cone_inner_angle: 360.,
             cone_outer_angle: 360.,
             cone_outer_gain: 0.,
+            hrtf_dataset: None,
+            air_absorption: 0.,
+            occlusion: 0.,
+            room: None,
             audio_node_options: AudioNodeOptions {
                 channel_count: 2,
                 channel_count_mode: ChannelCountMode::ClampedMax,
@@ -177,6 +743,9 @@ enum ControlMessage {
     ConeInnerAngle(f64),
     ConeOuterAngle(f64),
     ConeOuterGain(f64),
+    AirAbsorption(f64),
+    // Box this payload - one large variant can penalize the memory layout of this enum
+    Room(Box<Option<EarlyReflections>>),
 }
 
 /// Assert that the channel count is valid for the PannerNode
@@ -339,6 +908,10 @@ pub struct PannerNode {
     max_distance: f64,
     rolloff_factor: f64,
     panning_model: PanningModelType,
+    hrtf_dataset: HrtfDataset,
+    air_absorption: f64,
+    occlusion: AudioParam,
+    room: Option<ShoeboxRoom>,
 }
 
 impl AudioNode for PannerNode {
@@ -390,6 +963,11 @@ impl PannerNode {
     /// Can panic when loading HRIR-sphere
     #[allow(clippy::missing_panics_doc)]
     pub fn new<C: BaseAudioContext>(context: &C, options: PannerOptions) -> Self {
+        let hrtf_dataset = options
+            .hrtf_dataset
+            .clone()
+            .unwrap_or_else(|| context.hrtf_dataset());
+
         let mut node = context.base().register(|registration| {
             use crate::spatial::PARAM_OPTS;
 
@@ -409,6 +987,10 @@ impl PannerNode {
                 cone_outer_gain,
                 audio_node_options: channel_config,
                 panning_model,
+                hrtf_dataset: _,
+                air_absorption,
+                occlusion,
+                room: _,
             } = options;
 
             assert!(
@@ -424,6 +1006,7 @@ impl PannerNode {
                 "RangeError - rolloffFactor cannot be negative"
             );
             assert_valid_cone_outer_gain(cone_outer_gain);
+            assert_valid_air_absorption(air_absorption);
             assert_valid_channel_count(channel_config.channel_count);
             assert_valid_channel_count_mode(channel_config.channel_count_mode);
 
@@ -448,6 +1031,19 @@ impl PannerNode {
             param_oy.set_value(orientation_y);
             param_oz.set_value(orientation_z);
 
+            // occlusion param
+            let occlusion_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.,
+                max_value: 1.,
+                default_value: 0.,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut param_occlusion, render_occlusion) =
+                context.create_audio_param(occlusion_opts, &registration);
+            param_occlusion.set_automation_rate_constrained(true);
+            param_occlusion.set_value(occlusion);
+
             let render = PannerRenderer {
                 position_x: render_px,
                 position_y: render_py,
@@ -464,6 +1060,10 @@ impl PannerNode {
                 cone_outer_gain,
                 hrtf_state: None,
                 tail_time_counter: 0,
+                air_absorption,
+                occlusion: render_occlusion,
+                distance_filter: DistanceFilter::default(),
+                reflections: None,
             };
 
             let node = PannerNode {
@@ -483,6 +1083,10 @@ impl PannerNode {
                 cone_outer_angle,
                 cone_outer_gain,
                 panning_model,
+                hrtf_dataset,
+                air_absorption,
+                occlusion: param_occlusion,
+                room: None,
             };
 
             // instruct to BaseContext to add the AudioListener if it has not already
@@ -499,6 +1103,11 @@ impl PannerNode {
         // load the HRTF sphere if requested
         node.set_panning_model(options.panning_model);
 
+        // allocate the early reflections if requested
+        if options.room.is_some() {
+            node.set_room(options.room);
+        }
+
         node
     }
 
@@ -538,6 +1147,60 @@ impl PannerNode {
         self.orientation_z.set_value(z);
     }
 
+    /// Low-pass filtering and attenuation of the source by obstacles, in the range [0, 1]
+    ///
+    /// At `1.`, the source is attenuated by 12 dB and low-passed at 500 Hz. Note that this
+    /// parameter is not part of the Web Audio API specification.
+    pub fn occlusion(&self) -> &AudioParam {
+        &self.occlusion
+    }
+
+    /// Strength of the air absorption
+    ///
+    /// Air absorption is modeled with a low-pass filter, whose cutoff frequency decreases with
+    /// the distance to the listener. `1.` models air at 20°C and 50% humidity, `0.` disables
+    /// it. Note that this attribute is not part of the Web Audio API specification.
+    pub fn air_absorption(&self) -> f64 {
+        self.air_absorption
+    }
+
+    /// Set the strength of the air absorption
+    ///
+    /// # Panics
+    ///
+    /// Panics if the provided value is not finite or negative.
+    pub fn set_air_absorption(&mut self, value: f64) {
+        assert_valid_air_absorption(value);
+        self.air_absorption = value;
+        self.registration
+            .post_message(ControlMessage::AirAbsorption(value));
+    }
+
+    /// Room simulating the early reflections of the source
+    ///
+    /// Note that this attribute is not part of the Web Audio API specification.
+    pub fn room(&self) -> Option<&ShoeboxRoom> {
+        self.room.as_ref()
+    }
+
+    /// Set the room simulating the early reflections of the source, `None` disables them
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the room is not valid, see [`ShoeboxRoom`]
+    pub fn set_room(&mut self, room: Option<ShoeboxRoom>) {
+        let reflections = room.as_ref().map(|room| {
+            room.assert_valid();
+            EarlyReflections::new(room.clone(), self.context().sample_rate())
+        });
+
+        self.room = room;
+        self.registration
+            .post_message(ControlMessage::Room(Box::new(reflections)));
+    }
+
     pub fn distance_model(&self) -> DistanceModelType {
         self.distance_model
     }
@@ -645,7 +1308,7 @@ impl PannerNode {
             PanningModelType::EqualPower => None,
             PanningModelType::HRTF => {
                 let sample_rate = self.context().sample_rate() as u32;
-                let (processor, len) = load_hrtf_processor(sample_rate);
+                let (processor, len) = self.hrtf_dataset.load_processor(sample_rate);
                 Some(HrtfState::new(processor, len))
             }
         };
@@ -654,6 +1317,24 @@ impl PannerNode {
         self.registration
             .post_message(ControlMessage::PanningModel(Box::new(hrtf_option)));
     }
+
+    /// The HRIR dataset used by the HRTF panning model (not part of the spec)
+    pub fn hrtf_dataset(&self) -> &HrtfDataset {
+        &self.hrtf_dataset
+    }
+
+    /// Update the HRIR dataset used by the HRTF panning model (not part of the spec)
+    ///
+    /// Loading a dataset at a sample rate different from the one of the context for the first
+    /// time requires resampling of the impulse responses, which can take a while.
+    pub fn set_hrtf_dataset(&mut self, dataset: HrtfDataset) {
+        self.hrtf_dataset = dataset;
+
+        // reload the HRTF processor if currently in use
+        if self.panning_model == PanningModelType::HRTF {
+            self.set_panning_model(PanningModelType::HRTF);
+        }
+    }
 }
 
 #[derive(Copy, Clone)]
@@ -680,6 +1361,153 @@ struct PannerRenderer {
     cone_outer_gain: f64,
     hrtf_state: Option<HrtfState>, // use EqualPower panning model if `None`
     tail_time_counter: usize,
+    air_absorption: f64,
+    occlusion: AudioParamId,
+    distance_filter: DistanceFilter,
+    reflections: Option<EarlyReflections>,
+}
+
+/// Delay, and left and right gains of an early reflection
+#[derive(Debug, Clone, Copy, Default)]
+struct ReflectionTap {
+    delay: f32,
+    gain_left: f32,
+    gain_right: f32,
+}
+
+/// Early reflections of the source in a [`ShoeboxRoom`], using the image-source method
+///
+/// The reflections are delayed relative to the direct sound, panned with the equal-power
+/// algorithm and added to the output of the panner. Their positions are updated once per render
+/// quantum, the delays and gains are interpolated across the render quantum.
+#[derive(Debug)]
+struct EarlyReflections {
+    room: ShoeboxRoom,
+    sample_rate: f32,
+    /// Mono input history, its length is a power of two
+    delay_line: Vec<f32>,
+    /// Index of the first frame of the current render quantum in the delay line
+    write_index: usize,
+    /// Image sources of the current render quantum
+    images: Vec<([f32; 3], f32)>,
+    /// Taps of the previous render quantum, one per image source
+    taps: Vec<ReflectionTap>,
+    /// Longest delay (in samples) of a reflection, the tail time of the reflections
+    max_delay: usize,
+}
+
+impl EarlyReflections {
+    fn new(room: ShoeboxRoom, sample_rate: f32) -> Self {
+        let max_delay =
+            (room.max_image_distance() / crate::spatial::SPEED_OF_SOUND * sample_rate).ceil();
+        let max_delay = max_delay as usize;
+        let number_of_images = room.max_image_sources();
+
+        Self {
+            room,
+            sample_rate,
+            delay_line: vec![0.; (max_delay + 2 + RENDER_QUANTUM_SIZE).next_power_of_two()],
+            write_index: 0,
+            images: Vec::with_capacity(number_of_images),
+            taps: vec![ReflectionTap::default(); number_of_images],
+            max_delay,
+        }
+    }
+}
+
+/// Attenuation of the high frequencies by air at 20°C and 50% humidity, in dB per meter at 1 kHz
+///
+/// The attenuation grows with the square of the frequency.
+const AIR_ABSORPTION_DB_PER_METER: f32 = 0.0016;
+
+/// Attenuation and cutoff frequency of the occlusion low-pass at full occlusion
+const OCCLUSION_DB: f32 = -12.;
+const OCCLUSION_CUTOFF: f32 = 500.;
+
+/// Air absorption and occlusion filtering, applied to the input of the panner
+///
+/// Both effects are one-pole low-pass filters in series, the occlusion also applies a gain.
+#[derive(Debug)]
+struct DistanceFilter {
+    /// Filter memory of the air absorption and occlusion stages, per input channel
+    state: [[f32; 2]; 2],
+    /// Occlusion gain of the previous render quantum, to ramp between quanta
+    gain: f32,
+}
+
+impl Default for DistanceFilter {
+    fn default() -> Self {
+        Self {
+            state: [[0.; 2]; 2],
+            gain: 1.,
+        }
+    }
+}
+
+impl DistanceFilter {
+    /// Coefficient of a one-pole low-pass, the filter is transparent for a coefficient of 1
+    fn coefficient(cutoff: f32, sample_rate: f32) -> f32 {
+        if cutoff >= sample_rate / 2. {
+            1.
+        } else {
+            1. - (-2. * PI * cutoff / sample_rate).exp()
+        }
+    }
+
+    fn process(
+        &mut self,
+        buffer: &mut AudioRenderQuantum,
+        air_absorption: f32,
+        distance: f32,
+        occlusion: f32,
+        sample_rate: f32,
+    ) {
+        // cutoff frequency where the air absorption reaches 3 dB
+        let air_cutoff = if air_absorption * distance > 0. {
+            1000. * (3. / (AIR_ABSORPTION_DB_PER_METER * air_absorption * distance)).sqrt()
+        } else {
+            f32::INFINITY
+        };
+        let occlusion_cutoff = if occlusion > 0. {
+            20000. * (OCCLUSION_CUTOFF / 20000.).powf(occlusion)
+        } else {
+            f32::INFINITY
+        };
+        let coefficients = [
+            Self::coefficient(air_cutoff, sample_rate),
+            Self::coefficient(occlusion_cutoff, sample_rate),
+        ];
+
+        let gain = 10_f32.powf(OCCLUSION_DB * occlusion / 20.);
+        let gain_step = (gain - self.gain) / RENDER_QUANTUM_SIZE as f32;
+
+        buffer
+            .channels_mut()
+            .iter_mut()
+            .zip(self.state.iter_mut())
+            .for_each(|(channel, state)| {
+                channel.iter_mut().enumerate().for_each(|(i, sample)| {
+                    let mut value = *sample;
+
+                    state
+                        .iter_mut()
+                        .zip(coefficients)
+                        .filter(|(_, coefficient)| *coefficient < 1.)
+                        .for_each(|(memory, coefficient)| {
+                            *memory += coefficient * (value - *memory);
+                            value = *memory;
+                        });
+
+                    *sample = value * (self.gain + gain_step * (i + 1) as f32);
+                });
+            });
+
+        self.gain = gain;
+    }
+
+    fn reset(&mut self) {
+        *self = Self::default();
+    }
 }
 
 impl AudioProcessor for PannerRenderer {
@@ -688,7 +1516,7 @@ impl AudioProcessor for PannerRenderer {
         inputs: &[AudioRenderQuantum],
         outputs: &mut [AudioRenderQuantum],
         params: AudioParamValues<'_>,
-        _scope: &AudioWorkletGlobalScope,
+        scope: &AudioWorkletGlobalScope,
     ) -> bool {
         // Single input/output node
         let input = &inputs[0];
@@ -702,14 +1530,51 @@ impl AudioProcessor for PannerRenderer {
                 None => false,
                 Some(hrtf_state) => hrtf_state.tail_time_samples() > self.tail_time_counter,
             };
-            if !tail_time {
+            // early reflections have tail time equal to their longest delay
+            let reflections_tail_time = self
+                .reflections
+                .as_ref()
+                .is_some_and(|reflections| reflections.max_delay > self.tail_time_counter);
+            if !tail_time && !reflections_tail_time {
                 output.make_silent();
                 return false;
             }
 
             self.tail_time_counter += RENDER_QUANTUM_SIZE;
+        } else {
+            self.tail_time_counter = 0;
         }
 
+        // air absorption and occlusion filtering
+        let occlusion = params.get(&self.occlusion)[0];
+        let filtered;
+        let input = if self.air_absorption > 0. || occlusion > 0. || self.distance_filter.gain != 1.
+        {
+            let [listener_x, listener_y, listener_z, ..] = params.listener_params();
+            let distance = crate::spatial::distance(
+                [
+                    params.get(&self.position_x)[0],
+                    params.get(&self.position_y)[0],
+                    params.get(&self.position_z)[0],
+                ],
+                [listener_x[0], listener_y[0], listener_z[0]],
+            );
+
+            let mut buffer = input.clone();
+            self.distance_filter.process(
+                &mut buffer,
+                self.air_absorption as f32,
+                distance,
+                occlusion,
+                scope.sample_rate,
+            );
+            filtered = buffer;
+            &filtered
+        } else {
+            self.distance_filter.reset();
+            input
+        };
+
         // for borrow reasons, take the hrtf_state out of self
         let mut hrtf_state = self.hrtf_state.take();
 
@@ -899,8 +1764,40 @@ impl AudioProcessor for PannerRenderer {
         // put the hrtf_state back into self (borrow reasons)
         self.hrtf_state = hrtf_state;
 
-        // tail time only for HRTF panning
-        self.hrtf_state.is_some()
+        // add the early reflections, for borrow reasons take them out of self
+        if let Some(mut reflections) = self.reflections.take() {
+            let [listener_position_x, listener_position_y, listener_position_z, listener_forward_x, listener_forward_y, listener_forward_z, listener_up_x, listener_up_y, listener_up_z] =
+                params.listener_params();
+
+            let source_position = [
+                params.get(&self.position_x)[0],
+                params.get(&self.position_y)[0],
+                params.get(&self.position_z)[0],
+            ];
+            let listener_position = [
+                listener_position_x[0],
+                listener_position_y[0],
+                listener_position_z[0],
+            ];
+            let listener_forward = [
+                listener_forward_x[0],
+                listener_forward_y[0],
+                listener_forward_z[0],
+            ];
+            let listener_up = [listener_up_x[0], listener_up_y[0], listener_up_z[0]];
+
+            self.process_reflections(
+                &mut reflections,
+                input,
+                output,
+                source_position,
+                [listener_position, listener_forward, listener_up],
+            );
+            self.reflections = Some(reflections);
+        }
+
+        // tail time only for HRTF panning and early reflections
+        self.hrtf_state.is_some() || self.reflections.is_some()
     }
 
     fn onmessage(&mut self, msg: &mut dyn Any) {
@@ -913,6 +1810,8 @@ impl AudioProcessor for PannerRenderer {
                 ControlMessage::ConeInnerAngle(value) => self.cone_inner_angle = *value,
                 ControlMessage::ConeOuterAngle(value) => self.cone_outer_angle = *value,
                 ControlMessage::ConeOuterGain(value) => self.cone_outer_gain = *value,
+                ControlMessage::AirAbsorption(value) => self.air_absorption = *value,
+                ControlMessage::Room(value) => self.reflections = value.take(),
                 ControlMessage::PanningModel(value) => self.hrtf_state = value.take(),
             }
 
@@ -924,6 +1823,104 @@ impl AudioProcessor for PannerRenderer {
 }
 
 impl PannerRenderer {
+    fn process_reflections(
+        &self,
+        reflections: &mut EarlyReflections,
+        input: &AudioRenderQuantum,
+        output: &mut AudioRenderQuantum,
+        source_position: [f32; 3],
+        [listener_position, listener_forward, listener_up]: [[f32; 3]; 3],
+    ) {
+        let EarlyReflections {
+            room,
+            sample_rate,
+            delay_line,
+            write_index,
+            images,
+            taps,
+            max_delay,
+        } = reflections;
+
+        // write the input, mixed down to mono, in the delay line
+        let mask = delay_line.len() - 1;
+        let gain = 1. / input.number_of_channels() as f32;
+        for i in 0..RENDER_QUANTUM_SIZE {
+            let mono: f32 = input.channels().iter().map(|c| c[i]).sum();
+            delay_line[(*write_index + i) & mask] = gain * mono;
+        }
+
+        // no reflections if the source or the listener is out of the room
+        if room.contains(source_position) && room.contains(listener_position) {
+            room.image_sources(source_position, images);
+        } else {
+            images.clear();
+        }
+
+        let direct_distance = crate::spatial::distance(source_position, listener_position);
+        output.set_number_of_channels(2);
+        let [left, right] = output.stereo_mut();
+
+        for (index, previous) in taps.iter_mut().enumerate() {
+            let target = match images.get(index) {
+                Some(&(position, reflection_gain)) => {
+                    let distance = crate::spatial::distance(position, listener_position);
+                    let delay = (distance - direct_distance) / crate::spatial::SPEED_OF_SOUND
+                        * *sample_rate;
+
+                    let (azimuth, _) = crate::spatial::azimuth_and_elevation(
+                        position,
+                        listener_position,
+                        listener_forward,
+                        listener_up,
+                    );
+                    let (gain_left, gain_right) = equal_power_gains(azimuth);
+                    let gain = reflection_gain * self.dist_gain(position, listener_position);
+
+                    ReflectionTap {
+                        delay: delay.clamp(0., *max_delay as f32),
+                        gain_left: gain * gain_left,
+                        gain_right: gain * gain_right,
+                    }
+                }
+                // fade out and keep the previous delay
+                None => ReflectionTap {
+                    delay: previous.delay,
+                    ..ReflectionTap::default()
+                },
+            };
+
+            if previous.gain_left == 0.
+                && previous.gain_right == 0.
+                && target.gain_left == 0.
+                && target.gain_right == 0.
+            {
+                *previous = target;
+                continue;
+            }
+
+            let step = 1. / RENDER_QUANTUM_SIZE as f32;
+            for (i, (l, r)) in left.iter_mut().zip(right.iter_mut()).enumerate() {
+                let t = (i + 1) as f32 * step;
+                let interpolate = |from: f32, to: f32| from + (to - from) * t;
+
+                let delay = interpolate(previous.delay, target.delay);
+                let position = (*write_index + i) as f32 - delay + delay_line.len() as f32;
+                let index = position.floor();
+                let fraction = position - index;
+                let index = index as usize;
+                let sample = delay_line[index & mask] * (1. - fraction)
+                    + delay_line[(index + 1) & mask] * fraction;
+
+                *l += interpolate(previous.gain_left, target.gain_left) * sample;
+                *r += interpolate(previous.gain_right, target.gain_right) * sample;
+            }
+
+            *previous = target;
+        }
+
+        *write_index = (*write_index + RENDER_QUANTUM_SIZE) & mask;
+    }
+
     fn cone_gain(
         &self,
         source_position: [f32; 3],
@@ -985,6 +1982,20 @@ impl PannerRenderer {
     }
 }
 
+/// Left and right gains of a mono source at the given azimuth, with the equal-power algorithm
+fn equal_power_gains(azimuth: f32) -> (f32, f32) {
+    // clamp azimuth to range of [-180, 180], then wrap to range [-90, 90]
+    let mut azimuth = azimuth.clamp(-180., 180.);
+    if azimuth < -90. {
+        azimuth = -180. - azimuth;
+    } else if azimuth > 90. {
+        azimuth = 180. - azimuth;
+    }
+
+    let x = (azimuth + 90.) / 180.;
+    ((x * PI / 2.).cos(), (x * PI / 2.).sin())
+}
+
 fn apply_mono_to_stereo_gain(spatial_params: SpatialParams, l: &mut f32, r: &mut f32) {
     let SpatialParams {
         dist_gain,
@@ -1267,4 +2278,442 @@ mod tests {
         let right = output.channel_data(1).as_slice();
         assert!(right[128..256].iter().any(|v| *v >= 1E-6));
     }
+
+    #[test]
+    fn test_hrtf_dataset_default() {
+        let dataset = HrtfDataset::default();
+        assert_eq!(dataset.sample_rate(), 44100);
+        assert_eq!(dataset.length(), 512);
+        assert_eq!(dataset.number_of_points(), 187);
+
+        // shared instance
+        assert_eq!(dataset, HrtfDataset::default());
+
+        let from_file = HrtfDataset::from_file("resources/IRC_1003_C.bin").unwrap();
+        assert_eq!(from_file.number_of_points(), 187);
+        assert_ne!(from_file, dataset);
+    }
+
+    #[test]
+    fn test_hrtf_dataset_invalid() {
+        assert!(HrtfDataset::from_bytes(vec![]).is_err());
+        assert!(HrtfDataset::from_bytes(b"RIFF0000".to_vec()).is_err());
+        assert!(HrtfDataset::from_file("resources/does-not-exist.bin").is_err());
+
+        // truncated
+        let mut bytes = std::fs::read("resources/IRC_1003_C.bin").unwrap();
+        bytes.truncate(bytes.len() - 4);
+        assert!(HrtfDataset::from_bytes(bytes).is_err());
+
+        // coplanar measurements
+        let measurements: Vec<_> = (0..8)
+            .map(|i| HrirMeasurement {
+                azimuth: i as f32 * 45.,
+                elevation: 0.,
+                left: vec![1.],
+                right: vec![1.],
+            })
+            .collect();
+        assert!(HrtfDataset::from_measurements(44100, &measurements).is_err());
+    }
+
+    #[test]
+    fn test_convex_hull() {
+        // points of the bundled dataset, its triangulation is the convex hull
+        let resource = include_bytes!("../../resources/IRC_1003_C.bin");
+        let read = |i: usize| u32::from_le_bytes(resource[4 * i..4 * i + 4].try_into().unwrap());
+        let (length, count, indices) = (read(2) as usize, read(3) as usize, read(4) as usize);
+
+        let points: Vec<[f64; 3]> = (0..count)
+            .map(|i| {
+                let offset = 5 + indices + i * (3 + 2 * length);
+                let coord = |j| f32::from_bits(read(offset + j)) as f64;
+                [coord(0), coord(1), coord(2)]
+            })
+            .collect();
+
+        let faces = convex_hull(&points).unwrap();
+        assert_eq!(faces.len() * 3, indices);
+
+        // all faces are oriented towards the outside
+        faces.iter().for_each(|&[a, b, c]| {
+            let (a, b, c) = (points[a], points[b], points[c]);
+            let u = [b[0] - a[0], b[1] - a[1], b[2] - a[2]];
+            let v = [c[0] - a[0], c[1] - a[1], c[2] - a[2]];
+            let normal = [
+                u[1] * v[2] - u[2] * v[1],
+                u[2] * v[0] - u[0] * v[2],
+                u[0] * v[1] - u[1] * v[0],
+            ];
+            let outward: f64 = (0..3).map(|i| normal[i] * (a[i] + b[i] + c[i])).sum();
+            assert!(outward > 0.);
+        });
+    }
+
+    fn lateral_measurements() -> Vec<HrirMeasurement> {
+        // the louder ear is on the side of the measurement, 30 degrees grid
+        let mut measurements = vec![];
+
+        for elevation in (-2..=3).map(|e| e as f32 * 30.) {
+            for azimuth in (0..12).map(|a| a as f32 * 30.) {
+                let left = 0.5 * (1. + azimuth.to_radians().sin() * elevation.to_radians().cos());
+                let mut ir_left = vec![0.; 32];
+                ir_left[0] = left;
+                let mut ir_right = vec![0.; 32];
+                ir_right[0] = 1. - left;
+
+                measurements.push(HrirMeasurement {
+                    azimuth,
+                    elevation,
+                    left: ir_left,
+                    right: ir_right,
+                });
+            }
+        }
+
+        measurements
+    }
+
+    fn lateral_dataset() -> HrtfDataset {
+        HrtfDataset::from_measurements(44100, &lateral_measurements()).unwrap()
+    }
+
+    #[test]
+    fn test_hrtf_dataset_from_measurements() {
+        let dataset = lateral_dataset();
+        assert_eq!(dataset.sample_rate(), 44100);
+        assert_eq!(dataset.length(), 32);
+        // all azimuths at 90 degrees elevation share the same direction
+        assert_eq!(dataset.number_of_points(), 5 * 12 + 1);
+
+        for (position_x, louder_channel) in [(1., 1), (-1., 0)] {
+            let sample_rate = 44100.;
+            let mut context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, sample_rate);
+
+            let input = AudioBuffer::from(vec![vec![1.; RENDER_QUANTUM_SIZE]], sample_rate);
+            let mut src = context.create_buffer_source();
+            src.set_buffer(input);
+            src.start();
+
+            let options = PannerOptions {
+                panning_model: PanningModelType::HRTF,
+                hrtf_dataset: Some(dataset.clone()),
+                ..PannerOptions::default()
+            };
+            let panner = PannerNode::new(&context, options);
+            assert_eq!(panner.hrtf_dataset(), &dataset);
+            panner.position_x().set_value(position_x);
+
+            src.connect(&panner);
+            panner.connect(&context.destination());
+
+            let output = context.start_rendering_sync();
+            let energy =
+                |c: usize| -> f32 { output.get_channel_data(c).iter().map(|v| v * v).sum() };
+
+            assert!(energy(louder_channel) > 2. * energy(1 - louder_channel));
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "sofa")]
+    fn test_sofa_measurements() {
+        // [M, R, N] impulse responses, the left ear is louder than the right one
+        let ir: Vec<f64> = (0..4)
+            .flat_map(|m| [vec![1., m as f64], vec![0.5, 0.]])
+            .flatten()
+            .collect();
+        let spherical = [0., 0., 1., 90., 0., 1., 0., 90., 1., 45., -30., 1.5];
+
+        let (sample_rate, measurements) =
+            sofa_measurements(&ir, [4, 2, 2], &[48000.], &spherical, false).unwrap();
+        assert_eq!(sample_rate, 48000);
+        assert_eq!(measurements.len(), 4);
+        assert_eq!(measurements[3].azimuth, 45.);
+        assert_eq!(measurements[3].elevation, -30.);
+        assert_eq!(measurements[2].left, [1., 2.]);
+        assert_eq!(measurements[2].right, [0.5, 0.]);
+
+        // same positions in cartesian coordinates (x to the front, y to the left, z up)
+        let cartesian: Vec<f64> = spherical
+            .chunks_exact(3)
+            .flat_map(|p| {
+                let (azimuth, elevation) = (p[0].to_radians(), p[1].to_radians());
+                let xy = p[2] * elevation.cos();
+                [
+                    xy * azimuth.cos(),
+                    xy * azimuth.sin(),
+                    p[2] * elevation.sin(),
+                ]
+            })
+            .collect();
+        let (_, converted) =
+            sofa_measurements(&ir, [4, 2, 2], &[48000.], &cartesian, true).unwrap();
+        for (a, b) in measurements.iter().zip(&converted) {
+            assert_float_eq!(a.azimuth, b.azimuth, abs <= 1e-4);
+            assert_float_eq!(a.elevation, b.elevation, abs <= 1e-4);
+        }
+
+        // invalid layouts
+        assert!(sofa_measurements(&ir, [2, 4, 2], &[48000.], &spherical, false).is_err());
+        assert!(sofa_measurements(&ir, [4, 2, 2], &[44100., 48000.], &spherical, false).is_err());
+        assert!(sofa_measurements(&ir, [4, 2, 2], &[48000.], &spherical[..6], false).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "sofa")]
+    fn test_from_sofa() {
+        let measurements = lateral_measurements();
+        let ir: Vec<f64> = measurements
+            .iter()
+            .flat_map(|m| m.left.iter().chain(m.right.iter()))
+            .map(|&v| f64::from(v))
+            .collect();
+        let positions: Vec<f64> = measurements
+            .iter()
+            .flat_map(|m| [f64::from(m.azimuth), f64::from(m.elevation), 1.])
+            .collect();
+
+        let path = std::env::temp_dir().join("web-audio-api-test-from-sofa.sofa");
+        {
+            let mut file = netcdf::create(&path).unwrap();
+            file.add_attribute("SOFAConventions", "SimpleFreeFieldHRIR")
+                .unwrap();
+            file.add_dimension("I", 1).unwrap();
+            file.add_dimension("C", 3).unwrap();
+            file.add_dimension("M", measurements.len()).unwrap();
+            file.add_dimension("R", 2).unwrap();
+            file.add_dimension("N", 32).unwrap();
+
+            let mut variable = file
+                .add_variable::<f64>("Data.IR", &["M", "R", "N"])
+                .unwrap();
+            variable.put_values(&ir, ..).unwrap();
+
+            let mut variable = file
+                .add_variable::<f64>("Data.SamplingRate", &["I"])
+                .unwrap();
+            variable.put_values(&[44100.], ..).unwrap();
+
+            let mut variable = file
+                .add_variable::<f64>("SourcePosition", &["M", "C"])
+                .unwrap();
+            variable.put_attribute("Type", "spherical").unwrap();
+            variable.put_values(&positions, ..).unwrap();
+        }
+
+        let from_file = HrtfDataset::from_sofa(&path).unwrap();
+        let bytes = std::fs::read(&path).unwrap();
+        let from_bytes = HrtfDataset::from_sofa_bytes(&bytes).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let expected = lateral_dataset();
+        for dataset in [from_file, from_bytes] {
+            assert_eq!(dataset.sample_rate(), expected.sample_rate());
+            assert_eq!(dataset.length(), expected.length());
+            assert_eq!(dataset.number_of_points(), expected.number_of_points());
+        }
+
+        // only the SimpleFreeFieldHRIR convention is supported
+        let path = std::env::temp_dir().join("web-audio-api-test-from-sofa-convention.sofa");
+        {
+            let mut file = netcdf::create(&path).unwrap();
+            file.add_attribute("SOFAConventions", "GeneralFIR").unwrap();
+        }
+        assert!(HrtfDataset::from_sofa(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_hrtf_dataset_per_context_and_panner() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        assert_eq!(context.hrtf_dataset(), HrtfDataset::default());
+
+        let dataset = lateral_dataset();
+        context.set_hrtf_dataset(dataset.clone());
+        assert_eq!(context.hrtf_dataset(), dataset);
+
+        let mut panner = context.create_panner();
+        assert_eq!(panner.hrtf_dataset(), &dataset);
+
+        panner.set_panning_model(PanningModelType::HRTF);
+        panner.set_hrtf_dataset(HrtfDataset::default());
+        assert_eq!(panner.hrtf_dataset(), &HrtfDataset::default());
+
+        // the context dataset is left unchanged
+        assert_eq!(context.hrtf_dataset(), dataset);
+    }
+
+    /// Render a sine through a panner 100 meters in front of the listener, returns the energy
+    /// of the left channel over the last 100 ms
+    fn render_distance_filtered(frequency: f32, air_absorption: f64, occlusion: f32) -> f32 {
+        let sample_rate = 44100.;
+        let mut context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 64, sample_rate);
+
+        let options = PannerOptions {
+            position_z: -100.,
+            air_absorption,
+            occlusion,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        panner.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(frequency);
+        osc.connect(&panner);
+        osc.start();
+
+        let output = context.start_rendering_sync();
+        output.get_channel_data(0)[RENDER_QUANTUM_SIZE * 64 - 4410..]
+            .iter()
+            .map(|v| v * v)
+            .sum()
+    }
+
+    #[test]
+    fn test_distance_filter_defaults() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+
+        assert_eq!(panner.air_absorption(), 0.);
+        assert_float_eq!(panner.occlusion().value(), 0., abs <= 0.);
+        assert_eq!(panner.occlusion().automation_rate(), AutomationRate::K);
+
+        panner.set_air_absorption(2.);
+        assert_eq!(panner.air_absorption(), 2.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_air_absorption_negative() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+        panner.set_air_absorption(-1.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_air_absorption_nan() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+        panner.set_air_absorption(f64::NAN);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_air_absorption_infinite_option() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let options = PannerOptions {
+            air_absorption: f64::INFINITY,
+            ..PannerOptions::default()
+        };
+        let _ = PannerNode::new(&context, options);
+    }
+
+    #[test]
+    fn test_air_absorption() {
+        // high frequencies are absorbed
+        let dry = render_distance_filtered(10000., 0., 0.);
+        let absorbed = render_distance_filtered(10000., 1., 0.);
+        assert!(absorbed < 0.25 * dry);
+
+        // low frequencies are preserved
+        let dry = render_distance_filtered(100., 0., 0.);
+        let absorbed = render_distance_filtered(100., 1., 0.);
+        assert_float_eq!(absorbed, dry, r2nd <= 0.01);
+    }
+
+    #[test]
+    fn test_occlusion() {
+        // full occlusion attenuates low frequencies by 12 dB
+        let dry = render_distance_filtered(50., 0., 0.);
+        let occluded = render_distance_filtered(50., 0., 1.);
+        assert_float_eq!(occluded / dry, 10_f32.powf(-24. / 20.), r2nd <= 0.05);
+
+        // and high frequencies much more
+        let dry = render_distance_filtered(5000., 0., 0.);
+        let occluded = render_distance_filtered(5000., 0., 1.);
+        assert!(occluded < 0.01 * dry);
+    }
+
+    /// Render an impulse through a panner in the given room, returns the left and right
+    /// channels
+    fn render_room(position: [f32; 3], room: Option<ShoeboxRoom>) -> AudioBuffer {
+        let sample_rate = 44100.;
+        let mut context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE * 8, sample_rate);
+
+        let options = PannerOptions {
+            position_x: position[0],
+            position_y: position[1],
+            position_z: position[2],
+            room,
+            ..PannerOptions::default()
+        };
+        let panner = PannerNode::new(&context, options);
+        panner.connect(&context.destination());
+
+        let input = AudioBuffer::from(vec![vec![1.]], sample_rate);
+        let mut src = AudioBufferSourceNode::new(&context, AudioBufferSourceOptions::default());
+        src.set_buffer(input);
+        src.connect(&panner);
+        src.start();
+
+        context.start_rendering_sync()
+    }
+
+    #[test]
+    fn test_room() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+        assert_eq!(panner.room(), None);
+
+        panner.set_room(Some(ShoeboxRoom::default()));
+        assert_eq!(panner.room(), Some(&ShoeboxRoom::default()));
+
+        panner.set_room(None);
+        assert_eq!(panner.room(), None);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_room_invalid() {
+        let context = OfflineAudioContext::new(2, RENDER_QUANTUM_SIZE, 44100.);
+        let mut panner = context.create_panner();
+        panner.set_room(Some(ShoeboxRoom {
+            absorption: [2.; 6],
+            ..ShoeboxRoom::default()
+        }));
+    }
+
+    #[test]
+    fn test_room_early_reflections() {
+        let position = [2., 0., -1.];
+        let energy = |samples: &[f32]| samples.iter().map(|v| v * v).sum::<f32>();
+
+        // without room, only the direct sound
+        let output = render_room(position, None);
+        assert!(energy(&output.get_channel_data(1)[..1]) > 0.);
+        assert_eq!(energy(&output.get_channel_data(0)[1..]), 0.);
+        assert_eq!(energy(&output.get_channel_data(1)[1..]), 0.);
+
+        // the floor and ceiling reflections travel 1.506 m more than the direct sound
+        let room = ShoeboxRoom::default();
+        let first_reflection = (1.506 / crate::spatial::SPEED_OF_SOUND * 44100.) as usize;
+
+        let output = render_room(position, Some(room.clone()));
+        let left = output.get_channel_data(0);
+        let right = output.get_channel_data(1);
+        assert!(energy(&right[..1]) > 0.);
+        assert_eq!(energy(&left[1..first_reflection - 1]), 0.);
+        assert_eq!(energy(&right[1..first_reflection - 1]), 0.);
+        assert!(energy(&right[first_reflection..first_reflection + 3]) > 0.);
+        // the reflections on the left wall reach the left ear, after the end of the input
+        assert!(energy(&left[RENDER_QUANTUM_SIZE * 2..]) > 0.);
+
+        // no reflections when the source is out of the room
+        let output = render_room([5., 0., -1.], Some(room));
+        assert_eq!(energy(&output.get_channel_data(0)[1..]), 0.);
+        assert_eq!(energy(&output.get_channel_data(1)[1..]), 0.);
+    }
 }
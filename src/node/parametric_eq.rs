@@ -0,0 +1,677 @@
+//! The parametric equalizer control and renderer parts
+use std::any::Any;
+
+use arrayvec::ArrayVec;
+use num_complex::Complex;
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::{MAX_CHANNELS, RENDER_QUANTUM_SIZE};
+
+use super::biquad_filter::{calculate_coefs, Coefficients};
+use super::{AudioNode, AudioNodeOptions, BiquadFilterType, ChannelConfig};
+
+/// Options of a band of a [`ParametricEqNode`]
+#[derive(Clone, Debug)]
+pub struct ParametricEqBandOptions {
+    /// Filter type of the band
+    pub type_: BiquadFilterType,
+    /// Frequency of the band
+    pub frequency: f32,
+    /// Boost/attenuation of the band (dB)
+    pub gain: f32,
+    /// Quality factor of the band
+    pub q: f32,
+}
+
+impl Default for ParametricEqBandOptions {
+    fn default() -> Self {
+        Self {
+            type_: BiquadFilterType::Peaking,
+            frequency: 1000.,
+            gain: 0.,
+            q: 1.,
+        }
+    }
+}
+
+/// Options for constructing a [`ParametricEqNode`]
+#[derive(Clone, Debug)]
+pub struct ParametricEqOptions {
+    /// Bands of the equalizer, applied in order
+    pub bands: Vec<ParametricEqBandOptions>,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for ParametricEqOptions {
+    /// Four flat bands: a low shelf at 100Hz, two peaking filters at 400Hz and 2kHz and a
+    /// high shelf at 8kHz
+    fn default() -> Self {
+        let band = |type_, frequency| ParametricEqBandOptions {
+            type_,
+            frequency,
+            ..ParametricEqBandOptions::default()
+        };
+
+        Self {
+            bands: vec![
+                band(BiquadFilterType::Lowshelf, 100.),
+                band(BiquadFilterType::Peaking, 400.),
+                band(BiquadFilterType::Peaking, 2000.),
+                band(BiquadFilterType::Highshelf, 8000.),
+            ],
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// A band of a [`ParametricEqNode`]
+#[derive(Debug)]
+pub struct ParametricEqBand {
+    /// Current filter type
+    type_: BiquadFilterType,
+    /// frequency of the band - its impact on the frequency response of the band
+    /// depends on the `BiquadFilterType`
+    frequency: AudioParam,
+    /// boost/attenuation (dB) - its impact on the frequency response of the band
+    /// depends on the `BiquadFilterType`
+    gain: AudioParam,
+    /// quality factor - its impact on the frequency response of the band
+    /// depends on the `BiquadFilterType`
+    q: AudioParam,
+}
+
+impl ParametricEqBand {
+    /// Returns the filter type of the band
+    #[must_use]
+    pub fn type_(&self) -> BiquadFilterType {
+        self.type_
+    }
+
+    /// Returns the frequency audio parameter
+    #[must_use]
+    pub fn frequency(&self) -> &AudioParam {
+        &self.frequency
+    }
+
+    /// Returns the gain audio parameter
+    #[must_use]
+    pub fn gain(&self) -> &AudioParam {
+        &self.gain
+    }
+
+    /// Returns the Q audio parameter
+    #[must_use]
+    pub fn q(&self) -> &AudioParam {
+        &self.q
+    }
+
+    /// Coefficients of the band for the current values of the parameters
+    fn coefficients(&self, sample_rate: f32) -> Coefficients {
+        calculate_coefs(
+            self.type_,
+            f64::from(sample_rate),
+            f64::from(self.frequency.value()),
+            f64::from(self.gain.value()),
+            f64::from(self.q.value()),
+        )
+    }
+}
+
+/// Message changing the filter type of a band
+#[derive(Debug)]
+struct SetBandType {
+    index: usize,
+    type_: BiquadFilterType,
+}
+
+/// `ParametricEqNode` is a multi-band equalizer, made of a series of biquad filters
+///
+/// Each band behaves as a [`BiquadFilterNode`](super::BiquadFilterNode) with the same filter
+/// type, frequency, gain and Q, but all bands share the channel mixing and processing of a
+/// single node.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_parametric_eq`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{AudioContext, BaseAudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let eq = context.create_parametric_eq();
+/// eq.connect(&context.destination());
+///
+/// // boost the low end and cut the harshness around 2kHz
+/// eq.bands()[0].gain().set_value(6.);
+/// eq.bands()[2].gain().set_value(-4.);
+/// eq.bands()[2].q().set_value(2.);
+///
+/// let mut src = context.create_oscillator();
+/// src.connect(&eq);
+/// src.start();
+///
+/// // draw the curve of the equalizer
+/// let frequency_hz: Vec<f32> = (1..100).map(|i| 20. * 1000_f32.powf(i as f32 / 100.)).collect();
+/// let mut mag_response = vec![0.; frequency_hz.len()];
+/// let mut phase_response = vec![0.; frequency_hz.len()];
+/// eq.get_frequency_response(&frequency_hz, &mut mag_response, &mut phase_response);
+/// ```
+///
+#[derive(Debug)]
+pub struct ParametricEqNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    /// Bands of the equalizer
+    bands: Vec<ParametricEqBand>,
+}
+
+impl AudioNode for ParametricEqNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl ParametricEqNode {
+    /// returns a `ParametricEqNode` instance
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - parametric equalizer options
+    pub fn new<C: BaseAudioContext>(context: &C, options: ParametricEqOptions) -> Self {
+        context.base().register(move |registration| {
+            let sample_rate = context.sample_rate();
+
+            let ParametricEqOptions {
+                bands,
+                audio_node_options: channel_config,
+            } = options;
+
+            let (bands, renderers) = bands
+                .into_iter()
+                .map(|band| {
+                    let freq_options = AudioParamDescriptor {
+                        name: String::new(),
+                        min_value: 0.,
+                        max_value: sample_rate / 2.,
+                        default_value: 1000.,
+                        automation_rate: AutomationRate::A,
+                    };
+                    let (f_param, f_proc) = context.create_audio_param(freq_options, &registration);
+                    f_param.set_value(band.frequency);
+
+                    let gain_options = AudioParamDescriptor {
+                        name: String::new(),
+                        min_value: f32::MIN,
+                        max_value: 40. * f32::MAX.log10(),
+                        default_value: 0.,
+                        automation_rate: AutomationRate::A,
+                    };
+                    let (g_param, g_proc) = context.create_audio_param(gain_options, &registration);
+                    g_param.set_value(band.gain);
+
+                    let q_options = AudioParamDescriptor {
+                        name: String::new(),
+                        min_value: f32::MIN,
+                        max_value: f32::MAX,
+                        default_value: 1.,
+                        automation_rate: AutomationRate::A,
+                    };
+                    let (q_param, q_proc) = context.create_audio_param(q_options, &registration);
+                    q_param.set_value(band.q);
+
+                    let band_node = ParametricEqBand {
+                        type_: band.type_,
+                        frequency: f_param,
+                        gain: g_param,
+                        q: q_param,
+                    };
+
+                    let band_renderer = BandRenderer {
+                        type_: band.type_,
+                        frequency: f_proc,
+                        gain: g_proc,
+                        q: q_proc,
+                        xy: ArrayVec::new(),
+                    };
+
+                    (band_node, band_renderer)
+                })
+                .unzip();
+
+            let renderer = ParametricEqRenderer { bands: renderers };
+
+            let node = Self {
+                registration,
+                channel_config: channel_config.into(),
+                bands,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Returns the bands of the equalizer
+    #[must_use]
+    pub fn bands(&self) -> &[ParametricEqBand] {
+        &self.bands
+    }
+
+    /// Set the filter type of a band
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - index of the band
+    /// * `type_` - the biquad filter type (lowpass, highpass,...)
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `index` is out of bounds
+    pub fn set_band_type(&mut self, index: usize, type_: BiquadFilterType) {
+        assert!(
+            index < self.bands.len(),
+            "IndexSizeError - Invalid band index: {index} (number of bands: {})",
+            self.bands.len()
+        );
+
+        self.bands[index].type_ = type_;
+        self.registration.post_message(SetBandType { index, type_ });
+    }
+
+    /// Returns the combined frequency response of the bands for the specified frequencies
+    ///
+    /// # Arguments
+    ///
+    /// * `frequency_hz` - frequencies for which frequency response of the filter should be calculated
+    /// * `mag_response` - magnitude of the frequency response of the filter
+    /// * `phase_response` - phase of the frequency response of the filter
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if arguments' lengths don't match
+    ///
+    pub fn get_frequency_response(
+        &self,
+        frequency_hz: &[f32],
+        mag_response: &mut [f32],
+        phase_response: &mut [f32],
+    ) {
+        assert!(
+            frequency_hz.len() == mag_response.len() && mag_response.len() == phase_response.len(),
+            "InvalidAccessError - Parameter lengths must match",
+        );
+
+        let sample_rate = self.context().sample_rate();
+        let n_quist = sample_rate / 2.;
+
+        let coefs: Vec<_> = self
+            .bands
+            .iter()
+            .map(|band| band.coefficients(sample_rate))
+            .collect();
+
+        for (i, &freq) in frequency_hz.iter().enumerate() {
+            // same behavior as `BiquadFilterNode::get_frequency_response`
+            if freq < 0. || freq > n_quist {
+                mag_response[i] = f32::NAN;
+                phase_response[i] = f32::NAN;
+            } else {
+                let f = f64::from(freq / n_quist);
+                let response = coefs
+                    .iter()
+                    .fold(Complex::new(1., 0.), |acc, c| acc * c.response(f));
+
+                let (mag, phase) = response.to_polar();
+                mag_response[i] = mag as f32;
+                phase_response[i] = phase as f32;
+            }
+        }
+    }
+}
+
+/// Rendering part of a band of the equalizer
+struct BandRenderer {
+    /// `BiquadFilterType`
+    type_: BiquadFilterType,
+    frequency: AudioParamId,
+    gain: AudioParamId,
+    q: AudioParamId,
+    // keep filter state for each channel
+    xy: ArrayVec<[f64; 4], MAX_CHANNELS>,
+}
+
+/// `ParametricEqRenderer` represents the rendering part of `ParametricEqNode`
+struct ParametricEqRenderer {
+    bands: Vec<BandRenderer>,
+}
+
+impl AudioProcessor for ParametricEqRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // handle tail time
+        if input.is_silent()
+            && !self
+                .bands
+                .iter()
+                .flat_map(|band| band.xy.iter())
+                .any(|v| v.iter().copied().any(f64::is_normal))
+        {
+            // input is silent and filters history is clean
+            output.make_silent();
+            return false;
+        }
+
+        // the bands are applied in place on the output, if in tail time we
+        // should continue with previous number of channels
+        *output = input.clone();
+        let num_channels = if input.is_silent() {
+            self.bands.first().map_or(0, |band| band.xy.len())
+        } else {
+            input.number_of_channels()
+        };
+        output.set_number_of_channels(num_channels);
+
+        let sample_rate = f64::from(scope.sample_rate);
+
+        for band in self.bands.iter_mut() {
+            // @todo - handle channel change cleanly, could cause discontinuities
+            if num_channels != band.xy.len() {
+                band.xy.truncate(num_channels);
+                for _ in band.xy.len()..num_channels {
+                    band.xy.push([0.; 4]);
+                }
+            }
+
+            // get a-rate parameters
+            let type_ = band.type_;
+            let frequency = params.get(&band.frequency);
+            let gain = params.get(&band.gain);
+            let q = params.get(&band.q);
+
+            // compute first coef and fill the coef list with this value
+            let coef = calculate_coefs(
+                type_,
+                sample_rate,
+                f64::from(frequency[0]),
+                f64::from(gain[0]),
+                f64::from(q[0]),
+            );
+
+            let mut coefs_list = [coef; RENDER_QUANTUM_SIZE];
+            // if one of the params has a length of RENDER_QUANTUM_SIZE, we need
+            // to compute the coefs for each frame
+            if frequency.len() != 1 || gain.len() != 1 || q.len() != 1 {
+                coefs_list
+                    .iter_mut()
+                    .zip(frequency.iter().cycle())
+                    .zip(gain.iter().cycle())
+                    .zip(q.iter().cycle())
+                    .skip(1)
+                    .for_each(|(((coefs, &f), &g), &q)| {
+                        *coefs = calculate_coefs(
+                            type_,
+                            sample_rate,
+                            f64::from(f),
+                            f64::from(g),
+                            f64::from(q),
+                        );
+                    });
+            }
+
+            for (channel, xy) in output.channels_mut().iter_mut().zip(band.xy.iter_mut()) {
+                let [mut x1, mut x2, mut y1, mut y2] = *xy;
+
+                channel
+                    .iter_mut()
+                    .zip(coefs_list.iter())
+                    .for_each(|(s, c)| {
+                        let x = f64::from(*s);
+                        let mut y = c.b0 * x + c.b1 * x1 + c.b2 * x2 - c.a1 * y1 - c.a2 * y2;
+
+                        // flush NaN, Infinity, subnormal (and zero) to zero
+                        if !y.is_normal() {
+                            y = 0.;
+                        }
+
+                        x2 = x1;
+                        x1 = x;
+                        y2 = y1;
+                        y1 = y;
+                        *s = y as f32;
+                    });
+
+                *xy = [x1, x2, y1, y2];
+            }
+        }
+
+        true
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&SetBandType { index, type_ }) = msg.downcast_ref::<SetBandType>() {
+            self.bands[index].type_ = type_;
+            return;
+        }
+
+        log::warn!("ParametricEqRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioScheduledSourceNode, BiquadFilterNode, BiquadFilterOptions};
+
+    use super::*;
+
+    fn bands() -> Vec<ParametricEqBandOptions> {
+        vec![
+            ParametricEqBandOptions {
+                type_: BiquadFilterType::Lowshelf,
+                frequency: 200.,
+                gain: 6.,
+                q: 1.,
+            },
+            ParametricEqBandOptions {
+                type_: BiquadFilterType::Peaking,
+                frequency: 1500.,
+                gain: -9.,
+                q: 3.,
+            },
+            ParametricEqBandOptions {
+                type_: BiquadFilterType::Lowpass,
+                frequency: 8000.,
+                gain: 0.,
+                q: 0.,
+            },
+        ]
+    }
+
+    fn biquads(context: &OfflineAudioContext) -> Vec<BiquadFilterNode> {
+        bands()
+            .into_iter()
+            .map(|band| {
+                let options = BiquadFilterOptions {
+                    type_: band.type_,
+                    frequency: band.frequency,
+                    gain: band.gain,
+                    q: band.q,
+                    ..BiquadFilterOptions::default()
+                };
+                BiquadFilterNode::new(context, options)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_constructor() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let eq = context.create_parametric_eq();
+
+        assert_eq!(eq.bands().len(), 4);
+        assert_eq!(eq.bands()[0].type_(), BiquadFilterType::Lowshelf);
+        assert_eq!(eq.bands()[3].type_(), BiquadFilterType::Highshelf);
+        eq.bands().iter().for_each(|band| {
+            assert_float_eq!(band.gain().value(), 0., abs <= 0.);
+            assert_float_eq!(band.q().value(), 1., abs <= 0.);
+        });
+    }
+
+    #[test]
+    fn test_set_band_type() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut eq = context.create_parametric_eq();
+
+        eq.set_band_type(1, BiquadFilterType::Notch);
+        assert_eq!(eq.bands()[1].type_(), BiquadFilterType::Notch);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_set_band_type_out_of_bounds() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let mut eq = context.create_parametric_eq();
+        eq.set_band_type(4, BiquadFilterType::Notch);
+    }
+
+    #[test]
+    fn test_flat_by_default() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let eq = context.create_parametric_eq();
+
+        let freqs = [50., 100., 1000., 5000., 20000.];
+        let mut mags = [0.; 5];
+        let mut phases = [0.; 5];
+        eq.get_frequency_response(&freqs, &mut mags, &mut phases);
+
+        assert_float_eq!(mags, [1.; 5], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_frequency_response_matches_biquads() {
+        let context = OfflineAudioContext::new(1, RENDER_QUANTUM_SIZE, 44_100.);
+        let eq = ParametricEqNode::new(
+            &context,
+            ParametricEqOptions {
+                bands: bands(),
+                ..ParametricEqOptions::default()
+            },
+        );
+
+        let freqs = [-1., 100., 200., 1000., 1500., 4000., 8000., 16000., 22_051.];
+        let mut mags = [0.; 9];
+        let mut phases = [0.; 9];
+        eq.get_frequency_response(&freqs, &mut mags, &mut phases);
+
+        let mut expected_mags = [1.; 9];
+        let mut expected_phases = [0.; 9];
+        for biquad in biquads(&context) {
+            let mut band_mags = [0.; 9];
+            let mut band_phases = [0.; 9];
+            biquad.get_frequency_response(&freqs, &mut band_mags, &mut band_phases);
+
+            for i in 0..freqs.len() {
+                expected_mags[i] *= band_mags[i];
+                expected_phases[i] += band_phases[i];
+            }
+        }
+
+        assert!(mags[0].is_nan() && phases[0].is_nan());
+        assert!(mags[8].is_nan() && phases[8].is_nan());
+
+        for i in 1..8 {
+            assert_float_eq!(mags[i], expected_mags[i], abs <= 1e-5);
+            // compare wrapped phases
+            let diff = Complex::from_polar(1., phases[i] - expected_phases[i]);
+            assert_float_eq!(diff.re, 1., abs <= 1e-5);
+        }
+    }
+
+    #[test]
+    fn test_output_matches_biquads() {
+        let sample_rate = 44_100.;
+        let length = 4 * RENDER_QUANTUM_SIZE;
+
+        let render = |use_eq: bool| {
+            let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+            let mut src = context.create_oscillator();
+            src.frequency().set_value(300.);
+            src.frequency()
+                .exponential_ramp_to_value_at_time(10000., length as f64 / sample_rate as f64);
+            src.start();
+
+            if use_eq {
+                let eq = ParametricEqNode::new(
+                    &context,
+                    ParametricEqOptions {
+                        bands: bands(),
+                        ..ParametricEqOptions::default()
+                    },
+                );
+                // sample accurate automation
+                eq.bands()[1]
+                    .frequency()
+                    .linear_ramp_to_value_at_time(3000., 0.01);
+                src.connect(&eq);
+                eq.connect(&context.destination());
+            } else {
+                let biquads = biquads(&context);
+                biquads[1]
+                    .frequency()
+                    .linear_ramp_to_value_at_time(3000., 0.01);
+                src.connect(&biquads[0]);
+                biquads[0].connect(&biquads[1]);
+                biquads[1].connect(&biquads[2]);
+                biquads[2].connect(&context.destination());
+            }
+
+            context.start_rendering_sync()
+        };
+
+        let expected = render(false);
+        let result = render(true);
+
+        for channel in 0..2 {
+            assert_float_eq!(
+                result.get_channel_data(channel),
+                expected.get_channel_data(channel),
+                abs_all <= 1e-6
+            );
+        }
+    }
+}
@@ -0,0 +1,514 @@
+//! The phaser control and renderer parts
+use std::f32::consts::PI;
+
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::param::AudioParam;
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+
+use super::modulation::{
+    assert_valid_channel_count, assert_valid_channel_count_mode, Lfo, ModulationParamIds,
+    ModulationParams, ModulationValues,
+};
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Maximum number of all-pass stages
+const MAX_STAGES: usize = 12;
+
+/// Lowest break frequency (in Hz) of the all-pass stages
+const MIN_FREQUENCY: f32 = 100.;
+
+/// Highest break frequency (in Hz) of the all-pass stages, for a `depth` of 1
+///
+/// The break frequency is kept below `0.45 * sample_rate` at low sample rates.
+const MAX_FREQUENCY: f32 = 4000.;
+
+const DEFAULTS: ModulationValues = ModulationValues {
+    rate: 0.5,
+    depth: 0.7,
+    feedback: 0.5,
+    mix: 0.5,
+    spread: 0.5,
+};
+
+/// Options for constructing a [`PhaserNode`]
+#[derive(Clone, Debug)]
+pub struct PhaserOptions {
+    /// Number of first order all-pass stages, in the range `[1, 12]`
+    pub stages: usize,
+    /// Frequency (in Hz) of the break frequency modulation
+    pub rate: f32,
+    /// Amount of break frequency modulation, from 0 to 1
+    pub depth: f32,
+    /// Amount of wet signal fed back into the all-pass chain, from -0.95 to 0.95
+    pub feedback: f32,
+    /// Amount of wet signal in the output, from 0 (dry) to 1 (wet)
+    pub mix: f32,
+    /// Phase offset between the left and right modulations, from 0 (in phase) to 1
+    /// (opposite phase)
+    pub spread: f32,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for PhaserOptions {
+    fn default() -> Self {
+        Self {
+            stages: 4,
+            rate: DEFAULTS.rate,
+            depth: DEFAULTS.depth,
+            feedback: DEFAULTS.feedback,
+            mix: DEFAULTS.mix,
+            spread: DEFAULTS.spread,
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::ClampedMax,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// Assert that the number of all-pass stages is valid
+///
+/// # Panics
+///
+/// This function panics if given number of stages is not in the range [1, 12]
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_stages(stages: usize) {
+    assert!(
+        (1..=MAX_STAGES).contains(&stages),
+        "NotSupportedError - PhaserNode number of stages must be in the range [1, {MAX_STAGES}], got {stages}"
+    );
+}
+
+/// `PhaserNode` mixes its input with a copy filtered by a chain of modulated all-pass
+/// filters, producing sweeping notches in the spectrum
+///
+/// The break frequency of the first order all-pass stages is swept exponentially by a sine
+/// LFO from 100Hz up to 4kHz. Each pair of stages creates a notch when mixed with the dry
+/// signal. The input is mixed down to (or up to) stereo and the output is always stereo.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_phaser`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let phaser = context.create_phaser();
+/// phaser.rate().set_value(0.2);
+/// phaser.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&phaser);
+/// osc.start();
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example modulation`
+///
+#[derive(Debug)]
+pub struct PhaserNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    stages: usize,
+    params: ModulationParams,
+}
+
+impl AudioNode for PhaserNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_channel_count_mode(mode, "PhaserNode");
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_channel_count(count, "PhaserNode");
+        self.channel_config.set_count(count, self.registration());
+    }
+}
+
+impl PhaserNode {
+    /// Creates a `PhaserNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - phaser options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.stages` is not in the range `[1, 12]`
+    /// * `options.audio_node_options.channel_count` is greater than 2
+    /// * `options.audio_node_options.channel_count_mode` is `ChannelCountMode::Max`
+    ///
+    pub fn new<C: BaseAudioContext>(context: &C, options: PhaserOptions) -> Self {
+        context.base().register(move |registration| {
+            let PhaserOptions {
+                stages,
+                rate,
+                depth,
+                feedback,
+                mix,
+                spread,
+                audio_node_options,
+            } = options;
+
+            assert_valid_stages(stages);
+            assert_valid_channel_count_mode(audio_node_options.channel_count_mode, "PhaserNode");
+            assert_valid_channel_count(audio_node_options.channel_count, "PhaserNode");
+
+            let values = ModulationValues {
+                rate,
+                depth,
+                feedback,
+                mix,
+                spread,
+            };
+            let (params, ids) = ModulationParams::new(context, &registration, &DEFAULTS, &values);
+
+            let renderer = PhaserRenderer {
+                params: ids,
+                stages,
+                lfo: Lfo::default(),
+                states: [ChannelState::default(); 2],
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                stages,
+                params,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Number of first order all-pass stages
+    #[must_use]
+    pub fn stages(&self) -> usize {
+        self.stages
+    }
+
+    /// K-rate [`AudioParam`] representing the frequency (in Hz) of the break frequency
+    /// modulation, in the range `[0, 20]`.
+    #[must_use]
+    pub fn rate(&self) -> &AudioParam {
+        &self.params.rate
+    }
+
+    /// K-rate [`AudioParam`] representing the amount of break frequency modulation, from 0
+    /// (fixed at 100Hz) to 1 (sweeping from 100Hz to 4kHz).
+    #[must_use]
+    pub fn depth(&self) -> &AudioParam {
+        &self.params.depth
+    }
+
+    /// K-rate [`AudioParam`] representing the amount of wet signal fed back into the
+    /// all-pass chain, in the range `[-0.95, 0.95]`.
+    #[must_use]
+    pub fn feedback(&self) -> &AudioParam {
+        &self.params.feedback
+    }
+
+    /// A-rate [`AudioParam`] representing the balance between the dry and the
+    /// wet signals, from 0 (dry only) to 1 (wet only).
+    #[must_use]
+    pub fn mix(&self) -> &AudioParam {
+        &self.params.mix
+    }
+
+    /// K-rate [`AudioParam`] representing the phase offset between the left and right
+    /// channels modulations, from 0 (in phase) to 1 (opposite phase).
+    #[must_use]
+    pub fn spread(&self) -> &AudioParam {
+        &self.params.spread
+    }
+}
+
+/// All-pass chain states of a channel
+#[derive(Clone, Copy, Default)]
+struct ChannelState {
+    /// previous input and output of each stage
+    stages: [[f32; 2]; MAX_STAGES],
+    /// last output of the chain, fed back into its input
+    feedback: f32,
+}
+
+impl ChannelState {
+    fn is_silent(&self) -> bool {
+        !self.feedback.is_normal() && !self.stages.iter().flatten().any(|v| v.is_normal())
+    }
+}
+
+/// `PhaserRenderer` represents the rendering part of `PhaserNode`
+struct PhaserRenderer {
+    params: ModulationParamIds,
+    stages: usize,
+    lfo: Lfo,
+    states: [ChannelState; 2],
+}
+
+impl AudioProcessor for PhaserRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+        let sample_rate = scope.sample_rate;
+
+        // handle tail time
+        if input.is_silent() && self.states.iter().all(ChannelState::is_silent) {
+            self.states = [ChannelState::default(); 2];
+            output.make_silent();
+            return false;
+        }
+
+        // k-rate parameters
+        let increment = params.get(&self.params.rate)[0] / sample_rate;
+        let depth = params.get(&self.params.depth)[0];
+        let feedback = params.get(&self.params.feedback)[0];
+        let spread = params.get(&self.params.spread)[0];
+        let offsets = [0., 0.5 * spread];
+        // the bilinear transform breaks down at the Nyquist frequency
+        let max_frequency = MAX_FREQUENCY.min(0.45 * sample_rate);
+        let range = (max_frequency / MIN_FREQUENCY).ln() * depth;
+
+        // a-rate parameter
+        let mix = params.get(&self.params.mix);
+
+        let (input_left, input_right) = match input.number_of_channels() {
+            1 => (input.channel_data(0), input.channel_data(0)),
+            2 => (input.channel_data(0), input.channel_data(1)),
+            _ => panic!("PhaserNode should not have more than 2 channels to process"),
+        };
+
+        output.set_number_of_channels(2);
+        let [output_left, output_right] = output.stereo_mut();
+
+        let Self {
+            stages,
+            lfo,
+            states,
+            ..
+        } = self;
+
+        for (i, (o_left, o_right)) in output_left
+            .iter_mut()
+            .zip(output_right.iter_mut())
+            .enumerate()
+        {
+            let dry = [input_left[i], input_right[i]];
+            let mix = if mix.len() == 1 { mix[0] } else { mix[i] };
+            let mut out = [0.; 2];
+
+            out.iter_mut()
+                .zip(dry.iter())
+                .zip(states.iter_mut())
+                .zip(offsets.iter())
+                .for_each(|(((o, &x), state), &offset)| {
+                    // exponential sweep of the break frequency
+                    let frequency = MIN_FREQUENCY * (range * lfo.value(offset)).exp();
+                    let t = (PI * frequency / sample_rate).tan();
+                    let coef = (t - 1.) / (t + 1.);
+
+                    let wet = state.stages[..*stages].iter_mut().fold(
+                        feedback.mul_add(state.feedback, x),
+                        |acc, [x1, y1]| {
+                            let mut y = coef.mul_add(acc - *y1, *x1);
+                            // flush subnormals to zero
+                            if !y.is_normal() {
+                                y = 0.;
+                            }
+                            *x1 = acc;
+                            *y1 = y;
+                            y
+                        },
+                    );
+
+                    state.feedback = wet;
+                    *o = (1. - mix).mul_add(x, mix * wet);
+                });
+
+            *o_left = out[0];
+            *o_right = out[1];
+
+            lfo.advance(increment);
+        }
+
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let phaser = context.create_phaser();
+
+        assert_eq!(phaser.stages(), 4);
+        assert_float_eq!(phaser.rate().value(), 0.5, abs <= 0.);
+        assert_float_eq!(phaser.depth().value(), 0.7, abs <= 0.);
+        assert_float_eq!(phaser.feedback().value(), 0.5, abs <= 0.);
+        assert_float_eq!(phaser.mix().value(), 0.5, abs <= 0.);
+        assert_float_eq!(phaser.spread().value(), 0.5, abs <= 0.);
+        assert_eq!(phaser.channel_count(), 2);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_stages() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let _ = PhaserNode::new(
+            &context,
+            PhaserOptions {
+                stages: 13,
+                ..PhaserOptions::default()
+            },
+        );
+    }
+
+    #[test]
+    fn test_allpass_preserves_energy() {
+        let sample_rate = 44_100.;
+        let length = 8192;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        // the wet signal alone is an all-pass filter
+        let phaser = PhaserNode::new(
+            &context,
+            PhaserOptions {
+                rate: 0.,
+                feedback: 0.,
+                mix: 1.,
+                spread: 0.,
+                ..PhaserOptions::default()
+            },
+        );
+        phaser.connect(&context.destination());
+
+        let mut dirac = context.create_buffer(1, 1, sample_rate);
+        dirac.copy_to_channel(&[1.], 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&phaser);
+        src.set_buffer(dirac);
+        src.start();
+
+        let res = context.start_rendering_sync();
+        let energy: f32 = res.get_channel_data(0).iter().map(|v| v * v).sum();
+        assert_float_eq!(energy, 1., abs <= 1e-3);
+    }
+
+    #[test]
+    fn test_notch() {
+        let sample_rate = 44_100.;
+        let length = 44_100;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        // two stages with a fixed break frequency at 100Hz, mixed with the dry signal,
+        // cancel each other at the break frequency
+        let phaser = PhaserNode::new(
+            &context,
+            PhaserOptions {
+                stages: 2,
+                rate: 0.,
+                depth: 0.,
+                feedback: 0.,
+                mix: 0.5,
+                ..PhaserOptions::default()
+            },
+        );
+        phaser.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(MIN_FREQUENCY);
+        osc.connect(&phaser);
+        osc.start();
+
+        let res = context.start_rendering_sync();
+        let peak = res.get_channel_data(0)[22_050..]
+            .iter()
+            .fold(0_f32, |max, v| max.max(v.abs()));
+        assert!(peak < 0.01);
+    }
+
+    #[test]
+    fn test_sweep_below_nyquist() {
+        let sample_rate = 8_000.;
+        let length = 8_000;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        // the right modulation sits at the top of the sweep, clamped to 0.45 * sample_rate,
+        // where two stages mixed with the dry signal cancel each other
+        let phaser = PhaserNode::new(
+            &context,
+            PhaserOptions {
+                stages: 2,
+                rate: 0.,
+                depth: 1.,
+                feedback: 0.,
+                mix: 0.5,
+                spread: 1.,
+                ..PhaserOptions::default()
+            },
+        );
+        phaser.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(0.45 * sample_rate);
+        osc.connect(&phaser);
+        osc.start();
+
+        let res = context.start_rendering_sync();
+        let right = res.get_channel_data(1);
+        assert!(right.iter().all(|v| v.is_finite()));
+
+        let peak = right[4_000..].iter().fold(0_f32, |max, v| max.max(v.abs()));
+        assert!(peak < 0.01);
+    }
+}
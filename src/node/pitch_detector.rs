@@ -0,0 +1,597 @@
+//! The pitch detector control and renderer parts
+use crate::context::{AudioContextRegistration, BaseAudioContext};
+use crate::events::{EventHandler, EventPayload, EventType, PitchEvent};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelInterpretation};
+
+/// Absolute threshold of the cumulative mean normalized difference (YIN)
+const YIN_THRESHOLD: f32 = 0.15;
+/// Ratio of the highest normalized square difference peak a pitch peak should reach (MPM)
+const MPM_CUTOFF: f32 = 0.93;
+/// Minimum clarity for a frame to be considered pitched (MPM)
+const MPM_MIN_CLARITY: f32 = 0.5;
+/// Energy below which a frame is considered silent
+const SILENCE_THRESHOLD: f32 = 1e-8;
+
+/// Algorithm used by a [`PitchDetectorNode`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PitchDetectionAlgorithm {
+    /// YIN, de Cheveigné and Kawahara (2002)
+    #[default]
+    Yin,
+    /// McLeod Pitch Method, McLeod and Wyvill (2005)
+    Mpm,
+}
+
+/// Options for constructing a [`PitchDetectorNode`]
+#[derive(Clone, Debug)]
+pub struct PitchDetectorOptions {
+    /// Detection algorithm
+    pub algorithm: PitchDetectionAlgorithm,
+    /// Lowest detectable frequency (in Hz), defines the size of the analysed frames
+    pub min_frequency: f32,
+    /// Highest detectable frequency (in Hz)
+    pub max_frequency: f32,
+    /// Number of sample-frames between two consecutive analyses
+    pub hop_size: usize,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for PitchDetectorOptions {
+    fn default() -> Self {
+        Self {
+            algorithm: PitchDetectionAlgorithm::default(),
+            min_frequency: 50.,
+            max_frequency: 2000.,
+            hop_size: 512,
+            audio_node_options: AudioNodeOptions::default(),
+        }
+    }
+}
+
+/// `PitchDetectorNode` detects the fundamental frequency of its input
+///
+/// The node passes its input unchanged to its output. Every `hop_size` sample-frames,
+/// the render thread analyses the most recent frame of the (down mixed) input and
+/// dispatches a [`PitchEvent`] with the detected frequency and the confidence of the
+/// detection. Unpitched or silent frames do not dispatch any event.
+///
+/// The analysed frames are twice as long as the period of the lowest detectable
+/// frequency, so lowering `min_frequency` increases both the latency and the cost of
+/// the detection.
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_pitch_detector`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let detector = context.create_pitch_detector();
+/// detector.set_onpitch(|event| {
+///     println!("{:.1} Hz ({:.2})", event.frequency, event.confidence);
+/// });
+///
+/// let mut osc = context.create_oscillator();
+/// osc.frequency().set_value(220.);
+/// osc.connect(&detector);
+/// osc.start();
+///
+/// std::thread::sleep(std::time::Duration::from_secs(4));
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example detectors`
+///
+#[derive(Debug)]
+pub struct PitchDetectorNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    algorithm: PitchDetectionAlgorithm,
+    min_frequency: f32,
+    max_frequency: f32,
+    hop_size: usize,
+}
+
+impl AudioNode for PitchDetectorNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+}
+
+impl PitchDetectorNode {
+    /// Creates a `PitchDetectorNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - pitch detector options
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - `min_frequency` is not positive or is greater than or equal to `max_frequency`
+    /// - `max_frequency` is greater than half the sample rate
+    /// - `hop_size` is zero
+    pub fn new<C: BaseAudioContext>(context: &C, options: PitchDetectorOptions) -> Self {
+        let PitchDetectorOptions {
+            algorithm,
+            min_frequency,
+            max_frequency,
+            hop_size,
+            audio_node_options,
+        } = options;
+
+        let sample_rate = context.sample_rate();
+
+        assert!(
+            min_frequency > 0. && min_frequency < max_frequency,
+            "RangeError - Invalid min frequency: {min_frequency:?}, should be positive and lower than max frequency {max_frequency:?}"
+        );
+        assert!(
+            max_frequency <= sample_rate / 2.,
+            "RangeError - Invalid max frequency: {max_frequency:?}, should be lower than half the sample rate"
+        );
+        assert!(
+            hop_size > 0,
+            "RangeError - Invalid hop size: {hop_size:?}, should be greater than zero"
+        );
+
+        context.base().register(move |registration| {
+            let min_lag = ((sample_rate / max_frequency).floor() as usize).max(2);
+            let max_lag = (sample_rate / min_frequency).ceil() as usize;
+            // the correlation window covers the max lag, plus one lag for the interpolation
+            let window_size = max_lag + 1;
+            let frame_size = window_size + max_lag + 1;
+
+            let renderer = PitchDetectorRenderer {
+                algorithm,
+                min_lag,
+                max_lag,
+                window_size,
+                frame_size,
+                hop_size,
+                elapsed: 0,
+                history: vec![0.; frame_size + RENDER_QUANTUM_SIZE],
+                lags: vec![0.; max_lag + 2],
+            };
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                algorithm,
+                min_frequency,
+                max_frequency,
+                hop_size,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// Detection algorithm
+    #[must_use]
+    pub fn algorithm(&self) -> PitchDetectionAlgorithm {
+        self.algorithm
+    }
+
+    /// Lowest detectable frequency, in Hz
+    #[must_use]
+    pub fn min_frequency(&self) -> f32 {
+        self.min_frequency
+    }
+
+    /// Highest detectable frequency, in Hz
+    #[must_use]
+    pub fn max_frequency(&self) -> f32 {
+        self.max_frequency
+    }
+
+    /// Number of sample-frames between two consecutive analyses
+    #[must_use]
+    pub fn hop_size(&self) -> usize {
+        self.hop_size
+    }
+
+    /// Register callback to run when a pitch is detected
+    ///
+    /// Only a single event handler is active at any time. Calling this method multiple times will
+    /// override the previous event handler.
+    pub fn set_onpitch<F: FnMut(PitchEvent) + Send + 'static>(&self, mut callback: F) {
+        let callback = move |v| match v {
+            EventPayload::Pitch(v) => callback(v),
+            _ => unreachable!(),
+        };
+
+        self.context().set_event_handler(
+            EventType::Pitch(self.registration().id()),
+            EventHandler::Multiple(Box::new(callback)),
+        );
+    }
+
+    /// Unset the callback to run when a pitch is detected
+    pub fn clear_onpitch(&self) {
+        self.context()
+            .clear_event_handler(EventType::Pitch(self.registration().id()));
+    }
+}
+
+/// Parabolic interpolation of the extremum around `index`, returns the fractional
+/// offset to add to `index`
+fn parabolic_offset(values: &[f32], index: usize) -> f32 {
+    if index == 0 || index + 1 >= values.len() {
+        return 0.;
+    }
+
+    let (a, b, c) = (values[index - 1], values[index], values[index + 1]);
+    let denominator = a - 2. * b + c;
+
+    if denominator.abs() < f32::EPSILON {
+        0.
+    } else {
+        (0.5 * (a - c) / denominator).clamp(-1., 1.)
+    }
+}
+
+/// YIN estimation, returns the period (in samples) and the confidence
+///
+/// `lags` is filled with the cumulative mean normalized difference function
+fn yin(
+    frame: &[f32],
+    window_size: usize,
+    min_lag: usize,
+    max_lag: usize,
+    lags: &mut [f32],
+) -> Option<(f32, f32)> {
+    // difference function
+    lags[0] = 0.;
+    for (lag, value) in lags.iter_mut().enumerate().skip(1) {
+        *value = frame[..window_size]
+            .iter()
+            .zip(&frame[lag..lag + window_size])
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum();
+    }
+
+    // cumulative mean normalization
+    let mut running_sum = 0.;
+    lags[0] = 1.;
+    for (lag, value) in lags.iter_mut().enumerate().skip(1) {
+        running_sum += *value;
+        *value = if running_sum > 0. {
+            *value * lag as f32 / running_sum
+        } else {
+            1.
+        };
+    }
+
+    // first dip below the absolute threshold, refined to its local minimum
+    let mut lag = (min_lag..=max_lag).find(|&lag| lags[lag] < YIN_THRESHOLD)?;
+    while lag < max_lag && lags[lag + 1] < lags[lag] {
+        lag += 1;
+    }
+
+    let period = lag as f32 + parabolic_offset(lags, lag);
+    let confidence = (1. - lags[lag]).clamp(0., 1.);
+
+    Some((period, confidence))
+}
+
+/// McLeod Pitch Method estimation, returns the period (in samples) and the confidence
+///
+/// `lags` is filled with the normalized square difference function
+fn mpm(
+    frame: &[f32],
+    window_size: usize,
+    min_lag: usize,
+    max_lag: usize,
+    lags: &mut [f32],
+) -> Option<(f32, f32)> {
+    // normalized square difference function
+    for (lag, value) in lags.iter_mut().enumerate() {
+        let (acf, energy) = frame[..window_size]
+            .iter()
+            .zip(&frame[lag..lag + window_size])
+            .fold((0., 0.), |(acf, energy), (a, b)| {
+                (acf + a * b, energy + a * a + b * b)
+            });
+
+        *value = if energy > 0. { 2. * acf / energy } else { 0. };
+    }
+
+    // key maxima, between each positive going zero crossing and the next negative
+    // going one, after the first negative going zero crossing
+    let mut key_maxima = [(0, 0.); 32];
+    let mut count = 0;
+    let mut current: Option<(usize, f32)> = None;
+    let mut started = false;
+
+    for (lag, &value) in lags.iter().enumerate().take(max_lag + 1).skip(1) {
+        if !started {
+            started = value < 0.;
+            continue;
+        }
+
+        if value > 0. {
+            if lag >= min_lag && current.is_none_or(|(_, max)| value > max) {
+                current = Some((lag, value));
+            }
+        } else if let Some(maximum) = current.take() {
+            if count < key_maxima.len() {
+                key_maxima[count] = maximum;
+                count += 1;
+            }
+        }
+    }
+
+    if let Some(maximum) = current {
+        if count < key_maxima.len() {
+            key_maxima[count] = maximum;
+            count += 1;
+        }
+    }
+
+    let key_maxima = &key_maxima[..count];
+    let highest = key_maxima.iter().fold(0., |acc: f32, &(_, v)| acc.max(v));
+
+    if highest < MPM_MIN_CLARITY {
+        return None;
+    }
+
+    let &(lag, clarity) = key_maxima
+        .iter()
+        .find(|&&(_, value)| value >= MPM_CUTOFF * highest)?;
+
+    let period = lag as f32 + parabolic_offset(lags, lag);
+    let confidence = clarity.clamp(0., 1.);
+
+    Some((period, confidence))
+}
+
+/// `PitchDetectorRenderer` represents the rendering part of `PitchDetectorNode`
+struct PitchDetectorRenderer {
+    algorithm: PitchDetectionAlgorithm,
+    min_lag: usize,
+    max_lag: usize,
+    window_size: usize,
+    frame_size: usize,
+    hop_size: usize,
+    /// number of frames written since the last analysis
+    elapsed: usize,
+    /// the most recent `frame_size + RENDER_QUANTUM_SIZE` input frames
+    history: Vec<f32>,
+    lags: Vec<f32>,
+}
+
+impl AudioProcessor for PitchDetectorRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        _params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // pass through input
+        *output = input.clone();
+
+        // down mix to mono
+        let mut mono = input.clone();
+        mono.mix(1, ChannelInterpretation::Speakers);
+
+        let len = self.history.len();
+        self.history.copy_within(RENDER_QUANTUM_SIZE.., 0);
+        self.history[len - RENDER_QUANTUM_SIZE..].copy_from_slice(mono.channel_data(0));
+
+        self.elapsed += RENDER_QUANTUM_SIZE;
+
+        while self.elapsed >= self.hop_size {
+            self.elapsed -= self.hop_size;
+
+            // the analysed frame ends `elapsed` frames before the end of the quantum
+            let offset = self.elapsed;
+            let end = len - offset;
+            let frame = &self.history[end - self.frame_size..end];
+
+            let energy: f32 = frame.iter().map(|v| v * v).sum();
+            if energy < SILENCE_THRESHOLD {
+                continue;
+            }
+
+            let estimation = match self.algorithm {
+                PitchDetectionAlgorithm::Yin => yin(
+                    frame,
+                    self.window_size,
+                    self.min_lag,
+                    self.max_lag,
+                    &mut self.lags,
+                ),
+                PitchDetectionAlgorithm::Mpm => mpm(
+                    frame,
+                    self.window_size,
+                    self.min_lag,
+                    self.max_lag,
+                    &mut self.lags,
+                ),
+            };
+
+            if let Some((period, confidence)) = estimation {
+                let time = scope.current_time
+                    + (RENDER_QUANTUM_SIZE - offset) as f64 / scope.sample_rate as f64;
+                scope.send_pitch_event(scope.sample_rate / period, confidence, time);
+            }
+        }
+
+        // no tail-time
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use std::sync::{Arc, Mutex};
+
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::{AudioScheduledSourceNode, OscillatorType};
+
+    use super::*;
+
+    fn sine(freq: f32, sample_rate: f32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| (2. * PI * freq * i as f32 / sample_rate).sin())
+            .collect()
+    }
+
+    #[test]
+    fn test_yin() {
+        let sample_rate = 48_000.;
+        let (min_lag, max_lag) = (24, 480);
+        let window_size = max_lag + 1;
+        let mut lags = vec![0.; max_lag + 2];
+
+        for freq in [110., 261.63, 440., 1234.5] {
+            let frame = sine(freq, sample_rate, 2 * max_lag + 2);
+            let (period, confidence) =
+                yin(&frame, window_size, min_lag, max_lag, &mut lags).unwrap();
+
+            assert_float_eq!(sample_rate / period, freq, r2nd <= 0.005);
+            assert!(confidence > 0.9);
+        }
+    }
+
+    #[test]
+    fn test_mpm() {
+        let sample_rate = 48_000.;
+        let (min_lag, max_lag) = (24, 480);
+        let window_size = max_lag + 1;
+        let mut lags = vec![0.; max_lag + 2];
+
+        for freq in [110., 261.63, 440., 1234.5] {
+            let frame = sine(freq, sample_rate, 2 * max_lag + 2);
+            let (period, confidence) =
+                mpm(&frame, window_size, min_lag, max_lag, &mut lags).unwrap();
+
+            assert_float_eq!(sample_rate / period, freq, r2nd <= 0.005);
+            assert!(confidence > 0.9);
+        }
+    }
+
+    #[test]
+    fn test_noise_is_unpitched() {
+        let (min_lag, max_lag) = (24, 480);
+        let window_size = max_lag + 1;
+        let mut lags = vec![0.; max_lag + 2];
+
+        // deterministic white noise
+        let mut seed = 1_u32;
+        let frame: Vec<f32> = (0..2 * max_lag + 2)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (seed >> 8) as f32 / (1 << 23) as f32 - 1.
+            })
+            .collect();
+
+        assert!(yin(&frame, window_size, min_lag, max_lag, &mut lags).is_none());
+    }
+
+    #[test]
+    fn test_events() {
+        for algorithm in [PitchDetectionAlgorithm::Yin, PitchDetectionAlgorithm::Mpm] {
+            let sample_rate = 48_000.;
+            let mut context = OfflineAudioContext::new(1, 48_000, sample_rate);
+
+            let options = PitchDetectorOptions {
+                algorithm,
+                min_frequency: 80.,
+                hop_size: 1000,
+                ..PitchDetectorOptions::default()
+            };
+            let detector = PitchDetectorNode::new(&context, options);
+            detector.connect(&context.destination());
+
+            let events = Arc::new(Mutex::new(vec![]));
+            let events_clone = Arc::clone(&events);
+            detector.set_onpitch(move |event| events_clone.lock().unwrap().push(event));
+
+            // a sawtooth has a strong harmonic content
+            let mut osc = context.create_oscillator();
+            osc.set_type(OscillatorType::Sawtooth);
+            osc.frequency().set_value(196.);
+            osc.connect(&detector);
+            osc.start();
+
+            let _ = context.start_rendering_sync();
+
+            // the first frame is partly silent, it may or may not be detected
+            let hop_duration = 1000. / sample_rate as f64;
+            let events = events.lock().unwrap();
+            let events: Vec<_> = events
+                .iter()
+                .filter(|event| event.time > 1.5 * hop_duration)
+                .collect();
+            assert_eq!(events.len(), 47);
+
+            events.iter().enumerate().for_each(|(i, event)| {
+                // one event every hop
+                let expected = (i + 2) as f64 * 1000. / sample_rate as f64;
+                assert_float_eq!(event.time, expected, abs <= 1e-9);
+                assert_float_eq!(event.frequency, 196., r2nd <= 0.005);
+                assert!(event.confidence > 0.8);
+            });
+        }
+    }
+
+    #[test]
+    fn test_silence() {
+        let mut context = OfflineAudioContext::new(1, 48_000, 48_000.);
+
+        let detector = context.create_pitch_detector();
+        detector.connect(&context.destination());
+        detector.set_onpitch(|_| panic!("silence should not be pitched"));
+
+        let _ = context.start_rendering_sync();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_frequency_range() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let options = PitchDetectorOptions {
+            min_frequency: 1000.,
+            max_frequency: 500.,
+            ..PitchDetectorOptions::default()
+        };
+        let _ = PitchDetectorNode::new(&context, options);
+    }
+}
@@ -0,0 +1,812 @@
+//! The pitch shifter control and renderer parts
+use std::any::Any;
+use std::f32::consts::PI;
+use std::sync::Arc;
+
+use realfft::{num_complex::Complex, ComplexToReal, RealFftPlanner, RealToComplex};
+
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Maximum absolute value of the `pitch` parameter (in cents)
+const MAX_PITCH: f32 = 2400.;
+
+/// Number of overlapping analysis frames, the hop size is `frame_size / OVERLAP`
+const OVERLAP: usize = 4;
+
+/// Quefrency (in seconds) below which the cepstrum is kept to estimate the spectral
+/// envelope, must be shorter than the period of the highest expected fundamental
+const ENVELOPE_QUEFRENCY: f32 = 0.001;
+
+/// Quality of the [`PitchShifterNode`] processing, trading frequency resolution for latency
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum PitchShifterQuality {
+    /// Analysis frames of 1024 samples
+    Low,
+    /// Analysis frames of 2048 samples
+    #[default]
+    Medium,
+    /// Analysis frames of 4096 samples
+    High,
+}
+
+impl PitchShifterQuality {
+    fn frame_size(self) -> usize {
+        match self {
+            Self::Low => 1024,
+            Self::Medium => 2048,
+            Self::High => 4096,
+        }
+    }
+}
+
+/// Options for constructing a [`PitchShifterNode`]
+#[derive(Clone, Debug)]
+pub struct PitchShifterOptions {
+    /// Pitch shift (in cents)
+    pub pitch: f32,
+    /// Keep the spectral envelope of the input, so that voices are not "chipmunked"
+    pub preserve_formants: bool,
+    /// Processing quality, which also defines the latency of the node
+    pub quality: PitchShifterQuality,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for PitchShifterOptions {
+    fn default() -> Self {
+        Self {
+            pitch: 0.,
+            preserve_formants: false,
+            quality: PitchShifterQuality::default(),
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::ClampedMax,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// Assert that the channel count is valid for the PitchShifterNode
+///
+/// # Panics
+///
+/// This function panics if given count is greater than 2
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_count(count: usize) {
+    assert!(
+        count <= 2,
+        "NotSupportedError - PitchShifterNode channel count cannot be greater than two"
+    );
+}
+
+/// Assert that the channel count mode is valid for the PitchShifterNode
+///
+/// # Panics
+///
+/// This function panics if given count mode is [`ChannelCountMode::Max`]
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
+    assert_ne!(
+        mode,
+        ChannelCountMode::Max,
+        "NotSupportedError - PitchShifterNode channel count mode cannot be set to max"
+    );
+}
+
+/// `PitchShifterNode` changes the pitch of its input without changing its duration
+///
+/// The pitch is shifted by a phase vocoder: the input is analysed in overlapping frames,
+/// the partials are moved to their shifted frequencies in the spectrum, and the frames are
+/// resynthesized with coherent phases. When the formants are preserved, the spectral
+/// envelope of the input is estimated from its cepstrum and reapplied to the shifted
+/// partials.
+///
+/// The processing introduces a latency which depends on the
+/// [`quality`](PitchShifterOptions::quality) of the node, see
+/// [`latency`](PitchShifterNode::latency).
+///
+/// Note that this node is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_pitch_shifter`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use std::fs::File;
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let file = File::open("samples/vocals-dry.wav").unwrap();
+/// let buffer = context.decode_audio_data_sync(file).unwrap();
+///
+/// let mut pitch_shifter = context.create_pitch_shifter();
+/// // two semitones up
+/// pitch_shifter.pitch().set_value(200.);
+/// pitch_shifter.set_preserve_formants(true);
+/// pitch_shifter.connect(&context.destination());
+///
+/// let mut src = context.create_buffer_source();
+/// src.set_buffer(buffer);
+/// src.connect(&pitch_shifter);
+/// src.start();
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example pitch_shifter`
+///
+#[derive(Debug)]
+pub struct PitchShifterNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    pitch: AudioParam,
+    preserve_formants: bool,
+    quality: PitchShifterQuality,
+}
+
+impl AudioNode for PitchShifterNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_channel_count_mode(mode);
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_channel_count(count);
+        self.channel_config.set_count(count, self.registration());
+    }
+}
+
+impl PitchShifterNode {
+    /// Creates a `PitchShifterNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - pitch shifter options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.audio_node_options.channel_count` is greater than 2
+    /// * `options.audio_node_options.channel_count_mode` is `ChannelCountMode::Max`
+    ///
+    pub fn new<C: BaseAudioContext>(context: &C, options: PitchShifterOptions) -> Self {
+        context.base().register(move |registration| {
+            let PitchShifterOptions {
+                pitch,
+                preserve_formants,
+                quality,
+                audio_node_options,
+            } = options;
+
+            assert_valid_channel_count_mode(audio_node_options.channel_count_mode);
+            assert_valid_channel_count(audio_node_options.channel_count);
+
+            let pitch_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: -MAX_PITCH,
+                max_value: MAX_PITCH,
+                default_value: 0.,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut pitch_param, pitch_proc) =
+                context.create_audio_param(pitch_opts, &registration);
+            pitch_param.set_automation_rate_constrained(true);
+            pitch_param.set_value(pitch);
+
+            let renderer = PitchShifterRenderer::new(
+                context.sample_rate(),
+                pitch_proc,
+                preserve_formants,
+                quality.frame_size(),
+            );
+
+            let node = Self {
+                registration,
+                channel_config: audio_node_options.into(),
+                pitch: pitch_param,
+                preserve_formants,
+                quality,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// K-rate [`AudioParam`] representing the pitch shift in cents, in the range
+    /// `[-2400, 2400]`, i.e. up to two octaves down or up.
+    #[must_use]
+    pub fn pitch(&self) -> &AudioParam {
+        &self.pitch
+    }
+
+    /// Whether the spectral envelope of the input is preserved
+    #[must_use]
+    pub fn preserve_formants(&self) -> bool {
+        self.preserve_formants
+    }
+
+    /// Preserve (or not) the spectral envelope of the input
+    ///
+    /// Preserving the formants keeps shifted voices natural, at the cost of some extra
+    /// processing.
+    pub fn set_preserve_formants(&mut self, value: bool) {
+        self.preserve_formants = value;
+        self.registration.post_message(value);
+    }
+
+    /// Returns the processing quality of this node
+    #[must_use]
+    pub fn quality(&self) -> PitchShifterQuality {
+        self.quality
+    }
+
+    /// Returns the latency introduced by the processing, in seconds
+    ///
+    /// The latency is the size of the analysis frames, i.e. 1024, 2048 or 4096 samples
+    /// depending on the [`quality`](Self::quality). Signals mixed with the output of the node can be delayed by the same amount to stay
+    /// aligned.
+    #[must_use]
+    pub fn latency(&self) -> f64 {
+        self.quality.frame_size() as f64 / self.context().sample_rate() as f64
+    }
+}
+
+/// Analysis and synthesis states of a channel
+struct ChannelState {
+    /// last `frame_size` input samples
+    input: Vec<f32>,
+    /// overlap-added synthesis frames
+    accumulator: Vec<f32>,
+    /// samples ready to be output
+    output: Vec<f32>,
+    /// phases of the previous analysis frame
+    last_phases: Vec<f32>,
+    /// accumulated phases of the synthesis frames
+    sum_phases: Vec<f32>,
+}
+
+impl ChannelState {
+    fn new(frame_size: usize) -> Self {
+        let num_bins = frame_size / 2 + 1;
+
+        Self {
+            input: vec![0.; frame_size],
+            accumulator: vec![0.; frame_size],
+            output: vec![0.; frame_size / OVERLAP],
+            last_phases: vec![0.; num_bins],
+            sum_phases: vec![0.; num_bins],
+        }
+    }
+
+    fn reset(&mut self) {
+        self.input.fill(0.);
+        self.accumulator.fill(0.);
+        self.output.fill(0.);
+        self.last_phases.fill(0.);
+        self.sum_phases.fill(0.);
+    }
+}
+
+/// `PitchShifterRenderer` represents the rendering part of `PitchShifterNode`
+struct PitchShifterRenderer {
+    pitch: AudioParamId,
+    preserve_formants: bool,
+    frame_size: usize,
+    hop_size: usize,
+    /// number of cepstrum coefficients kept to estimate the spectral envelope
+    envelope_length: usize,
+    /// position in the current hop, the same for all channels
+    position: usize,
+    /// number of channels processed, kept during the tail time
+    number_of_channels: usize,
+    states: [ChannelState; 2],
+    /// Hann window, used for both the analysis and the synthesis
+    window: Vec<f32>,
+    forward: Arc<dyn RealToComplex<f32>>,
+    inverse: Arc<dyn ComplexToReal<f32>>,
+    // scratch buffers shared by the channels
+    frame: Vec<f32>,
+    spectrum: Vec<Complex<f32>>,
+    magnitudes: Vec<f32>,
+    frequencies: Vec<f32>,
+    envelope: Vec<f32>,
+    synthesis_magnitudes: Vec<f32>,
+    synthesis_frequencies: Vec<f32>,
+    fft_scratch: Vec<Complex<f32>>,
+    /// Number of samples left before the processing tail is considered silent
+    tail_remaining: usize,
+}
+
+impl PitchShifterRenderer {
+    fn new(
+        sample_rate: f32,
+        pitch: AudioParamId,
+        preserve_formants: bool,
+        frame_size: usize,
+    ) -> Self {
+        let mut planner = RealFftPlanner::<f32>::new();
+        let forward = planner.plan_fft_forward(frame_size);
+        let inverse = planner.plan_fft_inverse(frame_size);
+        let scratch_len = forward.get_scratch_len().max(inverse.get_scratch_len());
+
+        let num_bins = frame_size / 2 + 1;
+        let hop_size = frame_size / OVERLAP;
+
+        let window = (0..frame_size)
+            .map(|i| 0.5 - 0.5 * (2. * PI * i as f32 / frame_size as f32).cos())
+            .collect();
+
+        let envelope_length =
+            ((ENVELOPE_QUEFRENCY * sample_rate).ceil() as usize).clamp(1, frame_size / 2 - 1);
+
+        Self {
+            pitch,
+            preserve_formants,
+            frame_size,
+            hop_size,
+            envelope_length,
+            position: 0,
+            number_of_channels: 1,
+            states: [ChannelState::new(frame_size), ChannelState::new(frame_size)],
+            window,
+            forward,
+            inverse,
+            frame: vec![0.; frame_size],
+            spectrum: vec![Complex::default(); num_bins],
+            magnitudes: vec![0.; num_bins],
+            frequencies: vec![0.; num_bins],
+            envelope: vec![0.; num_bins],
+            synthesis_magnitudes: vec![0.; num_bins],
+            synthesis_frequencies: vec![0.; num_bins],
+            fft_scratch: vec![Complex::default(); scratch_len],
+            tail_remaining: 0,
+        }
+    }
+
+    /// Estimate the spectral envelope of `self.magnitudes` into `self.envelope`, by
+    /// keeping the low quefrencies of the real cepstrum
+    fn compute_envelope(&mut self) {
+        let Self {
+            frame_size,
+            envelope_length,
+            inverse,
+            forward,
+            frame,
+            spectrum,
+            magnitudes,
+            envelope,
+            fft_scratch,
+            ..
+        } = self;
+
+        spectrum
+            .iter_mut()
+            .zip(magnitudes.iter())
+            .for_each(|(s, m)| *s = Complex::new((m + 1e-9).ln(), 0.));
+
+        // real cepstrum
+        let _ = inverse.process_with_scratch(spectrum, frame, fft_scratch);
+
+        // lifter, the cepstrum is symmetric
+        frame[*envelope_length + 1..*frame_size - *envelope_length].fill(0.);
+
+        let _ = forward.process_with_scratch(frame, spectrum, fft_scratch);
+
+        let norm = 1. / *frame_size as f32;
+        envelope
+            .iter_mut()
+            .zip(spectrum.iter())
+            .for_each(|(e, s)| *e = (s.re * norm).exp());
+    }
+
+    /// Analyse the input of a channel, shift its partials by `ratio` and overlap-add the
+    /// resynthesized frame to the accumulator
+    fn process_frame(&mut self, channel: usize, ratio: f32) {
+        let frame_size = self.frame_size;
+        let num_bins = frame_size / 2 + 1;
+        // expected phase advance of bin 1 between two frames
+        let expected = 2. * PI * self.hop_size as f32 / frame_size as f32;
+
+        // analysis
+        {
+            let Self {
+                states,
+                window,
+                forward,
+                frame,
+                spectrum,
+                magnitudes,
+                frequencies,
+                fft_scratch,
+                ..
+            } = self;
+            let state = &mut states[channel];
+
+            frame
+                .iter_mut()
+                .zip(state.input.iter().zip(window.iter()))
+                .for_each(|(f, (i, w))| *f = i * w);
+
+            let _ = forward.process_with_scratch(frame, spectrum, fft_scratch);
+
+            spectrum
+                .iter()
+                .zip(state.last_phases.iter_mut())
+                .zip(magnitudes.iter_mut().zip(frequencies.iter_mut()))
+                .enumerate()
+                .for_each(|(k, ((s, last_phase), (m, f)))| {
+                    let (magnitude, phase) = s.to_polar();
+                    // deviation from the expected phase advance, wrapped to [-PI, PI]
+                    let mut delta = phase - *last_phase - k as f32 * expected;
+                    delta -= 2. * PI * (delta / (2. * PI)).round();
+                    *last_phase = phase;
+
+                    *m = magnitude;
+                    // true frequency of the partial, in bins
+                    *f = k as f32 + delta / expected;
+                });
+        }
+
+        if self.preserve_formants {
+            self.compute_envelope();
+        }
+
+        // shift the partials
+        self.synthesis_magnitudes.fill(0.);
+        self.synthesis_frequencies.fill(0.);
+
+        for k in 0..num_bins {
+            let target = (k as f32 * ratio).round() as usize;
+            if target >= num_bins {
+                break;
+            }
+
+            let magnitude = if self.preserve_formants {
+                // whiten the partial and apply the envelope at its new frequency
+                self.magnitudes[k] / self.envelope[k] * self.envelope[target]
+            } else {
+                self.magnitudes[k]
+            };
+
+            self.synthesis_magnitudes[target] += magnitude;
+            self.synthesis_frequencies[target] = self.frequencies[k] * ratio;
+        }
+
+        // synthesis
+        let Self {
+            states,
+            window,
+            inverse,
+            frame,
+            spectrum,
+            synthesis_magnitudes,
+            synthesis_frequencies,
+            fft_scratch,
+            ..
+        } = self;
+        let state = &mut states[channel];
+
+        spectrum
+            .iter_mut()
+            .zip(state.sum_phases.iter_mut())
+            .zip(
+                synthesis_magnitudes
+                    .iter()
+                    .zip(synthesis_frequencies.iter()),
+            )
+            .for_each(|((s, sum_phase), (m, f))| {
+                *sum_phase += f * expected;
+                *sum_phase -= 2. * PI * (*sum_phase / (2. * PI)).floor();
+                *s = Complex::from_polar(*m, *sum_phase);
+            });
+
+        // the DC and Nyquist bins of a real signal are real
+        spectrum[0].im = 0.;
+        spectrum[num_bins - 1].im = 0.;
+
+        let _ = inverse.process_with_scratch(spectrum, frame, fft_scratch);
+
+        // normalize the inverse FFT and the sum of the squared overlapping windows, which
+        // is 3/2 for Hann windows overlapping by a factor 4
+        let norm = 2. / (3. * frame_size as f32);
+        state
+            .accumulator
+            .iter_mut()
+            .zip(frame.iter().zip(window.iter()))
+            .for_each(|(a, (f, w))| *a += f * w * norm);
+    }
+}
+
+impl AudioProcessor for PitchShifterRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        _scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+
+        // keep the node alive until the last frames have been output
+        if !input.is_silent() {
+            self.tail_remaining = 2 * self.frame_size;
+        } else if self.tail_remaining == 0 {
+            self.states.iter_mut().for_each(ChannelState::reset);
+            output.make_silent();
+            return false;
+        } else {
+            self.tail_remaining = self.tail_remaining.saturating_sub(RENDER_QUANTUM_SIZE);
+        }
+
+        let pitch = params.get(&self.pitch)[0];
+        let ratio = 2_f32.powf(pitch / 1200.);
+
+        // if in tail time, we should continue with previous number of channels
+        if !input.is_silent() {
+            self.number_of_channels = input.number_of_channels();
+        }
+        let number_of_channels = self.number_of_channels;
+        output.set_number_of_channels(number_of_channels);
+
+        let hop_size = self.hop_size;
+        // samples of the input frame kept from the previous hops
+        let kept = self.frame_size - hop_size;
+        let mut offset = 0;
+
+        while offset < RENDER_QUANTUM_SIZE {
+            let len = (hop_size - self.position).min(RENDER_QUANTUM_SIZE - offset);
+            let range = offset..offset + len;
+            let position = self.position;
+
+            for channel in 0..number_of_channels {
+                let state = &mut self.states[channel];
+                let input_channel = if input.is_silent() {
+                    &input.channel_data(0)[range.clone()]
+                } else {
+                    &input.channel_data(channel)[range.clone()]
+                };
+                state.input[kept + position..kept + position + len].copy_from_slice(input_channel);
+
+                let output_channel = &mut output.channel_data_mut(channel)[range.clone()];
+                output_channel.copy_from_slice(&state.output[position..position + len]);
+            }
+
+            self.position += len;
+            offset += len;
+
+            if self.position == hop_size {
+                self.position = 0;
+
+                for channel in 0..number_of_channels {
+                    self.process_frame(channel, ratio);
+
+                    let state = &mut self.states[channel];
+                    state.output.copy_from_slice(&state.accumulator[..hop_size]);
+                    state.accumulator.copy_within(hop_size.., 0);
+                    state.accumulator[kept..].fill(0.);
+                    state.input.copy_within(hop_size.., 0);
+                }
+            }
+        }
+
+        true
+    }
+
+    fn onmessage(&mut self, msg: &mut dyn Any) {
+        if let Some(&preserve_formants) = msg.downcast_ref::<bool>() {
+            self.preserve_formants = preserve_formants;
+            return;
+        }
+
+        log::warn!("PitchShifterRenderer: Dropping incoming message {msg:?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    /// Estimate the frequency of a signal by counting its zero crossings
+    fn zero_crossings_frequency(signal: &[f32], sample_rate: f32) -> f32 {
+        let crossings = signal
+            .windows(2)
+            .filter(|w| w[0] <= 0. && w[1] > 0.)
+            .count();
+        crossings as f32 * sample_rate / signal.len() as f32
+    }
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let pitch_shifter = context.create_pitch_shifter();
+
+        assert_float_eq!(pitch_shifter.pitch().value(), 0., abs <= 0.);
+        assert!(!pitch_shifter.preserve_formants());
+        assert_eq!(pitch_shifter.quality(), PitchShifterQuality::Medium);
+        assert_float_eq!(pitch_shifter.latency(), 2048. / 44_100., abs <= 1e-9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_count() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let pitch_shifter = context.create_pitch_shifter();
+        pitch_shifter.set_channel_count(3);
+    }
+
+    #[test]
+    fn test_latency_and_identity() {
+        let sample_rate = 44_100.;
+        let length = 16_384;
+
+        for quality in [
+            PitchShifterQuality::Low,
+            PitchShifterQuality::Medium,
+            PitchShifterQuality::High,
+        ] {
+            let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+            let pitch_shifter = PitchShifterNode::new(
+                &context,
+                PitchShifterOptions {
+                    quality,
+                    ..PitchShifterOptions::default()
+                },
+            );
+            pitch_shifter.connect(&context.destination());
+
+            let mut osc = context.create_oscillator();
+            osc.frequency().set_value(441.);
+            osc.connect(&pitch_shifter);
+            osc.start();
+
+            let res = context.start_rendering_sync();
+            let channel = res.get_channel_data(0);
+
+            // without shift, the output is the input delayed by the latency
+            let latency = quality.frame_size();
+            let start = 2 * latency;
+            channel[start..].iter().enumerate().for_each(|(i, v)| {
+                let phase = 2. * PI * 441. * (start + i - latency) as f32 / sample_rate;
+                assert_float_eq!(*v, phase.sin(), abs <= 1e-2);
+            });
+        }
+    }
+
+    #[test]
+    fn test_pitch_shift_keeps_duration() {
+        let sample_rate = 44_100.;
+        let length = 44_100;
+
+        for (pitch, expected) in [(1200., 880.), (-1200., 220.), (700., 659.26)] {
+            let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+            let pitch_shifter = PitchShifterNode::new(
+                &context,
+                PitchShifterOptions {
+                    pitch,
+                    ..PitchShifterOptions::default()
+                },
+            );
+            pitch_shifter.connect(&context.destination());
+
+            let mut osc = context.create_oscillator();
+            osc.frequency().set_value(440.);
+            osc.connect(&pitch_shifter);
+            osc.start();
+            osc.stop_at(0.5);
+
+            let res = context.start_rendering_sync();
+            let channel = res.get_channel_data(0);
+
+            let frequency = zero_crossings_frequency(&channel[4096..20_000], sample_rate);
+            assert_float_eq!(frequency, expected, rmax <= 0.02);
+
+            // the output stops after the input stop time plus the latency, and the
+            // analysis frame spanning the stop time
+            let end = 22_050 + 2 * 2048;
+            assert!(channel[end..].iter().all(|v| v.abs() < 1e-3));
+            assert!(channel[22_050 + 1024..22_050 + 2048]
+                .iter()
+                .any(|v| v.abs() > 0.1));
+        }
+    }
+
+    #[test]
+    fn test_preserve_formants() {
+        let sample_rate = 44_100.;
+        let length = 44_100;
+
+        let render = |preserve_formants: bool| {
+            let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+            let pitch_shifter = PitchShifterNode::new(
+                &context,
+                PitchShifterOptions {
+                    pitch: 1200.,
+                    preserve_formants,
+                    ..PitchShifterOptions::default()
+                },
+            );
+            pitch_shifter.connect(&context.destination());
+
+            // sawtooth through a resonant lowpass: the spectral envelope peaks at 800Hz
+            let filter = context.create_biquad_filter();
+            filter.frequency().set_value(800.);
+            filter.q().set_value(10.);
+            filter.connect(&pitch_shifter);
+
+            let mut osc = context.create_oscillator();
+            osc.set_type(crate::node::OscillatorType::Sawtooth);
+            osc.frequency().set_value(100.);
+            osc.connect(&filter);
+            osc.start();
+
+            context.start_rendering_sync().get_channel_data(0).to_vec()
+        };
+
+        // energy of a signal around the given frequency
+        let energy_at = |signal: &[f32], frequency: f32| {
+            let (re, im) = signal
+                .iter()
+                .enumerate()
+                .fold((0., 0.), |(re, im), (i, v)| {
+                    let phase = 2. * PI * frequency * i as f32 / sample_rate;
+                    (re + v * phase.cos(), im + v * phase.sin())
+                });
+            re * re + im * im
+        };
+
+        let shifted = render(false);
+        let preserved = render(true);
+
+        // without formant preservation the resonance moves to 1600Hz, with formant
+        // preservation it stays at 800Hz (the partials are at multiples of 200Hz)
+        let shifted = &shifted[8192..];
+        let preserved = &preserved[8192..];
+        assert!(energy_at(shifted, 1600.) > energy_at(shifted, 800.));
+        assert!(energy_at(preserved, 800.) > energy_at(preserved, 1600.));
+    }
+}
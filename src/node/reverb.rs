@@ -0,0 +1,816 @@
+//! The reverb control and renderer parts
+use crate::context::{AudioContextRegistration, AudioParamId, BaseAudioContext};
+use crate::param::{AudioParam, AudioParamDescriptor, AutomationRate};
+use crate::render::{
+    AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope,
+};
+use crate::RENDER_QUANTUM_SIZE;
+
+use super::{AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation};
+
+/// Number of delay lines in the feedback delay network
+const NUM_DELAY_LINES: usize = 8;
+
+/// Length of the delay lines (in seconds) for a `size` of zero.
+///
+/// These are the comb filter tunings of Freeverb (1116 to 1617 samples at 44.1kHz), which are
+/// mutually prime and spread evenly enough to avoid audible flutter.
+const DELAY_LINE_LENGTHS: [f32; NUM_DELAY_LINES] = [
+    0.025_306, 0.026_939, 0.028_957, 0.030_748, 0.032_245, 0.033_810, 0.035_306, 0.036_667,
+];
+
+/// Scale factor applied to the delay line lengths when `size` is at its maximum value
+const MAX_SIZE_SCALE: f32 = 3.;
+
+/// Length of the allpass diffusers (in seconds), Freeverb tunings at 44.1kHz
+const ALLPASS_LENGTHS: [f32; 4] = [0.012_608, 0.010_000, 0.007_732, 0.005_102];
+
+/// Offset (in seconds) of the right channel allpass diffusers, decorrelates the stereo output
+const STEREO_SPREAD: f32 = 0.000_522;
+
+const ALLPASS_FEEDBACK: f32 = 0.5;
+
+/// Maximum value of the `damping` coefficient of the one-pole lowpass filters in the
+/// feedback loop, a coefficient of 1 would block the signal entirely.
+const MAX_DAMPING_COEFFICIENT: f32 = 0.7;
+
+/// Maximum value of the `pre_delay` parameter (in seconds)
+const MAX_PRE_DELAY: f32 = 1.;
+
+/// Options for constructing a [`ReverbNode`]
+#[derive(Clone, Debug)]
+pub struct ReverbOptions {
+    /// Time (in seconds) for the reverberation to decay by 60dB
+    pub decay_time: f32,
+    /// Delay (in seconds) applied to the input before it enters the reverberator
+    pub pre_delay: f32,
+    /// Attenuation of the high frequencies in the tail, from 0 (bright) to 1 (dark)
+    pub damping: f32,
+    /// Size of the simulated room, from 0 (small) to 1 (large)
+    pub size: f32,
+    /// Amount of reverberated signal in the output, from 0 (dry) to 1 (wet)
+    pub mix: f32,
+    /// audio node options
+    pub audio_node_options: AudioNodeOptions,
+}
+
+impl Default for ReverbOptions {
+    fn default() -> Self {
+        Self {
+            decay_time: 2.,
+            pre_delay: 0.,
+            damping: 0.5,
+            size: 0.5,
+            mix: 0.3,
+            audio_node_options: AudioNodeOptions {
+                channel_count: 2,
+                channel_count_mode: ChannelCountMode::ClampedMax,
+                channel_interpretation: ChannelInterpretation::Speakers,
+            },
+        }
+    }
+}
+
+/// Assert that the channel count is valid for the ReverbNode
+///
+/// # Panics
+///
+/// This function panics if given count is greater than 2
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_count(count: usize) {
+    assert!(
+        count <= 2,
+        "NotSupportedError - ReverbNode channel count cannot be greater than two"
+    );
+}
+
+/// Assert that the channel count mode is valid for the ReverbNode
+///
+/// # Panics
+///
+/// This function panics if given count mode is [`ChannelCountMode::Max`]
+///
+#[track_caller]
+#[inline(always)]
+fn assert_valid_channel_count_mode(mode: ChannelCountMode) {
+    assert_ne!(
+        mode,
+        ChannelCountMode::Max,
+        "NotSupportedError - ReverbNode channel count mode cannot be set to max"
+    );
+}
+
+/// `ReverbNode` applies a parametric (algorithmic) reverberation to its input
+///
+/// This node is not part of the Web Audio API specification. It provides a
+/// cheap alternative to the [`ConvolverNode`](super::ConvolverNode) when no
+/// impulse response is available, or when the CPU cost of a long impulse
+/// response is not affordable.
+///
+/// The reverberation is computed by a feedback delay network of 8 delay lines
+/// mixed through a Householder matrix, followed by Freeverb-style allpass
+/// diffusers. The input is mixed down to (or up to) stereo and the output is
+/// always stereo.
+///
+/// - see also: [`BaseAudioContext::create_reverb`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+///
+/// let context = AudioContext::default();
+///
+/// let reverb = context.create_reverb();
+/// reverb.decay_time().set_value(4.);
+/// reverb.mix().set_value(0.5);
+/// reverb.connect(&context.destination());
+///
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&reverb);
+/// osc.start();
+/// osc.stop_at(context.current_time() + 0.2);
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example reverb`
+///
+#[derive(Debug)]
+pub struct ReverbNode {
+    /// Represents the node instance and its associated audio context
+    registration: AudioContextRegistration,
+    /// Infos about audio node channel configuration
+    channel_config: ChannelConfig,
+    decay_time: AudioParam,
+    pre_delay: AudioParam,
+    damping: AudioParam,
+    size: AudioParam,
+    mix: AudioParam,
+}
+
+impl AudioNode for ReverbNode {
+    fn registration(&self) -> &AudioContextRegistration {
+        &self.registration
+    }
+
+    fn channel_config(&self) -> &ChannelConfig {
+        &self.channel_config
+    }
+
+    fn number_of_inputs(&self) -> usize {
+        1
+    }
+
+    fn number_of_outputs(&self) -> usize {
+        1
+    }
+
+    fn set_channel_count_mode(&self, mode: ChannelCountMode) {
+        assert_valid_channel_count_mode(mode);
+        self.channel_config
+            .set_count_mode(mode, self.registration());
+    }
+
+    fn set_channel_count(&self, count: usize) {
+        assert_valid_channel_count(count);
+        self.channel_config.set_count(count, self.registration());
+    }
+}
+
+impl ReverbNode {
+    /// Creates a `ReverbNode`
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - audio context in which the audio node will live.
+    /// * `options` - reverb options
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    ///
+    /// * `options.audio_node_options.channel_count` is greater than 2
+    /// * `options.audio_node_options.channel_count_mode` is `ChannelCountMode::Max`
+    ///
+    pub fn new<C: BaseAudioContext>(context: &C, options: ReverbOptions) -> Self {
+        context.base().register(move |registration| {
+            assert_valid_channel_count_mode(options.audio_node_options.channel_count_mode);
+            assert_valid_channel_count(options.audio_node_options.channel_count);
+
+            let decay_time_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.01,
+                max_value: 60.,
+                default_value: 2.,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut decay_time_param, decay_time_proc) =
+                context.create_audio_param(decay_time_opts, &registration);
+            decay_time_param.set_automation_rate_constrained(true);
+            decay_time_param.set_value(options.decay_time);
+
+            let pre_delay_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.,
+                max_value: MAX_PRE_DELAY,
+                default_value: 0.,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut pre_delay_param, pre_delay_proc) =
+                context.create_audio_param(pre_delay_opts, &registration);
+            pre_delay_param.set_automation_rate_constrained(true);
+            pre_delay_param.set_value(options.pre_delay);
+
+            let damping_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.,
+                max_value: 1.,
+                default_value: 0.5,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut damping_param, damping_proc) =
+                context.create_audio_param(damping_opts, &registration);
+            damping_param.set_automation_rate_constrained(true);
+            damping_param.set_value(options.damping);
+
+            let size_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.,
+                max_value: 1.,
+                default_value: 0.5,
+                automation_rate: AutomationRate::K,
+            };
+            let (mut size_param, size_proc) = context.create_audio_param(size_opts, &registration);
+            size_param.set_automation_rate_constrained(true);
+            size_param.set_value(options.size);
+
+            let mix_opts = AudioParamDescriptor {
+                name: String::new(),
+                min_value: 0.,
+                max_value: 1.,
+                default_value: 0.3,
+                automation_rate: AutomationRate::A,
+            };
+            let (mix_param, mix_proc) = context.create_audio_param(mix_opts, &registration);
+            mix_param.set_value(options.mix);
+
+            let renderer = ReverbRenderer::new(
+                context.sample_rate(),
+                &options,
+                decay_time_proc,
+                pre_delay_proc,
+                damping_proc,
+                size_proc,
+                mix_proc,
+            );
+
+            let node = Self {
+                registration,
+                channel_config: options.audio_node_options.into(),
+                decay_time: decay_time_param,
+                pre_delay: pre_delay_param,
+                damping: damping_param,
+                size: size_param,
+                mix: mix_param,
+            };
+
+            (node, Box::new(renderer))
+        })
+    }
+
+    /// K-rate [`AudioParam`] representing the time (in seconds) it takes for
+    /// the reverberation to decay by 60dB, also known as RT60.
+    #[must_use]
+    pub fn decay_time(&self) -> &AudioParam {
+        &self.decay_time
+    }
+
+    /// K-rate [`AudioParam`] representing the delay (in seconds) between the
+    /// dry signal and the onset of the reverberation, in the range `[0, 1]`.
+    #[must_use]
+    pub fn pre_delay(&self) -> &AudioParam {
+        &self.pre_delay
+    }
+
+    /// K-rate [`AudioParam`] controlling how fast the high frequencies decay
+    /// compared to the low frequencies, from 0 (bright) to 1 (dark).
+    #[must_use]
+    pub fn damping(&self) -> &AudioParam {
+        &self.damping
+    }
+
+    /// K-rate [`AudioParam`] controlling the size of the simulated room, i.e.
+    /// the echo density and spacing of the reverberation, from 0 to 1.
+    #[must_use]
+    pub fn size(&self) -> &AudioParam {
+        &self.size
+    }
+
+    /// A-rate [`AudioParam`] representing the balance between the dry and the
+    /// reverberated signals, from 0 (dry only) to 1 (wet only).
+    #[must_use]
+    pub fn mix(&self) -> &AudioParam {
+        &self.mix
+    }
+}
+
+/// Circular buffer of samples with integer read offsets
+struct DelayLine {
+    buffer: Vec<f32>,
+    write_index: usize,
+}
+
+impl DelayLine {
+    fn new(len: usize) -> Self {
+        Self {
+            buffer: vec![0.; len.max(1)],
+            write_index: 0,
+        }
+    }
+
+    /// Read the sample written `delay` samples ago, `delay` must be in the range `[1, len]`
+    #[inline(always)]
+    fn read(&self, delay: usize) -> f32 {
+        let len = self.buffer.len();
+        self.buffer[(self.write_index + len - delay) % len]
+    }
+
+    #[inline(always)]
+    fn write(&mut self, value: f32) {
+        self.buffer[self.write_index] = value;
+        self.write_index = (self.write_index + 1) % self.buffer.len();
+    }
+}
+
+/// Schroeder allpass filter used to diffuse the output of the delay network
+struct AllpassFilter {
+    line: DelayLine,
+}
+
+impl AllpassFilter {
+    fn new(len: usize) -> Self {
+        Self {
+            line: DelayLine::new(len),
+        }
+    }
+
+    #[inline(always)]
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.line.read(self.line.buffer.len());
+        self.line.write(delayed.mul_add(ALLPASS_FEEDBACK, input));
+        delayed - input
+    }
+}
+
+/// `ReverbRenderer` represents the rendering part of `ReverbNode`
+struct ReverbRenderer {
+    decay_time: AudioParamId,
+    pre_delay: AudioParamId,
+    damping: AudioParamId,
+    size: AudioParamId,
+    mix: AudioParamId,
+    pre_delay_lines: [DelayLine; 2],
+    delay_lines: [DelayLine; NUM_DELAY_LINES],
+    damping_states: [f32; NUM_DELAY_LINES],
+    /// Delay line lengths and gains of the previous render quantum, crossfaded into the new
+    /// ones so that `size` and `decay_time` changes do not make the output jump
+    lengths: [usize; NUM_DELAY_LINES],
+    gains: [f32; NUM_DELAY_LINES],
+    allpass_filters: [[AllpassFilter; 4]; 2],
+    /// Number of samples left before the reverberation tail is considered silent
+    tail_remaining: usize,
+}
+
+impl ReverbRenderer {
+    fn new(
+        sample_rate: f32,
+        initial: &ReverbOptions,
+        decay_time: AudioParamId,
+        pre_delay: AudioParamId,
+        damping: AudioParamId,
+        size: AudioParamId,
+        mix: AudioParamId,
+    ) -> Self {
+        let pre_delay_len = (MAX_PRE_DELAY * sample_rate).ceil() as usize + 1;
+        let pre_delay_lines = [DelayLine::new(pre_delay_len), DelayLine::new(pre_delay_len)];
+
+        let delay_lines = DELAY_LINE_LENGTHS
+            .map(|len| DelayLine::new((len * MAX_SIZE_SCALE * sample_rate).ceil() as usize));
+
+        // same clamping as the parameters
+        let initial_size = initial.size.clamp(0., 1.);
+        let initial_decay_time = initial.decay_time.clamp(0.01, 60.);
+        let lengths = delay_line_lengths(initial_size, sample_rate, &delay_lines);
+        let gains = delay_line_gains(&lengths, initial_decay_time, sample_rate);
+
+        let spread = STEREO_SPREAD * sample_rate;
+        let allpass_filters = [0., spread].map(|offset| {
+            ALLPASS_LENGTHS.map(|len| AllpassFilter::new((len * sample_rate + offset) as usize))
+        });
+
+        Self {
+            decay_time,
+            pre_delay,
+            damping,
+            size,
+            mix,
+            pre_delay_lines,
+            delay_lines,
+            damping_states: [0.; NUM_DELAY_LINES],
+            lengths,
+            gains,
+            allpass_filters,
+            tail_remaining: 0,
+        }
+    }
+}
+
+impl AudioProcessor for ReverbRenderer {
+    fn process(
+        &mut self,
+        inputs: &[AudioRenderQuantum],
+        outputs: &mut [AudioRenderQuantum],
+        params: AudioParamValues<'_>,
+        scope: &AudioWorkletGlobalScope,
+    ) -> bool {
+        // single input/output node
+        let input = &inputs[0];
+        let output = &mut outputs[0];
+        let sample_rate = scope.sample_rate;
+
+        let decay_time = params.get(&self.decay_time)[0];
+        let pre_delay = params.get(&self.pre_delay)[0];
+
+        // keep the node alive until the tail decayed below -120dB
+        if !input.is_silent() {
+            let tail_time = pre_delay + 2. * decay_time;
+            self.tail_remaining = (tail_time * sample_rate) as usize
+                + self.delay_lines[NUM_DELAY_LINES - 1].buffer.len();
+        } else if self.tail_remaining == 0 {
+            output.make_silent();
+            return false;
+        } else {
+            self.tail_remaining = self.tail_remaining.saturating_sub(RENDER_QUANTUM_SIZE);
+        }
+
+        // k-rate parameters, recompute the delay network coefficients
+        let damping = params.get(&self.damping)[0] * MAX_DAMPING_COEFFICIENT;
+        let size = params.get(&self.size)[0];
+        let pre_delay_samples = (pre_delay * sample_rate).round() as usize;
+
+        let lengths = delay_line_lengths(size, sample_rate, &self.delay_lines);
+        let gains = delay_line_gains(&lengths, decay_time, sample_rate);
+        let previous_lengths = std::mem::replace(&mut self.lengths, lengths);
+        let previous_gains = std::mem::replace(&mut self.gains, gains);
+        let crossfade = previous_lengths != lengths;
+
+        // a-rate parameter
+        let mix = params.get(&self.mix);
+
+        let (input_left, input_right) = match input.number_of_channels() {
+            1 => (input.channel_data(0), input.channel_data(0)),
+            2 => (input.channel_data(0), input.channel_data(1)),
+            _ => panic!("ReverbNode should not have more than 2 channels to process"),
+        };
+
+        output.set_number_of_channels(2);
+        let [output_left, output_right] = output.stereo_mut();
+
+        let Self {
+            pre_delay_lines,
+            delay_lines,
+            damping_states,
+            allpass_filters,
+            ..
+        } = self;
+
+        for (i, (o_left, o_right)) in output_left
+            .iter_mut()
+            .zip(output_right.iter_mut())
+            .enumerate()
+        {
+            let dry = [input_left[i], input_right[i]];
+            // progress of the transition from the previous lengths and gains
+            let t = (i + 1) as f32 / RENDER_QUANTUM_SIZE as f32;
+
+            // apply pre-delay
+            let mut delayed = [0.; 2];
+            delayed
+                .iter_mut()
+                .zip(pre_delay_lines.iter_mut())
+                .zip(dry.iter())
+                .for_each(|((d, line), &x)| {
+                    *d = if pre_delay_samples == 0 {
+                        x
+                    } else {
+                        line.read(pre_delay_samples)
+                    };
+                    line.write(x);
+                });
+
+            // read the delay lines and apply damping and decay
+            let mut taps = [0.; NUM_DELAY_LINES];
+            let mut feedback = [0.; NUM_DELAY_LINES];
+
+            for (j, line) in delay_lines.iter().enumerate() {
+                let tap = if crossfade {
+                    let previous = line.read(previous_lengths[j]);
+                    t.mul_add(line.read(lengths[j]) - previous, previous)
+                } else {
+                    line.read(lengths[j])
+                };
+                let gain = t.mul_add(gains[j] - previous_gains[j], previous_gains[j]);
+
+                damping_states[j] = damping.mul_add(damping_states[j] - tap, tap);
+                taps[j] = tap;
+                feedback[j] = damping_states[j] * gain;
+            }
+
+            // Householder feedback matrix `I - 2/N * 1 * 1^T` is orthogonal and cheap to compute
+            let sum = feedback.iter().sum::<f32>() * (2. / NUM_DELAY_LINES as f32);
+
+            for (j, line) in delay_lines.iter_mut().enumerate() {
+                // distribute left input on even lines and right input on odd lines
+                let x = 0.5 * delayed[j % 2];
+                line.write(x + feedback[j] - sum);
+            }
+
+            // sum the taps to the outputs with alternating signs
+            let mut wet = [0.; 2];
+            taps.chunks_exact(2).enumerate().for_each(|(k, pair)| {
+                let sign = if k % 2 == 0 { 1. } else { -1. };
+                wet[0] += sign * pair[0];
+                wet[1] += sign * pair[1];
+            });
+
+            wet.iter_mut()
+                .zip(allpass_filters.iter_mut())
+                .for_each(|(w, filters)| {
+                    *w = filters
+                        .iter_mut()
+                        .fold(*w * 0.5, |acc, filter| filter.process(acc));
+                });
+
+            let mix = if mix.len() == 1 { mix[0] } else { mix[i] };
+            *o_left = (1. - mix).mul_add(dry[0], mix * wet[0]);
+            *o_right = (1. - mix).mul_add(dry[1], mix * wet[1]);
+        }
+
+        true
+    }
+}
+
+/// Length (in samples) of each delay line for the given `size`
+fn delay_line_lengths(
+    size: f32,
+    sample_rate: f32,
+    lines: &[DelayLine; NUM_DELAY_LINES],
+) -> [usize; NUM_DELAY_LINES] {
+    let scale = 1. + (MAX_SIZE_SCALE - 1.) * size;
+    let mut lengths = [0; NUM_DELAY_LINES];
+
+    lengths
+        .iter_mut()
+        .zip(DELAY_LINE_LENGTHS.iter().zip(lines.iter()))
+        .for_each(|(length, (&base, line))| {
+            *length = ((base * scale * sample_rate) as usize).clamp(1, line.buffer.len());
+        });
+
+    lengths
+}
+
+/// Attenuation per pass through each delay line so that the overall decay reaches -60dB
+/// after `decay_time` seconds
+fn delay_line_gains(
+    lengths: &[usize; NUM_DELAY_LINES],
+    decay_time: f32,
+    sample_rate: f32,
+) -> [f32; NUM_DELAY_LINES] {
+    lengths.map(|length| 10_f32.powf(-3. * length as f32 / (decay_time * sample_rate)))
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constructor_default() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let reverb = context.create_reverb();
+
+        assert_float_eq!(reverb.decay_time().value(), 2., abs <= 0.);
+        assert_float_eq!(reverb.pre_delay().value(), 0., abs <= 0.);
+        assert_float_eq!(reverb.damping().value(), 0.5, abs <= 0.);
+        assert_float_eq!(reverb.size().value(), 0.5, abs <= 0.);
+        assert_float_eq!(reverb.mix().value(), 0.3, abs <= 0.);
+        assert_eq!(reverb.channel_count(), 2);
+        assert_eq!(reverb.channel_count_mode(), ChannelCountMode::ClampedMax);
+    }
+
+    #[test]
+    fn test_constructor_non_default() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let reverb = ReverbNode::new(
+            &context,
+            ReverbOptions {
+                decay_time: 5.,
+                pre_delay: 0.1,
+                damping: 0.2,
+                size: 0.9,
+                mix: 1.,
+                ..ReverbOptions::default()
+            },
+        );
+
+        assert_float_eq!(reverb.decay_time().value(), 5., abs <= 0.);
+        assert_float_eq!(reverb.pre_delay().value(), 0.1, abs <= 0.);
+        assert_float_eq!(reverb.damping().value(), 0.2, abs <= 0.);
+        assert_float_eq!(reverb.size().value(), 0.9, abs <= 0.);
+        assert_float_eq!(reverb.mix().value(), 1., abs <= 0.);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_invalid_channel_count() {
+        let context = OfflineAudioContext::new(2, 1, 44_100.);
+        let reverb = context.create_reverb();
+        reverb.set_channel_count(3);
+    }
+
+    #[test]
+    fn test_dry_signal() {
+        let sample_rate = 44_100.;
+        let mut context = OfflineAudioContext::new(2, 128, sample_rate);
+
+        let reverb = ReverbNode::new(
+            &context,
+            ReverbOptions {
+                mix: 0.,
+                ..ReverbOptions::default()
+            },
+        );
+        reverb.connect(&context.destination());
+
+        let mut src = context.create_constant_source();
+        src.connect(&reverb);
+        src.start();
+
+        let res = context.start_rendering_sync();
+        // mono input is upmixed to stereo
+        assert_float_eq!(res.get_channel_data(0)[..], [1.; 128], abs_all <= 0.);
+        assert_float_eq!(res.get_channel_data(1)[..], [1.; 128], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_pre_delay() {
+        let sample_rate = 48_000.;
+        let length = 48_000;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        let pre_delay = 0.1;
+        let reverb = ReverbNode::new(
+            &context,
+            ReverbOptions {
+                pre_delay,
+                mix: 1.,
+                ..ReverbOptions::default()
+            },
+        );
+        reverb.connect(&context.destination());
+
+        let mut dirac = context.create_buffer(1, 1, sample_rate);
+        dirac.copy_to_channel(&[1.], 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&reverb);
+        src.set_buffer(dirac);
+        src.start();
+
+        let res = context.start_rendering_sync();
+
+        // nothing comes out of the reverb before pre delay and shortest delay line
+        let onset = ((pre_delay + DELAY_LINE_LENGTHS[0]) * sample_rate) as usize;
+        for channel in [res.get_channel_data(0), res.get_channel_data(1)] {
+            assert_float_eq!(channel[..onset], vec![0.; onset][..], abs_all <= 0.);
+            assert!(channel[onset..].iter().any(|v| *v != 0.));
+        }
+    }
+
+    #[test]
+    fn test_stereo_decorrelated_and_decaying() {
+        let sample_rate = 44_100.;
+        let length = 44_100 * 2;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        let reverb = ReverbNode::new(
+            &context,
+            ReverbOptions {
+                decay_time: 0.5,
+                mix: 1.,
+                ..ReverbOptions::default()
+            },
+        );
+        reverb.connect(&context.destination());
+
+        let mut dirac = context.create_buffer(1, 1, sample_rate);
+        dirac.copy_to_channel(&[1.], 0);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&reverb);
+        src.set_buffer(dirac);
+        src.start();
+
+        let res = context.start_rendering_sync();
+        let left = res.get_channel_data(0);
+        let right = res.get_channel_data(1);
+
+        assert!(left != right);
+
+        let energy = |s: &[f32]| s.iter().map(|v| v * v).sum::<f32>();
+        let first = energy(&left[..11_025]);
+        // decay time of 0.5 sec means -60dB after 0.5 sec, so we expect the
+        // energy of the second half of the buffer to be far lower
+        let last = energy(&left[44_100..]);
+        assert!(first > 0.);
+        assert!(last < first * 1e-6);
+        assert!(left.iter().chain(right.iter()).all(|v| v.abs() < 1.));
+    }
+
+    #[test]
+    fn test_tail_keeps_node_alive() {
+        let sample_rate = 44_100.;
+        let length = 44_100;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        // drop the control thread handles so the render lifecycle rules kick in
+        {
+            let reverb = ReverbNode::new(
+                &context,
+                ReverbOptions {
+                    mix: 1.,
+                    ..ReverbOptions::default()
+                },
+            );
+            reverb.connect(&context.destination());
+
+            let mut src = context.create_constant_source();
+            src.connect(&reverb);
+            src.start();
+            src.stop_at(128. / sample_rate as f64);
+        }
+
+        let res = context.start_rendering_sync();
+        // the reverb still outputs signal after the source has stopped
+        assert!(res.get_channel_data(0)[22_050..].iter().any(|v| *v != 0.));
+    }
+
+    #[test]
+    fn test_size_ramp_is_continuous() {
+        let sample_rate = 44_100.;
+        let length = 2 * 44_100;
+        let mut context = OfflineAudioContext::new(2, length, sample_rate);
+
+        let reverb = ReverbNode::new(
+            &context,
+            ReverbOptions {
+                mix: 1.,
+                ..ReverbOptions::default()
+            },
+        );
+        // steady state during the first second, then grow the room
+        reverb
+            .size()
+            .set_value_at_time(0.5, 1.)
+            .linear_ramp_to_value_at_time(1., 2.);
+        reverb.connect(&context.destination());
+
+        let mut osc = context.create_oscillator();
+        osc.frequency().set_value(100.);
+        osc.connect(&reverb);
+        osc.start();
+
+        let res = context.start_rendering_sync();
+        let left = res.get_channel_data(0);
+        // largest step between two consecutive samples, relative to the peak amplitude
+        let relative_step = |s: &[f32]| {
+            let step = s.windows(2).map(|w| (w[1] - w[0]).abs()).fold(0., f32::max);
+            let peak = s.iter().fold(0., |max: f32, v| max.max(v.abs()));
+            step / peak
+        };
+
+        // the delay line lengths are crossfaded, the output is as smooth as the steady state
+        // signal (the room resonances change its amplitude)
+        let steady = relative_step(&left[22_050..44_100]);
+        let ramp = relative_step(&left[44_100..]);
+        assert!(ramp < 1.2 * steady);
+    }
+}
@@ -1,11 +1,10 @@
 use std::any::Any;
-
-use rubato::{FftFixedInOut, Resampler as _};
+use std::f64::consts::PI;
 
 use crate::{
     context::{AudioContextRegistration, BaseAudioContext},
     render::{AudioParamValues, AudioProcessor, AudioRenderQuantum, AudioWorkletGlobalScope},
-    RENDER_QUANTUM_SIZE,
+    MAX_CHANNELS, RENDER_QUANTUM_SIZE,
 };
 
 use super::{AudioNode, AudioNodeOptions, ChannelConfig};
@@ -22,6 +21,14 @@ pub enum OverSampleType {
     X2,
     /// Oversampled by a factor of 4
     X4,
+    /// Oversampled by a factor of 8
+    ///
+    /// Note that this variant is not part of the Web Audio API specification.
+    X8,
+    /// Oversampled by a factor of 16
+    ///
+    /// Note that this variant is not part of the Web Audio API specification.
+    X16,
 }
 
 impl From<u32> for OverSampleType {
@@ -30,11 +37,54 @@ impl From<u32> for OverSampleType {
             0 => OverSampleType::None,
             1 => OverSampleType::X2,
             2 => OverSampleType::X4,
+            3 => OverSampleType::X8,
+            4 => OverSampleType::X16,
             _ => unreachable!(),
         }
     }
 }
 
+impl OverSampleType {
+    fn factor(self) -> usize {
+        match self {
+            OverSampleType::None => 1,
+            OverSampleType::X2 => 2,
+            OverSampleType::X4 => 4,
+            OverSampleType::X8 => 8,
+            OverSampleType::X16 => 16,
+        }
+    }
+}
+
+/// Quality of the anti-aliasing filters used by the oversampling of a `WaveShaperNode`
+///
+/// Steeper filters better reject the aliasing introduced by the distortion curve, at the
+/// expense of a larger latency and more computations. The latency is given by
+/// [`WaveShaperNode::latency`].
+///
+/// Note that this enum is not part of the Web Audio API specification.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverSampleQuality {
+    /// Short filters, with a latency of 8 frames
+    Low,
+    /// Filters with a latency of 32 frames
+    #[default]
+    Medium,
+    /// Steep filters, with a latency of 64 frames
+    High,
+}
+
+impl OverSampleQuality {
+    /// Half length of the filters, in frames at the context sample rate
+    fn half_length(self) -> usize {
+        match self {
+            OverSampleQuality::Low => 4,
+            OverSampleQuality::Medium => 16,
+            OverSampleQuality::High => 32,
+        }
+    }
+}
+
 /// `WaveShaperNode` options
 // dictionary WaveShaperOptions : AudioNodeOptions {
 //   sequence<float> curve;
@@ -46,6 +96,8 @@ pub struct WaveShaperOptions {
     pub curve: Option<Vec<f32>>,
     /// Oversampling rate - default to `None`
     pub oversample: OverSampleType,
+    /// Quality of the oversampling filters - default to `Medium`
+    pub oversample_quality: OverSampleQuality,
     /// audio node options
     pub audio_node_options: AudioNodeOptions,
 }
@@ -54,6 +106,7 @@ impl Default for WaveShaperOptions {
     fn default() -> Self {
         Self {
             oversample: OverSampleType::None,
+            oversample_quality: OverSampleQuality::default(),
             curve: None,
             audio_node_options: AudioNodeOptions::default(),
         }
@@ -124,6 +177,8 @@ pub struct WaveShaperNode {
     curve: Option<Vec<f32>>,
     /// oversample type
     oversample: OverSampleType,
+    /// quality of the oversampling filters
+    oversample_quality: OverSampleQuality,
 }
 
 impl AudioNode for WaveShaperNode {
@@ -154,16 +209,14 @@ impl WaveShaperNode {
     pub fn new<C: BaseAudioContext>(context: &C, options: WaveShaperOptions) -> Self {
         let WaveShaperOptions {
             oversample,
+            oversample_quality,
             curve,
             audio_node_options: channel_config,
         } = options;
 
         let mut node = context.base().register(move |registration| {
-            let sample_rate = context.sample_rate() as usize;
-
             let renderer = WaveShaperRenderer::new(RendererConfig {
-                oversample,
-                sample_rate,
+                oversampler: oversampler(oversample, oversample_quality),
             });
 
             let node = Self {
@@ -171,6 +224,7 @@ impl WaveShaperNode {
                 channel_config: channel_config.into(),
                 curve: None,
                 oversample,
+                oversample_quality,
             };
 
             (node, Box::new(renderer))
@@ -225,159 +279,174 @@ impl WaveShaperNode {
     /// * `oversample` - the desired `OversampleType` variant
     pub fn set_oversample(&mut self, oversample: OverSampleType) {
         self.oversample = oversample;
-        self.registration.post_message(oversample);
+        self.registration
+            .post_message(oversampler(oversample, self.oversample_quality));
     }
-}
 
-#[derive(Debug, Clone, PartialEq, Eq)]
-struct ResamplerConfig {
-    channels: usize,
-    chunk_size_in: usize,
-    sample_rate_in: usize,
-    sample_rate_out: usize,
-}
-
-impl ResamplerConfig {
-    fn upsample_x2(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE;
-        let sample_rate_in = sample_rate;
-        let sample_rate_out = sample_rate * 2;
-        Self {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        }
+    /// Returns the quality of the oversampling filters of this node
+    #[must_use]
+    pub fn oversample_quality(&self) -> OverSampleQuality {
+        self.oversample_quality
     }
 
-    fn upsample_x4(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE;
-        let sample_rate_in = sample_rate;
-        let sample_rate_out = sample_rate * 4;
-        Self {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        }
+    /// set the quality of the oversampling filters of this node
+    ///
+    /// # Arguments
+    ///
+    /// * `quality` - the desired `OverSampleQuality` variant
+    pub fn set_oversample_quality(&mut self, quality: OverSampleQuality) {
+        self.oversample_quality = quality;
+        self.registration
+            .post_message(oversampler(self.oversample, quality));
     }
 
-    fn downsample_x2(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE * 2;
-        let sample_rate_in = sample_rate * 2;
-        let sample_rate_out = sample_rate;
-        Self {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
+    /// Returns the latency introduced by the oversampling filters, in seconds
+    ///
+    /// The output of the node is delayed by this amount when a curve is set and oversampling
+    /// is enabled. Signals mixed with the output of the node can be delayed by the same amount
+    /// to stay aligned.
+    #[must_use]
+    pub fn latency(&self) -> f64 {
+        if self.curve.is_none() || self.oversample == OverSampleType::None {
+            return 0.;
         }
-    }
 
-    fn downsample_x4(channels: usize, sample_rate: usize) -> Self {
-        let chunk_size_in = RENDER_QUANTUM_SIZE * 4;
-        let sample_rate_in = sample_rate * 4;
-        let sample_rate_out = sample_rate;
-        Self {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        }
+        let frames = 2 * self.oversample_quality.half_length();
+        frames as f64 / self.context().sample_rate() as f64
     }
 }
 
-struct Resampler {
-    config: ResamplerConfig,
-    processor: FftFixedInOut<f32>,
-    samples_out: Vec<Vec<f32>>,
+/// Polyphase FIR up and down sampler
+///
+/// The up and down sampling share the same linear phase low-pass filter, a Blackman-Harris
+/// windowed sinc whose cutoff lies slightly below the Nyquist frequency of the context. Each
+/// of them delays the signal by `half_length` frames.
+///
+/// The oversampler is built on the control thread, with the state of `MAX_CHANNELS` channels
+/// so that processing never allocates on the render thread.
+struct Oversampler {
+    /// oversampling factor
+    factor: usize,
+    /// half length of the filter, in frames at the context sample rate
+    half_length: usize,
+    /// filter taps, `2 * half_length * factor + 1` of them
+    filter: Vec<f32>,
+    /// per channel input history followed by the current render quantum
+    inputs: Vec<Vec<f32>>,
+    /// per channel upsampled history followed by the current upsampled render quantum
+    upsampled: Vec<Vec<f32>>,
 }
 
-impl Resampler {
-    fn new(config: ResamplerConfig) -> Self {
-        let ResamplerConfig {
-            channels,
-            chunk_size_in,
-            sample_rate_in,
-            sample_rate_out,
-        } = &config;
+impl Oversampler {
+    fn new(factor: usize, half_length: usize) -> Self {
+        let length = 2 * half_length * factor + 1;
+        let center = (half_length * factor) as f64;
+        // normalized to the oversampled rate
+        let cutoff = 0.45 / factor as f64;
+
+        let filter: Vec<f64> = (0..length)
+            .map(|n| {
+                let t = n as f64 - center;
+                let sinc = if t == 0. {
+                    2. * cutoff
+                } else {
+                    (2. * PI * cutoff * t).sin() / (PI * t)
+                };
+
+                let phase = 2. * PI * n as f64 / (length - 1) as f64;
+                let window = 0.35875 - 0.48829 * phase.cos() + 0.14128 * (2. * phase).cos()
+                    - 0.01168 * (3. * phase).cos();
 
-        let processor =
-            FftFixedInOut::new(*sample_rate_in, *sample_rate_out, *chunk_size_in, *channels)
-                .unwrap();
+                sinc * window
+            })
+            .collect();
 
-        let samples_out = processor.output_buffer_allocate(true);
+        // unity gain at DC
+        let sum: f64 = filter.iter().sum();
+        let filter: Vec<f32> = filter.iter().map(|h| (h / sum) as f32).collect();
+
+        let input_length = 2 * half_length + RENDER_QUANTUM_SIZE;
+        let upsampled_length = filter.len() - 1 + RENDER_QUANTUM_SIZE * factor;
 
         Self {
-            config,
-            processor,
-            samples_out,
+            factor,
+            half_length,
+            filter,
+            inputs: vec![vec![0.; input_length]; MAX_CHANNELS],
+            upsampled: vec![vec![0.; upsampled_length]; MAX_CHANNELS],
         }
     }
 
-    fn process<T>(&mut self, samples_in: &[T])
-    where
-        T: AsRef<[f32]>,
-    {
-        debug_assert_eq!(self.config.channels, samples_in.len());
-        // Processing the output from another resampler directly as input requires this assumption.
-        debug_assert!(samples_in
-            .iter()
-            .all(|channel| channel.as_ref().len() == self.processor.input_frames_next()));
-        let (in_len, out_len) = self
-            .processor
-            .process_into_buffer(samples_in, &mut self.samples_out[..], None)
-            .unwrap();
-        // All input samples must have been consumed.
-        debug_assert_eq!(in_len, samples_in[0].as_ref().len());
-        // All output samples must have been initialized.
-        debug_assert!(self
-            .samples_out
-            .iter()
-            .all(|channel| channel.len() == out_len));
+    /// Latency of the up and down sampling, in frames at the context sample rate
+    fn latency(&self) -> usize {
+        2 * self.half_length
     }
 
-    fn samples_out(&self) -> &[Vec<f32>] {
-        &self.samples_out[..]
-    }
+    /// Apply the curve to the oversampled signal of the given channel, in place
+    fn process(&mut self, channel: usize, samples: &mut [f32], curve: &[f32]) {
+        let factor = self.factor;
+        let filter = &self.filter[..];
+        let input_history = 2 * self.half_length;
+        let upsampled_history = filter.len() - 1;
+        let input = &mut self.inputs[channel];
+        let upsampled = &mut self.upsampled[channel];
 
-    fn samples_out_mut(&mut self) -> &mut [Vec<f32>] {
-        &mut self.samples_out[..]
+        input[input_history..].copy_from_slice(samples);
+
+        // upsample, the zero stuffing is folded in the phases of the filter
+        upsampled[upsampled_history..]
+            .chunks_exact_mut(factor)
+            .enumerate()
+            .for_each(|(m, frame)| {
+                let history = input[m..=m + input_history].iter().rev();
+
+                frame.iter_mut().enumerate().for_each(|(phase, s)| {
+                    let value: f32 = filter[phase..]
+                        .iter()
+                        .step_by(factor)
+                        .zip(history.clone())
+                        .map(|(h, x)| h * x)
+                        .sum();
+
+                    *s = apply_curve(curve, factor as f32 * value);
+                });
+            });
+
+        // downsample, only the kept samples are computed
+        samples.iter_mut().enumerate().for_each(|(m, s)| {
+            let start = m * factor;
+            *s = filter
+                .iter()
+                .zip(upsampled[start..=start + upsampled_history].iter().rev())
+                .map(|(h, z)| h * z)
+                .sum();
+        });
+
+        input.copy_within(RENDER_QUANTUM_SIZE.., 0);
+        upsampled.copy_within(RENDER_QUANTUM_SIZE * factor.., 0);
     }
 }
 
 /// Helper struct which regroups all parameters
 /// required to build `WaveShaperRenderer`
 struct RendererConfig {
-    /// oversample factor
-    oversample: OverSampleType,
-    /// Sample rate (equals to audio context sample rate)
-    sample_rate: usize,
+    /// up and down sampler, `None` if no oversampling is applied
+    oversampler: Option<Box<Oversampler>>,
 }
 
 /// `WaveShaperRenderer` represents the rendering part of `WaveShaperNode`
 struct WaveShaperRenderer {
-    /// oversample factor
-    oversample: OverSampleType,
     /// distortion curve
     curve: Option<Vec<f32>>,
-    /// Sample rate (equals to audio context sample rate)
-    sample_rate: usize,
-    /// Number of channels used to build the up/down sampler X2
-    channels_x2: usize,
-    /// Number of channels used to build the up/down sampler X4
-    channels_x4: usize,
-    // up sampler configured to multiply by 2 the input signal
-    upsampler_x2: Resampler,
-    // up sampler configured to multiply by 4 the input signal
-    upsampler_x4: Resampler,
-    // down sampler configured to divide by 2 the upsampled signal
-    downsampler_x2: Resampler,
-    // down sampler configured to divide by 4 the upsampled signal
-    downsampler_x4: Resampler,
+    /// up and down sampler, `None` if no oversampling is applied
+    oversampler: Option<Box<Oversampler>>,
     // check if silence can be propagated, i.e. if curve if None or if
     // it's output value for zero signal is zero (i.e. < 1e-9)
     can_propagate_silence: bool,
+    /// number of silent frames processed to flush the oversampling filters
+    tail_count: usize,
+    /// number of channels of the last non silent input, kept during tail time
+    number_of_channels: usize,
 }
 
 impl AudioProcessor for WaveShaperRenderer {
@@ -392,103 +461,52 @@ impl AudioProcessor for WaveShaperRenderer {
         let input = &inputs[0];
         let output = &mut outputs[0];
 
-        if input.is_silent() && self.can_propagate_silence {
-            output.make_silent();
-            return false;
+        let latency = match (&self.curve, &self.oversampler) {
+            (Some(_), Some(oversampler)) => oversampler.latency(),
+            _ => 0,
+        };
+
+        // handle tail time, the oversampling filters must be flushed before going silent
+        if input.is_silent() {
+            if self.can_propagate_silence && self.tail_count >= latency {
+                output.make_silent();
+                return false;
+            }
+
+            self.tail_count += RENDER_QUANTUM_SIZE;
+        } else {
+            self.tail_count = 0;
+            self.number_of_channels = input.number_of_channels();
         }
 
         *output = input.clone();
+        // if in tail time, flush the filters of every channel of the previous input
+        output.set_number_of_channels(self.number_of_channels);
 
         if let Some(curve) = &self.curve {
-            match self.oversample {
-                OverSampleType::None => {
+            match &mut self.oversampler {
+                None => {
                     output.modify_channels(|channel| {
                         channel.iter_mut().for_each(|o| *o = apply_curve(curve, *o));
                     });
                 }
-                OverSampleType::X2 => {
-                    let channels = output.channels();
-
-                    // recreate up/down sampler if number of channels changed
-                    if channels.len() != self.channels_x2 {
-                        self.channels_x2 = channels.len();
-
-                        self.upsampler_x2 = Resampler::new(ResamplerConfig::upsample_x2(
-                            self.channels_x2,
-                            self.sample_rate,
-                        ));
-
-                        self.downsampler_x2 = Resampler::new(ResamplerConfig::downsample_x2(
-                            self.channels_x2,
-                            self.sample_rate,
-                        ));
-                    }
-
-                    self.upsampler_x2.process(channels);
-                    for channel in self.upsampler_x2.samples_out_mut().iter_mut() {
-                        for s in channel.iter_mut() {
-                            *s = apply_curve(curve, *s);
-                        }
-                    }
-
-                    self.downsampler_x2.process(self.upsampler_x2.samples_out());
-
-                    for (processed, output) in self
-                        .downsampler_x2
-                        .samples_out()
-                        .iter()
-                        .zip(output.channels_mut())
-                    {
-                        output.copy_from_slice(&processed[..]);
-                    }
-                }
-                OverSampleType::X4 => {
-                    let channels = output.channels();
-
-                    // recreate up/down sampler if number of channels changed
-                    if channels.len() != self.channels_x4 {
-                        self.channels_x4 = channels.len();
-
-                        self.upsampler_x4 = Resampler::new(ResamplerConfig::upsample_x4(
-                            self.channels_x4,
-                            self.sample_rate,
-                        ));
-
-                        self.downsampler_x4 = Resampler::new(ResamplerConfig::downsample_x4(
-                            self.channels_x4,
-                            self.sample_rate,
-                        ));
-                    }
-
-                    self.upsampler_x4.process(channels);
-
-                    for channel in self.upsampler_x4.samples_out_mut().iter_mut() {
-                        for s in channel.iter_mut() {
-                            *s = apply_curve(curve, *s);
-                        }
-                    }
-
-                    self.downsampler_x4.process(self.upsampler_x4.samples_out());
-
-                    for (processed, output) in self
-                        .downsampler_x4
-                        .samples_out()
-                        .iter()
-                        .zip(output.channels_mut())
-                    {
-                        output.copy_from_slice(&processed[..]);
-                    }
+                Some(oversampler) => {
+                    output
+                        .channels_mut()
+                        .iter_mut()
+                        .enumerate()
+                        .for_each(|(index, channel)| oversampler.process(index, channel, curve));
                 }
             }
         }
 
-        // @tbc - rubato::FftFixedInOut doesn't seem to introduce any latency
-        false
+        self.tail_count < latency
     }
 
     fn onmessage(&mut self, msg: &mut dyn Any) {
-        if let Some(&oversample) = msg.downcast_ref::<OverSampleType>() {
-            self.oversample = oversample;
+        if let Some(oversampler) = msg.downcast_mut::<Option<Box<Oversampler>>>() {
+            // Avoid deallocation in the render thread by swapping the oversampler.
+            std::mem::swap(&mut self.oversampler, oversampler);
             return;
         }
 
@@ -517,41 +535,25 @@ impl AudioProcessor for WaveShaperRenderer {
 
 impl WaveShaperRenderer {
     /// returns an `WaveShaperRenderer` instance
-    #[allow(clippy::missing_const_for_fn)]
     fn new(config: RendererConfig) -> Self {
-        let RendererConfig {
-            sample_rate,
-            oversample,
-        } = config;
-
-        let channels_x2 = 1;
-        let channels_x4 = 1;
-
-        let upsampler_x2 = Resampler::new(ResamplerConfig::upsample_x2(channels_x2, sample_rate));
-
-        let downsampler_x2 =
-            Resampler::new(ResamplerConfig::downsample_x2(channels_x2, sample_rate));
-
-        let upsampler_x4 = Resampler::new(ResamplerConfig::upsample_x4(channels_x2, sample_rate));
-
-        let downsampler_x4 =
-            Resampler::new(ResamplerConfig::downsample_x4(channels_x2, sample_rate));
+        let RendererConfig { oversampler } = config;
 
         Self {
-            oversample,
             curve: None,
-            sample_rate,
-            channels_x2,
-            channels_x4,
-            upsampler_x2,
-            upsampler_x4,
-            downsampler_x2,
-            downsampler_x4,
+            oversampler,
             can_propagate_silence: true,
+            tail_count: 0,
+            number_of_channels: 1,
         }
     }
 }
 
+/// Build the up and down sampler for the given oversampling settings, on the control thread
+fn oversampler(oversample: OverSampleType, quality: OverSampleQuality) -> Option<Box<Oversampler>> {
+    (oversample != OverSampleType::None)
+        .then(|| Box::new(Oversampler::new(oversample.factor(), quality.half_length())))
+}
+
 #[inline]
 fn apply_curve(curve: &[f32], input: f32) -> f32 {
     if curve.is_empty() {
@@ -602,6 +604,8 @@ mod tests {
 
         assert_eq!(shaper.curve(), None);
         assert_eq!(shaper.oversample(), OverSampleType::None);
+        assert_eq!(shaper.oversample_quality(), OverSampleQuality::Medium);
+        assert_eq!(shaper.latency(), 0.);
     }
 
     #[test]
@@ -739,4 +743,151 @@ mod tests {
 
         assert_float_eq!(channel[..], expected[..], abs_all <= 0.);
     }
+
+    #[test]
+    fn test_latency() {
+        let sample_rate = 44100.;
+
+        for (quality, latency) in [
+            (OverSampleQuality::Low, 8),
+            (OverSampleQuality::Medium, 32),
+            (OverSampleQuality::High, 64),
+        ] {
+            let mut context = OfflineAudioContext::new(1, 2 * RENDER_QUANTUM_SIZE, sample_rate);
+
+            let options = WaveShaperOptions {
+                curve: Some(vec![-1., 1.]),
+                oversample: OverSampleType::X4,
+                oversample_quality: quality,
+                ..Default::default()
+            };
+            let shaper = WaveShaperNode::new(&context, options);
+            shaper.connect(&context.destination());
+            assert_float_eq!(shaper.latency(), latency as f64 / 44100., abs <= 0.);
+
+            // impulse at the end of the first render quantum, the filters must be
+            // flushed in the following silent render quantum
+            let mut buffer = context.create_buffer(1, RENDER_QUANTUM_SIZE, sample_rate);
+            buffer.copy_to_channel_with_offset(&[1.], 0, RENDER_QUANTUM_SIZE - 1);
+
+            let mut src = context.create_buffer_source();
+            src.connect(&shaper);
+            src.set_buffer(buffer);
+            src.start();
+
+            let result = context.start_rendering_sync();
+            let channel = result.get_channel_data(0);
+
+            let peak = (0..channel.len()).max_by(|&a, &b| channel[a].total_cmp(&channel[b]));
+            assert_eq!(peak, Some(RENDER_QUANTUM_SIZE - 1 + latency));
+            assert_float_eq!(channel.iter().sum::<f32>(), 1., abs <= 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_tail_keeps_number_of_channels() {
+        let sample_rate = 44100.;
+        let mut context = OfflineAudioContext::new(2, 2 * RENDER_QUANTUM_SIZE, sample_rate);
+
+        let options = WaveShaperOptions {
+            curve: Some(vec![-1., 1.]),
+            oversample: OverSampleType::X4,
+            ..Default::default()
+        };
+        let shaper = WaveShaperNode::new(&context, options);
+        shaper.connect(&context.destination());
+
+        // silent left channel, the right channel must be flushed during tail time
+        let mut buffer = context.create_buffer(2, RENDER_QUANTUM_SIZE, sample_rate);
+        buffer.copy_to_channel(&[0.5; RENDER_QUANTUM_SIZE], 1);
+
+        let mut src = context.create_buffer_source();
+        src.connect(&shaper);
+        src.set_buffer(buffer);
+        src.start();
+
+        let result = context.start_rendering_sync();
+        let left = result.get_channel_data(0);
+        let right = result.get_channel_data(1);
+
+        let tail = RENDER_QUANTUM_SIZE..RENDER_QUANTUM_SIZE + 16;
+        assert_float_eq!(left[tail.clone()], [0.; 16][..], abs_all <= 0.);
+        assert_float_eq!(right[tail], [0.5; 16][..], abs_all <= 1e-2);
+    }
+
+    #[test]
+    fn test_oversampler_preallocated_channels() {
+        // the state of every channel is allocated upfront, on the control thread
+        let mut oversampler = Oversampler::new(4, OverSampleQuality::Low.half_length());
+        let identity = [-1., 1.];
+
+        let mut samples = [0.5; RENDER_QUANTUM_SIZE];
+        oversampler.process(MAX_CHANNELS - 1, &mut samples, &identity);
+
+        // DC goes through unchanged once the filters are settled
+        let settled = 2 * oversampler.latency();
+        assert_float_eq!(
+            samples[settled..],
+            [0.5; RENDER_QUANTUM_SIZE][settled..],
+            abs_all <= 1e-3
+        );
+    }
+
+    #[test]
+    fn test_oversampling_reduces_aliasing() {
+        let sample_rate = 44100.;
+        let length = 64 * RENDER_QUANTUM_SIZE;
+        // 100ms window, the frequencies below are multiples of its resolution
+        let window = 4410;
+
+        // the 3rd harmonic of a 15kHz sine wave, at 45kHz, aliases to 900Hz
+        let alias_level = |oversample: OverSampleType| {
+            let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+            // hard clipping
+            let curve = (0..2048)
+                .map(|i| (3. * (i as f32 / 2047. * 2. - 1.)).clamp(-1., 1.))
+                .collect();
+
+            let options = WaveShaperOptions {
+                curve: Some(curve),
+                oversample,
+                oversample_quality: OverSampleQuality::High,
+                ..Default::default()
+            };
+            let shaper = WaveShaperNode::new(&context, options);
+            shaper.connect(&context.destination());
+
+            let mut osc = context.create_oscillator();
+            osc.frequency().set_value(15000.);
+            osc.connect(&shaper);
+            osc.start();
+
+            let result = context.start_rendering_sync();
+            let channel = &result.get_channel_data(0)[length - window..];
+
+            let (re, im) = channel
+                .iter()
+                .enumerate()
+                .fold((0., 0.), |(re, im), (i, s)| {
+                    let phase = 2. * std::f32::consts::PI * 900. * i as f32 / sample_rate;
+                    (re + s * phase.cos(), im + s * phase.sin())
+                });
+
+            (re * re + im * im).sqrt() / window as f32
+        };
+
+        let reference = alias_level(OverSampleType::None);
+        assert!(reference > 0.01);
+
+        for oversample in [
+            OverSampleType::X2,
+            OverSampleType::X4,
+            OverSampleType::X8,
+            OverSampleType::X16,
+        ] {
+            let level = alias_level(oversample);
+            assert!(level < reference / 1000., "{oversample:?}: {level}");
+        }
+    }
 }
@@ -1,13 +1,14 @@
 //! AudioParam interface
 
 use std::any::Any;
+use std::f32::consts::{FRAC_PI_2, PI};
 use std::slice::{Iter, IterMut};
 use std::sync::atomic::Ordering;
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
 
 use arrayvec::ArrayVec;
 
-use crate::context::AudioContextRegistration;
+use crate::context::{AudioContextRegistration, BaseAudioContext};
 use crate::node::{
     AudioNode, AudioNodeOptions, ChannelConfig, ChannelCountMode, ChannelInterpretation,
 };
@@ -87,6 +88,21 @@ fn compute_exponential_ramp_sample(
     start_value * ratio.powf(phase as f32)
 }
 
+// 𝑣(𝑡) = 𝑉0 + (𝑉1−𝑉0) * 𝑠((𝑡−𝑇0) / (𝑇1−𝑇0)), with 𝑠 the shape of the ramp
+#[inline(always)]
+fn compute_shaped_ramp_sample(
+    start_time: f64,
+    duration: f64,
+    start_value: f32,
+    diff: f32, // end_value - start_value
+    shape: RampShape,
+    time: f64,
+) -> f32 {
+    // rounding of the sample-frames may produce slightly negative phases
+    let phase = ((time - start_time) / duration).max(0.) as f32;
+    diff.mul_add(shape.apply(phase, diff > 0.), start_value)
+}
+
 // 𝑣(𝑡) = 𝑉1 + (𝑉0 − 𝑉1) * 𝑒^−((𝑡−𝑇0) / 𝜏)
 #[inline(always)]
 fn compute_set_target_sample(
@@ -138,6 +154,42 @@ impl AutomationRate {
     }
 }
 
+/// Shape of the ramps scheduled with [`AudioParam::shaped_ramp_to_value_at_time`]
+///
+/// Note that this is not part of the Web Audio API specification.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum RampShape {
+    /// Power curve, the progression of the ramp is raised to the given exponent.
+    ///
+    /// An exponent of 1 is a linear ramp, greater exponents start slowly and
+    /// accelerate, while lower exponents start fast and decelerate.
+    Power(f32),
+    /// Raised cosine S-curve, starting and ending slowly
+    SCurve,
+    /// Equal-power curve, i.e. sine shaped when the value increases and cosine
+    /// shaped when it decreases, so that the power of two crossfaded signals
+    /// stays constant
+    EqualPower,
+}
+
+impl RampShape {
+    // map the progression of the ramp in [0, 1] to the progression of its value
+    #[inline(always)]
+    fn apply(self, phase: f32, rising: bool) -> f32 {
+        match self {
+            Self::Power(exponent) => phase.powf(exponent),
+            Self::SCurve => 0.5 - 0.5 * (PI * phase).cos(),
+            Self::EqualPower => {
+                if rising {
+                    (FRAC_PI_2 * phase).sin()
+                } else {
+                    1. - (FRAC_PI_2 * phase).cos()
+                }
+            }
+        }
+    }
+}
+
 /// Options for constructing an [`AudioParam`]
 #[derive(Clone, Debug)]
 pub struct AudioParamDescriptor {
@@ -148,19 +200,124 @@ pub struct AudioParamDescriptor {
     pub max_value: f32,
 }
 
+/// Automation event scheduled on an [`AudioParam`], see [`AudioParam::scheduled_events`]
+///
+/// Each variant mirrors the `AudioParam` method that scheduled it, so that calling
+/// these methods with the listed events, in order, reproduces the timeline.
+#[non_exhaustive]
+#[derive(Clone, Debug, PartialEq)]
+pub enum AutomationEvent {
+    /// cf. [`AudioParam::set_value_at_time`]
+    SetValueAtTime { value: f32, start_time: f64 },
+    /// cf. [`AudioParam::linear_ramp_to_value_at_time`]
+    LinearRampToValueAtTime { value: f32, end_time: f64 },
+    /// cf. [`AudioParam::exponential_ramp_to_value_at_time`]
+    ExponentialRampToValueAtTime { value: f32, end_time: f64 },
+    /// cf. [`AudioParam::shaped_ramp_to_value_at_time`]
+    ShapedRampToValueAtTime {
+        value: f32,
+        end_time: f64,
+        shape: RampShape,
+    },
+    /// cf. [`AudioParam::set_target_at_time`]
+    SetTargetAtTime {
+        value: f32,
+        start_time: f64,
+        time_constant: f64,
+    },
+    /// cf. [`AudioParam::set_value_curve_at_time`]
+    SetValueCurveAtTime {
+        values: Vec<f32>,
+        start_time: f64,
+        duration: f64,
+    },
+    /// cf. [`AudioParam::cancel_and_hold_at_time`], only listed right after the
+    /// event it truncates
+    CancelAndHoldAtTime { cancel_time: f64 },
+}
+
+impl AutomationEvent {
+    /// Returns the event delayed by `offset` seconds
+    ///
+    /// `offset` may be negative to move the event earlier in time.
+    #[must_use]
+    pub fn time_shifted(self, offset: f64) -> Self {
+        self.map_time(|time| time + offset, 1.)
+    }
+
+    /// Returns the event stretched in time by `factor`, relative to time zero
+    ///
+    /// Durations and time constants are scaled too, so that the shape of the
+    /// automation is preserved.
+    #[must_use]
+    pub fn time_scaled(self, factor: f64) -> Self {
+        self.map_time(|time| time * factor, factor)
+    }
+
+    fn map_time<F: Fn(f64) -> f64>(self, map: F, factor: f64) -> Self {
+        match self {
+            Self::SetValueAtTime { value, start_time } => Self::SetValueAtTime {
+                value,
+                start_time: map(start_time),
+            },
+            Self::LinearRampToValueAtTime { value, end_time } => Self::LinearRampToValueAtTime {
+                value,
+                end_time: map(end_time),
+            },
+            Self::ExponentialRampToValueAtTime { value, end_time } => {
+                Self::ExponentialRampToValueAtTime {
+                    value,
+                    end_time: map(end_time),
+                }
+            }
+            Self::ShapedRampToValueAtTime {
+                value,
+                end_time,
+                shape,
+            } => Self::ShapedRampToValueAtTime {
+                value,
+                end_time: map(end_time),
+                shape,
+            },
+            Self::SetTargetAtTime {
+                value,
+                start_time,
+                time_constant,
+            } => Self::SetTargetAtTime {
+                value,
+                start_time: map(start_time),
+                time_constant: time_constant * factor,
+            },
+            Self::SetValueCurveAtTime {
+                values,
+                start_time,
+                duration,
+            } => Self::SetValueCurveAtTime {
+                values,
+                start_time: map(start_time),
+                duration: duration * factor,
+            },
+            Self::CancelAndHoldAtTime { cancel_time } => Self::CancelAndHoldAtTime {
+                cancel_time: map(cancel_time),
+            },
+        }
+    }
+}
+
 #[derive(PartialEq, Eq, Debug, Copy, Clone)]
 enum AudioParamEventType {
     SetValue,
     SetValueAtTime,
     LinearRampToValueAtTime,
     ExponentialRampToValueAtTime,
+    ShapedRampToValueAtTime,
     CancelScheduledValues,
     SetTargetAtTime,
     CancelAndHoldAtTime,
     SetValueCurveAtTime,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct AudioParamEvent {
     event_type: AudioParamEventType,
     value: f32,
@@ -169,37 +326,54 @@ pub(crate) struct AudioParamEvent {
     cancel_time: Option<f64>,   // populated by `CancelAndHoldAtTime` events
     duration: Option<f64>,      // populated by `SetValueCurveAtTime` events
     values: Option<Box<[f32]>>, // populated by `SetValueCurveAtTime` events
+    shape: Option<RampShape>,   // populated by `ShapedRampToValueAtTime` events
+}
+
+impl AudioParamEvent {
+    // Time used to order the events in the timeline: `SetValue` events are applied
+    // at the start of the render quantum that receives them, i.e. before any other
+    // event, so they are ordered as if scheduled at time zero
+    fn sort_time(&self) -> f64 {
+        if self.event_type == AudioParamEventType::SetValue {
+            0.
+        } else {
+            self.time
+        }
+    }
 }
 
-// Event queue that contains `AudioParamEvent`s, most of the time, events must be
-// ordered (using stable sort), some operation may break this ordering (e.g. `push`)
-// in which cases `sort` must be called explicitly.
-// In the current implementation of the param rendering, `sort` is called once after
-// all events have been inserted in the queue at each `tick` (with the exception
-// `CancelAndHoldAtTime` which needs a clean queue to find its neighbors, but this
-// occurs during the insertion of events)
-// After this point, the queue should be considered sorted and no operations that
-// breaks the ordering should be done.
+// Event queue that contains `AudioParamEvent`s, ordered by `sort_time` (using a
+// stable order for events at the same time). Events are inserted at their place,
+// so that the queue is always sorted.
+//
+// The events are resolved by `handle_incoming_event`, which is shared by the render
+// thread and by the control thread copy of the events, so that both see the exact
+// same timeline.
 #[derive(Debug, Default)]
 struct AudioParamEventTimeline {
     inner: Vec<AudioParamEvent>,
-    dirty: bool,
 }
 
 impl AudioParamEventTimeline {
     fn new() -> Self {
         Self {
             inner: Vec::with_capacity(32),
-            dirty: false,
         }
     }
 
-    fn push(&mut self, item: AudioParamEvent) {
-        self.dirty = true;
-        self.inner.push(item);
+    // Index where the event is inserted, i.e. after the events at the same time
+    fn insertion_index(&self, item: &AudioParamEvent) -> usize {
+        let time = item.sort_time();
+        self.inner
+            .partition_point(|queued| queued.sort_time() <= time)
+    }
+
+    fn insert(&mut self, item: AudioParamEvent) {
+        let index = self.insertion_index(&item);
+        self.inner.insert(index, item);
     }
 
-    // `pop` and `retain` preserve order so they don't make the queue dirty
+    // `pop` and `retain` preserve order
     fn pop(&mut self) -> Option<AudioParamEvent> {
         if !self.inner.is_empty() {
             Some(self.inner.remove(0))
@@ -215,6 +389,11 @@ impl AudioParamEventTimeline {
         self.inner.retain(func);
     }
 
+    // Remove the `count` first events
+    fn drain_front(&mut self, count: usize) {
+        self.inner.drain(..count);
+    }
+
     // Only used to handle special cases in `ExponentialRampToValueAtTime`:
     // as the replaced item has the same time, order is preserved.
     // If the method turned out to be used elsewhere, this could maybe
@@ -227,39 +406,581 @@ impl AudioParamEventTimeline {
         self.inner.is_empty()
     }
 
-    fn unsorted_peek(&self) -> Option<&AudioParamEvent> {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    fn get(&self, index: usize) -> Option<&AudioParamEvent> {
+        self.inner.get(index)
+    }
+
+    fn peek(&self) -> Option<&AudioParamEvent> {
         self.inner.first()
     }
 
-    // panic if dirty, we are doing something wrong here
-    fn peek(&self) -> Option<&AudioParamEvent> {
-        assert!(
-            !self.dirty,
-            "`AudioParamEventTimeline`: Invalid `.peek()` call, the queue is dirty"
-        );
-        self.inner.first()
-    }
+    fn next(&self) -> Option<&AudioParamEvent> {
+        self.inner.get(1)
+    }
+
+    fn iter(&self) -> Iter<'_, AudioParamEvent> {
+        self.inner.iter()
+    }
+
+    fn iter_mut(&mut self) -> IterMut<'_, AudioParamEvent> {
+        self.inner.iter_mut()
+    }
+
+    // Insert an incoming event in the timeline, or apply it if it is a cancel event
+    //
+    // `block_time` is the start time of the render quantum where the event is
+    // applied, `intrinsic_value` the value of the param at this time, and
+    // `has_last_event` tells whether an event has already ended.
+    fn handle_incoming_event(
+        &mut self,
+        mut event: AudioParamEvent,
+        block_time: f64,
+        intrinsic_value: f32,
+        has_last_event: bool,
+    ) {
+        // handle CancelScheduledValues events
+        // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-cancelscheduledvalues
+        if event.event_type == AudioParamEventType::CancelScheduledValues {
+            // remove all events in queue where event.time >= cancel_time
+            // i.e. keep all events where event.time < cancel_time
+            self.retain(|queued| queued.sort_time() < event.time);
+            return; // cancel_values events are not inserted in queue
+        }
+
+        if event.event_type == AudioParamEventType::CancelAndHoldAtTime {
+            // 1. Let 𝐸1 be the event (if any) at time 𝑡1 where 𝑡1 is the
+            // largest number satisfying 𝑡1 ≤ 𝑡𝑐.
+            // 2. Let 𝐸2 be the event (if any) at time 𝑡2 where 𝑡2 is the
+            // smallest number satisfying 𝑡𝑐 < 𝑡2.
+            let mut e1: Option<&mut AudioParamEvent> = None;
+            let mut e2: Option<&mut AudioParamEvent> = None;
+            let mut t1 = f64::MIN;
+            let mut t2 = f64::MAX;
+
+            for queued in self.iter_mut() {
+                let time = queued.sort_time();
+                // closest before cancel time: if several events at same time,
+                // we want the last one
+                if time >= t1 && time <= event.time {
+                    t1 = time;
+                    e1 = Some(queued);
+                    // closest after cancel time: if several events at same time,
+                    // we want the first one
+                } else if time < t2 && time > event.time {
+                    t2 = time;
+                    e2 = Some(queued);
+                }
+            }
+
+            // If 𝐸2 exists:
+            if let Some(matched) = e2 {
+                // If 𝐸2 is a linear or exponential ramp,
+                // Effectively rewrite 𝐸2 to be the same kind of ramp ending
+                // at time 𝑡𝑐 with an end value that would be the value of the
+                // original ramp at time 𝑡𝑐.
+                // @note - this is done during the actual computation of the
+                //  ramp using the cancel_time
+                if matched.event_type == AudioParamEventType::LinearRampToValueAtTime
+                    || matched.event_type == AudioParamEventType::ExponentialRampToValueAtTime
+                    || matched.event_type == AudioParamEventType::ShapedRampToValueAtTime
+                {
+                    matched.cancel_time = Some(event.time);
+                }
+            } else if let Some(matched) = e1 {
+                if matched.event_type == AudioParamEventType::SetTargetAtTime {
+                    // Implicitly insert a setValueAtTime event at time 𝑡𝑐 with
+                    // the value that the setTarget would
+                    // @note - same strategy as for ramps
+                    matched.cancel_time = Some(event.time);
+                } else if matched.event_type == AudioParamEventType::SetValueCurveAtTime {
+                    // If 𝐸1 is a setValueCurve with a start time of 𝑡3 and a duration of 𝑑
+                    // If 𝑡𝑐 <= 𝑡3 + 𝑑 :
+                    // Effectively replace this event with a setValueCurve event
+                    // with a start time of 𝑡3 and a new duration of 𝑡𝑐−𝑡3. However,
+                    // this is not a true replacement; this automation MUST take
+                    // care to produce the same output as the original, and not
+                    // one computed using a different duration. (That would cause
+                    // sampling of the value curve in a slightly different way,
+                    // producing different results.)
+                    let start_time = matched.time;
+                    let duration = matched.duration.unwrap();
+
+                    if event.time <= start_time + duration {
+                        matched.cancel_time = Some(event.time);
+                    }
+                }
+            }
+
+            // [spec] Remove all events with time greater than 𝑡𝑐.
+            self.retain(|queued| {
+                // if the event has a `cancel_time` we use it instead of `time`
+                let time = queued.cancel_time.unwrap_or(queued.sort_time());
+                time <= event.time
+            });
+            return; // cancel_and_hold events are not inserted timeline
+        }
+
+        // handle SetValueCurveAtTime
+        // @note - These rules are checked on the control thread first, so the
+        // render thread never panics on them
+        //
+        // [spec] If setValueCurveAtTime() is called for time 𝑇 and duration 𝐷
+        // and there are any events having a time strictly greater than 𝑇, but
+        // strictly less than 𝑇+𝐷, then a NotSupportedError exception MUST be thrown.
+        // In other words, it’s not ok to schedule a value curve during a time period
+        // containing other events, but it’s ok to schedule a value curve exactly
+        // at the time of another event.
+        if event.event_type == AudioParamEventType::SetValueCurveAtTime {
+            // check if we don't try to insert at the time of another event
+            let start_time = event.time;
+            let end_time = start_time + event.duration.unwrap();
+
+            for queued in self.iter() {
+                assert!(
+                    queued.time <= start_time || queued.time >= end_time,
+                    "NotSupportedError - scheduling SetValueCurveAtTime ({:?}) at time of another automation event ({:?})",
+                    event, queued,
+                );
+            }
+        }
+
+        // [spec] Similarly a NotSupportedError exception MUST be thrown if any
+        // automation method is called at a time which is contained in [𝑇,𝑇+𝐷), 𝑇
+        // being the time of the curve and 𝐷 its duration.
+        // @note - Cancel methods are not automation methods
+        if event.event_type == AudioParamEventType::SetValueAtTime
+            || event.event_type == AudioParamEventType::SetValue
+            || event.event_type == AudioParamEventType::LinearRampToValueAtTime
+            || event.event_type == AudioParamEventType::ExponentialRampToValueAtTime
+            || event.event_type == AudioParamEventType::ShapedRampToValueAtTime
+            || event.event_type == AudioParamEventType::SetTargetAtTime
+        {
+            for queued in self.iter() {
+                if queued.event_type == AudioParamEventType::SetValueCurveAtTime {
+                    let start_time = queued.time;
+                    let end_time = start_time + queued.duration.unwrap();
+
+                    assert!(
+                        event.time <= start_time || event.time >= end_time,
+                        "NotSupportedError - scheduling automation event ({:?}) during SetValueCurveAtTime ({:?})",
+                        event, queued,
+                    );
+                }
+            }
+        }
+
+        // SetValue events are applied at the start of the render quantum
+        if event.event_type == AudioParamEventType::SetValue {
+            event.time = block_time;
+        }
+
+        let is_ramp = event.event_type == AudioParamEventType::LinearRampToValueAtTime
+            || event.event_type == AudioParamEventType::ExponentialRampToValueAtTime
+            || event.event_type == AudioParamEventType::ShapedRampToValueAtTime;
+
+        // [spec] If the preceding event is a SetTarget event, 𝑇0 and 𝑉0 are
+        // chosen from the current time and value of SetTarget automation.
+        // i.e. a SetTarget event in progress is held at the current time, cf.
+        // `compute_set_target_automation` for events that have not started
+        if is_ramp {
+            let index = self.insertion_index(&event);
+
+            if let Some(previous) = index.checked_sub(1).map(|i| &mut self.inner[i]) {
+                if previous.event_type == AudioParamEventType::SetTargetAtTime
+                    && previous.time <= block_time
+                    && previous.cancel_time.is_none()
+                {
+                    previous.cancel_time = Some(block_time);
+                }
+            }
+        }
+
+        // If no event in the timeline and event_type is `LinearRampToValueAtTime`
+        // or `ExponentialRampToValue` at time, we must insert a `SetValueAtTime`
+        // with intrinsic value and calling time (same for `ShapedRampToValueAtTime`).
+        // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-linearramptovalueattime
+        // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-exponentialramptovalueattime
+        //
+        // for SetTarget, this behavior is not per se specified, but it allows
+        // to make sure we have a stable start_value available without having
+        // to store it elsewhere.
+        let needs_start_event = if is_ramp {
+            self.is_empty() && !has_last_event
+        } else {
+            self.is_empty() && event.event_type == AudioParamEventType::SetTargetAtTime
+        };
+
+        if needs_start_event {
+            self.insert(AudioParamEvent {
+                event_type: AudioParamEventType::SetValue,
+                value: intrinsic_value,
+                // applied before any other event
+                time: block_time,
+                time_constant: None,
+                cancel_time: None,
+                duration: None,
+                values: None,
+                shape: None,
+            });
+        }
+
+        self.insert(event);
+    }
+}
+
+// Control thread copy of the automation events, resolved by the same
+// `AudioParamEventTimeline` as in the `AudioParamProcessor` of the render thread.
+// Nothing is computed when events are scheduled: the automation curve is only
+// evaluated when queried, from the events and the `compute_*_sample` functions
+// shared with the render thread.
+#[derive(Debug)]
+struct AudioParamAutomation {
+    default_value: f32,
+    min_value: f32,
+    max_value: f32,
+    sample_rate: f64,
+    // time constant of the smoothing applied to the next direct value changes
+    smoothing: f64,
+    // events which are pending or in progress
+    events: AudioParamEventTimeline,
+    // end time and value of the last ended event, where the next event starts from
+    last: Option<(f64, f32)>,
+    // offset left by the last smoothed direct value change: (start time, offset,
+    // time constant)
+    smoothing_offset: (f64, f32, f64),
+}
+
+impl AudioParamAutomation {
+    // Whether the render thread has applied an event at `time` for the given
+    // sample-frame, cf. the `end_index` computations of the render thread
+    fn reached(&self, time: f64, frame: u64) -> bool {
+        frame as f64 >= (time * self.sample_rate).round()
+    }
+
+    // Value at `time` of the event at `index`, assuming it is in progress, followed
+    // by its end time and value. `prev` holds the end time and value of the
+    // previous event.
+    fn evaluate_event(&self, index: usize, prev: (f64, f32), time: f64) -> (f32, f64, f32) {
+        let event = self.events.get(index).unwrap();
+        let (prev_time, prev_value) = prev;
+
+        match event.event_type {
+            AudioParamEventType::SetValue | AudioParamEventType::SetValueAtTime => {
+                (event.value, event.time, event.value)
+            }
+            AudioParamEventType::LinearRampToValueAtTime
+            | AudioParamEventType::ExponentialRampToValueAtTime
+            | AudioParamEventType::ShapedRampToValueAtTime => {
+                // the duration is computed before clamping the end time to the
+                // cancel time, to keep the declared slope of the ramp
+                let duration = event.time - prev_time;
+                let end_time = event.cancel_time.unwrap_or(event.time);
+                let diff = event.value - prev_value;
+
+                let value_at = |time: f64| match event.event_type {
+                    AudioParamEventType::LinearRampToValueAtTime => {
+                        compute_linear_ramp_sample(prev_time, duration, prev_value, diff, time)
+                    }
+                    AudioParamEventType::ExponentialRampToValueAtTime => {
+                        let ratio = event.value / prev_value;
+                        compute_exponential_ramp_sample(
+                            prev_time, duration, prev_value, ratio, time,
+                        )
+                    }
+                    _ => {
+                        let shape = event.shape.unwrap();
+                        compute_shaped_ramp_sample(
+                            prev_time, duration, prev_value, diff, shape, time,
+                        )
+                    }
+                };
+
+                // exponential ramps from zero or changing sign behave as SetValueAtTime
+                if event.event_type == AudioParamEventType::ExponentialRampToValueAtTime
+                    && (prev_value == 0. || prev_value * event.value < 0.)
+                {
+                    (prev_value, end_time, event.value)
+                } else if event.cancel_time.is_some() {
+                    (value_at(time), end_time, value_at(end_time))
+                } else {
+                    (value_at(time), end_time, event.value)
+                }
+            }
+            AudioParamEventType::SetTargetAtTime => {
+                let time_constant = event.time_constant.unwrap();
+                let diff = prev_value - event.value;
+                let value_at = |time: f64| {
+                    if time < event.time {
+                        return prev_value;
+                    }
+
+                    let value = compute_set_target_sample(
+                        event.time,
+                        time_constant,
+                        event.value,
+                        diff,
+                        time,
+                    );
+                    if (event.value - value).abs() < SNAP_TO_TARGET {
+                        event.value
+                    } else {
+                        value
+                    }
+                };
+
+                // the event ends at the time of the next event, which starts from
+                // the current value, except ramps which replace the SetTarget event,
+                // or start from its value when they were scheduled if it had started
+                let end_time = match self.events.get(index + 1) {
+                    None => f64::INFINITY,
+                    Some(next) => match next.event_type {
+                        AudioParamEventType::LinearRampToValueAtTime
+                        | AudioParamEventType::ExponentialRampToValueAtTime
+                        | AudioParamEventType::ShapedRampToValueAtTime => {
+                            event.cancel_time.unwrap_or(event.time)
+                        }
+                        _ => next.time,
+                    },
+                };
+                let end_time = event.cancel_time.map_or(end_time, |c| c.min(end_time));
+
+                (value_at(time), end_time, value_at(end_time))
+            }
+            AudioParamEventType::SetValueCurveAtTime => {
+                let duration = event.duration.unwrap();
+                let values = event.values.as_deref().unwrap();
+                let value_at = |time: f64| {
+                    if time < event.time {
+                        prev_value
+                    } else {
+                        compute_set_value_curve_sample(event.time, duration, values, time)
+                    }
+                };
+
+                match event.cancel_time {
+                    Some(cancel_time) => (value_at(time), cancel_time, value_at(cancel_time)),
+                    None => (
+                        value_at(time),
+                        event.time + duration,
+                        values[values.len() - 1],
+                    ),
+                }
+            }
+            AudioParamEventType::CancelScheduledValues
+            | AudioParamEventType::CancelAndHoldAtTime => unreachable!(),
+        }
+    }
+
+    // Intrinsic value (i.e. without smoothing) at the given sample-frame
+    fn intrinsic_value_at(&self, frame: u64) -> f32 {
+        let time = frame as f64 / self.sample_rate;
+        let mut prev = self.last.unwrap_or((0., self.default_value));
+
+        for (index, event) in self.events.iter().enumerate() {
+            let is_ramp = matches!(
+                event.event_type,
+                AudioParamEventType::LinearRampToValueAtTime
+                    | AudioParamEventType::ExponentialRampToValueAtTime
+                    | AudioParamEventType::ShapedRampToValueAtTime
+            );
+
+            // ramps start at the end of the previous event, other events at their time
+            if !is_ramp && !self.reached(event.time, frame) {
+                return prev.1;
+            }
+
+            let (value, end_time, end_value) = self.evaluate_event(index, prev, time);
+            if !self.reached(end_time, frame) {
+                return value;
+            }
+
+            prev = (end_time, end_value);
+        }
+
+        prev.1
+    }
+
+    // Offset left by the smoothed direct value changes at the given sample-frame,
+    // i.e. 𝑂 * 𝑒^−((𝑡−𝑇0) / 𝜏)
+    fn smoothing_offset_at(&self, frame: u64) -> f32 {
+        let (start_time, offset, time_constant) = self.smoothing_offset;
+        if offset == 0. {
+            return 0.;
+        }
+
+        let time = frame as f64 / self.sample_rate;
+        let offset = offset * (-(time - start_time) / time_constant).exp() as f32;
+        if offset.abs() < SNAP_TO_TARGET {
+            0.
+        } else {
+            offset
+        }
+    }
+
+    // Drop the events that have ended before `block_time`, as the render thread does
+    fn prune(&mut self, block_time: f64) {
+        let mut prev = self.last.unwrap_or((0., self.default_value));
+        let mut count = 0;
+
+        for index in 0..self.events.len() {
+            let (_, end_time, end_value) = self.evaluate_event(index, prev, block_time);
+            if end_time >= block_time {
+                break;
+            }
+
+            prev = (end_time, end_value);
+            count += 1;
+        }
+
+        if count > 0 {
+            self.last = Some(prev);
+            self.events.drain_front(count);
+        }
+    }
+
+    // Insert the event as the render thread will do at the start of the render
+    // quantum at `frame`
+    fn handle_incoming_event(&mut self, event: AudioParamEvent, frame: u64) {
+        let block_time = frame as f64 / self.sample_rate;
+        self.prune(block_time);
+
+        let intrinsic_value = self.intrinsic_value_at(frame);
+
+        if event.event_type == AudioParamEventType::SetValue {
+            // glide from the current (smoothed) value to the new intrinsic value
+            let smoothing = event.time_constant.unwrap_or(0.);
+            self.smoothing_offset = if smoothing > 0. {
+                let offset = self.smoothing_offset_at(frame) + intrinsic_value - event.value;
+                (block_time, offset, smoothing)
+            } else {
+                (block_time, 0., 0.)
+            };
+        }
+
+        self.events
+            .handle_incoming_event(event, block_time, intrinsic_value, self.last.is_some());
+    }
+
+    // Fill `values` with the values of the sample-frames given by `frame_at`. Sample
+    // frames before `frame`, i.e. the start of the next render quantum, are evaluated
+    // at `frame`.
+    fn compute_values<F: Fn(usize) -> u64>(
+        &self,
+        values: &mut [f32],
+        frame_at: F,
+        frame: u64,
+        automation_rate: AutomationRate,
+    ) {
+        for (index, v) in values.iter_mut().enumerate() {
+            let mut frame = frame_at(index).max(frame);
+            // k-rate params hold the value of the first sample-frame of the render quantum
+            if !automation_rate.is_a_rate() {
+                frame -= frame % RENDER_QUANTUM_SIZE as u64;
+            }
+
+            let value = self.intrinsic_value_at(frame) + self.smoothing_offset_at(frame);
+
+            // same as `mix_to_output` without input
+            *v = if value.is_nan() {
+                self.default_value
+            } else {
+                value.max(self.min_value).min(self.max_value)
+            };
+        }
+    }
+
+    fn scheduled_events(&self) -> Vec<AutomationEvent> {
+        // a direct value change immediately overridden by another value is not listed
+        let overridden = |index: usize| {
+            let event = self.events.get(index).unwrap();
+            event.event_type == AudioParamEventType::SetValue
+                && self.events.get(index + 1).is_some_and(|next| {
+                    matches!(
+                        next.event_type,
+                        AudioParamEventType::SetValue | AudioParamEventType::SetValueAtTime
+                    ) && next.time <= event.time
+                })
+        };
+
+        let mut queued: Vec<_> = (0..self.events.len())
+            .filter(|&index| !overridden(index))
+            .map(|index| self.events.get(index).unwrap())
+            .collect();
+        // events truncated by a `CancelAndHoldAtTime` must be listed before the
+        // events scheduled after the cancel time, so that replaying the list does
+        // not remove them
+        queued.sort_by(|a, b| {
+            let a = a.cancel_time.unwrap_or(a.time);
+            let b = b.cancel_time.unwrap_or(b.time);
+            a.partial_cmp(&b).unwrap()
+        });
+
+        let mut events = Vec::with_capacity(queued.len() + 1);
+
+        // an automation in progress starts from the last ended event
+        if let Some((start_time, value)) = self.last {
+            if !queued.is_empty() {
+                events.push(AutomationEvent::SetValueAtTime { value, start_time });
+            }
+        }
+
+        for event in queued {
+            let value = event.value;
 
-    fn next(&self) -> Option<&AudioParamEvent> {
-        assert!(
-            !self.dirty,
-            "`AudioParamEventTimeline`: Invalid `.next()` call, the queue is dirty"
-        );
-        self.inner.get(1)
-    }
+            let automation_event = match event.event_type {
+                // `set_value` and implicitly inserted events are applied at the
+                // start of the next render quantum
+                AudioParamEventType::SetValue | AudioParamEventType::SetValueAtTime => {
+                    AutomationEvent::SetValueAtTime {
+                        value,
+                        start_time: event.time,
+                    }
+                }
+                AudioParamEventType::LinearRampToValueAtTime => {
+                    AutomationEvent::LinearRampToValueAtTime {
+                        value,
+                        end_time: event.time,
+                    }
+                }
+                AudioParamEventType::ExponentialRampToValueAtTime => {
+                    AutomationEvent::ExponentialRampToValueAtTime {
+                        value,
+                        end_time: event.time,
+                    }
+                }
+                AudioParamEventType::ShapedRampToValueAtTime => {
+                    AutomationEvent::ShapedRampToValueAtTime {
+                        value,
+                        end_time: event.time,
+                        shape: event.shape.unwrap(),
+                    }
+                }
+                AudioParamEventType::SetTargetAtTime => AutomationEvent::SetTargetAtTime {
+                    value,
+                    start_time: event.time,
+                    time_constant: event.time_constant.unwrap(),
+                },
+                AudioParamEventType::SetValueCurveAtTime => AutomationEvent::SetValueCurveAtTime {
+                    values: event.values.as_ref().unwrap().to_vec(),
+                    start_time: event.time,
+                    duration: event.duration.unwrap(),
+                },
+                AudioParamEventType::CancelScheduledValues
+                | AudioParamEventType::CancelAndHoldAtTime => unreachable!(),
+            };
 
-    fn sort(&mut self) {
-        self.inner
-            .sort_by(|a, b| a.time.partial_cmp(&b.time).unwrap());
-        self.dirty = false;
-    }
+            events.push(automation_event);
 
-    fn iter(&mut self) -> Iter<'_, AudioParamEvent> {
-        self.inner.iter()
-    }
+            if let Some(cancel_time) = event.cancel_time {
+                events.push(AutomationEvent::CancelAndHoldAtTime { cancel_time });
+            }
+        }
 
-    fn iter_mut(&mut self) -> IterMut<'_, AudioParamEvent> {
-        self.inner.iter_mut()
+        events
     }
 }
 
@@ -289,12 +1010,13 @@ impl std::fmt::Debug for AudioParam {
 // helper struct to attach / detach to context (for borrow reasons)
 #[derive(Debug, Clone)]
 pub(crate) struct AudioParamInner {
-    default_value: f32,                          // immutable
-    min_value: f32,                              // immutable
-    max_value: f32,                              // immutable
-    automation_rate_constrained: bool,           // effectively immutable
-    automation_rate: Arc<Mutex<AutomationRate>>, // shared with clones
-    current_value: Arc<AtomicF32>,               // shared with clones and with render thread
+    default_value: f32,                           // immutable
+    min_value: f32,                               // immutable
+    max_value: f32,                               // immutable
+    automation_rate_constrained: bool,            // effectively immutable
+    automation_rate: Arc<Mutex<AutomationRate>>,  // shared with clones
+    current_value: Arc<AtomicF32>,                // shared with clones and with render thread
+    automation: Arc<Mutex<AudioParamAutomation>>, // shared with clones, control thread only
 }
 
 impl AudioNode for AudioParam {
@@ -418,10 +1140,12 @@ impl AudioParam {
             event_type: AudioParamEventType::SetValue,
             value,
             time: 0.,
-            time_constant: None,
+            // time constant of the smoothing of the value change
+            time_constant: Some(self.smoothing()),
             cancel_time: None,
             duration: None,
             values: None,
+            shape: None,
         }
     }
 
@@ -446,6 +1170,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
+            shape: None,
         }
     }
 
@@ -471,6 +1196,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
+            shape: None,
         }
     }
 
@@ -498,6 +1224,59 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
+            shape: None,
+        }
+    }
+
+    /// Schedules a continuous change in parameter value from the previous
+    /// scheduled parameter value to the given value, following the given shape.
+    ///
+    /// The ramp behaves as a linear ramp whose progression is shaped according
+    /// to `shape`, e.g. `RampShape::Power(1.)` is equivalent to
+    /// [`linear_ramp_to_value_at_time`](Self::linear_ramp_to_value_at_time).
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    /// - `end_time` is negative
+    /// - the exponent of a `RampShape::Power` shape is not strictly positive
+    pub fn shaped_ramp_to_value_at_time(
+        &self,
+        value: f32,
+        end_time: f64,
+        shape: RampShape,
+    ) -> &Self {
+        self.send_event(self.shaped_ramp_to_value_at_time_raw(value, end_time, shape))
+    }
+
+    fn shaped_ramp_to_value_at_time_raw(
+        &self,
+        value: f32,
+        end_time: f64,
+        shape: RampShape,
+    ) -> AudioParamEvent {
+        assert_is_finite(value);
+        assert_valid_time_value(end_time);
+
+        if let RampShape::Power(exponent) = shape {
+            assert!(
+                exponent.is_finite() && exponent > 0.,
+                "RangeError - exponent ({:?}) should be strictly positive",
+                exponent
+            );
+        }
+
+        AudioParamEvent {
+            event_type: AudioParamEventType::ShapedRampToValueAtTime,
+            value,
+            time: end_time,
+            time_constant: None,
+            cancel_time: None,
+            duration: None,
+            values: None,
+            shape: Some(shape),
         }
     }
 
@@ -533,6 +1312,7 @@ impl AudioParam {
                 cancel_time: None,
                 duration: None,
                 values: None,
+                shape: None,
             }
         } else {
             AudioParamEvent {
@@ -543,6 +1323,7 @@ impl AudioParam {
                 cancel_time: None,
                 duration: None,
                 values: None,
+                shape: None,
             }
         }
     }
@@ -568,6 +1349,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
+            shape: None,
         }
     }
 
@@ -593,6 +1375,7 @@ impl AudioParam {
             cancel_time: None,
             duration: None,
             values: None,
+            shape: None,
         }
     }
 
@@ -632,9 +1415,185 @@ impl AudioParam {
             cancel_time: None,
             duration: Some(duration),
             values: Some(boxed_copy),
+            shape: None,
         }
     }
 
+    /// Time constant (in seconds) of the smoothing applied to the direct value
+    /// changes, 0 if smoothing is disabled
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    #[must_use]
+    pub fn smoothing(&self) -> f64 {
+        self.automation().smoothing
+    }
+
+    /// Smooth the changes of value made with [`set_value`](Self::set_value), to
+    /// prevent zipper noise when the value is driven by a user interface
+    ///
+    /// The value exponentially approaches each new value with the given time
+    /// constant (in seconds), as with [`set_target_at_time`](Self::set_target_at_time),
+    /// but without inserting automation events. Scheduled automation events are not
+    /// smoothed. A time constant of 0 (the default) disables the smoothing.
+    ///
+    /// The time constant applies to the subsequent calls to `set_value`, a change
+    /// of value in progress keeps the time constant it started with.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `time_constant` is negative
+    pub fn set_smoothing(&self, time_constant: f64) {
+        assert_valid_time_value(time_constant);
+
+        // the time constant is sent to the render thread with each value change
+        self.automation().smoothing = time_constant;
+    }
+
+    /// Compute the value of the `AudioParam` at the given time, according to the
+    /// currently scheduled automation events
+    ///
+    /// The value is computed with the same formulas as in the render thread, i.e. it
+    /// is the value of the sample-frame closest to `time`, taking the automation rate
+    /// into account and clamped to the nominal range. Inputs connected to the
+    /// `AudioParam` are not taken into account.
+    ///
+    /// Times before the current time of the context are evaluated at the start of
+    /// the next render quantum.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if `time` is negative
+    pub fn value_at_time(&self, time: f64) -> f32 {
+        assert_valid_time_value(time);
+
+        let mut value = [0.];
+        let sample_rate = self.context().sample_rate() as f64;
+        let frame = (time * sample_rate).round() as u64;
+
+        let automation_rate = self.automation_rate();
+        let next_frame = self.next_block_frame();
+        self.automation()
+            .compute_values(&mut value, |_| frame, next_frame, automation_rate);
+
+        value[0]
+    }
+
+    /// Fill `values` with the values of the `AudioParam` regularly spaced from
+    /// `start_time` to `start_time + duration`, according to the currently scheduled
+    /// automation events
+    ///
+    /// This is the counterpart of [`set_value_curve_at_time`](Self::set_value_curve_at_time)
+    /// and each value is computed as in [`value_at_time`](Self::value_at_time).
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if:
+    /// - `values` length is less than 2
+    /// - `start_time` is negative
+    /// - `duration` is negative or equal to zero
+    pub fn get_value_curve_at_time(&self, values: &mut [f32], start_time: f64, duration: f64) {
+        assert_sequence_length(values);
+        assert_valid_time_value(start_time);
+        assert_strictly_positive(duration);
+
+        let sample_rate = self.context().sample_rate() as f64;
+        let interval = duration / (values.len() - 1) as f64;
+        let frame_at = |index: usize| {
+            let time = (index as f64).mul_add(interval, start_time);
+            (time * sample_rate).round() as u64
+        };
+
+        let automation_rate = self.automation_rate();
+        let next_frame = self.next_block_frame();
+        self.automation()
+            .compute_values(values, frame_at, next_frame, automation_rate);
+    }
+
+    /// List the automation events which are still pending or in progress, ordered
+    /// as they should be scheduled to reproduce the automation timeline
+    ///
+    /// Note that values set with [`set_value`](Self::set_value) which are not yet
+    /// applied are listed as `SetValueAtTime` events, and that an automation in
+    /// progress is preceded by a `SetValueAtTime` event holding the value it
+    /// started from, so that the list can be replayed with
+    /// [`schedule_events`](Self::schedule_events).
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    #[must_use]
+    pub fn scheduled_events(&self) -> Vec<AutomationEvent> {
+        self.automation().scheduled_events()
+    }
+
+    /// Schedule the given automation events, in order
+    ///
+    /// This allows to restore a timeline listed by
+    /// [`scheduled_events`](Self::scheduled_events), possibly onto another
+    /// `AudioParam` and after [shifting](AutomationEvent::time_shifted) or
+    /// [scaling](AutomationEvent::time_scaled) the events in time.
+    ///
+    /// Note that this method is not part of the Web Audio API specification.
+    ///
+    /// # Panics
+    ///
+    /// Will panic if any event is invalid, in the same conditions as the
+    /// corresponding scheduling method
+    pub fn schedule_events<I: IntoIterator<Item = AutomationEvent>>(&self, events: I) -> &Self {
+        events.into_iter().for_each(|event| match event {
+            AutomationEvent::SetValueAtTime { value, start_time } => {
+                self.set_value_at_time(value, start_time);
+            }
+            AutomationEvent::LinearRampToValueAtTime { value, end_time } => {
+                self.linear_ramp_to_value_at_time(value, end_time);
+            }
+            AutomationEvent::ExponentialRampToValueAtTime { value, end_time } => {
+                self.exponential_ramp_to_value_at_time(value, end_time);
+            }
+            AutomationEvent::ShapedRampToValueAtTime {
+                value,
+                end_time,
+                shape,
+            } => {
+                self.shaped_ramp_to_value_at_time(value, end_time, shape);
+            }
+            AutomationEvent::SetTargetAtTime {
+                value,
+                start_time,
+                time_constant,
+            } => {
+                self.set_target_at_time(value, start_time, time_constant);
+            }
+            AutomationEvent::SetValueCurveAtTime {
+                values,
+                start_time,
+                duration,
+            } => {
+                self.set_value_curve_at_time(&values, start_time, duration);
+            }
+            AutomationEvent::CancelAndHoldAtTime { cancel_time } => {
+                self.cancel_and_hold_at_time(cancel_time);
+            }
+        });
+
+        self
+    }
+
+    fn automation(&self) -> MutexGuard<'_, AudioParamAutomation> {
+        self.raw_parts.automation.lock().unwrap()
+    }
+
+    // first sample-frame of the next render quantum, where new events are applied
+    fn next_block_frame(&self) -> u64 {
+        let sample_rate = self.context().sample_rate() as f64;
+        let frame = (self.context().current_time() * sample_rate).round() as u64;
+        frame.next_multiple_of(RENDER_QUANTUM_SIZE as u64)
+    }
+
     // helper function to detach from context (for borrow reasons)
     pub(crate) fn into_raw_parts(self) -> AudioParamInner {
         let Self {
@@ -656,7 +1615,12 @@ impl AudioParam {
     }
 
     fn send_event(&self, event: AudioParamEvent) -> &Self {
+        let mut automation = self.automation();
+        automation.handle_incoming_event(event.clone(), self.next_block_frame());
+
         self.registration().post_message(event);
+        drop(automation); // drop guard after sending message to prevent out of order arrivals
+                          // on concurrent access
         self
     }
 }
@@ -680,6 +1644,9 @@ pub(crate) struct AudioParamProcessor {
     event_timeline: AudioParamEventTimeline,
     last_event: Option<AudioParamEvent>,
     buffer: ArrayVec<f32, RENDER_QUANTUM_SIZE>,
+    smoothing: f64,        // time constant of the last smoothed direct value change
+    smoothing_offset: f32, // offset from the intrinsic value at the start of next block
+    next_block_time: f64,  // start time of the next block, where incoming events are applied
 }
 
 impl AudioProcessor for AudioParamProcessor {
@@ -717,6 +1684,7 @@ impl AudioProcessor for AudioParamProcessor {
                 cancel_time: None,
                 duration: None,
                 values: None,
+                shape: None,
             };
             let event = std::mem::replace(event, tombstone_event);
             self.handle_incoming_event(event);
@@ -809,13 +1777,9 @@ impl AudioParamProcessor {
         // handle CancelScheduledValues events
         // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-cancelscheduledvalues
         if event.event_type == AudioParamEventType::CancelScheduledValues {
-            // peek current event before inserting new events, and possibly sort
-            // the queue, we need that for checking that we are (or not) in the middle
-            // of a ramp when handling `CancelScheduledValues`
-            // @note - probably not robust enough in some edge cases where the
-            // event is not the first received at this tick (`SetValueCurveAtTime`
-            // and `CancelAndHold` need to sort the queue)
-            let some_current_event = self.event_timeline.unsorted_peek();
+            // peek current event, we need that for checking that we are (or not)
+            // in the middle of a ramp when handling `CancelScheduledValues`
+            let some_current_event = self.event_timeline.peek();
 
             match some_current_event {
                 None => (),
@@ -830,6 +1794,7 @@ impl AudioParamProcessor {
                     match current_event.event_type {
                         AudioParamEventType::LinearRampToValueAtTime
                         | AudioParamEventType::ExponentialRampToValueAtTime
+                        | AudioParamEventType::ShapedRampToValueAtTime
                             // we are in the middle of a ramp
                             //
                             // @note - Firefox and Chrome behave differently
@@ -844,206 +1809,43 @@ impl AudioParamProcessor {
                             // because the original value (**from before such
                             // automation**) is restored immediately."
                             //
-                            // @note - last_event cannot be `None` here, because
-                            // linear or exponential ramps are always preceded
-                            // by another event (even a set_value_at_time
-                            // inserted implicitly), so if the ramp is the next
-                            // event that means that at least one event has
-                            // already been processed.
-                            if current_event.time >= event.time => {
-                                let last_event = self.last_event.as_ref().unwrap();
-                                self.intrinsic_value = last_event.value;
-                            }
-                        _ => (),
-                    }
-                }
-            }
-
-            // remove all events in queue where event.time >= cancel_time
-            // i.e. keep all events where event.time < cancel_time
-            self.event_timeline
-                .retain(|queued| queued.time < event.time);
-            return; // cancel_values events are not inserted in queue
-        }
-
-        if event.event_type == AudioParamEventType::CancelAndHoldAtTime {
-            // 1. Let 𝐸1 be the event (if any) at time 𝑡1 where 𝑡1 is the
-            // largest number satisfying 𝑡1 ≤ 𝑡𝑐.
-            // 2. Let 𝐸2 be the event (if any) at time 𝑡2 where 𝑡2 is the
-            // smallest number satisfying 𝑡𝑐 < 𝑡2.
-            let mut e1: Option<&mut AudioParamEvent> = None;
-            let mut e2: Option<&mut AudioParamEvent> = None;
-            let mut t1 = f64::MIN;
-            let mut t2 = f64::MAX;
-            // we need a sorted timeline here to find siblings
-            self.event_timeline.sort();
-
-            for queued in self.event_timeline.iter_mut() {
-                // closest before cancel time: if several events at same time,
-                // we want the last one
-                if queued.time >= t1 && queued.time <= event.time {
-                    t1 = queued.time;
-                    e1 = Some(queued);
-                    // closest after cancel time: if several events at same time,
-                    // we want the first one
-                } else if queued.time < t2 && queued.time > event.time {
-                    t2 = queued.time;
-                    e2 = Some(queued);
-                }
-            }
-
-            // If 𝐸2 exists:
-            if let Some(matched) = e2 {
-                // If 𝐸2 is a linear or exponential ramp,
-                // Effectively rewrite 𝐸2 to be the same kind of ramp ending
-                // at time 𝑡𝑐 with an end value that would be the value of the
-                // original ramp at time 𝑡𝑐.
-                // @note - this is done during the actual computation of the
-                //  ramp using the cancel_time
-                if matched.event_type == AudioParamEventType::LinearRampToValueAtTime
-                    || matched.event_type == AudioParamEventType::ExponentialRampToValueAtTime
-                {
-                    matched.cancel_time = Some(event.time);
-                }
-            } else if let Some(matched) = e1 {
-                if matched.event_type == AudioParamEventType::SetTargetAtTime {
-                    // Implicitly insert a setValueAtTime event at time 𝑡𝑐 with
-                    // the value that the setTarget would
-                    // @note - same strategy as for ramps
-                    matched.cancel_time = Some(event.time);
-                } else if matched.event_type == AudioParamEventType::SetValueCurveAtTime {
-                    // If 𝐸1 is a setValueCurve with a start time of 𝑡3 and a duration of 𝑑
-                    // If 𝑡𝑐 <= 𝑡3 + 𝑑 :
-                    // Effectively replace this event with a setValueCurve event
-                    // with a start time of 𝑡3 and a new duration of 𝑡𝑐−𝑡3. However,
-                    // this is not a true replacement; this automation MUST take
-                    // care to produce the same output as the original, and not
-                    // one computed using a different duration. (That would cause
-                    // sampling of the value curve in a slightly different way,
-                    // producing different results.)
-                    let start_time = matched.time;
-                    let duration = matched.duration.unwrap();
-
-                    if event.time <= start_time + duration {
-                        matched.cancel_time = Some(event.time);
-                    }
-                }
-            }
-
-            // [spec] Remove all events with time greater than 𝑡𝑐.
-            self.event_timeline.retain(|queued| {
-                let mut time = queued.time;
-                // if the event has a `cancel_time` we use it instead of `time`
-                if let Some(cancel_time) = queued.cancel_time {
-                    time = cancel_time;
-                }
-
-                time <= event.time
-            });
-            return; // cancel_and_hold events are not inserted timeline
-        }
-
-        // handle SetValueCurveAtTime
-        // @note - These rules argue in favor of having events inserted in
-        // the control thread, let's panic for now
-        //
-        // [spec] If setValueCurveAtTime() is called for time 𝑇 and duration 𝐷
-        // and there are any events having a time strictly greater than 𝑇, but
-        // strictly less than 𝑇+𝐷, then a NotSupportedError exception MUST be thrown.
-        // In other words, it’s not ok to schedule a value curve during a time period
-        // containing other events, but it’s ok to schedule a value curve exactly
-        // at the time of another event.
-        if event.event_type == AudioParamEventType::SetValueCurveAtTime {
-            // check if we don't try to insert at the time of another event
-            let start_time = event.time;
-            let end_time = start_time + event.duration.unwrap();
-
-            for queued in self.event_timeline.iter() {
-                assert!(
-                    queued.time <= start_time || queued.time >= end_time,
-                    "NotSupportedError - scheduling SetValueCurveAtTime ({:?}) at time of another automation event ({:?})",
-                    event, queued,
-                );
-            }
-        }
-
-        // [spec] Similarly a NotSupportedError exception MUST be thrown if any
-        // automation method is called at a time which is contained in [𝑇,𝑇+𝐷), 𝑇
-        // being the time of the curve and 𝐷 its duration.
-        // @note - Cancel methods are not automation methods
-        if event.event_type == AudioParamEventType::SetValueAtTime
-            || event.event_type == AudioParamEventType::SetValue
-            || event.event_type == AudioParamEventType::LinearRampToValueAtTime
-            || event.event_type == AudioParamEventType::ExponentialRampToValueAtTime
-            || event.event_type == AudioParamEventType::SetTargetAtTime
-        {
-            for queued in self.event_timeline.iter() {
-                if queued.event_type == AudioParamEventType::SetValueCurveAtTime {
-                    let start_time = queued.time;
-                    let end_time = start_time + queued.duration.unwrap();
-
-                    assert!(
-                        event.time <= start_time || event.time >= end_time,
-                        "NotSupportedError - scheduling automation event ({:?}) during SetValueCurveAtTime ({:?})",
-                        event, queued,
-                    );
+                            // @note - last_event cannot be `None` here, because
+                            // linear or exponential ramps are always preceded
+                            // by another event (even a set_value_at_time
+                            // inserted implicitly), so if the ramp is the next
+                            // event that means that at least one event has
+                            // already been processed.
+                            if current_event.time >= event.time => {
+                                let last_event = self.last_event.as_ref().unwrap();
+                                self.intrinsic_value = last_event.value;
+                            }
+                        _ => (),
+                    }
                 }
             }
         }
 
         // handle SetValue - param intrinsic value must be updated from event value
         if event.event_type == AudioParamEventType::SetValue {
-            self.intrinsic_value = event.value;
-        }
-
-        // If no event in the timeline and event_type is `LinearRampToValueAtTime`
-        // or `ExponentialRampToValue` at time, we must insert a `SetValueAtTime`
-        // with intrinsic value and calling time.
-        // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-linearramptovalueattime
-        // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-exponentialramptovalueattime
-        if self.event_timeline.is_empty()
-            && self.last_event.is_none()
-            && (event.event_type == AudioParamEventType::LinearRampToValueAtTime
-                || event.event_type == AudioParamEventType::ExponentialRampToValueAtTime)
-        {
-            let set_value_event = AudioParamEvent {
-                event_type: AudioParamEventType::SetValue,
-                value: self.intrinsic_value,
-                // make sure the event is applied before any other event, time
-                // will be replaced by the block timestamp during event processing
-                time: 0.,
-                time_constant: None,
-                cancel_time: None,
-                duration: None,
-                values: None,
-            };
-
-            self.event_timeline.push(set_value_event);
-        }
-
-        // for SetTarget, this behavior is not per se specified, but it allows
-        // to make sure we have a stable start_value available without having
-        // to store it elsewhere.
-        if self.event_timeline.is_empty()
-            && event.event_type == AudioParamEventType::SetTargetAtTime
-        {
-            let set_value_event = AudioParamEvent {
-                event_type: AudioParamEventType::SetValue,
-                value: self.intrinsic_value,
-                // make sure the event is applied before any other event, time
-                // will be replaced by the block timestamp during event processing
-                time: 0.,
-                time_constant: None,
-                cancel_time: None,
-                duration: None,
-                values: None,
-            };
+            // glide from the current (smoothed) value to the new intrinsic value, with
+            // the time constant set when the value was changed
+            let smoothing = event.time_constant.unwrap_or(0.);
+            if smoothing > 0. {
+                self.smoothing = smoothing;
+                self.smoothing_offset += self.intrinsic_value - event.value;
+            } else {
+                self.smoothing_offset = 0.;
+            }
 
-            self.event_timeline.push(set_value_event);
+            self.intrinsic_value = event.value;
         }
 
-        self.event_timeline.push(event);
-        self.event_timeline.sort();
+        self.event_timeline.handle_incoming_event(
+            event,
+            self.next_block_time,
+            self.intrinsic_value,
+            self.last_event.is_some(),
+        );
     }
 
     // https://www.w3.org/TR/webaudio/%23dom-audioparam-linearramptovalueattime#dom-audioparam-setvalueattime
@@ -1051,13 +1853,13 @@ impl AudioParamProcessor {
         let event = self.event_timeline.peek().unwrap();
         let mut time = event.time;
 
-        // `set_value` and implicitly inserted events are inserted with `time = 0.`,
+        // `set_value` and implicitly inserted events are ordered as if at `time = 0.`,
         // so that we ensure they are processed first. Replacing their `time` with
         // `block_time` allows to conform to the spec:
         // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-value
         // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-linearramptovalueattime
         // cf. https://www.w3.org/TR/webaudio/#dom-audioparam-exponentialramptovalueattime
-        if time == 0. {
+        if event.event_type == AudioParamEventType::SetValue || time == 0. {
             time = infos.block_time;
         }
 
@@ -1206,6 +2008,7 @@ impl AudioParamProcessor {
                 cancel_time: None,
                 duration: None,
                 values: None,
+                shape: None,
             };
 
             self.event_timeline.replace_peek(event);
@@ -1278,6 +2081,96 @@ impl AudioParamProcessor {
         false
     }
 
+    // Same as `compute_linear_ramp_automation` with the progression of the ramp
+    // shaped by `event.shape`
+    // 𝑣(𝑡) = 𝑉0 + (𝑉1−𝑉0) * 𝑠((𝑡−𝑇0) / (𝑇1−𝑇0))
+    fn compute_shaped_ramp_automation(&mut self, infos: &BlockInfos) -> bool {
+        let event = self.event_timeline.peek().unwrap();
+        let last_event = self.last_event.as_ref().unwrap();
+
+        let start_time = last_event.time;
+        let mut end_time = event.time;
+        // compute duration before clapping `end_time` to `cancel_time`, to keep
+        // declared shape of the ramp consistent
+        let duration = end_time - start_time;
+        if let Some(cancel_time) = event.cancel_time {
+            end_time = cancel_time;
+        }
+
+        let start_value = last_event.value;
+        let end_value = event.value;
+        let diff = end_value - start_value;
+        let shape = event.shape.unwrap();
+
+        if infos.is_a_rate {
+            let start_index = self.buffer.len();
+            let end_index = ((end_time - infos.block_time).max(0.) / infos.dt).round() as usize;
+            let end_index_clipped = end_index.min(infos.count);
+
+            if end_index_clipped > start_index {
+                let mut time = (start_index as f64).mul_add(infos.dt, infos.block_time);
+
+                let mut value = 0.;
+                for _ in start_index..end_index_clipped {
+                    value = compute_shaped_ramp_sample(
+                        start_time,
+                        duration,
+                        start_value,
+                        diff,
+                        shape,
+                        time,
+                    );
+                    self.buffer.push(value);
+                    time += infos.dt;
+                }
+                self.intrinsic_value = value;
+            }
+        }
+
+        // Event will continue in next tick:
+        // compute value for `next_block_time` so that `param.value()`
+        // stays coherent, also allows to properly fill k-rate
+        // within next block too
+        if end_time >= infos.next_block_time {
+            let value = compute_shaped_ramp_sample(
+                start_time,
+                duration,
+                start_value,
+                diff,
+                shape,
+                infos.next_block_time,
+            );
+            self.intrinsic_value = value;
+
+            return true;
+        }
+
+        // Event cancelled during this block
+        if event.cancel_time.is_some() {
+            let value = compute_shaped_ramp_sample(
+                start_time,
+                duration,
+                start_value,
+                diff,
+                shape,
+                end_time,
+            );
+
+            self.intrinsic_value = value;
+
+            let mut last_event = self.event_timeline.pop().unwrap();
+            last_event.time = end_time;
+            last_event.value = value;
+            self.last_event = Some(last_event);
+        // Event ended during this block
+        } else {
+            self.intrinsic_value = end_value;
+            self.last_event = self.event_timeline.pop();
+        }
+
+        false
+    }
+
     // https://webaudio.github.io/web-audio-api/#dom-audioparam-settargetattime
     // 𝑣(𝑡) = 𝑉1 + (𝑉0 − 𝑉1) * 𝑒^−((𝑡−𝑇0) / 𝜏)
     // Note that as SetTarget never resolves on an end value, a `SetTarget` event
@@ -1294,7 +2187,8 @@ impl AudioParamProcessor {
         if let Some(next_event) = some_next_event {
             match next_event.event_type {
                 AudioParamEventType::LinearRampToValueAtTime
-                | AudioParamEventType::ExponentialRampToValueAtTime => {
+                | AudioParamEventType::ExponentialRampToValueAtTime
+                | AudioParamEventType::ShapedRampToValueAtTime => {
                     // [spec] If the preceding event is a SetTarget
                     // event, 𝑇0 and 𝑉0 are chosen from the current
                     // time and value of SetTarget automation. That
@@ -1307,8 +2201,13 @@ impl AudioParamProcessor {
                     // 𝑇0 is the current context time, and 𝑉0 is
                     // the current SetTarget automation value at time 𝑇0.
                     // In both cases, the automation curve is continuous.
-                    end_time = infos.block_time;
-                    ended = true;
+                    //
+                    // i.e. a SetTarget that has not started yet is replaced
+                    // by the ramp from its start time, and is held until then
+                    if event.time < infos.next_block_time {
+                        end_time = infos.block_time.max(event.time);
+                        ended = true;
+                    }
                 }
                 _ => {
                     // For all other events, the SetTarget
@@ -1365,6 +2264,13 @@ impl AudioParamProcessor {
         }
 
         if !ended {
+            // [spec] 𝑉0 is the initial value (the [[current value]] attribute)
+            // at 𝑇0 (the startTime parameter)
+            // i.e. the event does not start within this block, hold the current value
+            if infos.next_block_time < start_time {
+                return true;
+            }
+
             // compute value for `next_block_time` so that `param.value()`
             // stays coherent (see. comment in `AudioParam`)
             // allows to properly fill k-rate within next block too
@@ -1400,6 +2306,7 @@ impl AudioParamProcessor {
                     cancel_time: None,
                     duration: None,
                     values: None,
+                    shape: None,
                 };
 
                 self.event_timeline.replace_peek(event);
@@ -1505,10 +2412,52 @@ impl AudioParamProcessor {
 
     fn compute_buffer(&mut self, block_time: f64, dt: f64, count: usize) {
         // Set [[current value]] to the value of paramIntrinsicValue at the
-        // beginning of this render quantum.
-        let clamped = self.intrinsic_value.clamp(self.min_value, self.max_value);
+        // beginning of this render quantum, including the smoothing of direct
+        // value changes
+        let value = self.intrinsic_value + self.smoothing_offset;
+        let clamped = value.clamp(self.min_value, self.max_value);
         self.current_value.store(clamped, Ordering::Release);
 
+        self.compute_automation(block_time, dt, count);
+        self.next_block_time = dt.mul_add(count as f64, block_time);
+
+        if self.smoothing_offset != 0. {
+            self.apply_smoothing(dt, count);
+        }
+    }
+
+    // Add the exponentially decaying offset left by smoothed direct value changes,
+    // i.e. 𝑣(𝑡) = 𝑎(𝑡) + 𝑂 * 𝑒^−((𝑡−𝑇0) / 𝜏), with 𝑎 the automation
+    fn apply_smoothing(&mut self, dt: f64, count: usize) {
+        let coef = (-dt / self.smoothing).exp() as f32;
+
+        if self.automation_rate.is_a_rate() {
+            // constant blocks only contain a single value
+            if self.buffer.len() == 1 {
+                let value = self.buffer[0];
+                for _ in 1..count {
+                    self.buffer.push(value);
+                }
+            }
+
+            let mut offset = self.smoothing_offset;
+            self.buffer.iter_mut().for_each(|v| {
+                *v += offset;
+                offset *= coef;
+            });
+        } else {
+            self.buffer[0] += self.smoothing_offset;
+        }
+
+        // computed the same way for both automation rates
+        self.smoothing_offset *= coef.powi(count as i32);
+
+        if self.smoothing_offset.abs() < SNAP_TO_TARGET {
+            self.smoothing_offset = 0.;
+        }
+    }
+
+    fn compute_automation(&mut self, block_time: f64, dt: f64, count: usize) {
         // clear the buffer for this block
         self.buffer.clear();
 
@@ -1519,7 +2468,8 @@ impl AudioParamProcessor {
         // Several cases allow us to do so:
         // - The timeline is empty
         // - The timeline is not empty: in such case if `event.time >= next_block_time`
-        //   AND `event_type` is not `LinearRampToValueAtTime` or `ExponentialRampToValueAtTime`
+        //   AND `event_type` is not `LinearRampToValueAtTime`, `ExponentialRampToValueAtTime`
+        //   or `ShapedRampToValueAtTime`
         //   this is safe, i.e.:
         //   + For linear and exponential ramps `event.time` is the end time while their
         //   start time is `last_event.time`, therefore if `peek()` is of these
@@ -1532,6 +2482,7 @@ impl AudioParamProcessor {
             Some(event) => {
                 if event.event_type != AudioParamEventType::LinearRampToValueAtTime
                     && event.event_type != AudioParamEventType::ExponentialRampToValueAtTime
+                    && event.event_type != AudioParamEventType::ShapedRampToValueAtTime
                 {
                     event.time >= next_block_time
                 } else {
@@ -1581,6 +2532,9 @@ impl AudioParamProcessor {
                 Some(AudioParamEventType::ExponentialRampToValueAtTime) => {
                     self.compute_exponential_ramp_automation(&block_infos)
                 }
+                Some(AudioParamEventType::ShapedRampToValueAtTime) => {
+                    self.compute_shaped_ramp_automation(&block_infos)
+                }
                 Some(AudioParamEventType::SetTargetAtTime) => {
                     self.compute_set_target_automation(&block_infos)
                 }
@@ -1626,6 +2580,17 @@ pub(crate) fn audio_param_pair(
 
     let current_value = Arc::new(AtomicF32::new(default_value));
 
+    let automation = AudioParamAutomation {
+        default_value,
+        min_value,
+        max_value,
+        sample_rate: registration.context().sample_rate() as f64,
+        smoothing: 0.,
+        events: AudioParamEventTimeline::new(),
+        last: None,
+        smoothing_offset: (0., 0., 0.),
+    };
+
     let param = AudioParam {
         registration: registration.into(),
         raw_parts: AudioParamInner {
@@ -1635,6 +2600,7 @@ pub(crate) fn audio_param_pair(
             automation_rate_constrained: false,
             automation_rate: Arc::new(Mutex::new(automation_rate)),
             current_value: Arc::clone(&current_value),
+            automation: Arc::new(Mutex::new(automation)),
         },
     };
 
@@ -1648,6 +2614,9 @@ pub(crate) fn audio_param_pair(
         event_timeline: AudioParamEventTimeline::new(),
         last_event: None,
         buffer: ArrayVec::new(),
+        smoothing: 0.,
+        smoothing_offset: 0.,
+        next_block_time: 0.,
     };
 
     (param, processor)
@@ -1655,9 +2624,12 @@ pub(crate) fn audio_param_pair(
 
 #[cfg(test)]
 mod tests {
+    use std::f32::consts::FRAC_PI_4;
+
     use float_eq::assert_float_eq;
 
     use crate::context::{BaseAudioContext, OfflineAudioContext};
+    use crate::node::AudioScheduledSourceNode;
     use crate::render::Alloc;
 
     use super::*;
@@ -2399,6 +3371,96 @@ mod tests {
         assert_float_eq!(vs, &res[10..], abs_all <= 1e-7);
     }
 
+    #[test]
+    fn test_shaped_ramp_a_rate() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+
+        let opts = AudioParamDescriptor {
+            name: String::new(),
+            automation_rate: AutomationRate::A,
+            default_value: 0.,
+            min_value: -10.,
+            max_value: 10.,
+        };
+        let (param, mut render) = audio_param_pair(opts, context.mock_registration());
+
+        render.handle_incoming_event(param.set_value_at_time_raw(0., 0.));
+        // power ramp to 1 from t = 0 to t = 4
+        render.handle_incoming_event(param.shaped_ramp_to_value_at_time_raw(
+            1.,
+            4.,
+            RampShape::Power(2.),
+        ));
+        // s-curve ramp to 0 from t = 4 to t = 8
+        render.handle_incoming_event(param.shaped_ramp_to_value_at_time_raw(
+            0.,
+            8.,
+            RampShape::SCurve,
+        ));
+
+        let vs = render.compute_intrinsic_values(0., 1., 10);
+        let s_curve = |phase: f32| 1. - (0.5 - 0.5 * (PI * phase).cos());
+        let expected = [
+            0.,
+            1. / 16.,
+            0.25,
+            9. / 16.,
+            1.,
+            s_curve(0.25),
+            s_curve(0.5),
+            s_curve(0.75),
+            0.,
+            0.,
+        ];
+        assert_float_eq!(vs, &expected[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_shaped_ramp_equal_power() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+
+        let opts = AudioParamDescriptor {
+            name: String::new(),
+            automation_rate: AutomationRate::A,
+            default_value: 0.,
+            min_value: 0.,
+            max_value: 1.,
+        };
+        let (fade_in, mut render_in) = audio_param_pair(opts.clone(), context.mock_registration());
+        let (fade_out, mut render_out) = audio_param_pair(opts, context.mock_registration());
+
+        render_in.handle_incoming_event(fade_in.set_value_at_time_raw(0., 0.));
+        render_in.handle_incoming_event(fade_in.shaped_ramp_to_value_at_time_raw(
+            1.,
+            10.,
+            RampShape::EqualPower,
+        ));
+        render_out.handle_incoming_event(fade_out.set_value_at_time_raw(1., 0.));
+        render_out.handle_incoming_event(fade_out.shaped_ramp_to_value_at_time_raw(
+            0.,
+            10.,
+            RampShape::EqualPower,
+        ));
+
+        let vs_in = render_in.compute_intrinsic_values(0., 1., 10).to_vec();
+        let vs_out = render_out.compute_intrinsic_values(0., 1., 10);
+
+        // the sum of the powers of the crossfaded signals is constant
+        vs_in.iter().zip(vs_out).for_each(|(i, o)| {
+            assert_float_eq!(i * i + o * o, 1., abs <= 1e-6);
+        });
+        assert_float_eq!(vs_in[5], FRAC_PI_4.sin(), abs <= 1e-6);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_shaped_ramp_invalid_exponent() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+        let gain = context.create_gain();
+        gain.gain()
+            .shaped_ramp_to_value_at_time(0., 1., RampShape::Power(0.));
+    }
+
     #[test]
     fn test_set_target_at_time_a_rate() {
         let context = OfflineAudioContext::new(1, 1, 48000.);
@@ -2642,6 +3704,37 @@ mod tests {
         assert_float_eq!(vs[5], 1., abs <= 0.);
     }
 
+    #[test]
+    fn test_set_target_at_time_not_started_followed_by_ramp() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+        let opts = AudioParamDescriptor {
+            name: String::new(),
+            automation_rate: AutomationRate::A,
+            default_value: 0.,
+            min_value: 0.,
+            max_value: 10.,
+        };
+        let (param, mut render) = audio_param_pair(opts, context.mock_registration());
+
+        render.handle_incoming_event(param.set_value_at_time_raw(1., 0.));
+        render.handle_incoming_event(param.set_target_at_time_raw(0., 15., 1.));
+        render.handle_incoming_event(param.linear_ramp_to_value_at_time_raw(3., 25.));
+
+        // the value is held until the SetTarget start time
+        let vs = render.compute_intrinsic_values(0., 1., 10);
+        assert_float_eq!(vs, &[1.; 10][..], abs_all <= 0.);
+
+        // the ramp replaces the SetTarget event, from its start time
+        let mut res = vec![1.; 5];
+        res.extend((15..25).map(|t| 1. + 2. * (t - 15) as f32 / 10.));
+        res.extend([3.; 5]);
+
+        let vs = render.compute_intrinsic_values(10., 1., 10);
+        assert_float_eq!(vs, &res[0..10], abs_all <= 1e-6);
+        let vs = render.compute_intrinsic_values(20., 1., 10);
+        assert_float_eq!(vs, &res[10..20], abs_all <= 1e-6);
+    }
+
     #[test]
     fn test_set_target_at_time_a_rate_followed_by_ramp() {
         let context = OfflineAudioContext::new(1, 1, 48000.);
@@ -3274,6 +4367,64 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_smoothing() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+
+        let opts = AudioParamDescriptor {
+            name: String::new(),
+            automation_rate: AutomationRate::A,
+            default_value: 0.,
+            min_value: -10.,
+            max_value: 10.,
+        };
+        let (param, mut render) = audio_param_pair(opts, context.mock_registration());
+
+        param.set_smoothing(2.);
+        render.handle_incoming_event(param.set_value_raw(1.));
+
+        let vs = render.compute_intrinsic_values(0., 1., 10);
+        let expected: Vec<f32> = (0..10).map(|t| 1. - (-(t as f32) / 2.).exp()).collect();
+        assert_float_eq!(vs, &expected[..], abs_all <= 1e-6);
+
+        // scheduled events are not smoothed, but the remaining offset still decays
+        render.handle_incoming_event(param.set_value_at_time_raw(5., 12.));
+
+        let vs = render.compute_intrinsic_values(10., 1., 10);
+        let expected: Vec<f32> = (10..20)
+            .map(|t| {
+                let target = if t < 12 { 1. } else { 5. };
+                target - (-(t as f32) / 2.).exp()
+            })
+            .collect();
+        assert_float_eq!(vs, &expected[..], abs_all <= 1e-6);
+    }
+
+    #[test]
+    fn test_smoothing_value_at_time() {
+        let context = OfflineAudioContext::new(1, 1, 48000.);
+        let gain = context.create_gain();
+        let param = gain.gain();
+
+        assert_float_eq!(param.smoothing(), 0., abs <= 0.);
+        param.set_smoothing(0.01);
+        assert_float_eq!(param.smoothing(), 0.01, abs <= 0.);
+
+        // from the default value of 1 to 0
+        param.set_value(0.);
+
+        let expected = (-0.005_f64 / 0.01).exp() as f32;
+        assert_float_eq!(param.value_at_time(0.005), expected, abs <= 1e-4);
+        // no automation event is inserted
+        assert_eq!(
+            param.scheduled_events(),
+            vec![AutomationEvent::SetValueAtTime {
+                value: 0.,
+                start_time: 0.
+            }]
+        );
+    }
+
     #[test]
     fn test_update_automation_rate_to_k() {
         let context = OfflineAudioContext::new(1, 1, 48000.);
@@ -3498,6 +4649,148 @@ mod tests {
         assert_float_eq!(output.channel_data(0)[0], 1., abs <= 0.);
     }
 
+    #[test]
+    fn test_value_at_time() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let gain = context.create_gain();
+        let param = gain.gain();
+
+        param.set_value_at_time(0., 0.);
+        param.linear_ramp_to_value_at_time(1., 1.);
+
+        assert_float_eq!(param.value_at_time(0.), 0., abs <= 0.);
+        assert_float_eq!(param.value_at_time(0.5), 0.5, abs <= 1e-6);
+        assert_float_eq!(param.value_at_time(2.), 1., abs <= 0.);
+
+        // k-rate params hold the value of the first sample-frame of the render quantum
+        param.set_automation_rate(AutomationRate::K);
+        let block_time = (187 * RENDER_QUANTUM_SIZE) as f32 / 48000.;
+        assert_float_eq!(param.value_at_time(0.5), block_time, abs <= 1e-6);
+    }
+
+    #[test]
+    fn test_get_value_curve_at_time_matches_rendering() {
+        let sample_rate = 48000.;
+        let length = RENDER_QUANTUM_SIZE * 20;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start();
+
+        let offset = src.offset();
+        offset.set_value_at_time(0.2, 0.);
+        offset.linear_ramp_to_value_at_time(1., 0.01);
+        offset.exponential_ramp_to_value_at_time(0.5, 0.02);
+        offset.set_target_at_time(-0.5, 0.025, 0.005);
+        offset.set_value_curve_at_time(&[0., 1., -1., 0.5], 0.04, 0.01);
+        offset.cancel_and_hold_at_time(0.047);
+
+        let mut expected = vec![0.; length];
+        let duration = (length - 1) as f64 / sample_rate as f64;
+        offset.get_value_curve_at_time(&mut expected, 0., duration);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_get_value_curve_at_time_matches_rendering_set_value_and_cancel() {
+        let sample_rate = 48000.;
+        let length = RENDER_QUANTUM_SIZE * 20;
+        let mut context = OfflineAudioContext::new(1, length, sample_rate);
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        src.start();
+
+        let offset = src.offset();
+        offset.set_value(0.3);
+        // the ramp replaces the SetTarget event, which has not started
+        offset.set_target_at_time(1., 0.005, 0.002);
+        offset.linear_ramp_to_value_at_time(0., 0.02);
+        offset.set_value_at_time(0.7, 0.03);
+        offset.cancel_scheduled_values(0.03);
+
+        let mut expected = vec![0.; length];
+        let duration = (length - 1) as f64 / sample_rate as f64;
+        offset.get_value_curve_at_time(&mut expected, 0., duration);
+
+        let output = context.start_rendering_sync();
+        assert_float_eq!(output.get_channel_data(0), &expected[..], abs_all <= 0.);
+    }
+
+    #[test]
+    fn test_scheduled_events() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let gain = context.create_gain();
+        let param = gain.gain();
+
+        param.set_value_at_time(0.5, 1.);
+        param.linear_ramp_to_value_at_time(1., 2.);
+        param.set_target_at_time(0., 3., 0.1);
+        param.cancel_and_hold_at_time(1.5);
+        param.set_value_at_time(0.2, 1.8);
+
+        assert_eq!(
+            param.scheduled_events(),
+            vec![
+                // value set when creating the node
+                AutomationEvent::SetValueAtTime {
+                    value: 1.,
+                    start_time: 0.
+                },
+                AutomationEvent::SetValueAtTime {
+                    value: 0.5,
+                    start_time: 1.
+                },
+                AutomationEvent::LinearRampToValueAtTime {
+                    value: 1.,
+                    end_time: 2.
+                },
+                AutomationEvent::CancelAndHoldAtTime { cancel_time: 1.5 },
+                AutomationEvent::SetValueAtTime {
+                    value: 0.2,
+                    start_time: 1.8
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_schedule_events() {
+        let context = OfflineAudioContext::new(1, 128, 48000.);
+        let gain = context.create_gain();
+        let param = gain.gain();
+
+        param.set_value_at_time(0.5, 1.);
+        param.linear_ramp_to_value_at_time(1., 2.);
+        param.set_target_at_time(0., 3., 0.1);
+        param.set_value_curve_at_time(&[0., 1.], 4., 1.);
+        param.cancel_and_hold_at_time(4.5);
+
+        let events = param.scheduled_events();
+
+        // restore as is
+        let other = context.create_gain();
+        other.gain().schedule_events(events.clone());
+        assert_eq!(other.gain().scheduled_events(), events);
+
+        // restore stretched and delayed
+        let other = context.create_gain();
+        other.gain().schedule_events(
+            events
+                .into_iter()
+                .map(|event| event.time_scaled(2.).time_shifted(1.)),
+        );
+
+        let mut expected = [0.; 10];
+        param.get_value_curve_at_time(&mut expected, 0., 5.);
+        let mut values = [0.; 10];
+        other.gain().get_value_curve_at_time(&mut values, 1., 10.);
+        assert_float_eq!(values, expected, abs_all <= 1e-3);
+    }
+
     #[test]
     fn test_full_render_chain() {
         let alloc = Alloc::with_capacity(1);
@@ -1,6 +1,6 @@
 //! Audio processing code that runs on the audio rendering thread
 use crate::context::{AudioNodeId, AudioParamId};
-use crate::events::{AudioProcessingEvent, ErrorEvent, EventDispatch};
+use crate::events::{AudioProcessingEvent, ErrorEvent, EventDispatch, OnsetEvent, PitchEvent};
 use crate::{AudioBuffer, Event, RENDER_QUANTUM_SIZE};
 
 use super::{graph::Node, AudioRenderQuantum, NodeCollection};
@@ -74,6 +74,33 @@ impl AudioWorkletGlobalScope {
         let _ = self.event_sender.try_send(dispatch);
     }
 
+    pub(crate) fn send_pitch_event(&self, frequency: f32, confidence: f32, time: f64) {
+        // sending could fail if the channel is saturated or the main thread is shutting down
+        let event = PitchEvent {
+            frequency,
+            confidence,
+            time,
+            event: Event {
+                type_: "PitchEvent",
+            },
+        };
+        let dispatch = EventDispatch::pitch(self.node_id.get(), event);
+        let _ = self.event_sender.try_send(dispatch);
+    }
+
+    pub(crate) fn send_onset_event(&self, time: f64, strength: f32) {
+        // sending could fail if the channel is saturated or the main thread is shutting down
+        let event = OnsetEvent {
+            time,
+            strength,
+            event: Event {
+                type_: "OnsetEvent",
+            },
+        };
+        let dispatch = EventDispatch::onset(self.node_id.get(), event);
+        let _ = self.event_sender.try_send(dispatch);
+    }
+
     pub(crate) fn report_error(&self, error: Box<dyn Any + Send>) {
         pub fn type_name_of_val<T: ?Sized>(_val: &T) -> &'static str {
             std::any::type_name::<T>()
@@ -45,6 +45,8 @@ pub(crate) struct RenderThread {
     stats: AudioStats,
     event_sender: Sender<EventDispatch>,
     garbage_collector: Option<llq::Producer<Box<dyn Any + Send>>>,
+    /// context time at which the control thread should run its scheduled callbacks
+    scheduler_wakeup: f64,
 }
 
 // SAFETY:
@@ -104,6 +106,7 @@ impl RenderThread {
             stats,
             event_sender,
             garbage_collector: None,
+            scheduler_wakeup: f64::INFINITY,
         }
     }
 
@@ -246,6 +249,9 @@ impl RenderThread {
                     .unwrap()
                     .set_channel_interpretation(id, interpretation);
             }
+            SchedulerWakeup { when } => {
+                self.scheduler_wakeup = when;
+            }
         }
 
         ControlFlow::Continue(()) // continue handling more messages
@@ -393,6 +399,23 @@ impl RenderThread {
                 .unwrap_or(&[0.; RENDER_QUANTUM_SIZE]);
             b.extend_from_slice(&c[..remaining]);
         });
+
+        self.wakeup_scheduler();
+    }
+
+    /// Notify the control thread if the scheduler wakeup time is reached
+    fn wakeup_scheduler(&mut self) {
+        let current_frame = self.frames_played.load(Ordering::Relaxed);
+        let current_time = current_frame as f64 / self.sample_rate as f64;
+
+        if current_time >= self.scheduler_wakeup
+            && self
+                .event_sender
+                .try_send(EventDispatch::scheduler())
+                .is_ok()
+        {
+            self.scheduler_wakeup = f64::INFINITY;
+        }
     }
 
     /// Run destructors of all alive nodes in the audio graph
@@ -526,6 +549,8 @@ impl RenderThread {
                 self.buffer_offset = Some((channel_offset, destination_buffer));
             }
 
+            self.wakeup_scheduler();
+
             // handle addition/removal of nodes/edges
             self.handle_control_messages();
         }
@@ -298,6 +298,230 @@ pub fn angle(
     angle.abs()
 }
 
+/// Speed of sound in air, in meters per second
+pub(crate) const SPEED_OF_SOUND: f32 = 343.;
+
+/// Highest supported reflection order of a [`ShoeboxRoom`]
+pub(crate) const MAX_REFLECTION_ORDER: usize = 3;
+
+/// Rectangular room, simulating the early reflections of [`PannerNode`](crate::node::PannerNode)s
+///
+/// The walls of the room are aligned with the axes of the coordinate system of the
+/// [`AudioListener`]. For each panner inside the room, the paths of the sound reflected by the
+/// walls are computed with the image-source method, from the position of the panner to the
+/// position of the listener. Each reflection is delayed, attenuated by the walls it hits and by
+/// the distance model of the panner, and panned from the direction it reaches the listener.
+///
+/// The late reverberation of the room is shared by all the sources: use
+/// [`reverb_options`](Self::reverb_options) to create a [`ReverbNode`](crate::node::ReverbNode)
+/// and connect the panners to it as a send.
+///
+/// Note that this is not part of the Web Audio API specification.
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, PannerOptions, PannerNode, ReverbNode};
+/// use web_audio_api::ShoeboxRoom;
+///
+/// let context = AudioContext::default();
+///
+/// let room = ShoeboxRoom {
+///     dimensions: [6., 3., 8.],
+///     ..ShoeboxRoom::default()
+/// };
+///
+/// // shared late reverberation
+/// let reverb = ReverbNode::new(&context, room.reverb_options());
+/// reverb.connect(&context.destination());
+///
+/// let options = PannerOptions {
+///     position_x: 2.,
+///     room: Some(room),
+///     ..PannerOptions::default()
+/// };
+/// let panner = PannerNode::new(&context, options);
+/// panner.connect(&context.destination());
+/// panner.connect(&reverb);
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct ShoeboxRoom {
+    /// Center of the room, in meters
+    pub center: [f32; 3],
+    /// Width (x), height (y) and depth (z) of the room, in meters
+    pub dimensions: [f32; 3],
+    /// Absorption coefficient of each wall, in the range [0, 1]
+    ///
+    /// The walls are ordered left (-x), right (+x), floor (-y), ceiling (+y), front (-z) and
+    /// back (+z).
+    pub absorption: [f32; 6],
+    /// Maximum number of wall reflections of the simulated sound paths, in the range [0, 3]
+    pub reflection_order: usize,
+}
+
+impl Default for ShoeboxRoom {
+    fn default() -> Self {
+        Self {
+            center: [0.; 3],
+            dimensions: [8., 3., 10.],
+            absorption: [0.3; 6],
+            reflection_order: 2,
+        }
+    }
+}
+
+impl ShoeboxRoom {
+    /// Volume of the room, in cubic meters
+    pub fn volume(&self) -> f32 {
+        self.dimensions.iter().product()
+    }
+
+    /// Reverberation time of the room (in seconds), given by the Sabine formula
+    pub fn decay_time(&self) -> f32 {
+        let [width, height, depth] = self.dimensions;
+        let areas = [height * depth, width * depth, width * height];
+
+        let absorption_area: f32 = self
+            .absorption
+            .iter()
+            .enumerate()
+            .map(|(wall, alpha)| areas[wall / 2] * alpha)
+            .sum();
+
+        if absorption_area > 0. {
+            0.161 * self.volume() / absorption_area
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    /// Options of a [`ReverbNode`](crate::node::ReverbNode) simulating the late
+    /// reverberation of the room, to be used as a send
+    ///
+    /// The pre-delay skips the part of the reverberation simulated by the early reflections.
+    pub fn reverb_options(&self) -> crate::node::ReverbOptions {
+        let [width, height, depth] = self.dimensions;
+        let surface = 2. * (width * height + width * depth + height * depth);
+        let mean_free_path = 4. * self.volume() / surface;
+
+        crate::node::ReverbOptions {
+            decay_time: self.decay_time().min(60.),
+            pre_delay: ((self.reflection_order + 1) as f32 * mean_free_path / SPEED_OF_SOUND)
+                .min(1.),
+            size: (mean_free_path / 10.).clamp(0., 1.),
+            mix: 1.,
+            ..crate::node::ReverbOptions::default()
+        }
+    }
+
+    /// Assert that the room is valid
+    ///
+    /// # Panics
+    ///
+    /// This function panics if a dimension is not strictly positive, an absorption coefficient
+    /// is not in the range [0, 1] or the reflection order is greater than 3
+    #[track_caller]
+    pub(crate) fn assert_valid(&self) {
+        assert!(
+            self.dimensions.iter().all(|d| *d > 0.),
+            "RangeError - room dimensions must be strictly positive"
+        );
+        assert!(
+            self.absorption.iter().all(|a| (0. ..=1.).contains(a)),
+            "RangeError - wall absorption must be in the range [0, 1]"
+        );
+        assert!(
+            self.reflection_order <= MAX_REFLECTION_ORDER,
+            "NotSupportedError - reflection order must be in the range [0, {MAX_REFLECTION_ORDER}]"
+        );
+    }
+
+    /// Position of the given point relative to the lower corner of the room
+    fn local(&self, point: [f32; 3]) -> [f32; 3] {
+        std::array::from_fn(|i| point[i] - self.center[i] + self.dimensions[i] / 2.)
+    }
+
+    /// Whether the given point is inside the room
+    pub(crate) fn contains(&self, point: [f32; 3]) -> bool {
+        let local = self.local(point);
+        (0..3).all(|i| local[i] >= 0. && local[i] <= self.dimensions[i])
+    }
+
+    /// Maximum number of image sources of the room
+    pub(crate) fn max_image_sources(&self) -> usize {
+        let n = self.reflection_order as isize;
+        let mut count = 0;
+        for x in -n..=n {
+            for y in -n..=n {
+                for z in -n..=n {
+                    let order = x.abs() + y.abs() + z.abs();
+                    if order > 0 && order <= n {
+                        count += 1;
+                    }
+                }
+            }
+        }
+        count
+    }
+
+    /// Upper bound of the distance between a source and its image sources
+    pub(crate) fn max_image_distance(&self) -> f32 {
+        let diagonal = vec3_len(self.dimensions);
+        2. * (self.reflection_order + 1) as f32 * diagonal
+    }
+
+    /// Image sources of the given source position, with their reflection gain
+    ///
+    /// The images are cleared from `output` first. The direct path is not included.
+    pub(crate) fn image_sources(&self, source: [f32; 3], output: &mut Vec<([f32; 3], f32)>) {
+        output.clear();
+
+        let order = self.reflection_order as isize;
+        let local = self.local(source);
+        let offset: [f32; 3] = std::array::from_fn(|i| self.center[i] - self.dimensions[i] / 2.);
+        let reflection = self.absorption.map(|alpha| (1. - alpha).sqrt());
+
+        // images along a single axis: the mirrored coordinate, the number of reflections on
+        // the lower and upper walls, see Allen and Berkley (1979)
+        let axis = |i: usize, q: isize, j: isize| {
+            let coordinate = (1 - 2 * q) as f32 * local[i] + 2. * j as f32 * self.dimensions[i];
+            let lower = (j - q).abs();
+            let upper = j.abs();
+            let gain =
+                reflection[2 * i].powi(lower as i32) * reflection[2 * i + 1].powi(upper as i32);
+            (coordinate + offset[i], lower + upper, gain)
+        };
+
+        for qx in 0..2 {
+            for jx in -order..=order {
+                let (x, order_x, gain_x) = axis(0, qx, jx);
+                if order_x > order {
+                    continue;
+                }
+                for qy in 0..2 {
+                    for jy in -order..=order {
+                        let (y, order_y, gain_y) = axis(1, qy, jy);
+                        if order_x + order_y > order {
+                            continue;
+                        }
+                        for qz in 0..2 {
+                            for jz in -order..=order {
+                                let (z, order_z, gain_z) = axis(2, qz, jz);
+                                let total = order_x + order_y + order_z;
+                                if total == 0 || total > order {
+                                    continue;
+                                }
+                                output.push(([x, y, z], gain_x * gain_y * gain_z));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use float_eq::assert_float_eq;
@@ -391,4 +615,72 @@ mod tests {
 
         assert_float_eq!(angle, 90., abs <= 0.);
     }
+
+    #[test]
+    fn test_room_decay_time() {
+        let room = ShoeboxRoom {
+            dimensions: [10., 10., 10.],
+            absorption: [0.5; 6],
+            ..ShoeboxRoom::default()
+        };
+
+        // 0.161 * 1000 / (600 * 0.5)
+        assert_float_eq!(room.decay_time(), 0.536_666, abs <= 1e-5);
+
+        let room = ShoeboxRoom {
+            absorption: [0.; 6],
+            ..ShoeboxRoom::default()
+        };
+        assert_eq!(room.decay_time(), f32::INFINITY);
+        assert_eq!(room.reverb_options().decay_time, 60.);
+    }
+
+    #[test]
+    fn test_room_image_sources() {
+        let room = ShoeboxRoom {
+            center: [0., 1., 0.],
+            dimensions: [4., 2., 6.],
+            absorption: [0., 0.36, 0., 0., 0., 0.],
+            reflection_order: 1,
+        };
+        assert!(room.contains([1., 0.5, 0.]));
+        assert!(!room.contains([3., 0.5, 0.]));
+
+        let mut images = vec![];
+        room.image_sources([1., 0.5, 0.], &mut images);
+        assert_eq!(images.len(), 6);
+        assert_eq!(images.len(), room.max_image_sources());
+
+        let mut expected = vec![
+            ([-5., 0.5, 0.], 1.), // left wall
+            ([3., 0.5, 0.], 0.8), // right wall
+            ([1., -0.5, 0.], 1.), // floor
+            ([1., 3.5, 0.], 1.),  // ceiling
+            ([1., 0.5, -6.], 1.), // front wall
+            ([1., 0.5, 6.], 1.),  // back wall
+        ];
+
+        let key = |(p, _): &([f32; 3], f32)| p.map(|v| (v * 1000.) as i32);
+        images.sort_by_key(key);
+        expected.sort_by_key(key);
+
+        for ((position, gain), (expected_position, expected_gain)) in images.iter().zip(&expected) {
+            assert_float_eq!(position, expected_position, abs_all <= 1e-5);
+            assert_float_eq!(*gain, *expected_gain, abs <= 1e-5);
+        }
+
+        // second order
+        let room = ShoeboxRoom {
+            reflection_order: 2,
+            ..room
+        };
+        room.image_sources([1., 0.5, 0.], &mut images);
+        assert_eq!(images.len(), 24);
+        assert_eq!(images.len(), room.max_image_sources());
+        let max_distance = images
+            .iter()
+            .map(|(p, _)| distance(*p, [1., 0.5, 0.]))
+            .fold(0., f32::max);
+        assert!(max_distance <= room.max_image_distance());
+    }
 }
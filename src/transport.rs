@@ -0,0 +1,763 @@
+//! Musical time transport
+
+use crate::context::{BaseAudioContext, ConcreteBaseAudioContext};
+use crate::node::AudioScheduledSourceNode;
+use crate::AudioParam;
+
+#[track_caller]
+fn assert_valid_tempo(tempo: f64) {
+    assert!(
+        tempo.is_finite() && tempo > 0.,
+        "RangeError - Invalid tempo: {tempo:?}, should be strictly positive"
+    );
+}
+
+#[track_caller]
+fn assert_valid_time_signature(time_signature: TimeSignature) {
+    assert!(
+        time_signature.beats_per_bar > 0 && time_signature.beat_unit > 0,
+        "RangeError - Invalid time signature: {time_signature:?}, should not contain zero"
+    );
+}
+
+/// Position in musical time, in bars, beats and ticks
+///
+/// All fields are zero-based, i.e. `MusicalTime::new(0, 0, 0)` is the first beat of
+/// the first bar. Beats and ticks exceeding the length of a bar or of a beat simply
+/// carry over to the next bar or beat.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct MusicalTime {
+    /// Number of bars
+    pub bars: u32,
+    /// Number of beats in the bar
+    pub beats: u32,
+    /// Number of ticks in the beat
+    pub ticks: u32,
+}
+
+impl MusicalTime {
+    /// Number of ticks in a beat
+    pub const TICKS_PER_BEAT: u32 = 960;
+
+    /// Creates a `MusicalTime` from its bars, beats and ticks
+    #[must_use]
+    pub const fn new(bars: u32, beats: u32, ticks: u32) -> Self {
+        Self { bars, beats, ticks }
+    }
+}
+
+/// Time signature of a [`Transport`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct TimeSignature {
+    /// Number of beats in a bar (numerator)
+    pub beats_per_bar: u32,
+    /// Note value of a beat (denominator), e.g. 4 for a quarter note
+    pub beat_unit: u32,
+}
+
+impl Default for TimeSignature {
+    fn default() -> Self {
+        Self {
+            beats_per_bar: 4,
+            beat_unit: 4,
+        }
+    }
+}
+
+/// Options for constructing a [`Transport`]
+#[derive(Clone, Debug)]
+pub struct TransportOptions {
+    /// Initial tempo, in beats per minute
+    pub tempo: f64,
+    /// Time signature
+    pub time_signature: TimeSignature,
+}
+
+impl Default for TransportOptions {
+    fn default() -> Self {
+        Self {
+            tempo: 120.,
+            time_signature: TimeSignature::default(),
+        }
+    }
+}
+
+// Change of tempo at a given beat, either instantaneous or reached with a ramp
+// that is linear in musical time from the previous tempo event
+#[derive(Copy, Clone, Debug)]
+struct TempoEvent {
+    beat: f64,
+    tempo: f64,
+    ramp: bool,
+    // seconds elapsed from beat zero, derived from the previous events
+    time: f64,
+}
+
+// Duration in seconds of `beats` beats from the start of the segment, i.e. the
+// integral of 60 / tempo(beat)
+fn segment_duration(start: &TempoEvent, end: Option<&TempoEvent>, beats: f64) -> f64 {
+    match end {
+        Some(end) if end.ramp && end.tempo != start.tempo => {
+            let slope = (end.tempo - start.tempo) / (end.beat - start.beat);
+            60. / slope * (slope.mul_add(beats, start.tempo) / start.tempo).ln()
+        }
+        _ => 60. * beats / start.tempo,
+    }
+}
+
+// Number of beats elapsed `seconds` after the start of the segment, inverse
+// of `segment_duration`
+fn segment_beats(start: &TempoEvent, end: Option<&TempoEvent>, seconds: f64) -> f64 {
+    match end {
+        Some(end) if end.ramp && end.tempo != start.tempo => {
+            let slope = (end.tempo - start.tempo) / (end.beat - start.beat);
+            start.tempo * ((seconds * slope / 60.).exp() - 1.) / slope
+        }
+        _ => seconds * start.tempo / 60.,
+    }
+}
+
+/// `Transport` maps musical time (bars, beats and ticks) to the time of an audio
+/// context, to schedule sources and automation events in musical time
+///
+/// The transport follows a tempo map, which can contain instantaneous tempo changes
+/// and tempo ramps, and can repeat a loop region. All conversions are computed from
+/// the time at which the transport started, so that musical positions never drift
+/// from the context time, whatever the tempo changes.
+///
+/// Note that this is not part of the Web Audio API specification.
+///
+/// - see also: [`BaseAudioContext::create_transport`]
+///
+/// # Usage
+///
+/// ```no_run
+/// use web_audio_api::context::{BaseAudioContext, AudioContext};
+/// use web_audio_api::node::{AudioNode, AudioScheduledSourceNode};
+/// use web_audio_api::MusicalTime;
+///
+/// let context = AudioContext::default();
+///
+/// let mut transport = context.create_transport();
+/// transport.set_tempo(90.);
+/// transport.start();
+///
+/// // play a note on the third beat of the second bar
+/// let mut osc = context.create_oscillator();
+/// osc.connect(&context.destination());
+/// transport.start_source_at_position(&mut osc, MusicalTime::new(1, 2, 0));
+/// transport.stop_source_at_position(&mut osc, MusicalTime::new(1, 3, 0));
+///
+/// std::thread::sleep(std::time::Duration::from_secs(4));
+/// ```
+///
+/// # Examples
+///
+/// - `cargo run --release --example transport`
+///
+#[derive(Clone, Debug)]
+pub struct Transport {
+    context: ConcreteBaseAudioContext,
+    time_signature: TimeSignature,
+    // sorted by beat, the first event is always at beat zero
+    tempo_map: Vec<TempoEvent>,
+    loop_region: Option<(MusicalTime, MusicalTime)>,
+    // context time of the beat zero, if started
+    origin: Option<f64>,
+}
+
+impl Transport {
+    /// Creates a `Transport`, stopped
+    ///
+    /// # Panics
+    ///
+    /// This function panics if:
+    /// - the tempo is not strictly positive
+    /// - the time signature contains a zero
+    pub fn new<C: BaseAudioContext>(context: &C, options: TransportOptions) -> Self {
+        let TransportOptions {
+            tempo,
+            time_signature,
+        } = options;
+
+        assert_valid_tempo(tempo);
+        assert_valid_time_signature(time_signature);
+
+        Self {
+            context: context.base().clone(),
+            time_signature,
+            tempo_map: vec![TempoEvent {
+                beat: 0.,
+                tempo,
+                ramp: false,
+                time: 0.,
+            }],
+            loop_region: None,
+            origin: None,
+        }
+    }
+
+    /// Start the transport from the first beat, immediately
+    pub fn start(&mut self) {
+        let when = self.context.current_time();
+        self.start_at_with_position(when, MusicalTime::default());
+    }
+
+    /// Start the transport from the first beat, at the given context time
+    pub fn start_at(&mut self, when: f64) {
+        self.start_at_with_position(when, MusicalTime::default());
+    }
+
+    /// Start the transport from the given position, at the given context time
+    pub fn start_at_with_position(&mut self, when: f64, position: MusicalTime) {
+        let beat = self.musical_time_to_beats(position);
+        self.origin = Some(when - self.seconds_at_beat(beat));
+    }
+
+    /// Stop the transport
+    ///
+    /// Sources and automation events already scheduled are not cancelled.
+    pub fn stop(&mut self) {
+        self.origin = None;
+    }
+
+    /// Whether the transport is started
+    #[must_use]
+    pub fn is_started(&self) -> bool {
+        self.origin.is_some()
+    }
+
+    /// Time signature of the transport
+    #[must_use]
+    pub fn time_signature(&self) -> TimeSignature {
+        self.time_signature
+    }
+
+    /// Update the time signature of the transport
+    ///
+    /// The tempo map and the loop region are expressed in musical time, so they
+    /// follow the new bar length. If the transport is started, it continues from
+    /// the current beat.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the time signature contains a zero
+    pub fn set_time_signature(&mut self, time_signature: TimeSignature) {
+        assert_valid_time_signature(time_signature);
+
+        // keep the tempo map at the same musical positions
+        let previous = self.time_signature.beats_per_bar as f64;
+        let current = time_signature.beats_per_bar as f64;
+        let to_new_beats = |beat: f64| {
+            let bars = (beat / previous).floor();
+            bars.mul_add(current, beat - bars * previous)
+        };
+
+        self.preserve_position(|transport| {
+            transport.time_signature = time_signature;
+            transport
+                .tempo_map
+                .iter_mut()
+                .for_each(|event| event.beat = to_new_beats(event.beat));
+            transport.update_tempo_map();
+        });
+    }
+
+    /// Tempo at the current position, in beats per minute
+    ///
+    /// Returns the initial tempo if the transport is not started.
+    #[must_use]
+    pub fn tempo(&self) -> f64 {
+        let beat = self.current_beat().unwrap_or(0.);
+        self.tempo_at_beat(beat)
+    }
+
+    /// Tempo at the given position, in beats per minute
+    #[must_use]
+    pub fn tempo_at_position(&self, position: MusicalTime) -> f64 {
+        self.tempo_at_beat(self.musical_time_to_beats(position))
+    }
+
+    /// Set the tempo from the current position (or from the first beat if the
+    /// transport is not started), removing all subsequent tempo changes
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive
+    pub fn set_tempo(&mut self, tempo: f64) {
+        assert_valid_tempo(tempo);
+
+        let beat = self.current_beat().unwrap_or(0.);
+        self.preserve_position(|transport| {
+            transport.tempo_map.retain(|event| event.beat < beat);
+            transport.insert_tempo_event(beat, tempo, false);
+        });
+    }
+
+    /// Schedule an instantaneous tempo change at the given position
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive
+    pub fn set_tempo_at_position(&mut self, tempo: f64, position: MusicalTime) {
+        assert_valid_tempo(tempo);
+
+        let beat = self.musical_time_to_beats(position);
+        self.preserve_position(|transport| transport.insert_tempo_event(beat, tempo, false));
+    }
+
+    /// Schedule a tempo ramp from the previous tempo change, reaching the given
+    /// tempo at the given position
+    ///
+    /// The tempo changes linearly in musical time along the ramp.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the tempo is not strictly positive
+    pub fn linear_ramp_to_tempo_at_position(&mut self, tempo: f64, position: MusicalTime) {
+        assert_valid_tempo(tempo);
+
+        let beat = self.musical_time_to_beats(position);
+        self.preserve_position(|transport| transport.insert_tempo_event(beat, tempo, true));
+    }
+
+    /// Loop region of the transport, if any
+    #[must_use]
+    pub fn loop_region(&self) -> Option<(MusicalTime, MusicalTime)> {
+        self.loop_region
+    }
+
+    /// Repeat the region between `start` and `end` once the transport reaches `end`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `end` is not after `start`
+    pub fn set_loop(&mut self, start: MusicalTime, end: MusicalTime) {
+        assert!(
+            self.musical_time_to_beats(start) < self.musical_time_to_beats(end),
+            "RangeError - Invalid loop region: loop end {end:?} should be after loop start {start:?}"
+        );
+
+        self.preserve_position(|transport| transport.loop_region = Some((start, end)));
+    }
+
+    /// Remove the loop region
+    pub fn clear_loop(&mut self) {
+        self.preserve_position(|transport| transport.loop_region = None);
+    }
+
+    /// Convert a position to a duration in seconds from the first beat, following
+    /// the tempo map but ignoring the loop region
+    #[must_use]
+    pub fn position_to_seconds(&self, position: MusicalTime) -> f64 {
+        self.seconds_at_beat(self.musical_time_to_beats(position))
+    }
+
+    /// Convert a duration in seconds from the first beat to a position (rounded to the
+    /// nearest tick), following the tempo map but ignoring the loop region
+    #[must_use]
+    pub fn seconds_to_position(&self, seconds: f64) -> MusicalTime {
+        self.beats_to_musical_time(self.beat_at_seconds(seconds))
+    }
+
+    /// Current position of the transport, `None` if the transport is not started or
+    /// has not reached its first beat yet
+    #[must_use]
+    pub fn position(&self) -> Option<MusicalTime> {
+        self.position_at_time(self.context.current_time())
+    }
+
+    /// Position of the transport at the given context time, `None` if the transport
+    /// is not started or has not reached its first beat at that time
+    #[must_use]
+    pub fn position_at_time(&self, time: f64) -> Option<MusicalTime> {
+        self.beat_at_time(time)
+            .map(|beat| self.beats_to_musical_time(beat))
+    }
+
+    /// Next context time at which the transport plays the given position, `None` if
+    /// the transport is not started or will not play this position anymore
+    ///
+    /// When a loop region is set, positions inside the region are played at each
+    /// iteration while positions after the region are never played.
+    #[must_use]
+    pub fn time_at_position(&self, position: MusicalTime) -> Option<f64> {
+        let origin = self.origin?;
+        let now = self.context.current_time();
+        let beat = self.musical_time_to_beats(position);
+        let time = origin + self.seconds_at_beat(beat);
+
+        if let Some((start, end)) = self.loop_region {
+            let start = self.musical_time_to_beats(start);
+            let end = self.musical_time_to_beats(end);
+
+            if beat >= end {
+                return None;
+            }
+
+            if beat >= start && time < now {
+                let duration = self.seconds_at_beat(end) - self.seconds_at_beat(start);
+                let iterations = ((now - time) / duration).ceil();
+                return Some(iterations.mul_add(duration, time));
+            }
+        }
+
+        (time >= now).then_some(time)
+    }
+
+    /// Start the source at the next time the transport plays the given position
+    ///
+    /// Returns the context time at which the source is scheduled, `None` if the
+    /// position is not played anymore, in which case the source is not started.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source was already started
+    pub fn start_source_at_position<N: AudioScheduledSourceNode + ?Sized>(
+        &self,
+        node: &mut N,
+        position: MusicalTime,
+    ) -> Option<f64> {
+        let time = self.time_at_position(position)?;
+        node.start_at(time);
+        Some(time)
+    }
+
+    /// Stop the source at the next time the transport plays the given position
+    ///
+    /// Returns the context time at which the source is scheduled to stop, `None` if
+    /// the position is not played anymore, in which case the source is not stopped.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the source was not started yet
+    pub fn stop_source_at_position<N: AudioScheduledSourceNode + ?Sized>(
+        &self,
+        node: &mut N,
+        position: MusicalTime,
+    ) -> Option<f64> {
+        let time = self.time_at_position(position)?;
+        node.stop_at(time);
+        Some(time)
+    }
+
+    /// Schedule a value change of the param at the next time the transport plays
+    /// the given position, cf. [`AudioParam::set_value_at_time`]
+    ///
+    /// Returns the context time of the automation event, `None` if the position is
+    /// not played anymore, in which case no event is scheduled.
+    pub fn set_value_at_position(
+        &self,
+        param: &AudioParam,
+        value: f32,
+        position: MusicalTime,
+    ) -> Option<f64> {
+        let time = self.time_at_position(position)?;
+        param.set_value_at_time(value, time);
+        Some(time)
+    }
+
+    /// Schedule a linear ramp of the param, ending at the next time the transport
+    /// plays the given position, cf. [`AudioParam::linear_ramp_to_value_at_time`]
+    ///
+    /// Returns the context time of the automation event, `None` if the position is
+    /// not played anymore, in which case no event is scheduled.
+    pub fn linear_ramp_to_value_at_position(
+        &self,
+        param: &AudioParam,
+        value: f32,
+        position: MusicalTime,
+    ) -> Option<f64> {
+        let time = self.time_at_position(position)?;
+        param.linear_ramp_to_value_at_time(value, time);
+        Some(time)
+    }
+
+    fn musical_time_to_beats(&self, position: MusicalTime) -> f64 {
+        let MusicalTime { bars, beats, ticks } = position;
+        let ticks = ticks as f64 / MusicalTime::TICKS_PER_BEAT as f64;
+        (bars as f64).mul_add(self.time_signature.beats_per_bar as f64, beats as f64) + ticks
+    }
+
+    fn beats_to_musical_time(&self, beat: f64) -> MusicalTime {
+        let ticks_per_bar = self.time_signature.beats_per_bar * MusicalTime::TICKS_PER_BEAT;
+        let ticks = (beat * MusicalTime::TICKS_PER_BEAT as f64).round().max(0.) as u64;
+
+        MusicalTime {
+            bars: (ticks / ticks_per_bar as u64) as u32,
+            beats: ((ticks % ticks_per_bar as u64) / MusicalTime::TICKS_PER_BEAT as u64) as u32,
+            ticks: (ticks % MusicalTime::TICKS_PER_BEAT as u64) as u32,
+        }
+    }
+
+    fn seconds_at_beat(&self, beat: f64) -> f64 {
+        let index = self.tempo_map.partition_point(|e| e.beat <= beat).max(1) - 1;
+        let event = &self.tempo_map[index];
+        let next = self.tempo_map.get(index + 1);
+
+        event.time + segment_duration(event, next, beat - event.beat)
+    }
+
+    fn beat_at_seconds(&self, seconds: f64) -> f64 {
+        let index = self.tempo_map.partition_point(|e| e.time <= seconds).max(1) - 1;
+        let event = &self.tempo_map[index];
+        let next = self.tempo_map.get(index + 1);
+
+        event.beat + segment_beats(event, next, seconds - event.time)
+    }
+
+    fn tempo_at_beat(&self, beat: f64) -> f64 {
+        let index = self.tempo_map.partition_point(|e| e.beat <= beat).max(1) - 1;
+        let event = &self.tempo_map[index];
+
+        match self.tempo_map.get(index + 1) {
+            Some(next) if next.ramp => {
+                let phase = (beat - event.beat) / (next.beat - event.beat);
+                (next.tempo - event.tempo).mul_add(phase, event.tempo)
+            }
+            _ => event.tempo,
+        }
+    }
+
+    // beat played at the given context time, taking the loop region into account
+    fn beat_at_time(&self, time: f64) -> Option<f64> {
+        let mut seconds = time - self.origin?;
+
+        if seconds < 0. {
+            return None;
+        }
+
+        if let Some((start, end)) = self.loop_region {
+            let start = self.seconds_at_beat(self.musical_time_to_beats(start));
+            let end = self.seconds_at_beat(self.musical_time_to_beats(end));
+
+            if seconds >= end {
+                seconds = start + (seconds - start) % (end - start);
+            }
+        }
+
+        Some(self.beat_at_seconds(seconds))
+    }
+
+    fn current_beat(&self) -> Option<f64> {
+        self.beat_at_time(self.context.current_time())
+    }
+
+    // Apply a change of the tempo map or of the loop region while keeping the
+    // current beat at the current time, so that a started transport does not jump
+    fn preserve_position<F: FnOnce(&mut Self)>(&mut self, change: F) {
+        let now = self.context.current_time();
+        let beat = self.beat_at_time(now);
+
+        change(self);
+
+        if let Some(beat) = beat {
+            self.origin = Some(now - self.seconds_at_beat(beat));
+        }
+    }
+
+    fn insert_tempo_event(&mut self, beat: f64, tempo: f64, ramp: bool) {
+        // the first tempo event cannot be a ramp
+        let event = TempoEvent {
+            beat,
+            tempo,
+            ramp: ramp && beat > 0.,
+            time: 0.,
+        };
+
+        let index = self.tempo_map.partition_point(|e| e.beat < beat);
+        if self.tempo_map.get(index).is_some_and(|e| e.beat == beat) {
+            self.tempo_map[index] = event;
+        } else {
+            self.tempo_map.insert(index, event);
+        }
+
+        self.update_tempo_map();
+    }
+
+    fn update_tempo_map(&mut self) {
+        for index in 1..self.tempo_map.len() {
+            let previous = self.tempo_map[index - 1];
+            let current = self.tempo_map[index];
+            let duration =
+                segment_duration(&previous, Some(&current), current.beat - previous.beat);
+            self.tempo_map[index].time = previous.time + duration;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use float_eq::assert_float_eq;
+
+    use crate::context::OfflineAudioContext;
+    use crate::node::AudioNode;
+
+    use super::*;
+
+    #[test]
+    fn test_constant_tempo() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let transport = context.create_transport();
+
+        // 120 bpm, 4/4
+        let position = MusicalTime::new(2, 1, 480);
+        assert_float_eq!(transport.position_to_seconds(position), 4.75, abs <= 1e-12);
+        assert_eq!(transport.seconds_to_position(4.75), position);
+        assert_float_eq!(transport.tempo(), 120., abs <= 0.);
+    }
+
+    #[test]
+    fn test_tempo_changes() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut transport = context.create_transport();
+
+        // 60 bpm during the first bar, then ramp to 120 bpm during the second bar
+        transport.set_tempo(60.);
+        transport.set_tempo_at_position(60., MusicalTime::new(1, 0, 0));
+        transport.linear_ramp_to_tempo_at_position(120., MusicalTime::new(2, 0, 0));
+
+        assert_float_eq!(
+            transport.position_to_seconds(MusicalTime::new(1, 0, 0)),
+            4.,
+            abs <= 1e-12
+        );
+        // integral of 60 / (60 + 15 * beat) over 4 beats
+        let ramp = 4. * 2_f64.ln();
+        assert_float_eq!(
+            transport.position_to_seconds(MusicalTime::new(2, 0, 0)),
+            4. + ramp,
+            abs <= 1e-12
+        );
+        assert_float_eq!(
+            transport.position_to_seconds(MusicalTime::new(3, 0, 0)),
+            4. + ramp + 2.,
+            abs <= 1e-12
+        );
+        assert_float_eq!(
+            transport.tempo_at_position(MusicalTime::new(1, 2, 0)),
+            90.,
+            abs <= 1e-12
+        );
+
+        // round trip along the ramp
+        let position = MusicalTime::new(1, 2, 123);
+        let seconds = transport.position_to_seconds(position);
+        assert_eq!(transport.seconds_to_position(seconds), position);
+    }
+
+    #[test]
+    fn test_time_at_position() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut transport = context.create_transport();
+
+        assert_eq!(transport.time_at_position(MusicalTime::default()), None);
+
+        transport.start_at(1.);
+        assert_eq!(transport.position(), None);
+        assert_float_eq!(
+            transport
+                .time_at_position(MusicalTime::new(1, 0, 0))
+                .unwrap(),
+            3.,
+            abs <= 1e-12
+        );
+
+        // restart in the middle of the second bar
+        transport.start_at_with_position(0., MusicalTime::new(1, 2, 0));
+        assert_eq!(transport.position(), Some(MusicalTime::new(1, 2, 0)));
+        assert_eq!(transport.time_at_position(MusicalTime::new(1, 0, 0)), None);
+        assert_float_eq!(
+            transport
+                .time_at_position(MusicalTime::new(2, 0, 0))
+                .unwrap(),
+            1.,
+            abs <= 1e-12
+        );
+    }
+
+    #[test]
+    fn test_loop_region() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut transport = context.create_transport();
+
+        // loop the second bar, from 2s to 4s
+        transport.set_loop(MusicalTime::new(1, 0, 0), MusicalTime::new(2, 0, 0));
+        transport.start_at_with_position(0., MusicalTime::new(1, 3, 0));
+
+        assert_eq!(
+            transport.position_at_time(1.),
+            Some(MusicalTime::new(1, 1, 0))
+        );
+        // positions in the loop are played at each iteration
+        assert_float_eq!(
+            transport
+                .time_at_position(MusicalTime::new(1, 2, 0))
+                .unwrap(),
+            1.5,
+            abs <= 1e-12
+        );
+        // positions after the loop region are never played
+        assert_eq!(transport.time_at_position(MusicalTime::new(2, 0, 0)), None);
+
+        transport.clear_loop();
+        assert_float_eq!(
+            transport
+                .time_at_position(MusicalTime::new(2, 0, 0))
+                .unwrap(),
+            0.5,
+            abs <= 1e-12
+        );
+    }
+
+    #[test]
+    fn test_set_time_signature() {
+        let context = OfflineAudioContext::new(1, 128, 48_000.);
+        let mut transport = context.create_transport();
+
+        transport.set_tempo_at_position(60., MusicalTime::new(1, 0, 0));
+        transport.set_time_signature(TimeSignature {
+            beats_per_bar: 3,
+            beat_unit: 4,
+        });
+
+        // the tempo change follows the start of the second bar
+        assert_float_eq!(
+            transport.tempo_at_position(MusicalTime::new(0, 2, 0)),
+            120.,
+            abs <= 0.
+        );
+        assert_float_eq!(
+            transport.tempo_at_position(MusicalTime::new(1, 0, 0)),
+            60.,
+            abs <= 0.
+        );
+    }
+
+    #[test]
+    fn test_schedule_in_musical_time() {
+        let sample_rate = 48_000.;
+        let mut context = OfflineAudioContext::new(1, 48_000, sample_rate);
+
+        let mut transport = context.create_transport();
+        transport.set_tempo(240.);
+        transport.start();
+
+        let mut src = context.create_constant_source();
+        src.connect(&context.destination());
+        // 240 bpm: one beat every 250ms
+        let start = transport.start_source_at_position(&mut src, MusicalTime::new(0, 1, 0));
+        assert_eq!(start, Some(0.25));
+        transport.set_value_at_position(src.offset(), 0.5, MusicalTime::new(0, 2, 0));
+
+        let output = context.start_rendering_sync();
+        let output = output.get_channel_data(0);
+
+        // the start time of the source is compared with the accumulated time of
+        // each sample-frame, so only check the neighbouring sample-frames
+        assert_float_eq!(output[11_999], 0., abs <= 0.);
+        assert_float_eq!(output[12_001], 1., abs <= 0.);
+        assert_float_eq!(output[23_999], 1., abs <= 0.);
+        assert_float_eq!(output[24_000], 0.5, abs <= 0.);
+    }
+}